@@ -607,6 +607,7 @@ pub fn run_doctest_foreign(
     use epic_wallet_libwallet::{api_impl, WalletInst};
     use epic_wallet_util::epic_keychain::ExtKeychain;
 
+    use crate::config::SpendPolicy;
     use crate::core::global::ChainTypes;
     use crate::core::{core::feijoada, global};
     use epic_wallet_util::epic_util as util;
@@ -652,8 +653,15 @@ pub fn run_doctest_foreign(
             >;
     let lc = wallet1.lc_provider().unwrap();
     let _ = lc.set_top_level_directory(&format!("{}/wallet1", test_dir));
-    lc.create_wallet(None, Some(rec_phrase_1), 32, empty_string.clone(), false)
-        .unwrap();
+    lc.create_wallet(
+        None,
+        Some(rec_phrase_1),
+        32,
+        empty_string.clone(),
+        false,
+        None,
+    )
+    .unwrap();
     let mask1 = lc
         .open_wallet(None, empty_string.clone(), use_token, true)
         .unwrap();
@@ -687,8 +695,15 @@ pub fn run_doctest_foreign(
             >;
     let lc = wallet2.lc_provider().unwrap();
     let _ = lc.set_top_level_directory(&format!("{}/wallet2", test_dir));
-    lc.create_wallet(None, Some(rec_phrase_2), 32, empty_string.clone(), false)
-        .unwrap();
+    lc.create_wallet(
+        None,
+        Some(rec_phrase_2),
+        32,
+        empty_string.clone(),
+        false,
+        None,
+    )
+    .unwrap();
     let mask2 = lc
         .open_wallet(None, empty_string.clone(), use_token, true)
         .unwrap();
@@ -752,8 +767,15 @@ pub fn run_doctest_foreign(
                 selection_strategy_is_use_all: true,
                 ..Default::default()
             };
-            api_impl::owner::process_invoice_tx(&mut **w, (&mask1).as_ref(), &slate, args, true)
-                .unwrap()
+            api_impl::owner::process_invoice_tx(
+                &mut **w,
+                (&mask1).as_ref(),
+                &slate,
+                args,
+                &SpendPolicy::default(),
+                true,
+            )
+            .unwrap()
         };
         println!("INIT INVOICE SLATE");
         // Spit out slate for input to finalize_invoice_tx
@@ -773,7 +795,15 @@ pub fn run_doctest_foreign(
             selection_strategy_is_use_all: true,
             ..Default::default()
         };
-        let slate = api_impl::owner::init_send_tx(&mut **w, (&mask1).as_ref(), args, true).unwrap();
+        let slate = api_impl::owner::init_send_tx(
+            &mut **w,
+            (&mask1).as_ref(),
+            args,
+            &SpendPolicy::default(),
+            None,
+            true,
+        )
+        .unwrap();
         println!("INIT SLATE");
         // Spit out slate for input to finalize_tx
         println!("{}", serde_json::to_string_pretty(&slate).unwrap());
@@ -40,6 +40,7 @@ mod foreign;
 mod foreign_rpc;
 
 mod owner;
+mod owner_events;
 mod owner_rpc;
 mod owner_rpc_s;
 
@@ -48,8 +49,9 @@ mod types;
 pub use crate::foreign::{Foreign, ForeignCheckMiddleware, ForeignCheckMiddlewareFn};
 pub use crate::foreign_rpc::ForeignRpc;
 pub use crate::owner::Owner;
+pub use crate::owner_events::{OwnerEvent, OwnerEventStream};
 pub use crate::owner_rpc::OwnerRpc;
-pub use crate::owner_rpc_s::OwnerRpcS;
+pub use crate::owner_rpc_s::{fill_optional_params, OwnerRpcS};
 
 pub use crate::foreign_rpc::foreign_rpc as foreign_rpc_client;
 pub use crate::foreign_rpc::run_doctest_foreign;
@@ -21,15 +21,19 @@ use uuid::Uuid;
 use crate::config::{EpicboxConfig, TorConfig, WalletConfig};
 use crate::core::core::Transaction;
 use crate::core::global;
-use crate::impls::create_sender;
 use crate::impls::EpicboxChannel;
+use crate::impls::{create_sender, find_contact, HttpWebhookSender};
 use crate::keychain::{Identifier, Keychain};
-use crate::libwallet::api_impl::owner_updater::{start_updater_log_thread, StatusMessage};
+use crate::libwallet::api_impl::owner_updater::{
+	start_updater_log_thread, StatusListeners, StatusMessage,
+};
 use crate::libwallet::api_impl::{owner, owner_updater};
 use crate::libwallet::{
-	address, AcctPathMapping, EpicboxAddress, Error, InitTxArgs, IssueInvoiceTxArgs, NodeClient,
-	NodeHeightResult, PaymentProof, RetrieveOutputsResult, RetrieveTxsResult, Slate, TxLogEntry,
-	WalletInfo, WalletInst, WalletLCProvider,
+	address, AcctPathMapping, Contact, EpicboxAddress, EpicboxListenerStatus, Error, InitTxArgs,
+	IssueInvoiceTxArgs, LabelSearchResult, NodeCacheStats, NodeClient, NodeHeightResult,
+	OutboxEntry, PaymentProof, RetrieveOutputsResult, RetrieveTxsResult, ScanProgress,
+	SelectionEstimate, Slate, TxLogEntry, TxProof, VerifiedTxProof, WalletInfo, WalletInst,
+	WalletLCProvider,
 };
 
 use crate::util::logger::LoggingConfig;
@@ -37,7 +41,7 @@ use crate::util::secp::key::SecretKey;
 use crate::util::{from_hex, static_secp_instance, Mutex, ZeroingString};
 use ed25519_dalek::VerifyingKey as DalekPublicKey;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{channel, Sender};
+use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
@@ -77,6 +81,8 @@ where
 	/// Holds all update and status messages returned by the
 	/// updater process
 	updater_messages: Arc<Mutex<Vec<StatusMessage>>>,
+	/// Channels the updater messages are also forwarded to
+	status_listeners: StatusListeners,
 	/// Optional TOR configuration, holding address of sender and
 	/// data directory
 	tor_config: Mutex<Option<TorConfig>>,
@@ -173,17 +179,21 @@ where
 		is_node_synced: Arc<AtomicBool>,
 	) -> Self {
 		let updater_running = Arc::new(AtomicBool::new(false));
-		let updater = Arc::new(Mutex::new(owner_updater::Updater::new(
-			wallet_inst.clone(),
-			updater_running.clone(),
-		)));
+		let mut updater = owner_updater::Updater::new(wallet_inst.clone(), updater_running.clone());
+		updater.set_webhook_sender(Some(Box::new(HttpWebhookSender::new())));
+		let updater = Arc::new(Mutex::new(updater));
 
 		let updater_messages = Arc::new(Mutex::new(vec![]));
+		let status_listeners = Arc::new(Mutex::new(vec![]));
 		let tx = match custom_channel {
 			Some(c) => c,
 			None => {
 				let (tx, rx) = channel();
-				let _ = start_updater_log_thread(rx, updater_messages.clone());
+				let _ = start_updater_log_thread(
+					rx,
+					updater_messages.clone(),
+					status_listeners.clone(),
+				);
 				tx
 			}
 		};
@@ -196,6 +206,7 @@ where
 			updater_running,
 			status_tx: Mutex::new(Some(tx)),
 			updater_messages,
+			status_listeners,
 			tor_config: Mutex::new(None),
 			epicbox_config: Mutex::new(None),
 			is_node_synced,
@@ -373,6 +384,114 @@ where
 		owner::set_active_account(&mut **w, label)
 	}
 
+	/// Returns all contacts stored in the wallet's address book, ordered by name.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A Vector of [`Contact`](../epic_wallet_libwallet/types/struct.Contact.html) data
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # epic_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// use std::sync::atomic::AtomicBool;
+	/// let api_owner = Owner::new(wallet.clone(), None, Arc::new(AtomicBool::new(true)));
+	///
+	/// let result = api_owner.contacts(None);
+	///
+	/// if let Ok(contacts) = result {
+	///		//...
+	/// }
+	/// ```
+
+	pub fn contacts(&self, keychain_mask: Option<&SecretKey>) -> Result<Vec<Contact>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::contacts(&mut **w)
+	}
+
+	/// Adds a contact to the wallet's address book, replacing any existing contact
+	/// with the same name. Contacts can be used as a send destination by passing
+	/// `@name` as the destination.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `contact` - The [`Contact`](../epic_wallet_libwallet/types/struct.Contact.html) to add.
+	/// The method must be one of `http`, `tor`, `epicbox` or `keybase`.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # epic_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// use epic_wallet_libwallet::Contact;
+	/// use std::sync::atomic::AtomicBool;
+	/// let api_owner = Owner::new(wallet.clone(), None, Arc::new(AtomicBool::new(true)));
+	///
+	/// let contact = Contact {
+	/// 	name: "alice".to_owned(),
+	/// 	method: "http".to_owned(),
+	/// 	destination: "http://127.0.0.1:3415".to_owned(),
+	/// 	proof_address: None,
+	/// };
+	/// let result = api_owner.add_contact(None, contact);
+	/// ```
+
+	pub fn add_contact(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		contact: Contact,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::add_contact(&mut **w, keychain_mask, contact)
+	}
+
+	/// Removes a contact from the wallet's address book.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `name` - The name of the contact to remove.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # epic_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// use std::sync::atomic::AtomicBool;
+	/// let api_owner = Owner::new(wallet.clone(), None, Arc::new(AtomicBool::new(true)));
+	///
+	/// let result = api_owner.remove_contact(None, "alice");
+	/// ```
+
+	pub fn remove_contact(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		name: &str,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::remove_contact(&mut **w, keychain_mask, name)
+	}
+
 	/// Returns a list of outputs from the active account in the wallet.
 	///
 	/// # Arguments
@@ -477,6 +596,176 @@ where
 		)
 	}
 
+	/// Freezes an output, so it's never selected for spending until it is unfrozen via
+	/// [`unfreeze_output`](struct.Owner.html#method.unfreeze_output). Frozen outputs are
+	/// reported separately in [`WalletInfo`](../epic_wallet_libwallet/types/struct.WalletInfo.html)
+	/// as `amount_frozen`.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `commit` - The hex encoded commitment of the output to freeze. Only unspent or
+	/// unconfirmed outputs can be frozen.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # epic_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// use std::sync::atomic::AtomicBool;
+	/// let api_owner = Owner::new(wallet.clone(), None, Arc::new(AtomicBool::new(true)));
+	/// let result = api_owner.freeze_output(
+	/// 	None,
+	/// 	"089be87c488db1e7c783b19272a83b23bce56a5263163554b345c6f7ffedac517e",
+	/// );
+	/// ```
+
+	pub fn freeze_output(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		commit: &str,
+	) -> Result<(), Error> {
+		owner::set_output_frozen(self.wallet_inst.clone(), keychain_mask, commit, true)
+	}
+
+	/// Unfreezes an output previously frozen via
+	/// [`freeze_output`](struct.Owner.html#method.freeze_output), making it available
+	/// for spending again.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `commit` - The hex encoded commitment of the output to unfreeze.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered.
+
+	pub fn unfreeze_output(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		commit: &str,
+	) -> Result<(), Error> {
+		owner::set_output_frozen(self.wallet_inst.clone(), keychain_mask, commit, false)
+	}
+
+	/// Sets a private label on an output, stored only in the local wallet database.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `commit` - The hex encoded commitment of the output to label.
+	/// * `label` - The label to set. `None` or an empty string clears any existing label.
+	/// Labels longer than 256 characters are truncated.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # epic_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// use std::sync::atomic::AtomicBool;
+	/// let api_owner = Owner::new(wallet.clone(), None, Arc::new(AtomicBool::new(true)));
+	/// let result = api_owner.set_output_label(
+	/// 	None,
+	/// 	"089be87c488db1e7c783b19272a83b23bce56a5263163554b345c6f7ffedac517e",
+	/// 	Some("pending audit".to_owned()),
+	/// );
+	/// ```
+
+	pub fn set_output_label(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		commit: &str,
+		label: Option<String>,
+	) -> Result<(), Error> {
+		owner::set_output_label(self.wallet_inst.clone(), keychain_mask, commit, label)
+	}
+
+	/// Sets a private label on a transaction log entry in the active account, stored only in
+	/// the local wallet database.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_id` - If present, the transaction with the given local id is labelled.
+	/// * `tx_slate_id` - If present, the transaction(s) with the given slate id are labelled.
+	/// Exactly one of `tx_id` and `tx_slate_id` must be provided.
+	/// * `label` - The label to set. `None` or an empty string clears any existing label.
+	/// Labels longer than 256 characters are truncated.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # epic_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// use std::sync::atomic::AtomicBool;
+	/// let api_owner = Owner::new(wallet.clone(), None, Arc::new(AtomicBool::new(true)));
+	/// let result = api_owner.set_tx_label(None, Some(1), None, Some("invoice #42".to_owned()));
+	/// ```
+
+	pub fn set_tx_label(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+		label: Option<String>,
+	) -> Result<(), Error> {
+		owner::set_tx_label(
+			self.wallet_inst.clone(),
+			keychain_mask,
+			tx_id,
+			tx_slate_id,
+			label,
+		)
+	}
+
+	/// Returns all transaction log entries and outputs in the active account whose label
+	/// contains the given query. Matching is case insensitive.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `query` - The text to search labels for.
+	///
+	/// # Returns
+	/// * Ok with a [`LabelSearchResult`](../epic_wallet_libwallet/api_impl/types/struct.LabelSearchResult.html)
+	/// containing the matching transactions and outputs if successful
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # epic_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// use std::sync::atomic::AtomicBool;
+	/// let api_owner = Owner::new(wallet.clone(), None, Arc::new(AtomicBool::new(true)));
+	/// let result = api_owner.search_labels(None, "invoice");
+	///
+	/// if let Ok(found) = result {
+	/// 	// Do something
+	/// }
+	/// ```
+
+	pub fn search_labels(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		query: &str,
+	) -> Result<LabelSearchResult, Error> {
+		owner::search_labels(self.wallet_inst.clone(), keychain_mask, query)
+	}
+
 	/// Returns a list of transaction log entries from the active account in the wallet.
 	///
 	/// # Arguments
@@ -687,7 +976,8 @@ where
 	/// transaction outputs should be locked via a call to
 	/// [`tx_lock_outputs`](struct.Owner.html#method.tx_lock_outputs). This must be called before calling
 	/// [`finalize_tx`](struct.Owner.html#method.finalize_tx).
-	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered.
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as `PolicyViolation` if the transaction breaks the spending policy of the wallet.
 	///
 	/// # Remarks
 	///
@@ -731,14 +1021,43 @@ where
 	pub fn init_send_tx(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		args: InitTxArgs,
+		mut args: InitTxArgs,
 		is_node_synced: Arc<AtomicBool>,
 	) -> Result<Slate, Error> {
+		let contacts = {
+			let mut w_lock = self.wallet_inst.lock();
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			owner::contacts(&mut **w)?
+		};
+		// resolve address book destinations, filling in the contact's
+		// payment proof address if none was given
+		if let Some(sa) = args.send_args.as_mut() {
+			if let Some(c) = find_contact(&sa.dest, &contacts)? {
+				sa.method = c.method.clone();
+				sa.dest = c.destination.clone();
+				if args.payment_proof_recipient_address.is_none() {
+					if let Some(p) = c.proof_address.as_ref() {
+						let proof_address = address::ed25519_parse_pubkey(p)?;
+						args.payment_proof_recipient_address = Some(proof_address);
+					}
+				}
+			}
+		}
 		let send_args = args.send_args.clone();
 		let mut slate = {
 			let mut w_lock = self.wallet_inst.lock();
-			let w = w_lock.lc_provider()?.wallet_inst()?;
-			owner::init_send_tx(&mut **w, keychain_mask, args, self.doctest_mode)?
+			let lc = w_lock.lc_provider()?;
+			let policy = lc.spend_policy();
+			let w = lc.wallet_inst()?;
+			let dest = send_args.as_ref().map(|sa| sa.dest.as_str());
+			owner::init_send_tx(
+				&mut **w,
+				keychain_mask,
+				args,
+				&policy,
+				dest,
+				self.doctest_mode,
+			)?
 		};
 
 		// Helper functionality. If send arguments exist, attempt to send
@@ -746,7 +1065,7 @@ where
 			Some(sa) => {
 				//TODO: in case of keybase, the response might take 60s and leave the service hanging
 				match sa.method.as_ref() {
-					"http" | "keybase" | "epicbox" => {}
+					"http" | "tor" | "keybase" | "epicbox" => {}
 					_ => {
 						error!("unsupported payment method: {}", sa.method);
 						return Err(
@@ -778,8 +1097,9 @@ where
 					self.tx_lock_outputs(keychain_mask, &slate, 0, Some(sa.dest))?;
 					return Ok(slate);
 				} else {
-					let comm_adapter = create_sender(&sa.method, &sa.dest, is_node_synced)
-						.map_err(|e| Error::GenericError(format!("{}", e)))?;
+					let comm_adapter =
+						create_sender(&sa.method, &sa.dest, &contacts, is_node_synced)
+							.map_err(|e| Error::GenericError(format!("{}", e)))?;
 					slate = comm_adapter.send_tx(&slate)?;
 				}
 
@@ -790,7 +1110,14 @@ where
 				};
 
 				if sa.post_tx {
-					self.post_tx(keychain_mask, &slate.tx, sa.fluff)?;
+					match self.post_tx(keychain_mask, &slate.tx, sa.fluff) {
+						// finalized, but has to be reposted once the lock height is reached
+						Err(Error::TransactionLocked(h, tip)) => warn!(
+							"Transaction {} is locked until height {} (chain at {}), repost it then",
+							slate.id, h, tip
+						),
+						r => r?,
+					}
 				}
 				Ok(slate)
 			}
@@ -798,6 +1125,166 @@ where
 		}
 	}
 
+	/// Initiates a new transaction as the sender, like
+	/// [`init_send_tx`](struct.Owner.html#method.init_send_tx), for callers
+	/// that send the slate themselves rather than through the `send_args` of
+	/// the arguments, which must not be set. The spending policy of the wallet
+	/// checks `dest` is an allowed destination.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `args` - [`InitTxArgs`](../epic_wallet_libwallet/types/struct.InitTxArgs.html),
+	/// transaction initialization arguments, without `send_args`.
+	/// * `dest` - Where the slate is going to be sent, once address book
+	/// names were resolved.
+	///
+	/// # Returns
+	/// * a result containing the transaction
+	/// [Slate](../epic_wallet_libwallet/slate/struct.Slate.html), to be sent to `dest`
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as `PolicyViolation` if the transaction breaks the spending policy of the wallet.
+	///
+	/// # Example
+	/// Set up as in [new](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # epic_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// use std::sync::atomic::AtomicBool;
+	/// let mut api_owner = Owner::new(wallet.clone(), None, Arc::new(AtomicBool::new(true)));
+	/// let args = InitTxArgs {
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 2,
+	/// 	..Default::default()
+	/// };
+	/// let dest = "http://127.0.0.1:3415";
+	/// let result = api_owner.init_send_tx_to(None, args, dest);
+	///
+	/// if let Ok(slate) = result {
+	/// 	// Send slate to dest
+	/// 	// ...
+	/// 	api_owner.tx_lock_outputs(None, &slate, 0, Some(dest.to_owned()));
+	/// }
+	/// ```
+
+	pub fn init_send_tx_to(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		args: InitTxArgs,
+		dest: &str,
+	) -> Result<Slate, Error> {
+		if args.send_args.is_some() {
+			return Err(Error::ArgumentError(
+				"send_args can't be given along with a destination".to_owned(),
+			));
+		}
+		let mut w_lock = self.wallet_inst.lock();
+		let lc = w_lock.lc_provider()?;
+		let policy = lc.spend_policy();
+		let w = lc.wallet_inst()?;
+		owner::init_send_tx(
+			&mut **w,
+			keychain_mask,
+			args,
+			&policy,
+			Some(dest),
+			self.doctest_mode,
+		)
+	}
+
+	/// Estimates the fee, amount locked and number of inputs of a transaction for each of
+	/// the built-in coin selection strategies, without locking outputs or creating the
+	/// transaction.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `args` - [`InitTxArgs`](../epic_wallet_libwallet/types/struct.InitTxArgs.html),
+	/// transaction initialization arguments. The selection strategy fields are ignored.
+	///
+	/// # Returns
+	/// * `Ok(Vec<SelectionEstimate>)` with one estimate per selection strategy if successful.
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [new](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # epic_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// use std::sync::atomic::AtomicBool;
+	/// let mut api_owner = Owner::new(wallet.clone(), None, Arc::new(AtomicBool::new(true)));
+	/// let args = InitTxArgs {
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 2,
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.estimate_selection_strategies(None, args);
+	///
+	/// if let Ok(estimates) = result {
+	/// 	for e in estimates {
+	/// 		println!("{}: fee {}, {} inputs", e.strategy, e.fee, e.num_inputs);
+	/// 	}
+	/// }
+	/// ```
+
+	pub fn estimate_selection_strategies(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		args: InitTxArgs,
+	) -> Result<Vec<SelectionEstimate>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::estimate_selection_strategies(&mut **w, keychain_mask, args)
+	}
+
+	/// Selects the inputs of one transaction per amount at once, so that a batch of
+	/// transactions can be created one after the other without running short of outputs
+	/// halfway. Nothing is locked.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `args` - [`InitTxArgs`](../epic_wallet_libwallet/types/struct.InitTxArgs.html),
+	/// transaction initialization arguments shared by every transaction. The amount is ignored.
+	/// * `amounts` - The amount of each transaction.
+	///
+	/// # Returns
+	/// * `Ok(Vec<Vec<String>>)` with the commitments each transaction is to spend, to be given
+	/// as the `inputs` of its [`InitTxArgs`](../epic_wallet_libwallet/types/struct.InitTxArgs.html).
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// `NotEnoughFunds` if not every amount can be covered.
+	///
+	/// # Example
+	/// Set up as in [new](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # epic_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// use std::sync::atomic::AtomicBool;
+	/// let mut api_owner = Owner::new(wallet.clone(), None, Arc::new(AtomicBool::new(true)));
+	/// let args = InitTxArgs {
+	/// 	minimum_confirmations: 2,
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.plan_inputs(None, args, &[2_000_000_000, 1_000_000_000]);
+	///
+	/// if let Ok(plan) = result {
+	/// 	for inputs in plan {
+	/// 		println!("{} inputs", inputs.len());
+	/// 	}
+	/// }
+	/// ```
+
+	pub fn plan_inputs(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		args: InitTxArgs,
+		amounts: &[u64],
+	) -> Result<Vec<Vec<String>>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::plan_inputs(&mut **w, keychain_mask, args, amounts)
+	}
+
 	/// Issues a new invoice transaction slate, essentially a `request for payment`.
 	/// The slate created by this function will contain the amount, an output for the amount,
 	/// as well as round 1 of singature creation complete. The slate should then be send
@@ -907,8 +1394,17 @@ where
 		args: InitTxArgs,
 	) -> Result<Slate, Error> {
 		let mut w_lock = self.wallet_inst.lock();
-		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::process_invoice_tx(&mut **w, keychain_mask, slate, args, self.doctest_mode)
+		let lc = w_lock.lc_provider()?;
+		let policy = lc.spend_policy();
+		let w = lc.wallet_inst()?;
+		owner::process_invoice_tx(
+			&mut **w,
+			keychain_mask,
+			slate,
+			args,
+			&policy,
+			self.doctest_mode,
+		)
 	}
 
 	/// Locks the outputs associated with the inputs to the transaction in the given
@@ -1046,8 +1542,10 @@ where
 		slate: &Slate,
 	) -> Result<Slate, Error> {
 		let mut w_lock = self.wallet_inst.lock();
-		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::finalize_tx(&mut **w, keychain_mask, &slate)
+		let lc = w_lock.lc_provider()?;
+		let policy = lc.spend_policy();
+		let w = lc.wallet_inst()?;
+		owner::finalize_tx(&mut **w, keychain_mask, &slate, &policy)
 	}
 
 	/// Posts a completed transaction to the listening node for validation and inclusion in a block
@@ -1437,6 +1935,44 @@ where
 		)
 	}
 
+	/// Returns the progress of a running or interrupted scan. Scans save their progress as
+	/// they go, and an interrupted scan is resumed by the next call to
+	/// [`scan`](struct.Owner.html#method.scan) or by the updater.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with the [`ScanProgress`](../epic_wallet_libwallet/types/struct.ScanProgress.html),
+	/// or `None` if no scan is in progress
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # epic_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	/// use std::sync::atomic::AtomicBool;
+	///
+	/// let api_owner = Owner::new(wallet.clone(), None, Arc::new(AtomicBool::new(true)));
+	/// let result = api_owner.scan_progress(None);
+	///
+	/// if let Ok(Some(p)) = result {
+	///		println!("{}% scanned", p.percentage_complete);
+	/// }
+	/// ```
+
+	pub fn scan_progress(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<Option<ScanProgress>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::scan_progress(&mut **w)
+	}
+
 	/// Retrieves the last known height known by the wallet. This is determined as follows:
 	/// * If the wallet can successfully contact its configured node, the reported node
 	/// height is returned, and the `updated_from_node` field in the response is `true`
@@ -1495,6 +2031,43 @@ where
 		Ok(res)
 	}
 
+	/// Returns the hit and miss counters of the node lookup cache since the wallet was
+	/// started.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with the [`NodeCacheStats`](../epic_wallet_libwallet/types/struct.NodeCacheStats.html),
+	/// or `None` if the node client doesn't cache lookups
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # epic_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	/// use std::sync::atomic::AtomicBool;
+	///
+	/// let api_owner = Owner::new(wallet.clone(), None, Arc::new(AtomicBool::new(true)));
+	/// let result = api_owner.node_cache_stats(None);
+	///
+	/// if let Ok(Some(s)) = result {
+	///		println!("{} kernel lookups answered from the cache", s.kernel_hits);
+	/// }
+	/// ```
+
+	pub fn node_cache_stats(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<Option<NodeCacheStats>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		Ok(w.w2n_client().cache_stats())
+	}
+
 	// LIFECYCLE FUNCTIONS
 
 	/// Retrieve the top-level directory for the wallet. This directory should contain the
@@ -1670,6 +2243,9 @@ where
 	/// * `mnemonic_length`: Desired length of mnemonic in bytes (16 or 32, either 12 or 24 words).
 	/// Use 0 if mnemonic isn't being used.
 	/// * `password`: The password used to encrypt/decrypt the `wallet.seed` file
+	/// * `mnemonic_passphrase`: Optional BIP39 passphrase mixed into the keychain derivation.
+	/// It is never stored, and the exact same passphrase is required along with the mnemonic
+	/// to recover the wallet.
 	///
 	/// # Returns
 	/// * Ok if successful
@@ -1701,7 +2277,7 @@ where
 	///
 	///	// create new wallet wirh random seed
 	///	let pw = ZeroingString::from("my_password");
-	/// let result = api_owner.create_wallet(None, None, 0, pw);
+	/// let result = api_owner.create_wallet(None, None, 0, pw, None);
 	///
 	/// if let Ok(r) = result {
 	///		//...
@@ -1714,6 +2290,7 @@ where
 		mnemonic: Option<ZeroingString>,
 		mnemonic_length: u32,
 		password: ZeroingString,
+		mnemonic_passphrase: Option<ZeroingString>,
 	) -> Result<(), Error> {
 		let mut w_lock = self.wallet_inst.lock();
 		let lc = w_lock.lc_provider()?;
@@ -1723,6 +2300,7 @@ where
 			mnemonic_length as usize,
 			password,
 			self.doctest_mode,
+			mnemonic_passphrase,
 		)
 	}
 
@@ -1768,7 +2346,7 @@ where
 	///
 	///	// create new wallet wirh random seed
 	///	let pw = ZeroingString::from("my_password");
-	/// let _ = api_owner.create_wallet(None, None, 0, pw.clone());
+	/// let _ = api_owner.create_wallet(None, None, 0, pw.clone(), None);
 	///
 	/// let result = api_owner.open_wallet(None, pw, true);
 	///
@@ -2120,6 +2698,23 @@ where
 		Ok(q.split_off(index))
 	}
 
+	/// Subscribe to the updater messages as they are created, for callers that
+	/// would rather be pushed messages than poll
+	/// [`get_updater_messages`](struct.Owner.html#method.get_updater_messages).
+	/// Messages are still added to the internal queue as well.
+	///
+	/// Nothing is received when the API was created with a custom status
+	/// channel, as the messages never reach the API then.
+	///
+	/// # Returns
+	/// * A receiver of [`StatusMessage`](../epic_wallet_libwallet/api_impl/owner_updater/enum.StatusMessage.html),
+	/// unsubscribed once dropped
+	pub fn subscribe_updater_messages(&self) -> Receiver<StatusMessage> {
+		let (tx, rx) = channel();
+		self.status_listeners.lock().push(tx);
+		rx
+	}
+
 	/// Retrieve the public "addresses" associated with the active account at the
 	/// given derivation path.
 	///
@@ -2438,6 +3033,154 @@ where
 		owner::verify_payment_proof(self.wallet_inst.clone(), keychain_mask, proof)
 	}
 
+	/// Returns the [TxProof](../epic_wallet_libwallet/struct.TxProof.html) stored for a
+	/// transaction whose slate was received over epicbox. The proof holds the message as it was
+	/// signed by the counterparty, and can be exported as JSON as evidence of what they sent.
+	///
+	/// Either the `tx_id` or `tx_slate_id` argument must be provided, or the function will return
+	/// an error.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_id` - If `Some(i)` return the proof associated with the transaction with id `i`
+	/// * `tx_slate_id` - If `Some(uuid)`, return the proof associated with the transaction with the
+	/// given `uuid`
+	///
+	/// # Returns
+	/// * Ok([TxProof](../epic_wallet_libwallet/struct.TxProof.html)) if successful
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered
+	/// or the transaction wasn't received with a proof
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # epic_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// use std::sync::atomic::AtomicBool;
+	/// let api_owner = Owner::new(wallet.clone(), None, Arc::new(AtomicBool::new(true)));
+	/// let tx_slate_id = Some(Uuid::parse_str("0436430c-2b02-624c-2032-570501212b00").unwrap());
+	///
+	/// let result = api_owner.retrieve_tx_proof(None, None, tx_slate_id);
+	///
+	/// if let Ok(p) = result {
+	///		let verified = api_owner.verify_tx_proof(&p);
+	///		//...
+	/// }
+	/// ```
+
+	pub fn retrieve_tx_proof(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<TxProof, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+
+		owner::retrieve_tx_proof(&mut **w, tx_id, tx_slate_id)
+	}
+
+	/// Verifies a [TxProof](../epic_wallet_libwallet/struct.TxProof.html). This checks the
+	/// counterparty's signature over the message, decrypts the slate it carries with the stored
+	/// key and checks the proof's amount, fee, inputs and outputs against that slate.
+	///
+	/// Verification doesn't use the wallet, so anyone can verify a proof exported from it.
+	///
+	/// # Arguments
+	/// * `proof` - A [TxProof](../epic_wallet_libwallet/struct.TxProof.html)
+	///
+	/// # Returns
+	/// * Ok([VerifiedTxProof](../epic_wallet_libwallet/api_impl/types/struct.VerifiedTxProof.html))
+	/// with the addresses and the slate the proof is for, if the proof is valid
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if it isn't
+	pub fn verify_tx_proof(&self, proof: &TxProof) -> Result<VerifiedTxProof, Error> {
+		owner::verify_tx_proof(proof)
+	}
+
+	/// Returns the slates sent over epicbox, oldest first, along with their delivery state. A
+	/// slate is kept in this outbox from before it's first sent, and the listener sends it again
+	/// until the relay accepts it or it expires. Slates are removed a week after that.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with a vector of [OutboxEntry](../epic_wallet_libwallet/struct.OutboxEntry.html) if
+	/// successful
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # epic_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// use std::sync::atomic::AtomicBool;
+	/// let api_owner = Owner::new(wallet.clone(), None, Arc::new(AtomicBool::new(true)));
+	///
+	/// let result = api_owner.retrieve_outbox(None);
+	///
+	/// if let Ok(entries) = result {
+	///		//...
+	/// }
+	/// ```
+
+	pub fn retrieve_outbox(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<Vec<OutboxEntry>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+
+		owner::retrieve_outbox(&mut **w)
+	}
+
+	/// Returns the status of the epicbox listener, as last recorded by it: whether it's connecting,
+	/// listening or waiting to reconnect, along with why it lost its connection. The listener
+	/// usually runs in a process of its own, and refreshes the status with every heartbeat while
+	/// listening, so a status that isn't refreshed anymore means the listener is gone.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with the [EpicboxListenerStatus](../epic_wallet_libwallet/struct.EpicboxListenerStatus.html),
+	/// or `None` if the listener never ran
+	/// * or [`libwallet::Error`](../epic_wallet_libwallet/struct.Error.html) if an error is encountered
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # epic_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// use std::sync::atomic::AtomicBool;
+	/// let api_owner = Owner::new(wallet.clone(), None, Arc::new(AtomicBool::new(true)));
+	///
+	/// let result = api_owner.retrieve_epicbox_status(None);
+	///
+	/// if let Ok(Some(status)) = result {
+	///		//...
+	/// }
+	/// ```
+
+	pub fn retrieve_epicbox_status(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<Option<EpicboxListenerStatus>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+
+		owner::retrieve_epicbox_status(&mut **w)
+	}
+
 	/// Updates the mempool status for a given slate.
 	pub fn update_mempool_status(
 		&self,
@@ -0,0 +1,149 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Events pushed to owner API clients: updater status messages as they are
+//! sent, and changes to the transactions and balance of the active account,
+//! found by comparing the wallet with what was last reported.
+
+use crate::keychain::{Identifier, Keychain};
+use crate::libwallet::api_impl::owner_updater::StatusMessage;
+use crate::libwallet::{Error, NodeClient, TxLogEntry, WalletInfo, WalletLCProvider};
+use crate::Owner;
+
+use std::collections::HashMap;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Confirmations the reported balance is computed with
+const BALANCE_MIN_CONFIRMATIONS: u64 = 10;
+
+/// Event streamed to owner API clients
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum OwnerEvent {
+	/// Status message of the updater thread
+	UpdaterStatus(StatusMessage),
+	/// A transaction of the active account was created or changed
+	Transaction(TxLogEntry),
+	/// The balance of the active account changed
+	Balance(WalletInfo),
+}
+
+/// What a client was last told about the wallet
+struct KnownState {
+	/// Active account
+	account: Identifier,
+	/// Transactions by id, as json to compare them
+	txs: HashMap<u32, String>,
+	balance: WalletInfo,
+}
+
+/// Source of the events of one client. The current state of the wallet is
+/// taken as known to the client when the stream starts, only what changes
+/// afterwards is reported.
+pub struct OwnerEventStream<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	owner: Arc<Owner<L, C, K>>,
+	messages: Receiver<StatusMessage>,
+	known: Option<KnownState>,
+}
+
+impl<L, C, K> OwnerEventStream<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	/// Start a stream of the events of the given API
+	pub fn new(owner: Arc<Owner<L, C, K>>) -> Self {
+		let messages = owner.subscribe_updater_messages();
+		let mut stream = OwnerEventStream {
+			owner,
+			messages,
+			known: None,
+		};
+		let _ = stream.wallet_changes();
+		stream
+	}
+
+	/// Wait up to `timeout` for an updater message, and return it along with
+	/// all other events since the last call. Empty if nothing happened.
+	pub fn next_events(&mut self, timeout: Duration) -> Vec<OwnerEvent> {
+		let mut events = vec![];
+		match self.messages.recv_timeout(timeout) {
+			Ok(m) => events.push(OwnerEvent::UpdaterStatus(m)),
+			Err(RecvTimeoutError::Timeout) => {}
+			// no updater messages will ever come, wait for wallet changes only
+			Err(RecvTimeoutError::Disconnected) => std::thread::sleep(timeout),
+		}
+		events.extend(self.messages.try_iter().map(OwnerEvent::UpdaterStatus));
+		match self.wallet_changes() {
+			Ok(changes) => events.extend(changes),
+			// most likely the wallet is closed, which is no change
+			Err(e) => debug!("Unable to check the wallet for events: {}", e),
+		}
+		events
+	}
+
+	/// Transactions and balance that changed since last checked. Nothing
+	/// is reported the first time, or after the active account changed.
+	fn wallet_changes(&mut self) -> Result<Vec<OwnerEvent>, Error> {
+		let account = {
+			let mut w_lock = self.owner.wallet_inst.lock();
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			w.parent_key_id()
+		};
+		let res = self
+			.owner
+			.retrieve_txs(None, false, None, None, None, None, None)?;
+		let (_, balance) =
+			self.owner
+				.retrieve_summary_info(None, false, BALANCE_MIN_CONFIRMATIONS)?;
+
+		let mut events = vec![];
+		let known = match self.known {
+			Some(ref k) if k.account == account => Some(k),
+			_ => None,
+		};
+		let mut txs = HashMap::new();
+		for tx in res.txs {
+			let json = serde_json::to_string(&tx)?;
+			if let Some(k) = known {
+				if k.txs.get(&tx.id) != Some(&json) {
+					events.push(OwnerEvent::Transaction(tx.clone()));
+				}
+			}
+			txs.insert(tx.id, json);
+		}
+		if let Some(k) = known {
+			// a new block alone isn't a balance change
+			let mut last = k.balance.clone();
+			last.last_confirmed_height = balance.last_confirmed_height;
+			if last != balance {
+				events.push(OwnerEvent::Balance(balance.clone()));
+			}
+		}
+		self.known = Some(KnownState {
+			account,
+			txs,
+			balance,
+		});
+		Ok(events)
+	}
+}
@@ -185,6 +185,8 @@ pub trait OwnerRpc: Sync + Send {
                         "commit": "089be87c488db1e7c783b19272a83b23bce56a5263163554b345c6f7ffedac517e",
                         "output": {
                             "commit": "089be87c488db1e7c783b19272a83b23bce56a5263163554b345c6f7ffedac517e",
+                            "frozen": false,
+                            "label": null,
                             "height": "1",
                             "is_coinbase": true,
                             "key_id": "0300000000000000000000000000000000",
@@ -201,6 +203,8 @@ pub trait OwnerRpc: Sync + Send {
                         "commit": "09d8836ffd38ffca42567ef965fdcf1f35b05aeb357664d70cd482438ca0ca0c9e",
                         "output": {
                             "commit": "09d8836ffd38ffca42567ef965fdcf1f35b05aeb357664d70cd482438ca0ca0c9e",
+                            "frozen": false,
+                            "label": null,
                             "height": "2",
                             "is_coinbase": true,
                             "key_id": "0300000000000000000000000100000000",
@@ -319,7 +323,10 @@ pub trait OwnerRpc: Sync + Send {
                     "tx_slate_id": null,
                     "tx_type": "ConfirmedCoinbase",
                     "public_addr": null,
-                    "confirmation_height": 1
+                    "label": null,
+                    "confirmation_height": 1,
+                    "lock_height": null,
+                    "tx_proof": null
                     },
                     {
                     "amount_credited": "1457920000",
@@ -341,7 +348,10 @@ pub trait OwnerRpc: Sync + Send {
                     "tx_slate_id": null,
                     "tx_type": "ConfirmedCoinbase",
                     "public_addr": null,
-                    "confirmation_height": 2
+                    "label": null,
+                    "confirmation_height": 2,
+                    "lock_height": null,
+                    "tx_proof": null
                     }
                 ]
             }
@@ -389,6 +399,7 @@ pub trait OwnerRpc: Sync + Send {
                     "amount_awaiting_confirmation": "0",
                     "amount_awaiting_finalization": "0",
                     "amount_currently_spendable": "1457920000",
+                    "amount_frozen": "0",
                     "amount_immature": "4373760000",
                     "amount_locked": "0",
                     "last_confirmed_height": "4",
@@ -1502,6 +1513,7 @@ pub fn run_doctest_owner(
     use epic_wallet_libwallet::{api_impl, WalletInst};
     use epic_wallet_util::epic_keychain::ExtKeychain;
 
+    use crate::config::SpendPolicy;
     use crate::core::global::ChainTypes;
     use crate::core::{core::feijoada, global};
     use ed25519_dalek::VerifyingKey as DalekPublicKey;
@@ -1547,8 +1559,15 @@ pub fn run_doctest_owner(
             >;
     let lc = wallet1.lc_provider().unwrap();
     let _ = lc.set_top_level_directory(&format!("{}/wallet1", test_dir));
-    lc.create_wallet(None, Some(rec_phrase_1), 32, empty_string.clone(), false)
-        .unwrap();
+    lc.create_wallet(
+        None,
+        Some(rec_phrase_1),
+        32,
+        empty_string.clone(),
+        false,
+        None,
+    )
+    .unwrap();
     let mask1 = lc
         .open_wallet(None, empty_string.clone(), use_token, true)
         .unwrap();
@@ -1582,8 +1601,15 @@ pub fn run_doctest_owner(
             >;
     let lc = wallet2.lc_provider().unwrap();
     let _ = lc.set_top_level_directory(&format!("{}/wallet2", test_dir));
-    lc.create_wallet(None, Some(rec_phrase_2), 32, empty_string.clone(), false)
-        .unwrap();
+    lc.create_wallet(
+        None,
+        Some(rec_phrase_2),
+        32,
+        empty_string.clone(),
+        false,
+        None,
+    )
+    .unwrap();
     let mask2 = lc
         .open_wallet(None, empty_string.clone(), use_token, true)
         .unwrap();
@@ -1656,8 +1682,15 @@ pub fn run_doctest_owner(
             payment_proof_recipient_address: proof_address,
             ..Default::default()
         };
-        let mut slate =
-            api_impl::owner::init_send_tx(&mut **w, (&mask1).as_ref(), args, true).unwrap();
+        let mut slate = api_impl::owner::init_send_tx(
+            &mut **w,
+            (&mask1).as_ref(),
+            args,
+            &SpendPolicy::default(),
+            None,
+            true,
+        )
+        .unwrap();
         println!("INITIAL SLATE");
         println!("{}", serde_json::to_string_pretty(&slate).unwrap());
         {
@@ -1682,7 +1715,13 @@ pub fn run_doctest_owner(
         println!("RECEIPIENT SLATE");
         println!("{}", serde_json::to_string_pretty(&slate).unwrap());
         if finalize_tx {
-            slate = api_impl::owner::finalize_tx(&mut **w, (&mask2).as_ref(), &slate).unwrap();
+            slate = api_impl::owner::finalize_tx(
+                &mut **w,
+                (&mask2).as_ref(),
+                &slate,
+                &SpendPolicy::default(),
+            )
+            .unwrap();
             error!("FINALIZED TX SLATE");
             println!("{}", serde_json::to_string_pretty(&slate).unwrap());
         }
@@ -1706,6 +1745,8 @@ pub fn run_doctest_owner(
     let mut api_owner = Owner::new(wallet1, None, Arc::new(AtomicBool::new(true)));
     api_owner.doctest_mode = true;
     let res = if use_token {
+        let mut request = request;
+        crate::fill_optional_params(&mut request);
         let owner_api = &api_owner as &dyn OwnerRpcS;
         owner_api.handle_request(request).as_option()
     } else {
@@ -21,9 +21,11 @@ use crate::core::global;
 use crate::keychain::{Identifier, Keychain};
 use crate::libwallet::slate_versions::v3::TransactionV3;
 use crate::libwallet::{
-    AcctPathMapping, EpicboxAddress, Error, InitTxArgs, IssueInvoiceTxArgs, NodeClient,
-    NodeHeightResult, PaymentProof, RetrieveOutputsResult, RetrieveTxsResult, Slate, SlateVersion,
-    StatusMessage, TxLogEntry, VersionedSlate, WalletInfo, WalletLCProvider,
+    AcctPathMapping, Contact, EpicboxAddress, EpicboxListenerStatus, Error, InitTxArgs,
+    IssueInvoiceTxArgs, LabelSearchResult, NodeCacheStats, NodeClient, NodeHeightResult,
+    OutboxEntry, PaymentProof, RetrieveOutputsResult, RetrieveTxsResult, ScanProgress,
+    SelectionEstimate, Slate, SlateVersion, StatusMessage, TxLogEntry, TxProof, VerifiedTxProof,
+    VersionedSlate, WalletInfo, WalletLCProvider,
 };
 use crate::util::logger::LoggingConfig;
 use crate::util::secp::key::{PublicKey, SecretKey};
@@ -143,6 +145,96 @@ pub trait OwnerRpcS {
      */
     fn set_active_account(&self, token: Token, label: &String) -> Result<(), Error>;
 
+    /**
+    Networked version of [Owner::contacts](struct.Owner.html#method.contacts).
+
+    # Json rpc example
+
+    ```
+    # epic_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "method": "contacts",
+        "params": {
+            "token": "d202964900000000d302964900000000d402964900000000d502964900000000"
+        },
+        "id": 1
+    }
+    # "#
+    # ,
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "result": {
+            "Ok": []
+        },
+        "id": 1
+    }
+    # "#
+    # , true, 0, false, false, false, false);
+    ```
+     */
+    fn contacts(&self, token: Token) -> Result<Vec<Contact>, Error>;
+
+    /**
+    Networked version of [Owner::add_contact](struct.Owner.html#method.add_contact).
+
+    # Json rpc example
+
+    ```
+    # epic_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "method": "add_contact",
+        "params": {
+            "token": "d202964900000000d302964900000000d402964900000000d502964900000000",
+            "contact": {
+                "name": "alice",
+                "method": "http",
+                "destination": "http://127.0.0.1:3415",
+                "proof_address": null
+            }
+        },
+        "id": 1
+    }
+    # "#
+    # ,
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "result": {
+            "Ok": null
+        },
+        "id": 1
+    }
+    # "#
+    # , true, 0, false, false, false, false);
+    ```
+     */
+    fn add_contact(&self, token: Token, contact: Contact) -> Result<(), Error>;
+
+    /**
+    Networked version of [Owner::remove_contact](struct.Owner.html#method.remove_contact).
+
+    # Json rpc example
+
+    ```text
+        {
+            "jsonrpc": "2.0",
+            "method": "remove_contact",
+            "params": {
+                "token": "d202964900000000d302964900000000d402964900000000d502964900000000",
+                "name": "alice"
+            },
+            "id": 1
+        }
+    ```
+    The result is `{"Ok": null}` if the contact existed and was removed.
+     */
+    fn remove_contact(&self, token: Token, name: String) -> Result<(), Error>;
+
     /**
     Networked version of [Owner::retrieve_outputs](struct.Owner.html#method.retrieve_outputs).
 
@@ -214,6 +306,8 @@ pub trait OwnerRpcS {
                         "commit": "089be87c488db1e7c783b19272a83b23bce56a5263163554b345c6f7ffedac517e",
                         "output": {
                             "commit": "089be87c488db1e7c783b19272a83b23bce56a5263163554b345c6f7ffedac517e",
+                            "frozen": false,
+                            "label": null,
                             "height": "1",
                             "is_coinbase": true,
                             "key_id": "0300000000000000000000000000000000",
@@ -230,6 +324,8 @@ pub trait OwnerRpcS {
                         "commit": "09d8836ffd38ffca42567ef965fdcf1f35b05aeb357664d70cd482438ca0ca0c9e",
                         "output": {
                             "commit": "09d8836ffd38ffca42567ef965fdcf1f35b05aeb357664d70cd482438ca0ca0c9e",
+                            "frozen": false,
+                            "label": null,
                             "height": "2",
                             "is_coinbase": true,
                             "key_id": "0300000000000000000000000100000000",
@@ -261,6 +357,188 @@ pub trait OwnerRpcS {
         sort_order: Option<String>,
     ) -> Result<RetrieveOutputsResult, Error>;
 
+    /**
+    Networked version of [Owner::freeze_output](struct.Owner.html#method.freeze_output).
+
+    # Json rpc example
+
+    ```
+    # epic_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "method": "freeze_output",
+        "params": {
+            "token": "d202964900000000d302964900000000d402964900000000d502964900000000",
+            "commit": "089be87c488db1e7c783b19272a83b23bce56a5263163554b345c6f7ffedac517e"
+        },
+        "id": 1
+    }
+    # "#
+    # ,
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "result": {
+            "Ok": null
+        },
+        "id": 1
+    }
+    # "#
+    # , true, 2, false, false, false, false);
+    ```
+     */
+    fn freeze_output(&self, token: Token, commit: String) -> Result<(), Error>;
+
+    /**
+    Networked version of [Owner::unfreeze_output](struct.Owner.html#method.unfreeze_output).
+
+    # Json rpc example
+
+    ```
+    # epic_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "method": "unfreeze_output",
+        "params": {
+            "token": "d202964900000000d302964900000000d402964900000000d502964900000000",
+            "commit": "089be87c488db1e7c783b19272a83b23bce56a5263163554b345c6f7ffedac517e"
+        },
+        "id": 1
+    }
+    # "#
+    # ,
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "result": {
+            "Ok": null
+        },
+        "id": 1
+    }
+    # "#
+    # , true, 2, false, false, false, false);
+    ```
+     */
+    fn unfreeze_output(&self, token: Token, commit: String) -> Result<(), Error>;
+
+    /**
+    Networked version of [Owner::set_output_label](struct.Owner.html#method.set_output_label).
+
+    # Json rpc example
+
+    ```
+    # epic_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "method": "set_output_label",
+        "params": {
+            "token": "d202964900000000d302964900000000d402964900000000d502964900000000",
+            "commit": "089be87c488db1e7c783b19272a83b23bce56a5263163554b345c6f7ffedac517e",
+            "label": "pending audit"
+        },
+        "id": 1
+    }
+    # "#
+    # ,
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "result": {
+            "Ok": null
+        },
+        "id": 1
+    }
+    # "#
+    # , true, 2, false, false, false, false);
+    ```
+     */
+    fn set_output_label(
+        &self,
+        token: Token,
+        commit: String,
+        label: Option<String>,
+    ) -> Result<(), Error>;
+
+    /**
+    Networked version of [Owner::set_tx_label](struct.Owner.html#method.set_tx_label).
+
+    # Json rpc example
+
+    ```
+    # epic_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "method": "set_tx_label",
+        "params": {
+            "token": "d202964900000000d302964900000000d402964900000000d502964900000000",
+            "tx_id": 1,
+            "tx_slate_id": null,
+            "label": "invoice #42"
+        },
+        "id": 1
+    }
+    # "#
+    # ,
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "result": {
+            "Ok": null
+        },
+        "id": 1
+    }
+    # "#
+    # , true, 2, false, false, false, false);
+    ```
+     */
+    fn set_tx_label(
+        &self,
+        token: Token,
+        tx_id: Option<u32>,
+        tx_slate_id: Option<Uuid>,
+        label: Option<String>,
+    ) -> Result<(), Error>;
+
+    /**
+    Networked version of [Owner::search_labels](struct.Owner.html#method.search_labels).
+
+    # Json rpc example
+
+    ```
+    # epic_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "method": "search_labels",
+        "params": {
+            "token": "d202964900000000d302964900000000d402964900000000d502964900000000",
+            "query": "invoice"
+        },
+        "id": 1
+    }
+    # "#
+    # ,
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "result": {
+            "Ok": {
+                "outputs": [],
+                "txs": []
+            }
+        },
+        "id": 1
+    }
+    # "#
+    # , true, 2, false, false, false, false);
+    ```
+     */
+    fn search_labels(&self, token: Token, query: String) -> Result<LabelSearchResult, Error>;
+
     /**
     Networked version of [Owner::retrieve_txs](struct.Owner.html#method.retrieve_txs).
 
@@ -349,7 +627,10 @@ pub trait OwnerRpcS {
                 "payment_proof": null,
                 "tx_type": "ConfirmedCoinbase",
                 "public_addr": null,
-                "confirmation_height": 1
+                "label": null,
+                "confirmation_height": 1,
+                "lock_height": null,
+                "tx_proof": null
                 },
                 {
                 "amount_credited": "1457920000",
@@ -371,7 +652,10 @@ pub trait OwnerRpcS {
                 "tx_slate_id": null,
                 "tx_type": "ConfirmedCoinbase",
                 "public_addr": null,
-                "confirmation_height": 2
+                "label": null,
+                "confirmation_height": 2,
+                "lock_height": null,
+                "tx_proof": null
                 }
           ]
         }
@@ -434,6 +718,7 @@ pub trait OwnerRpcS {
                     "amount_awaiting_confirmation": "0",
                     "amount_awaiting_finalization": "0",
                     "amount_currently_spendable": "1457920000",
+                    "amount_frozen": "0",
                     "amount_immature": "4373760000",
                     "amount_locked": "0",
                     "last_confirmed_height": "4",
@@ -558,6 +843,44 @@ pub trait OwnerRpcS {
 
     fn init_send_tx(&self, token: Token, args: InitTxArgs) -> Result<VersionedSlate, Error>;
 
+    /**
+    Networked version of [Owner::estimate_selection_strategies](struct.Owner.html#method.estimate_selection_strategies).
+
+    # Json rpc example
+
+    ```text
+        {
+            "jsonrpc": "2.0",
+            "method": "estimate_selection_strategies",
+            "params": {
+                "token": "d202964900000000d302964900000000d402964900000000d502964900000000",
+                "args": {
+                    "src_acct_name": null,
+                    "amount": "60000000",
+                    "minimum_confirmations": 2,
+                    "max_outputs": 500,
+                    "num_change_outputs": 1,
+                    "selection_strategy_is_use_all": true,
+                    "message": null,
+                    "target_slate_version": null,
+                    "payment_proof_recipient_address": null,
+                    "ttl_blocks": null,
+                    "send_args": null
+                }
+            },
+            "id": 1
+        }
+    ```
+    The result contains one entry per selection strategy, e.g.
+    `{"strategy": "branch_and_bound", "total": "60700000", "fee": "700000", "num_inputs": 1}`
+    */
+
+    fn estimate_selection_strategies(
+        &self,
+        token: Token,
+        args: InitTxArgs,
+    ) -> Result<Vec<SelectionEstimate>, Error>;
+
     /**
     Networked version of [Owner::issue_invoice_tx](struct.Owner.html#method.issue_invoice_tx).
 
@@ -1404,6 +1727,52 @@ pub trait OwnerRpcS {
         delete_unconfirmed: bool,
     ) -> Result<(), Error>;
 
+    /**
+    Networked version of [Owner::scan_progress](struct.Owner.html#method.scan_progress).
+
+    # Json rpc example
+
+    ```
+    # epic_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "method": "scan_progress",
+        "params": {
+            "token": "d202964900000000d302964900000000d402964900000000d502964900000000"
+        },
+        "id": 1
+    }
+    # "#
+    # ,
+    # r#"
+    {
+        "id": 1,
+        "jsonrpc": "2.0",
+        "result": {
+            "Ok": null
+        }
+    }
+    # "#
+    # , true, 0, false, false, false, false);
+    ```
+
+    While a scan is running or after it was interrupted, the result holds its progress:
+
+    ```text
+    {
+        "start_height": 1,
+        "end_height": 1250000,
+        "start_pmmr_index": 1,
+        "end_pmmr_index": 3981224,
+        "last_pmmr_index": 1837000,
+        "outputs_found": 42,
+        "percentage_complete": 46
+    }
+    ```
+     */
+    fn scan_progress(&self, token: Token) -> Result<Option<ScanProgress>, Error>;
+
     /**
     Networked version of [Owner::node_height](struct.Owner.html#method.node_height).
 
@@ -1440,6 +1809,50 @@ pub trait OwnerRpcS {
      */
     fn node_height(&self, token: Token) -> Result<NodeHeightResult, Error>;
 
+    /**
+    Networked version of [Owner::node_cache_stats](struct.Owner.html#method.node_cache_stats).
+
+    # Json rpc example
+
+    ```
+    # epic_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "method": "node_cache_stats",
+        "params": {
+            "token": "d202964900000000d302964900000000d402964900000000d502964900000000"
+        },
+        "id": 1
+    }
+    # "#
+    # ,
+    # r#"
+    {
+        "id": 1,
+        "jsonrpc": "2.0",
+        "result": {
+            "Ok": null
+        }
+    }
+    # "#
+    # , true, 0, false, false, false, false);
+    ```
+
+    When the wallet's node client caches lookups, the result holds its counters:
+
+    ```text
+    {
+        "kernel_hits": 120,
+        "kernel_misses": 14,
+        "output_hits": 3400,
+        "output_misses": 260,
+        "reorgs": 0
+    }
+    ```
+     */
+    fn node_cache_stats(&self, token: Token) -> Result<Option<NodeCacheStats>, Error>;
+
     /**
         Initializes the secure JSON-RPC API. This function must be called and a shared key
         established before any other OwnerAPI JSON-RPC function can be called.
@@ -1658,7 +2071,8 @@ pub trait OwnerRpcS {
             "name": null,
             "mnemonic": null,
             "mnemonic_length": 0,
-            "password": "my_secret_password"
+            "password": "my_secret_password",
+            "mnemonic_passphrase": null
         },
         "id": 1
     }
@@ -1675,6 +2089,63 @@ pub trait OwnerRpcS {
     # "#
     # , true, 0, false, false, false, false);
     ```
+
+    `mnemonic_passphrase` was added last and may be left out, by named as well as
+    by positional requests. A wallet recovered from a mnemonic and passphrase:
+
+    ```
+    # epic_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "method": "create_wallet",
+        "params": [
+            null,
+            "fat twenty mean degree forget shell check candy immense awful flame next during february bulb bike sun wink theory day kiwi embrace peace lunch",
+            32,
+            "my_secret_password",
+            "my secret passphrase"
+        ],
+        "id": 1
+    }
+    # "#
+    # ,
+    # r#"
+    {
+        "id": 1,
+        "jsonrpc": "2.0",
+        "result": {
+            "Ok": null
+        }
+    }
+    # "#
+    # , true, 0, false, false, false, false);
+    ```
+
+    The same request from a client that predates the passphrase:
+
+    ```
+    # epic_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+    # r#"
+    {
+        "jsonrpc": "2.0",
+        "method": "create_wallet",
+        "params": [null, null, 0, "my_secret_password"],
+        "id": 1
+    }
+    # "#
+    # ,
+    # r#"
+    {
+        "id": 1,
+        "jsonrpc": "2.0",
+        "result": {
+            "Ok": null
+        }
+    }
+    # "#
+    # , true, 0, false, false, false, false);
+    ```
     */
 
     fn create_wallet(
@@ -1683,6 +2154,7 @@ pub trait OwnerRpcS {
         mnemonic: Option<String>,
         mnemonic_length: u32,
         password: String,
+        mnemonic_passphrase: Option<String>,
     ) -> Result<(), Error>;
 
     /**
@@ -2160,6 +2632,97 @@ pub trait OwnerRpcS {
         proof: PaymentProof,
     ) -> Result<(bool, bool), Error>;
 
+    /**
+    Networked version of [Owner::retrieve_tx_proof](struct.Owner.html#method.retrieve_tx_proof).
+
+    # Json rpc example
+
+    ```text
+        {
+            "jsonrpc": "2.0",
+            "method": "retrieve_tx_proof",
+            "params": {
+                "token": "d202964900000000d302964900000000d402964900000000d502964900000000",
+                "tx_id": null,
+                "tx_slate_id": "0436430c-2b02-624c-2032-570501212b00"
+            },
+            "id": 1
+        }
+    ```
+    The result is `{"Ok": proof}`, the proof holding the sender's epicbox `address`, the signed
+    `message` and its `signature`, the decryption `key` and the `amount`, `fee`, `inputs` and
+    `outputs` of the slate it carries.
+    */
+    fn retrieve_tx_proof(
+        &self,
+        token: Token,
+        tx_id: Option<u32>,
+        tx_slate_id: Option<Uuid>,
+    ) -> Result<TxProof, Error>;
+
+    /**
+    Networked version of [Owner::verify_tx_proof](struct.Owner.html#method.verify_tx_proof).
+
+    # Json rpc example
+
+    ```text
+        {
+            "jsonrpc": "2.0",
+            "method": "verify_tx_proof",
+            "params": {
+                "proof": { ... }
+            },
+            "id": 1
+        }
+    ```
+    The result is `{"Ok": {"from_address": ..., "to_address": ..., "slate_id": ..., "amount": ...,
+    "fee": ..., "num_inputs": ..., "num_outputs": ...}}` if the proof is valid.
+    */
+    fn verify_tx_proof(&self, proof: TxProof) -> Result<VerifiedTxProof, Error>;
+
+    /**
+    Networked version of [Owner::retrieve_outbox](struct.Owner.html#method.retrieve_outbox).
+
+    # Json rpc example
+
+    ```text
+        {
+            "jsonrpc": "2.0",
+            "method": "retrieve_outbox",
+            "params": {
+                "token": "d202964900000000d302964900000000d402964900000000d502964900000000"
+            },
+            "id": 1
+        }
+    ```
+    The result is `{"Ok": [entry, ...]}`, each entry holding the `slate_id`, the `dest` address,
+    the serialized slate `payload`, its delivery `status` (`queued`, `posted`, `acknowledged`,
+    `response_received` or `expired`), the number of `attempts` and when the next one is due.
+    */
+    fn retrieve_outbox(&self, token: Token) -> Result<Vec<OutboxEntry>, Error>;
+
+    /**
+    Networked version of [Owner::retrieve_epicbox_status](struct.Owner.html#method.retrieve_epicbox_status).
+
+    # Json rpc example
+
+    ```text
+        {
+            "jsonrpc": "2.0",
+            "method": "retrieve_epicbox_status",
+            "params": {
+                "token": "d202964900000000d302964900000000d402964900000000d502964900000000"
+            },
+            "id": 1
+        }
+    ```
+    The result is `{"Ok": null}` if the listener never ran, otherwise `{"Ok": status}` with its
+    `state` (`connecting`, `listening` or `reconnecting`), the number of `reconnect_attempts`,
+    when the status was `updated`, the `next_attempt` and the `last_error` while reconnecting.
+    */
+    fn retrieve_epicbox_status(&self, token: Token)
+        -> Result<Option<EpicboxListenerStatus>, Error>;
+
     /**
     Networked version of [Owner::set_tor_config](struct.Owner.html#method.set_tor_config).
 
@@ -2252,6 +2815,18 @@ where
         Owner::set_active_account(self, (&token.keychain_mask).as_ref(), label)
     }
 
+    fn contacts(&self, token: Token) -> Result<Vec<Contact>, Error> {
+        Owner::contacts(self, (&token.keychain_mask).as_ref())
+    }
+
+    fn add_contact(&self, token: Token, contact: Contact) -> Result<(), Error> {
+        Owner::add_contact(self, (&token.keychain_mask).as_ref(), contact)
+    }
+
+    fn remove_contact(&self, token: Token, name: String) -> Result<(), Error> {
+        Owner::remove_contact(self, (&token.keychain_mask).as_ref(), &name)
+    }
+
     fn retrieve_outputs(
         &self,
         token: Token,
@@ -2275,6 +2850,43 @@ where
         )
     }
 
+    fn freeze_output(&self, token: Token, commit: String) -> Result<(), Error> {
+        Owner::freeze_output(self, (&token.keychain_mask).as_ref(), &commit)
+    }
+
+    fn unfreeze_output(&self, token: Token, commit: String) -> Result<(), Error> {
+        Owner::unfreeze_output(self, (&token.keychain_mask).as_ref(), &commit)
+    }
+
+    fn set_output_label(
+        &self,
+        token: Token,
+        commit: String,
+        label: Option<String>,
+    ) -> Result<(), Error> {
+        Owner::set_output_label(self, (&token.keychain_mask).as_ref(), &commit, label)
+    }
+
+    fn set_tx_label(
+        &self,
+        token: Token,
+        tx_id: Option<u32>,
+        tx_slate_id: Option<Uuid>,
+        label: Option<String>,
+    ) -> Result<(), Error> {
+        Owner::set_tx_label(
+            self,
+            (&token.keychain_mask).as_ref(),
+            tx_id,
+            tx_slate_id,
+            label,
+        )
+    }
+
+    fn search_labels(&self, token: Token, query: String) -> Result<LabelSearchResult, Error> {
+        Owner::search_labels(self, (&token.keychain_mask).as_ref(), &query)
+    }
+
     fn retrieve_txs(
         &self,
         token: Token,
@@ -2322,6 +2934,14 @@ where
         Ok(VersionedSlate::into_version(slate, version))
     }
 
+    fn estimate_selection_strategies(
+        &self,
+        token: Token,
+        args: InitTxArgs,
+    ) -> Result<Vec<SelectionEstimate>, Error> {
+        Owner::estimate_selection_strategies(self, (&token.keychain_mask).as_ref(), args)
+    }
+
     fn issue_invoice_tx(
         &self,
         token: Token,
@@ -2415,10 +3035,18 @@ where
         )
     }
 
+    fn scan_progress(&self, token: Token) -> Result<Option<ScanProgress>, Error> {
+        Owner::scan_progress(self, (&token.keychain_mask).as_ref())
+    }
+
     fn node_height(&self, token: Token) -> Result<NodeHeightResult, Error> {
         Owner::node_height(self, (&token.keychain_mask).as_ref())
     }
 
+    fn node_cache_stats(&self, token: Token) -> Result<Option<NodeCacheStats>, Error> {
+        Owner::node_cache_stats(self, (&token.keychain_mask).as_ref())
+    }
+
     fn init_secure_api(&self, ecdh_pubkey: ECDHPubkey) -> Result<ECDHPubkey, Error> {
         let secp_inst = static_secp_instance();
         let secp = secp_inst.lock();
@@ -2475,13 +3103,21 @@ where
         mnemonic: Option<String>,
         mnemonic_length: u32,
         password: String,
+        mnemonic_passphrase: Option<String>,
     ) -> Result<(), Error> {
         let n = name.as_ref().map(|s| s.as_str());
         let m = match mnemonic {
             Some(s) => Some(ZeroingString::from(s)),
             None => None,
         };
-        Owner::create_wallet(self, n, m, mnemonic_length, ZeroingString::from(password))
+        Owner::create_wallet(
+            self,
+            n,
+            m,
+            mnemonic_length,
+            ZeroingString::from(password),
+            mnemonic_passphrase.map(ZeroingString::from),
+        )
     }
 
     fn open_wallet(&self, name: Option<String>, password: String) -> Result<Token, Error> {
@@ -2585,6 +3221,31 @@ where
     ) -> Result<(bool, bool), Error> {
         Owner::verify_payment_proof(self, (&token.keychain_mask).as_ref(), &proof)
     }
+
+    fn retrieve_tx_proof(
+        &self,
+        token: Token,
+        tx_id: Option<u32>,
+        tx_slate_id: Option<Uuid>,
+    ) -> Result<TxProof, Error> {
+        Owner::retrieve_tx_proof(self, (&token.keychain_mask).as_ref(), tx_id, tx_slate_id)
+    }
+
+    fn verify_tx_proof(&self, proof: TxProof) -> Result<VerifiedTxProof, Error> {
+        Owner::verify_tx_proof(self, &proof)
+    }
+
+    fn retrieve_outbox(&self, token: Token) -> Result<Vec<OutboxEntry>, Error> {
+        Owner::retrieve_outbox(self, (&token.keychain_mask).as_ref())
+    }
+
+    fn retrieve_epicbox_status(
+        &self,
+        token: Token,
+    ) -> Result<Option<EpicboxListenerStatus>, Error> {
+        Owner::retrieve_epicbox_status(self, (&token.keychain_mask).as_ref())
+    }
+
     fn proof_address_from_onion_v3(&self, address_v3: String) -> Result<PubAddress, Error> {
         let address = Owner::proof_address_from_onion_v3(self, &address_v3)?;
         Ok(PubAddress { address })
@@ -2599,3 +3260,34 @@ where
         Ok(())
     }
 }
+
+/// Trailing parameters added to a method after its release, with the number of
+/// parameters the method took before. They are all optional.
+const TRAILING_OPTIONAL_PARAMS: [(&str, &str, usize); 1] =
+    [("create_wallet", "mnemonic_passphrase", 4)];
+
+/// Fills in trailing optional parameters a request leaves out with `null`, so
+/// clients written before those parameters existed keep working with both
+/// positional and named params.
+pub fn fill_optional_params(request: &mut serde_json::Value) {
+    let method = match request["method"].as_str() {
+        Some(m) => m.to_owned(),
+        None => return,
+    };
+    for (m, name, position) in TRAILING_OPTIONAL_PARAMS.iter() {
+        if *m != method {
+            continue;
+        }
+        match request.get_mut("params") {
+            Some(serde_json::Value::Array(params)) if params.len() == *position => {
+                params.push(serde_json::Value::Null);
+            }
+            Some(serde_json::Value::Object(params)) => {
+                params
+                    .entry(name.to_string())
+                    .or_insert(serde_json::Value::Null);
+            }
+            _ => {}
+        }
+    }
+}
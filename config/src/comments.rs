@@ -66,6 +66,14 @@ fn comments() -> HashMap<String, String> {
 
 ### path of the secret token used by the API to authenticate the calls
 ### comment it to disable basic auth
+"
+        .to_string(),
+    );
+    retval.insert(
+        "owner_api_tokens_path".to_string(),
+        "
+### path of the scoped tokens also accepted by the owner API, managed with
+### the api_tokens command. Only used along with the secret above.
 "
         .to_string(),
     );
@@ -73,6 +81,30 @@ fn comments() -> HashMap<String, String> {
         "check_node_api_http_addr".to_string(),
         "
 ### where the wallet should find a running node
+"
+        .to_string(),
+    );
+    retval.insert(
+        "fallback_node_api_http_addrs".to_string(),
+        "
+### further nodes the wallet switches to, in order, when the node above
+### can't be reached, e.g. [\"http://10.0.0.2:3413\"]
+"
+        .to_string(),
+    );
+    retval.insert(
+        "node_cross_check".to_string(),
+        "
+### confirm the chain tip and kernel lookups with a second node before
+### trusting them. Needs at least one fallback node.
+"
+        .to_string(),
+    );
+    retval.insert(
+        "node_cache".to_string(),
+        "
+### answer repeated kernel and output lookups from a cache until the next
+### block. Set to false to always ask the node.
 "
         .to_string(),
     );
@@ -122,6 +154,124 @@ fn comments() -> HashMap<String, String> {
 ### Unit: Minute. Default value 1440 minutes for one day.
 ### Refer to https://keybase.io/blog/keybase-exploding-messages for detail.
 ### To disable this notification, set it as 0.
+"
+        .to_string(),
+    );
+    retval.insert(
+        "seed_kdf_memory_kib".to_string(),
+        "
+### Argon2id parameters used whenever the wallet seed file is encrypted.
+### Seed files written with weaker parameters are upgraded the next time
+### the wallet is opened. Memory cost in KiB.
+"
+        .to_string(),
+    );
+    retval.insert(
+        "seed_kdf_iterations".to_string(),
+        "
+### Argon2id number of passes over the memory
+"
+        .to_string(),
+    );
+    retval.insert(
+        "seed_kdf_parallelism".to_string(),
+        "
+### Argon2id degree of parallelism
+"
+        .to_string(),
+    );
+    retval.insert(
+        "scan_batch_size".to_string(),
+        "
+### Number of PMMR indices requested from the node at a time when scanning
+### the chain for wallet outputs
+"
+        .to_string(),
+    );
+    retval.insert(
+        "scan_fetch_threads".to_string(),
+        "
+### Number of requests to the node kept in flight while scanning
+"
+        .to_string(),
+    );
+    retval.insert(
+        "scan_rewind_threads".to_string(),
+        "
+### Number of threads checking fetched outputs for ones belonging to this
+### wallet while scanning. 0 uses one thread per CPU core.
+"
+        .to_string(),
+    );
+    retval.insert(
+        "webhook_urls".to_string(),
+        "
+### URLs wallet events (transaction received, confirmed, cancelled and
+### reorged) are posted to as JSON while the updater runs
+"
+        .to_string(),
+    );
+    retval.insert(
+        "webhook_secret_path".to_string(),
+        "
+### location of the secret webhook payloads are signed with. The HMAC-SHA256
+### of <timestamp>.<body> is sent in the X-Epic-Signature header as
+### sha256=<hex>, the timestamp in the X-Epic-Timestamp header. Receivers
+### should reject stale timestamps so a captured request can't be replayed
+"
+        .to_string(),
+    );
+    retval.insert(
+        "webhook_confirmations".to_string(),
+        "
+### number of confirmations after which a transaction is reported confirmed
+"
+        .to_string(),
+    );
+    retval.insert(
+        "webhook_max_attempts".to_string(),
+        "
+### number of attempts at delivering an event, with growing delays in
+### between, before it is dropped
+"
+        .to_string(),
+    );
+    retval.insert(
+        "policy_max_tx_amount".to_string(),
+        "
+### spending policy, enforced for the command line and the owner API alike.
+### amounts are in base units, and limits left out don't apply.
+### largest amount a single transaction may send
+"
+        .to_string(),
+    );
+    retval.insert(
+        "policy_daily_limit".to_string(),
+        "
+### most an account may send in any 24 hours
+"
+        .to_string(),
+    );
+    retval.insert(
+        "policy_weekly_limit".to_string(),
+        "
+### most an account may send in any 7 days
+"
+        .to_string(),
+    );
+    retval.insert(
+        "policy_allowed_destinations".to_string(),
+        "
+### only destinations transactions may be sent to. Transactions without a
+### destination, such as paid invoices or slates saved to a file, are refused.
+"
+        .to_string(),
+    );
+    retval.insert(
+        "policy_min_ttl_blocks".to_string(),
+        "
+### smallest ttl_blocks a transaction may be created with. Transactions
+### without a TTL are refused.
 "
         .to_string(),
     );
@@ -277,6 +427,16 @@ fn comments() -> HashMap<String, String> {
         .to_string(),
     );
 
+    retval.insert(
+        "epicbox_relays".to_string(),
+        "
+### Further epicbox relays to listen on, as domain or domain:port, in order
+### of priority. Slates are posted to them when the recipient's relay is down.
+#epicbox_relays = [\"relay.example.com\", \"relay.example.org:8443\"]
+"
+        .to_string(),
+    );
+
     retval
 }
 
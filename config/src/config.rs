@@ -41,6 +41,8 @@ pub const EPIC_WALLET_DIR: &'static str = "wallet_data";
 pub const API_SECRET_FILE_NAME: &'static str = ".api_secret";
 /// Owner API secret
 pub const OWNER_API_SECRET_FILE_NAME: &'static str = ".owner_api_secret";
+/// Owner API scoped tokens
+pub const OWNER_API_TOKENS_FILE_NAME: &'static str = ".owner_api_tokens";
 
 fn get_epic_path(chain_type: &global::ChainTypes) -> Result<PathBuf, ConfigError> {
 	// Check if epic dir exists
@@ -242,6 +244,10 @@ impl GlobalWalletConfig {
 		secret_path.push(OWNER_API_SECRET_FILE_NAME);
 		self.members.as_mut().unwrap().wallet.api_secret_path =
 			Some(secret_path.to_str().unwrap().to_owned());
+		let mut tokens_path = wallet_home.clone();
+		tokens_path.push(OWNER_API_TOKENS_FILE_NAME);
+		self.members.as_mut().unwrap().wallet.owner_api_tokens_path =
+			Some(tokens_path.to_str().unwrap().to_owned());
 		let mut node_secret_path = wallet_home.clone();
 		node_secret_path.push(API_SECRET_FILE_NAME);
 		self.members.as_mut().unwrap().wallet.node_api_secret_path =
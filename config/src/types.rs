@@ -37,11 +37,20 @@ pub struct WalletConfig {
     pub owner_api_interface: Option<String>,
     /// Location of the secret for basic auth on the Owner API
     pub api_secret_path: Option<String>,
+    /// Location of the scoped tokens also accepted by the Owner API
+    pub owner_api_tokens_path: Option<String>,
     /// Location of the node api secret for basic auth on the Epic API
     pub node_api_secret_path: Option<String>,
     /// The api address of a running server node against which transaction inputs
     /// will be checked during send
     pub check_node_api_http_addr: String,
+    /// Nodes to fail over to when the one at `check_node_api_http_addr`
+    /// can't be reached
+    pub fallback_node_api_http_addrs: Option<Vec<String>>,
+    /// Whether to confirm chain tips and kernels with a second node
+    pub node_cross_check: Option<bool>,
+    /// Whether to cache kernel and output lookups until the next block
+    pub node_cache: Option<bool>,
     /// Whether to include foreign API endpoints on the Owner API
     pub owner_api_include_foreign: Option<bool>,
     /// The directory in which wallet files are stored
@@ -58,6 +67,36 @@ pub struct WalletConfig {
     pub dark_background_color_scheme: Option<bool>,
     /// The exploding lifetime (minutes) for keybase notification on coins received
     pub keybase_notify_ttl: Option<u16>,
+    /// Argon2id memory cost (KiB) of the wallet seed encryption key
+    pub seed_kdf_memory_kib: Option<u32>,
+    /// Argon2id number of passes of the wallet seed encryption key
+    pub seed_kdf_iterations: Option<u32>,
+    /// Argon2id degree of parallelism of the wallet seed encryption key
+    pub seed_kdf_parallelism: Option<u32>,
+    /// Number of PMMR indices requested from the node at a time during a scan
+    pub scan_batch_size: Option<u64>,
+    /// Number of node requests kept in flight during a scan
+    pub scan_fetch_threads: Option<usize>,
+    /// Number of threads rewinding range proofs during a scan, 0 for one per CPU core
+    pub scan_rewind_threads: Option<usize>,
+    /// URLs wallet events are posted to
+    pub webhook_urls: Option<Vec<String>>,
+    /// Location of the secret webhook payloads are signed with
+    pub webhook_secret_path: Option<String>,
+    /// Number of confirmations after which a transaction is reported confirmed
+    pub webhook_confirmations: Option<u64>,
+    /// Number of attempts at delivering an event before giving up on it
+    pub webhook_max_attempts: Option<u32>,
+    /// Largest amount a single transaction may send
+    pub policy_max_tx_amount: Option<u64>,
+    /// Most an account may send in 24 hours
+    pub policy_daily_limit: Option<u64>,
+    /// Most an account may send in 7 days
+    pub policy_weekly_limit: Option<u64>,
+    /// Only destinations transactions may be sent to
+    pub policy_allowed_destinations: Option<Vec<String>>,
+    /// Smallest `ttl_blocks` a transaction may be created with
+    pub policy_min_ttl_blocks: Option<u64>,
 }
 
 impl Default for WalletConfig {
@@ -73,8 +112,12 @@ impl Default for WalletConfig {
             owner_api_listen_port: Some(WalletConfig::default_owner_api_listen_port()),
             owner_api_interface: Some(WalletConfig::default_owner_api_interface()),
             api_secret_path: Some(".owner_api_secret".to_string()),
+            owner_api_tokens_path: Some(".owner_api_tokens".to_string()),
             node_api_secret_path: Some(".api_secret".to_string()),
             check_node_api_http_addr: "http://127.0.0.1:3413".to_string(),
+            fallback_node_api_http_addrs: Some(vec![]),
+            node_cross_check: Some(false),
+            node_cache: Some(true),
             owner_api_include_foreign: Some(false),
             data_file_dir: ".".to_string(),
             no_commit_cache: Some(false),
@@ -82,6 +125,21 @@ impl Default for WalletConfig {
             tls_certificate_key: None,
             dark_background_color_scheme: Some(true),
             keybase_notify_ttl: Some(1440),
+            seed_kdf_memory_kib: Some(SeedKdfConfig::default().memory_kib),
+            seed_kdf_iterations: Some(SeedKdfConfig::default().iterations),
+            seed_kdf_parallelism: Some(SeedKdfConfig::default().parallelism),
+            scan_batch_size: Some(ScanConfig::default().batch_size),
+            scan_fetch_threads: Some(ScanConfig::default().fetch_threads),
+            scan_rewind_threads: Some(ScanConfig::default().rewind_threads),
+            webhook_urls: Some(vec![]),
+            webhook_secret_path: None,
+            webhook_confirmations: Some(WebhookConfig::default().confirmations),
+            webhook_max_attempts: Some(WebhookConfig::default().max_attempts),
+            policy_max_tx_amount: None,
+            policy_daily_limit: None,
+            policy_weekly_limit: None,
+            policy_allowed_destinations: None,
+            policy_min_ttl_blocks: None,
         }
     }
 }
@@ -125,6 +183,171 @@ impl WalletConfig {
             self.owner_api_listen_port()
         )
     }
+
+    /// All node addresses, the preferred node first
+    pub fn node_api_http_addrs(&self) -> Vec<String> {
+        let mut addrs = vec![self.check_node_api_http_addr.clone()];
+        addrs.extend(
+            self.fallback_node_api_http_addrs
+                .clone()
+                .unwrap_or_default(),
+        );
+        addrs
+    }
+
+    /// Seed encryption parameters, defaulting to sensible values if missing.
+    pub fn seed_kdf(&self) -> SeedKdfConfig {
+        let default = SeedKdfConfig::default();
+        SeedKdfConfig {
+            memory_kib: self.seed_kdf_memory_kib.unwrap_or(default.memory_kib),
+            iterations: self.seed_kdf_iterations.unwrap_or(default.iterations),
+            parallelism: self.seed_kdf_parallelism.unwrap_or(default.parallelism),
+        }
+    }
+
+    /// Scan concurrency, defaulting to sensible values if missing.
+    pub fn scan(&self) -> ScanConfig {
+        let default = ScanConfig::default();
+        ScanConfig {
+            batch_size: self.scan_batch_size.unwrap_or(default.batch_size),
+            fetch_threads: self.scan_fetch_threads.unwrap_or(default.fetch_threads),
+            rewind_threads: self.scan_rewind_threads.unwrap_or(default.rewind_threads),
+        }
+    }
+
+    /// Webhook settings, defaulting to sensible values if missing.
+    pub fn webhooks(&self) -> WebhookConfig {
+        let default = WebhookConfig::default();
+        WebhookConfig {
+            urls: self.webhook_urls.clone().unwrap_or(default.urls),
+            secret_path: self.webhook_secret_path.clone(),
+            confirmations: self.webhook_confirmations.unwrap_or(default.confirmations),
+            max_attempts: self.webhook_max_attempts.unwrap_or(default.max_attempts),
+        }
+    }
+
+    /// Spending policy, unrestricted where not set
+    pub fn spend_policy(&self) -> SpendPolicy {
+        SpendPolicy {
+            max_tx_amount: self.policy_max_tx_amount,
+            daily_limit: self.policy_daily_limit,
+            weekly_limit: self.policy_weekly_limit,
+            allowed_destinations: self.policy_allowed_destinations.clone(),
+            min_ttl_blocks: self.policy_min_ttl_blocks,
+        }
+    }
+}
+
+/// Argon2id parameters the wallet seed encryption key is derived with
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SeedKdfConfig {
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    /// Number of passes
+    pub iterations: u32,
+    /// Degree of parallelism
+    pub parallelism: u32,
+}
+
+impl Default for SeedKdfConfig {
+    fn default() -> SeedKdfConfig {
+        SeedKdfConfig {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+/// How a UTXO scan spreads its work. Batches of outputs are fetched from
+/// the node by `fetch_threads` concurrent requests and their range proofs
+/// rewound by `rewind_threads` workers.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ScanConfig {
+    /// Number of PMMR indices per node request
+    pub batch_size: u64,
+    /// Number of node requests in flight
+    pub fetch_threads: usize,
+    /// Number of rewind workers, 0 for one per CPU core
+    pub rewind_threads: usize,
+}
+
+impl ScanConfig {
+    /// Number of rewind workers to start
+    pub fn rewind_workers(&self) -> usize {
+        match self.rewind_threads {
+            0 => std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+            n => n,
+        }
+    }
+}
+
+impl Default for ScanConfig {
+    fn default() -> ScanConfig {
+        ScanConfig {
+            batch_size: 1000,
+            fetch_threads: 4,
+            rewind_threads: 0,
+        }
+    }
+}
+
+/// Where and how wallet events are posted to webhooks
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WebhookConfig {
+    /// URLs each event is posted to, none disables webhooks
+    pub urls: Vec<String>,
+    /// Location of the HMAC-SHA256 secret payloads are signed with
+    pub secret_path: Option<String>,
+    /// Number of confirmations after which a transaction is reported confirmed
+    pub confirmations: u64,
+    /// Number of delivery attempts before an event is dropped
+    pub max_attempts: u32,
+}
+
+impl WebhookConfig {
+    /// Whether any webhook is configured
+    pub fn is_enabled(&self) -> bool {
+        !self.urls.is_empty()
+    }
+}
+
+impl Default for WebhookConfig {
+    fn default() -> WebhookConfig {
+        WebhookConfig {
+            urls: vec![],
+            secret_path: None,
+            confirmations: 10,
+            max_attempts: 20,
+        }
+    }
+}
+
+/// Limits on what the wallet sends, enforced whatever the transaction is
+/// created through. Amounts are in base units; unset limits don't apply.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SpendPolicy {
+    /// Largest amount a single transaction may send
+    pub max_tx_amount: Option<u64>,
+    /// Most an account may send in any 24 hours
+    pub daily_limit: Option<u64>,
+    /// Most an account may send in any 7 days
+    pub weekly_limit: Option<u64>,
+    /// Destinations transactions may be sent to. When set, transactions
+    /// without a destination, such as paid invoices, are refused.
+    pub allowed_destinations: Option<Vec<String>>,
+    /// Smallest `ttl_blocks` a transaction may be created with. When set,
+    /// transactions without a TTL are refused.
+    pub min_ttl_blocks: Option<u64>,
+}
+
+impl SpendPolicy {
+    /// Whether any limit is set
+    pub fn is_enabled(&self) -> bool {
+        self != &SpendPolicy::default()
+    }
 }
 /// Error type wrapping config errors.
 #[derive(Debug, Error)]
@@ -178,6 +401,33 @@ pub struct EpicboxConfig {
     pub epicbox_protocol_unsecure: Option<bool>,
     /// Epicbox address id
     pub epicbox_address_index: Option<u32>,
+    /// Further relays as `domain` or `domain:port`, in order of priority. They
+    /// are listened on as well, and posted to when a relay is down.
+    pub epicbox_relays: Option<Vec<String>>,
+}
+
+impl EpicboxConfig {
+    /// Domain and port of every relay in order of priority, the one of the
+    /// wallet's address first
+    pub fn relays(&self) -> Vec<(String, Option<u16>)> {
+        let mut relays = vec![];
+        if let Some(ref domain) = self.epicbox_domain {
+            relays.push((domain.clone(), self.epicbox_port));
+        }
+        for relay in self.epicbox_relays.iter().flatten() {
+            let relay = match relay.rsplit_once(':') {
+                Some((domain, port)) => match port.parse() {
+                    Ok(port) => (domain.to_owned(), Some(port)),
+                    Err(_) => (relay.clone(), None),
+                },
+                None => (relay.clone(), None),
+            };
+            if !relays.contains(&relay) {
+                relays.push(relay);
+            }
+        }
+        relays
+    }
 }
 
 impl Default for EpicboxConfig {
@@ -187,6 +437,7 @@ impl Default for EpicboxConfig {
             epicbox_port: Some(443),
             epicbox_protocol_unsecure: Some(false),
             epicbox_address_index: Some(0),
+            epicbox_relays: None,
         }
     }
 }
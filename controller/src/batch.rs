@@ -0,0 +1,296 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Batch payouts: the list of recipients read from a CSV or JSON file, and
+//! the progress journal written alongside it. Every state change of a payout
+//! is recorded in the journal before the next step is taken, so an
+//! interrupted batch can be resumed without paying anyone twice.
+
+use crate::core::core::amount_from_hr_string;
+use crate::libwallet::{Error, Slate, SlateVersion, VersionedSlate};
+use serde_derive::{Deserialize, Serialize};
+use serde_json as json;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Methods a batch payout can be dispatched with. Anything requiring user
+/// interaction per slate (file, emoji) makes no sense in a batch.
+pub const BATCH_METHODS: [&str; 4] = ["http", "tor", "keybase", "epicbox"];
+
+/// One line of a batch payout file
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Payout {
+	/// Destination: an url, tor or epicbox address, or an `@contact`
+	pub dest: String,
+	/// Amount with optional fraction, e.g. "12.423"
+	pub amount: String,
+	/// Send method, the batch default is used when missing
+	#[serde(default)]
+	pub method: Option<String>,
+}
+
+/// Progress of a single payout. Each state implies all the previous ones.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum PayoutStatus {
+	/// Nothing done yet
+	Pending,
+	/// Slate created and its inputs locked
+	Locked,
+	/// Slate dispatched to the recipient
+	Sent,
+	/// Recipient's response finalized
+	Finalized,
+	/// Transaction posted to the node
+	Posted,
+}
+
+impl fmt::Display for PayoutStatus {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let s = match self {
+			PayoutStatus::Pending => "Pending",
+			PayoutStatus::Locked => "Locked",
+			PayoutStatus::Sent => "Sent",
+			PayoutStatus::Finalized => "Finalized",
+			PayoutStatus::Posted => "Posted",
+		};
+		write!(f, "{}", s)
+	}
+}
+
+/// Journal record of a single payout
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JournalEntry {
+	/// Destination as given in the payout file
+	pub dest: String,
+	/// Send method
+	pub method: String,
+	/// Amount in nanoepic
+	pub amount: u64,
+	/// How far the payout got
+	pub status: PayoutStatus,
+	/// Id of the slate created for this payout
+	#[serde(default)]
+	pub slate_id: Option<Uuid>,
+	/// Latest known slate for this payout, serialized
+	#[serde(default)]
+	pub slate: Option<String>,
+	/// Last error encountered, if any
+	#[serde(default)]
+	pub error: Option<String>,
+}
+
+impl JournalEntry {
+	/// Latest known slate for this payout
+	pub fn slate(&self) -> Result<Option<Slate>, Error> {
+		match self.slate {
+			Some(ref s) => Ok(Some(Slate::deserialize_upgrade(s)?)),
+			None => Ok(None),
+		}
+	}
+
+	/// Record the payout moved to `status` with the given slate
+	pub fn advance(&mut self, status: PayoutStatus, slate: &Slate) -> Result<(), Error> {
+		let vslate = VersionedSlate::into_version(slate.clone(), SlateVersion::V3);
+		self.slate = Some(json::to_string(&vslate)?);
+		self.slate_id = Some(slate.id);
+		self.status = status;
+		self.error = None;
+		Ok(())
+	}
+}
+
+/// Progress journal of a whole batch
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BatchJournal {
+	/// One entry per payout, in file order
+	pub entries: Vec<JournalEntry>,
+}
+
+impl BatchJournal {
+	/// Fresh journal for the given payouts
+	pub fn new(payouts: &[Payout], default_method: &str) -> Result<BatchJournal, Error> {
+		let mut entries = vec![];
+		for (i, p) in payouts.iter().enumerate() {
+			let method = p
+				.method
+				.clone()
+				.filter(|m| !m.trim().is_empty())
+				.unwrap_or_else(|| default_method.to_owned());
+			if !BATCH_METHODS.contains(&method.as_str()) {
+				return Err(Error::ArgumentError(format!(
+					"Payout {}: unsupported method '{}', expected one of {:?}",
+					i + 1,
+					method,
+					BATCH_METHODS
+				)));
+			}
+			let amount = amount_from_hr_string(p.amount.trim()).map_err(|e| {
+				Error::ArgumentError(format!("Payout {}: invalid amount: {:?}", i + 1, e))
+			})?;
+			if amount == 0 {
+				return Err(Error::ArgumentError(format!(
+					"Payout {}: amount must be greater than zero",
+					i + 1
+				)));
+			}
+			entries.push(JournalEntry {
+				dest: p.dest.trim().to_owned(),
+				method,
+				amount,
+				status: PayoutStatus::Pending,
+				slate_id: None,
+				slate: None,
+				error: None,
+			});
+		}
+		Ok(BatchJournal { entries })
+	}
+
+	/// Load the journal at `path`, or start a new one if there is none yet.
+	/// An existing journal must describe exactly the same payouts.
+	pub fn load_or_create(
+		path: &Path,
+		payouts: &[Payout],
+		default_method: &str,
+	) -> Result<BatchJournal, Error> {
+		let fresh = BatchJournal::new(payouts, default_method)?;
+		if !path.exists() {
+			return Ok(fresh);
+		}
+		let file = File::open(path)?;
+		let journal: BatchJournal = json::from_reader(BufReader::new(file))
+			.map_err(|e| Error::GenericError(format!("Invalid batch journal: {}", e)))?;
+		let matches = journal.entries.len() == fresh.entries.len()
+			&& journal
+				.entries
+				.iter()
+				.zip(fresh.entries.iter())
+				.all(|(a, b)| a.dest == b.dest && a.amount == b.amount && a.method == b.method);
+		if !matches {
+			return Err(Error::ArgumentError(format!(
+				"Batch journal {} does not match the payout file",
+				path.display()
+			)));
+		}
+		Ok(journal)
+	}
+
+	/// Persist the journal. Written to a temporary file first and renamed, so
+	/// a crash never leaves a truncated journal behind.
+	pub fn save(&self, path: &Path) -> Result<(), Error> {
+		let tmp = path.with_extension("tmp");
+		fs::write(&tmp, json::to_string_pretty(self)?)?;
+		fs::rename(&tmp, path)?;
+		Ok(())
+	}
+
+	/// Whether every payout has been posted
+	pub fn is_complete(&self) -> bool {
+		self.entries
+			.iter()
+			.all(|e| e.status == PayoutStatus::Posted)
+	}
+}
+
+/// Read the payouts of a batch file in CSV (with a `dest,amount,method`
+/// header) or JSON (an array of objects with the same fields) format
+pub fn read_payouts(path: &Path, format: &str) -> Result<Vec<Payout>, Error> {
+	let file = File::open(path)?;
+	let payouts: Vec<Payout> = match format {
+		"json" => json::from_reader(BufReader::new(file))
+			.map_err(|e| Error::ArgumentError(format!("JSON parse error: {}", e)))?,
+		"csv" => {
+			let mut rdr = csv::ReaderBuilder::new()
+				.trim(csv::Trim::All)
+				.flexible(true)
+				.from_reader(BufReader::new(file));
+			let mut payouts = vec![];
+			for result in rdr.deserialize() {
+				let p: Payout =
+					result.map_err(|e| Error::ArgumentError(format!("CSV parse error: {}", e)))?;
+				payouts.push(p);
+			}
+			payouts
+		}
+		_ => {
+			return Err(Error::ArgumentError(
+				"Invalid format, must be 'json' or 'csv'".to_string(),
+			))
+		}
+	};
+	if payouts.is_empty() {
+		return Err(Error::ArgumentError(format!(
+			"No payouts found in {}",
+			path.display()
+		)));
+	}
+	Ok(payouts)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use std::env;
+
+	fn payouts() -> Vec<Payout> {
+		vec![
+			Payout {
+				dest: "http://127.0.0.1:3415".to_owned(),
+				amount: "1.5".to_owned(),
+				method: Some("http".to_owned()),
+			},
+			Payout {
+				dest: "@alice".to_owned(),
+				amount: "2".to_owned(),
+				method: None,
+			},
+		]
+	}
+
+	#[test]
+	fn journal_defaults_and_validates() {
+		let j = BatchJournal::new(&payouts(), "epicbox").unwrap();
+		assert_eq!(j.entries[0].method, "http");
+		assert_eq!(j.entries[0].amount, amount_from_hr_string("1.5").unwrap());
+		assert_eq!(j.entries[1].method, "epicbox");
+		assert!(!j.is_complete());
+
+		let mut bad = payouts();
+		bad[0].method = Some("file".to_owned());
+		assert!(BatchJournal::new(&bad, "http").is_err());
+		let mut bad = payouts();
+		bad[1].amount = "0".to_owned();
+		assert!(BatchJournal::new(&bad, "http").is_err());
+	}
+
+	#[test]
+	fn journal_resumes_only_matching_batch() {
+		let path = env::temp_dir().join(format!("batch_journal_{}.json", Uuid::new_v4()));
+		let mut j = BatchJournal::new(&payouts(), "epicbox").unwrap();
+		j.entries[0].status = PayoutStatus::Posted;
+		j.save(&path).unwrap();
+
+		let resumed = BatchJournal::load_or_create(&path, &payouts(), "epicbox").unwrap();
+		assert_eq!(resumed.entries[0].status, PayoutStatus::Posted);
+		assert_eq!(resumed.entries[1].status, PayoutStatus::Pending);
+
+		let mut changed = payouts();
+		changed[1].amount = "3".to_owned();
+		assert!(BatchJournal::load_or_create(&path, &changed, "epicbox").is_err());
+		let _ = fs::remove_file(&path);
+	}
+}
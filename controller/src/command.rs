@@ -169,24 +169,29 @@ use crate::api::TLSConfig;
 use crate::config::{EpicboxConfig, TorConfig, WalletConfig, WALLET_CONFIG_FILE_NAME};
 use crate::core::{core, global};
 
+use crate::batch::{self, BatchJournal, PayoutStatus};
 use crate::impls::{
-    create_sender, EpicboxChannel, EpicboxListenChannel, KeybaseAllChannels, SlateGetter as _,
-    SlateReceiver as _,
+    create_sender, find_contact, resolve_contact, EpicboxChannel, EpicboxListenChannel,
+    KeybaseAllChannels, SlateGetter as _, SlateReceiver as _,
 };
 use crate::impls::{EmojiSlate, PathToSlate, SlatePutter};
 use crate::keychain;
+use crate::libwallet::api_impl::owner as owner_impl;
 use crate::libwallet::{
-    address, Error, InitTxArgs, IssueInvoiceTxArgs, NodeClient, PaymentProof, WalletInst,
+    address, armor, Address, Contact, EpicboxAddress, Error, InitTxArgs, IssueInvoiceTxArgs,
+    NodeClient, PaymentProof, SelectionStrategy, Slate, TxLogEntryType, TxProof, WalletInst,
     WalletLCProvider,
 };
+use crate::tokens::{ApiScope, ApiTokenStore};
 
-use crate::util::secp::key::SecretKey;
+use crate::util::secp::key::{PublicKey, SecretKey};
 use crate::util::{to_hex, Mutex, ZeroingString};
 use crate::{controller, display};
 
 use serde_json as json;
 use std::fs::File;
 use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::thread;
 
@@ -194,12 +199,29 @@ use std::sync::atomic::AtomicBool;
 use std::time::Duration;
 use uuid::Uuid;
 
-fn show_recovery_phrase(phrase: ZeroingString) {
+fn show_recovery_phrase(phrase: ZeroingString, passphrase: Option<bool>) {
     println!("Your recovery phrase is:");
     println!();
     println!("{}", &*phrase);
     println!();
     println!("Please back-up these words in a non-digital format.");
+    match passphrase {
+        Some(true) => {
+            println!();
+            println!(
+                "WARNING: This wallet is protected by a mnemonic passphrase. The passphrase is \
+                 not stored anywhere and is NOT part of the words above. Without it the wallet \
+                 can't be recovered."
+            );
+        }
+        Some(false) => {}
+        None => {
+            println!(
+                "If this wallet was created with a mnemonic passphrase, it is required along \
+                 with these words to recover it."
+            );
+        }
+    }
 }
 
 /// Arguments common to all wallet commands
@@ -221,6 +243,8 @@ pub struct InitArgs {
     pub password: ZeroingString,
     pub config: WalletConfig,
     pub recovery_phrase: Option<ZeroingString>,
+    /// Optional BIP39 passphrase mixed into the keychain derivation
+    pub mnemonic_passphrase: Option<ZeroingString>,
     pub restore: bool,
 }
 
@@ -250,10 +274,12 @@ where
         args.list_length,
         args.password.clone(),
         false,
+        args.mnemonic_passphrase.clone(),
     )?;
 
     let m = p.get_mnemonic(None, args.password)?;
-    show_recovery_phrase(m);
+    let has_passphrase = args.mnemonic_passphrase.map_or(false, |p| !p.is_empty());
+    show_recovery_phrase(m, Some(has_passphrase));
     Ok(())
 }
 
@@ -274,7 +300,7 @@ where
     let mut w_lock = wallet.lock();
     let p = w_lock.lc_provider()?;
     let m = p.get_mnemonic(None, args.passphrase)?;
-    show_recovery_phrase(m);
+    show_recovery_phrase(m, None);
     Ok(())
 }
 
@@ -312,32 +338,16 @@ where
             config.clone(),
             is_node_synced.clone(),
         ),
-        "epicbox" => {
-            let mut reconnections = 0;
-            loop {
-                let listener = EpicboxListenChannel::new()?.listen(
-                    wallet.clone(),
-                    keychain_mask.clone(),
-                    epicbox_config.clone(),
-                    &mut reconnections,
-                    is_node_synced.clone(),
-                    tor_config.clone(),
-                );
-                info!("Reconnect to epicbox");
-                match listener {
-                    Err(e) => {
-                        error!("Error in listener loop ({})", e);
-                    }
-                    Ok(_) => (),
-                }
-                if reconnections >= 5 {
-                    break;
-                }
-                let duration = std::time::Duration::from_secs(20);
-                std::thread::sleep(duration);
-            }
-            return Err(Error::EpicboxReconnectLimit);
-        }
+        // reconnects on its own, for as long as the process runs
+        "epicbox" => EpicboxListenChannel::new()?
+            .supervise(
+                wallet.clone(),
+                keychain_mask.clone(),
+                epicbox_config.clone(),
+                is_node_synced.clone(),
+                tor_config.clone(),
+            )
+            .map_err(|e| e.into()),
         method => {
             return Err(Error::ArgumentError(format!(
                 "No listener for method {}",
@@ -376,6 +386,7 @@ where
         km,
         config.owner_api_listen_addr().as_str(),
         g_args.api_secret.clone(),
+        config.owner_api_tokens_path.clone(),
         g_args.tls_conf.clone(),
         config.owner_api_include_foreign.clone(),
         Some(tor_config.clone()),
@@ -443,21 +454,116 @@ where
     Ok(())
 }
 
+/// Contacts command args
+pub enum ContactsArgs {
+    List,
+    Add(Contact),
+    Remove(String),
+}
+
+pub fn contacts<L, C, K>(
+    wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+    keychain_mask: Option<&SecretKey>,
+    args: ContactsArgs,
+    is_node_synced: Arc<AtomicBool>,
+) -> Result<(), Error>
+where
+    L: WalletLCProvider<'static, C, K> + 'static,
+    C: NodeClient + 'static,
+    K: keychain::Keychain + 'static,
+{
+    controller::owner_single_use(
+        wallet,
+        keychain_mask,
+        |api, m| {
+            match args {
+                ContactsArgs::List => {
+                    let contacts = api.contacts(m)?;
+                    display::contacts(contacts);
+                }
+                ContactsArgs::Add(contact) => {
+                    let name = contact.name.clone();
+                    api.add_contact(m, contact)?;
+                    info!("Contact '{}' added", name);
+                }
+                ContactsArgs::Remove(name) => {
+                    api.remove_contact(m, &name)?;
+                    info!("Contact '{}' removed", name);
+                }
+            }
+            Ok(())
+        },
+        is_node_synced,
+    )?;
+    Ok(())
+}
+
+/// API tokens command args
+pub enum ApiTokensArgs {
+    List,
+    Issue {
+        name: String,
+        scope: ApiScope,
+        daily_limit: Option<u64>,
+    },
+    Revoke(String),
+}
+
+/// Manage the scoped tokens accepted by the owner API. Doesn't need the
+/// wallet to be open, a running owner API picks up changes right away.
+pub fn api_tokens(config: &WalletConfig, args: ApiTokensArgs) -> Result<(), Error> {
+    let path = match config.owner_api_tokens_path {
+        Some(ref p) => PathBuf::from(p),
+        None => {
+            return Err(Error::ArgumentError(
+                "No owner_api_tokens_path set in the wallet configuration".to_owned(),
+            ))
+        }
+    };
+    let mut store = ApiTokenStore::load(&path)?;
+    match args {
+        ApiTokensArgs::List => display::api_tokens(&store.tokens),
+        ApiTokensArgs::Issue {
+            name,
+            scope,
+            daily_limit,
+        } => {
+            let token = store.issue(&name, scope, daily_limit)?;
+            store.save(&path)?;
+            println!("Token '{}' issued with scope '{}':\n", name, scope);
+            println!("{}\n", token);
+            println!("Keep it safe, it can't be displayed again.");
+            if config.api_secret_path.is_none() {
+                warn!("No api_secret_path is set, the owner API accepts any request");
+            }
+        }
+        ApiTokensArgs::Revoke(name) => {
+            store.revoke(&name)?;
+            store.save(&path)?;
+            info!("Token '{}' revoked", name);
+        }
+    }
+    Ok(())
+}
+
 /// Arguments for the send command
 pub struct SendArgs {
     pub amount: u64,
     pub message: Option<String>,
     pub minimum_confirmations: u64,
-    pub selection_strategy: String,
+    pub selection_strategy: SelectionStrategy,
     pub estimate_selection_strategies: bool,
     pub method: String,
     pub dest: String,
     pub change_outputs: usize,
     pub fluff: bool,
     pub max_outputs: usize,
+    pub inputs: Option<Vec<String>>,
     pub target_slate_version: Option<u16>,
     pub payment_proof_address: Option<String>,
     pub ttl_blocks: Option<u64>,
+    pub lock_height: Option<u64>,
+    pub lock_after_blocks: Option<u64>,
 }
 
 pub fn send<L, C, K>(
@@ -480,29 +586,35 @@ where
         keychain_mask,
         |api, m| {
             if args.estimate_selection_strategies {
-                let strategies = vec!["smallest", "all"]
-                    .into_iter()
-                    .map(|strategy| {
-                        let init_args = InitTxArgs {
-                            src_acct_name: None,
-                            amount: args.amount,
-                            minimum_confirmations: args.minimum_confirmations,
-                            max_outputs: args.max_outputs as u32,
-                            num_change_outputs: args.change_outputs as u32,
-                            selection_strategy_is_use_all: strategy == "all",
-                            estimate_only: Some(true),
-                            ..Default::default()
-                        };
-                        let slate = api
-                            .init_send_tx(m, init_args, is_node_synced.clone())
-                            .unwrap();
-                        (strategy, slate.amount, slate.fee)
-                    })
-                    .collect();
-                display::estimate(args.amount, strategies, dark_scheme);
+                let init_args = InitTxArgs {
+                    src_acct_name: None,
+                    amount: args.amount,
+                    minimum_confirmations: args.minimum_confirmations,
+                    max_outputs: args.max_outputs as u32,
+                    num_change_outputs: args.change_outputs as u32,
+                    ..Default::default()
+                };
+                let estimates = api.estimate_selection_strategies(m, init_args)?;
+                display::estimate(args.amount, estimates, dark_scheme);
             } else {
-                let payment_proof_recipient_address = match args.payment_proof_address {
-                    Some(ref p) => Some(address::ed25519_parse_pubkey(p)?),
+                // resolve address book destinations, defaulting to the contact's
+                // payment proof address
+                let contacts = api.contacts(m)?;
+                let contact = find_contact(&args.dest, &contacts)?;
+                let (method, dest) = match args.method.as_str() {
+                    // armored slates are encrypted to the contact's address
+                    "armor" => (
+                        args.method.clone(),
+                        contact.map_or(args.dest.clone(), |c| c.destination.clone()),
+                    ),
+                    _ => resolve_contact(&args.method, &args.dest, &contacts)?,
+                };
+                let payment_proof_address = match args.payment_proof_address {
+                    Some(ref p) => Some(p),
+                    None => contact.and_then(|c| c.proof_address.as_ref()),
+                };
+                let payment_proof_recipient_address = match payment_proof_address {
+                    Some(p) => Some(address::ed25519_parse_pubkey(p)?),
                     None => None,
                 };
                 let init_args = InitTxArgs {
@@ -511,15 +623,18 @@ where
                     minimum_confirmations: args.minimum_confirmations,
                     max_outputs: args.max_outputs as u32,
                     num_change_outputs: args.change_outputs as u32,
-                    selection_strategy_is_use_all: args.selection_strategy == "all",
+                    selection_strategy: Some(args.selection_strategy),
+                    inputs: args.inputs.clone(),
                     message: args.message.clone(),
                     target_slate_version: args.target_slate_version,
                     payment_proof_recipient_address,
                     ttl_blocks: args.ttl_blocks,
+                    lock_height: args.lock_height,
+                    lock_after_blocks: args.lock_after_blocks,
                     send_args: None,
                     ..Default::default()
                 };
-                let result = api.init_send_tx(m, init_args, is_node_synced.clone());
+                let result = api.init_send_tx_to(m, init_args, &dest);
                 let mut slate = match result {
                     Ok(s) => {
                         info!(
@@ -536,12 +651,23 @@ where
                     }
                 };
 
-                match args.method.as_str() {
+                match method.as_str() {
                     "emoji" => {
                         println!("{}", EmojiSlate().encode(&slate));
                         api.tx_lock_outputs(m, &slate, 0, Some(args.dest))?;
                         return Ok(());
                     }
+                    "armor" => {
+                        let recipient = match dest.as_str() {
+                            "" => None,
+                            d => Some(EpicboxAddress::from_str(d)?.public_key()?),
+                        };
+                        let secret_key = armor_secret_key(wallet.clone(), m)?;
+                        let encryption = recipient.as_ref().map(|r| (&secret_key, r));
+                        println!("{}", armor::armor_slate(&slate, encryption)?);
+                        api.tx_lock_outputs(m, &slate, 0, Some(args.dest))?;
+                        return Ok(());
+                    }
                     "file" => {
                         PathToSlate((&args.dest).into()).put_tx(&slate)?;
                         api.tx_lock_outputs(m, &slate, 0, Some(args.dest))?;
@@ -564,9 +690,8 @@ where
                         })?;
                     }
                     "epicbox" => {
-                        let epicbox_channel =
-                            Box::new(EpicboxChannel::new(&args.dest, epicbox_config))
-                                .expect("error starting epicbox");
+                        let epicbox_channel = Box::new(EpicboxChannel::new(&dest, epicbox_config))
+                            .expect("error starting epicbox");
 
                         let km = match keychain_mask.as_ref() {
                             None => None,
@@ -588,7 +713,8 @@ where
                         return Ok(());
                     }
                     method => {
-                        let sender = create_sender(method, &args.dest, is_node_synced.clone())?;
+                        let sender =
+                            create_sender(method, &dest, &contacts, is_node_synced.clone())?;
 
                         slate = sender.send_tx(&slate)?;
                         api.tx_lock_outputs(m, &slate, 0, Some(args.dest))?;
@@ -625,6 +751,14 @@ where
 						);
                         return Ok(());
                     }
+                    Err(Error::TransactionLocked(h, tip)) => {
+                        warn!(
+                            "Transaction with slate_id {} is locked until height {} (chain at {}). \
+                             Use the repost command once that height is reached.",
+                            slate.id, h, tip
+                        );
+                        return Ok(());
+                    }
                     Err(e) => {
                         error!("Tx not sent: {}", e);
                         return Err(e);
@@ -638,6 +772,387 @@ where
     Ok(())
 }
 
+/// Batch send command arguments
+pub struct BatchSendArgs {
+    pub input: String,
+    pub format: String, // "json" or "csv"
+    pub journal: Option<String>,
+    pub method: String,
+    pub message: Option<String>,
+    pub minimum_confirmations: u64,
+    pub selection_strategy: SelectionStrategy,
+    pub change_outputs: usize,
+    pub max_outputs: usize,
+    pub fluff: bool,
+    pub concurrency: usize,
+}
+
+/// Dispatch a single batch payout slate, returning the recipient's response
+fn dispatch_payout<L, C, K>(
+    wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+    keychain_mask: Option<SecretKey>,
+    method: &str,
+    dest: &str,
+    slate: &Slate,
+    contacts: &[Contact],
+    tor_config: Option<TorConfig>,
+    epicbox_config: Option<EpicboxConfig>,
+    is_node_synced: Arc<AtomicBool>,
+) -> Result<Slate, Error>
+where
+    L: WalletLCProvider<'static, C, K> + 'static,
+    C: NodeClient + 'static,
+    K: keychain::Keychain + 'static,
+{
+    match method {
+        "epicbox" => {
+            let tor_conf = tor_config.ok_or_else(|| {
+                Error::ArgumentError("TorConfig is required for epicbox send".to_string())
+            })?;
+            EpicboxChannel::new(&dest.to_owned(), epicbox_config)?.send(
+                wallet,
+                keychain_mask,
+                slate,
+                is_node_synced,
+                tor_conf,
+            )
+        }
+        method => create_sender(method, dest, contacts, is_node_synced)?.send_tx(slate),
+    }
+}
+
+/// Pay many recipients in one go. Inputs for all payouts are selected and
+/// locked up front, then the slates are dispatched concurrently. Progress is
+/// kept in a journal next to the input file, running the same command again
+/// resumes an interrupted batch where it left off.
+pub fn batch_send<L, C, K>(
+    wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+    keychain_mask: Option<&SecretKey>,
+    tor_config: Option<TorConfig>,
+    epicbox_config: Option<EpicboxConfig>,
+    args: BatchSendArgs,
+    dark_scheme: bool,
+    is_node_synced: Arc<AtomicBool>,
+) -> Result<(), Error>
+where
+    L: WalletLCProvider<'static, C, K> + 'static,
+    C: NodeClient + 'static,
+    K: keychain::Keychain + 'static,
+{
+    let journal_path = match args.journal {
+        Some(ref j) => PathBuf::from(j),
+        None => PathBuf::from(format!("{}.journal", args.input)),
+    };
+    let payouts = batch::read_payouts(Path::new(&args.input), &args.format)?;
+    let mut journal = BatchJournal::load_or_create(&journal_path, &payouts, &args.method)?;
+    let km = keychain_mask.map(|m| m.to_owned());
+
+    controller::owner_single_use(
+        wallet.clone(),
+        keychain_mask,
+        |api, m| {
+            // resolve every recipient first, so a typo in the file fails the
+            // batch before anything gets locked
+            let contacts = api.contacts(m)?;
+            let mut targets = vec![];
+            for e in &journal.entries {
+                let contact = find_contact(&e.dest, &contacts)?;
+                let (method, dest) = resolve_contact(&e.method, &e.dest, &contacts)?;
+                let proof_address = match contact.and_then(|c| c.proof_address.as_ref()) {
+                    Some(p) => Some(address::ed25519_parse_pubkey(p)?),
+                    None => None,
+                };
+                targets.push((method, dest, proof_address));
+            }
+
+            // refresh from the node, then reconcile a resumed journal with
+            // the wallet's transaction log
+            api.retrieve_summary_info(m, true, args.minimum_confirmations)?;
+            for e in journal.entries.iter_mut() {
+                e.error = None;
+                let slate_id = match e.slate_id {
+                    Some(id) if e.status != PayoutStatus::Posted => id,
+                    _ => continue,
+                };
+                let txs = api
+                    .retrieve_txs(m, false, None, Some(slate_id), None, None, None)?
+                    .txs;
+                match txs.first() {
+                    Some(tx) if tx.confirmed => e.status = PayoutStatus::Posted,
+                    Some(tx) if tx.tx_type == TxLogEntryType::TxSentCancelled => {
+                        // never finalized, so nothing can have been paid:
+                        // plan the payout again from scratch
+                        if e.status < PayoutStatus::Finalized {
+                            e.status = PayoutStatus::Pending;
+                            e.slate_id = None;
+                            e.slate = None;
+                        } else {
+                            e.error = Some(format!("Transaction {} was cancelled", slate_id));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            journal.save(&journal_path)?;
+
+            // select the inputs of every new payout before locking anything,
+            // so a wallet short of outputs for the whole batch fails here and
+            // not halfway, while the change of earlier payouts is unconfirmed
+            let pending: Vec<usize> = (0..journal.entries.len())
+                .filter(|i| journal.entries[*i].status == PayoutStatus::Pending)
+                .collect();
+            let amounts: Vec<u64> = pending.iter().map(|i| journal.entries[*i].amount).collect();
+            let plan_args = InitTxArgs {
+                minimum_confirmations: args.minimum_confirmations,
+                max_outputs: args.max_outputs as u32,
+                num_change_outputs: args.change_outputs as u32,
+                selection_strategy: Some(args.selection_strategy),
+                ..Default::default()
+            };
+            let plan = api.plan_inputs(m, plan_args, &amounts)?;
+
+            // create and lock one slate per payout, spending the planned
+            // inputs. Should one still fail, e.g. refused by the spending
+            // policy, the locks taken so far are released.
+            let mut created = vec![];
+            let mut failure = None;
+            for (i, inputs) in pending.into_iter().zip(plan) {
+                let init_args = InitTxArgs {
+                    src_acct_name: None,
+                    amount: journal.entries[i].amount,
+                    minimum_confirmations: args.minimum_confirmations,
+                    max_outputs: args.max_outputs as u32,
+                    num_change_outputs: args.change_outputs as u32,
+                    selection_strategy: Some(args.selection_strategy),
+                    inputs: Some(inputs),
+                    message: args.message.clone(),
+                    payment_proof_recipient_address: targets[i].2.clone(),
+                    ..Default::default()
+                };
+                let dest = journal.entries[i].dest.clone();
+                let result = api
+                    .init_send_tx_to(m, init_args, &targets[i].1)
+                    .and_then(|slate| {
+                        api.tx_lock_outputs(m, &slate, 0, Some(dest))?;
+                        Ok(slate)
+                    });
+                match result {
+                    Ok(slate) => {
+                        journal.entries[i].advance(PayoutStatus::Locked, &slate)?;
+                        created.push(i);
+                    }
+                    Err(e) => {
+                        // later payouts would most likely fail the same way
+                        failure = Some((i, e));
+                        break;
+                    }
+                }
+                journal.save(&journal_path)?;
+            }
+
+            // the new payouts are created all or nothing, so a batch that
+            // doesn't fit once fees are added doesn't leave funds locked
+            if let Some((i, err)) = failure {
+                error!("Payout {} not created: {}", i + 1, err);
+                journal.entries[i].error = Some(err.to_string());
+                for j in created {
+                    let e = &mut journal.entries[j];
+                    match api.cancel_tx(m, None, e.slate_id) {
+                        Ok(_) => {
+                            e.status = PayoutStatus::Pending;
+                            e.slate_id = None;
+                            e.slate = None;
+                            e.error = Some(format!("Released, payout {} failed", i + 1));
+                        }
+                        Err(err) => {
+                            error!("Payout {} could not be released: {}", j + 1, err);
+                            e.error = Some(err.to_string());
+                        }
+                    }
+                }
+                journal.save(&journal_path)?;
+            }
+
+            // dispatch the locked slates, `concurrency` recipients at a time
+            let mut to_send = vec![];
+            for (i, e) in journal.entries.iter().enumerate() {
+                if e.status == PayoutStatus::Locked {
+                    if let Some(slate) = e.slate()? {
+                        to_send.push((i, slate));
+                    }
+                }
+            }
+            for chunk in to_send.chunks(args.concurrency.max(1)) {
+                let results: Vec<(usize, Result<Slate, Error>)> = thread::scope(|s| {
+                    let handles: Vec<_> = chunk
+                        .iter()
+                        .map(|(i, slate)| {
+                            let (method, dest, _) = &targets[*i];
+                            let wallet = wallet.clone();
+                            let km = km.clone();
+                            let contacts = &contacts;
+                            let tor_config = tor_config.clone();
+                            let epicbox_config = epicbox_config.clone();
+                            let is_node_synced = is_node_synced.clone();
+                            s.spawn(move || {
+                                dispatch_payout(
+                                    wallet,
+                                    km,
+                                    method,
+                                    dest,
+                                    slate,
+                                    contacts,
+                                    tor_config,
+                                    epicbox_config,
+                                    is_node_synced,
+                                )
+                            })
+                        })
+                        .collect();
+                    handles
+                        .into_iter()
+                        .zip(chunk.iter())
+                        .map(|(h, (i, _))| {
+                            let res = h.join().unwrap_or_else(|_| {
+                                Err(Error::GenericError("Payout dispatch panicked".to_string()))
+                            });
+                            (*i, res)
+                        })
+                        .collect()
+                });
+                for (i, res) in results {
+                    let e = &mut journal.entries[i];
+                    match res {
+                        Ok(slate) => e.advance(PayoutStatus::Sent, &slate)?,
+                        Err(err) => {
+                            error!("Payout {} to {} not sent: {}", i + 1, e.dest, err);
+                            e.error = Some(err.to_string());
+                        }
+                    }
+                }
+                journal.save(&journal_path)?;
+            }
+
+            // finalize and post what came back. Epicbox responses arrive
+            // asynchronously and are finalized by the epicbox listener.
+            for i in 0..journal.entries.len() {
+                let e = &mut journal.entries[i];
+                if targets[i].0 == "epicbox" || e.error.is_some() {
+                    continue;
+                }
+                if e.status == PayoutStatus::Sent {
+                    let slate = match e.slate()? {
+                        Some(s) => s,
+                        None => continue,
+                    };
+                    let result = api
+                        .verify_slate_messages(m, &slate)
+                        .and_then(|_| api.finalize_tx(m, &slate));
+                    match result {
+                        Ok(slate) => e.advance(PayoutStatus::Finalized, &slate)?,
+                        Err(err) => {
+                            error!("Payout {} to {} not finalized: {}", i + 1, e.dest, err);
+                            e.error = Some(err.to_string());
+                        }
+                    }
+                    journal.save(&journal_path)?;
+                }
+                let e = &mut journal.entries[i];
+                if e.status == PayoutStatus::Finalized {
+                    let slate = match e.slate()? {
+                        Some(s) => s,
+                        None => continue,
+                    };
+                    match api.post_tx(m, &slate.tx, args.fluff) {
+                        Ok(_) => e.advance(PayoutStatus::Posted, &slate)?,
+                        Err(err) => {
+                            error!("Payout {} to {} not posted: {}", i + 1, e.dest, err);
+                            e.error = Some(err.to_string());
+                        }
+                    }
+                    journal.save(&journal_path)?;
+                }
+            }
+            Ok(())
+        },
+        is_node_synced.clone(),
+    )?;
+
+    display::batch_payouts(&journal.entries, dark_scheme);
+    let failed = journal.entries.iter().filter(|e| e.error.is_some()).count();
+    if failed > 0 {
+        return Err(Error::GenericError(format!(
+            "{} of {} payouts failed, run the command again to resume (journal: {})",
+            failed,
+            journal.entries.len(),
+            journal_path.display()
+        )));
+    }
+    if journal.is_complete() {
+        warn!("All {} payouts posted", journal.entries.len());
+    } else {
+        warn!("Epicbox payouts will complete once the recipients respond, run the command again to check on them");
+    }
+    Ok(())
+}
+
+/// How a slate handed to `receive` or `finalize` was encoded
+enum SlateEncoding {
+    Emoji,
+    File,
+    /// Armored, with the sender's key if the slate was encrypted
+    Armored(Option<PublicKey>),
+}
+
+/// The wallet's epicbox key, armored slates are encrypted with it
+fn armor_secret_key<L, C, K>(
+    wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+    keychain_mask: Option<&SecretKey>,
+) -> Result<SecretKey, Error>
+where
+    L: WalletLCProvider<'static, C, K>,
+    C: NodeClient + 'static,
+    K: keychain::Keychain + 'static,
+{
+    wallet_lock!(wallet, w);
+    let parent_key_id = w.parent_key_id();
+    let k = w.keychain(keychain_mask)?;
+    address::address_from_derivation_path(&k, &parent_key_id, 0)
+}
+
+/// Read a slate from an emoji string or a file. Armored slates are detected
+/// both in the file and pasted directly as the input.
+fn read_slate<L, C, K>(
+    wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+    keychain_mask: Option<&SecretKey>,
+    method: &str,
+    input: &str,
+) -> Result<(Slate, SlateEncoding), Error>
+where
+    L: WalletLCProvider<'static, C, K>,
+    C: NodeClient + 'static,
+    K: keychain::Keychain + 'static,
+{
+    if method == "emoji" {
+        return Ok((EmojiSlate().decode(input)?, SlateEncoding::Emoji));
+    }
+    let armored = if armor::is_armored(input) {
+        Some(input.to_owned())
+    } else {
+        std::fs::read_to_string(input)
+            .ok()
+            .filter(|c| armor::is_armored(c))
+    };
+    match armored {
+        Some(text) => {
+            let secret_key = armor_secret_key(wallet, keychain_mask)?;
+            let (slate, sender) = armor::unarmor_slate(&text, Some(&secret_key))?;
+            Ok((slate, SlateEncoding::Armored(sender)))
+        }
+        None => Ok((PathToSlate(input.into()).get_tx()?, SlateEncoding::File)),
+    }
+}
+
 /// Receive command argument
 pub struct ReceiveArgs {
     pub input: String,
@@ -656,19 +1171,14 @@ where
     C: NodeClient + 'static,
     K: keychain::Keychain + 'static,
 {
-    let method = args.method.as_str();
-    let mut slate;
-    if method == "emoji" {
-        slate = EmojiSlate().decode(&args.input.as_str())?;
-    } else {
-        slate = PathToSlate((&args.input).into()).get_tx()?;
-    }
+    let (mut slate, encoding) =
+        read_slate(wallet.clone(), keychain_mask, &args.method, &args.input)?;
 
     let km = match keychain_mask.as_ref() {
         None => None,
         Some(&m) => Some(m.to_owned()),
     };
-    controller::foreign_single_use(wallet, km, |api| {
+    controller::foreign_single_use(wallet.clone(), km, |api| {
         if let Err(e) = api.verify_slate_messages(&slate) {
             error!("Error validating participant messages: {}", e);
             return Err(e);
@@ -676,15 +1186,32 @@ where
         slate = api.receive_tx(&slate, Some(&g_args.account), args.message.clone(), None)?;
         Ok(())
     })?;
-    if method == "emoji" {
-        println!("\n\nThis is your response emoji string. Please send it back to the payer to finalize the transaction:\n\n{}", EmojiSlate().encode(&slate));
-        info!("Response emoji.response generated, and can be sent back to the transaction originator.");
-    } else {
-        PathToSlate(format!("{}.response", args.input).into()).put_tx(&slate)?;
-        info!(
-			"Response file {}.response generated, and can be sent back to the transaction originator.",
-			args.input
-		);
+    match encoding {
+        SlateEncoding::Emoji => {
+            println!("\n\nThis is your response emoji string. Please send it back to the payer to finalize the transaction:\n\n{}", EmojiSlate().encode(&slate));
+            info!("Response emoji.response generated, and can be sent back to the transaction originator.");
+        }
+        SlateEncoding::File => {
+            PathToSlate(format!("{}.response", args.input).into()).put_tx(&slate)?;
+            info!(
+				"Response file {}.response generated, and can be sent back to the transaction originator.",
+				args.input
+			);
+        }
+        SlateEncoding::Armored(sender) => {
+            // encrypted slates get an encrypted response
+            let secret_key = armor_secret_key(wallet, keychain_mask)?;
+            let armored = armor::armor_slate(&slate, sender.as_ref().map(|s| (&secret_key, s)))?;
+            println!("\n\nThis is your response slate. Please send it back to the payer to finalize the transaction:\n\n{}", armored);
+            if Path::new(&args.input).is_file() {
+                let mut f = File::create(format!("{}.response", args.input))?;
+                f.write_all(armored.as_bytes())?;
+                info!(
+					"Response file {}.response generated, and can be sent back to the transaction originator.",
+					args.input
+				);
+            }
+        }
     }
 
     Ok(())
@@ -710,13 +1237,7 @@ where
     C: NodeClient + 'static,
     K: keychain::Keychain + 'static,
 {
-    let method = args.method.as_str();
-    let mut slate;
-    if method == "emoji" {
-        slate = EmojiSlate().decode(&args.input.as_str())?;
-    } else {
-        slate = PathToSlate((&args.input).into()).get_tx()?;
-    }
+    let (mut slate, _) = read_slate(wallet.clone(), keychain_mask, &args.method, &args.input)?;
 
     // Rather than duplicating the entire command, we'll just
     // try to determine what kind of finalization this is
@@ -848,7 +1369,7 @@ where
 pub struct ProcessInvoiceArgs {
     pub message: Option<String>,
     pub minimum_confirmations: u64,
-    pub selection_strategy: String,
+    pub selection_strategy: SelectionStrategy,
     pub method: String,
     pub dest: String,
     pub max_outputs: usize,
@@ -876,26 +1397,16 @@ where
         keychain_mask,
         |api, m| {
             if args.estimate_selection_strategies {
-                let strategies = vec!["smallest", "all"]
-                    .into_iter()
-                    .map(|strategy| {
-                        let init_args = InitTxArgs {
-                            src_acct_name: None,
-                            amount: slate.amount,
-                            minimum_confirmations: args.minimum_confirmations,
-                            max_outputs: args.max_outputs as u32,
-                            num_change_outputs: 1u32,
-                            selection_strategy_is_use_all: strategy == "all",
-                            estimate_only: Some(true),
-                            ..Default::default()
-                        };
-                        let slate = api
-                            .init_send_tx(m, init_args, is_node_synced.clone())
-                            .unwrap();
-                        (strategy, slate.amount, slate.fee)
-                    })
-                    .collect();
-                display::estimate(slate.amount, strategies, dark_scheme);
+                let init_args = InitTxArgs {
+                    src_acct_name: None,
+                    amount: slate.amount,
+                    minimum_confirmations: args.minimum_confirmations,
+                    max_outputs: args.max_outputs as u32,
+                    num_change_outputs: 1u32,
+                    ..Default::default()
+                };
+                let estimates = api.estimate_selection_strategies(m, init_args)?;
+                display::estimate(slate.amount, estimates, dark_scheme);
             } else {
                 let init_args = InitTxArgs {
                     src_acct_name: None,
@@ -903,7 +1414,7 @@ where
                     minimum_confirmations: args.minimum_confirmations,
                     max_outputs: args.max_outputs as u32,
                     num_change_outputs: 1u32,
-                    selection_strategy_is_use_all: args.selection_strategy == "all",
+                    selection_strategy: Some(args.selection_strategy),
                     message: args.message.clone(),
                     ttl_blocks: args.ttl_blocks,
                     send_args: None,
@@ -948,7 +1459,9 @@ where
                         })?;
                     }
                     method => {
-                        let sender = create_sender(method, &args.dest, is_node_synced.clone())?;
+                        let contacts = api.contacts(m)?;
+                        let sender =
+                            create_sender(method, &args.dest, &contacts, is_node_synced.clone())?;
                         slate = sender.send_tx(&slate)?;
                         api.tx_lock_outputs(m, &slate, 0, Some(args.dest))?;
                     }
@@ -995,6 +1508,7 @@ where
 /// Outputs command args
 pub struct OutputsArgs {
     pub show_full_history: bool,
+    pub frozen: bool,
     pub limit: Option<usize>,       // Number of items to return
     pub offset: Option<usize>,      // Starting index
     pub sort_order: Option<String>, // "asc" or "desc", default is "asc"
@@ -1018,16 +1532,35 @@ where
         keychain_mask,
         |api, m| {
             let res = api.node_height(m)?;
-            let outputs_result = api.retrieve_outputs(
+            // frozen outputs are filtered here, so paginate after filtering
+            let (limit, offset) = match args.frozen {
+                true => (None, None),
+                false => (args.limit, args.offset),
+            };
+            let mut outputs_result = api.retrieve_outputs(
                 m,
                 g_args.show_spent,
                 true,
                 args.show_full_history,
                 None,
-                args.limit,
-                args.offset,
+                limit,
+                offset,
                 args.sort_order,
             )?;
+            if args.frozen {
+                let frozen: Vec<_> = outputs_result
+                    .outputs
+                    .into_iter()
+                    .filter(|o| o.output.frozen)
+                    .collect();
+                let offset = args.offset.unwrap_or(0);
+                let limit = args.limit.unwrap_or(frozen.len());
+                outputs_result.pager.total_records = frozen.len();
+                outputs_result.outputs = frozen.into_iter().skip(offset).take(limit).collect();
+                outputs_result.pager.records_read = outputs_result.outputs.len();
+                outputs_result.pager.limit = args.limit.unwrap_or(0);
+                outputs_result.pager.offset = offset;
+            }
             display::outputs(
                 &g_args.account,
                 res.height,
@@ -1047,10 +1580,98 @@ where
     Ok(())
 }
 
+/// Freeze/unfreeze command args
+pub struct FreezeArgs {
+    pub commit: String,
+    pub frozen: bool,
+}
+
+pub fn freeze<L, C, K>(
+    wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+    keychain_mask: Option<&SecretKey>,
+    args: FreezeArgs,
+    is_node_synced: Arc<AtomicBool>,
+) -> Result<(), Error>
+where
+    L: WalletLCProvider<'static, C, K> + 'static,
+    C: NodeClient + 'static,
+    K: keychain::Keychain + 'static,
+{
+    controller::owner_single_use(
+        wallet.clone(),
+        keychain_mask,
+        |api, m| {
+            let (result, action) = match args.frozen {
+                true => (api.freeze_output(m, &args.commit), "frozen"),
+                false => (api.unfreeze_output(m, &args.commit), "unfrozen"),
+            };
+            match result {
+                Ok(_) => {
+                    info!("Output {} {}", args.commit, action);
+                    Ok(())
+                }
+                Err(e) => {
+                    error!("Output could not be {}: {}", action, e);
+                    Err(e)
+                }
+            }
+        },
+        is_node_synced,
+    )?;
+    Ok(())
+}
+
+/// Label command args
+pub struct LabelArgs {
+    pub tx_id: Option<u32>,
+    pub tx_slate_id: Option<Uuid>,
+    pub commit: Option<String>,
+    pub label: Option<String>,
+}
+
+pub fn label<L, C, K>(
+    wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+    keychain_mask: Option<&SecretKey>,
+    args: LabelArgs,
+    is_node_synced: Arc<AtomicBool>,
+) -> Result<(), Error>
+where
+    L: WalletLCProvider<'static, C, K> + 'static,
+    C: NodeClient + 'static,
+    K: keychain::Keychain + 'static,
+{
+    controller::owner_single_use(
+        wallet.clone(),
+        keychain_mask,
+        |api, m| {
+            let result = match &args.commit {
+                Some(commit) => api.set_output_label(m, commit, args.label.clone()),
+                None => api.set_tx_label(m, args.tx_id, args.tx_slate_id, args.label.clone()),
+            };
+            match result {
+                Ok(_) => {
+                    match &args.label {
+                        Some(l) => info!("Label set to '{}'", l),
+                        None => info!("Label cleared"),
+                    }
+                    Ok(())
+                }
+                Err(e) => {
+                    error!("Label could not be set: {}", e);
+                    Err(e)
+                }
+            }
+        },
+        is_node_synced,
+    )?;
+    Ok(())
+}
+
 /// Txs command args
 pub struct TxsArgs {
     pub id: Option<u32>,
     pub tx_slate_id: Option<Uuid>,
+    pub label: Option<String>,
     pub limit: Option<usize>,       // Number of items to return
     pub offset: Option<usize>,      // Starting index
     pub sort_order: Option<String>, // "asc" or "desc", default is "asc"
@@ -1074,17 +1695,26 @@ where
         keychain_mask,
         |api, m| {
             let res = api.node_height(m)?;
-            let txs_result = api.retrieve_txs(
+            // label matches are filtered here, so paginate after filtering
+            let (limit, offset) = match args.label {
+                Some(_) => (None, None),
+                None => (args.limit, args.offset),
+            };
+            let mut txs_result = api.retrieve_txs(
                 m,
                 true,
                 args.id,
                 args.tx_slate_id,
-                args.limit,
-                args.offset,
+                limit,
+                offset,
                 args.sort_order,
             )?;
+            if let Some(query) = &args.label {
+                txs_result.filter_by_label(query, args.limit, args.offset);
+            }
 
             let display_details = args.id.is_some() || args.tx_slate_id.is_some();
+            let outbox = api.retrieve_outbox(m)?;
 
             // if given a particular transaction id or uuid, also get and display associated
             // inputs/outputs and messages
@@ -1114,6 +1744,7 @@ where
                     res.height,
                     txs_result.refresh_from_node,
                     &txs_result.txs,
+                    &outbox,
                     display_details,
                     txs_result.pager.records_read,
                     txs_result.pager.total_records,
@@ -1147,6 +1778,7 @@ where
                     res.height,
                     txs_result.refresh_from_node,
                     &txs_result.txs,
+                    &outbox,
                     display_details,
                     txs_result.pager.records_read,
                     txs_result.pager.total_records,
@@ -1499,3 +2131,71 @@ where
     )?;
     Ok(())
 }
+
+pub fn tx_proof_export<L, C, K>(
+    wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+    keychain_mask: Option<&SecretKey>,
+    args: ProofExportArgs,
+    is_node_synced: Arc<AtomicBool>,
+) -> Result<(), Error>
+where
+    L: WalletLCProvider<'static, C, K> + 'static,
+    C: NodeClient + 'static,
+    K: keychain::Keychain + 'static,
+{
+    controller::owner_single_use(
+        wallet.clone(),
+        keychain_mask,
+        |api, m| match api.retrieve_tx_proof(m, args.id, args.tx_slate_id) {
+            Ok(p) => {
+                let mut proof_file = File::create(args.output_file.clone())?;
+                proof_file.write_all(json::to_string_pretty(&p).unwrap().as_bytes())?;
+                proof_file.sync_all()?;
+                warn!("Tx proof exported to {}", args.output_file);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Tx proof export failed: {}", e);
+                Err(e)
+            }
+        },
+        is_node_synced,
+    )?;
+    Ok(())
+}
+
+/// Verify an exported tx proof. The wallet isn't needed, so this can be done
+/// by anyone the proof is given to.
+pub fn tx_proof_verify(args: ProofVerifyArgs) -> Result<(), Error> {
+    let mut proof = String::new();
+    File::open(&args.input_file)
+        .and_then(|mut f| f.read_to_string(&mut proof))
+        .map_err(|e| {
+            error!("Unable to open tx proof file at {}: {}", args.input_file, e);
+            Error::TxProof(format!("{}", e))
+        })?;
+    let proof: TxProof = json::from_str(&proof).map_err(|e| {
+        error!("Unable to parse tx proof file: {}", e);
+        Error::TxProof(format!("{}", e))
+    })?;
+    match owner_impl::verify_tx_proof(&proof) {
+        Ok(v) => {
+            println!("Tx proof is valid.");
+            println!("Slate {} sent", v.slate_id);
+            println!("  from: {}", v.from_address);
+            println!("  to:   {}", v.to_address);
+            println!(
+                "  amount: {}, fee: {}, inputs: {}, outputs: {}",
+                core::amount_to_hr_string(v.amount, false),
+                core::amount_to_hr_string(v.fee, false),
+                v.num_inputs,
+                v.num_outputs
+            );
+            Ok(())
+        }
+        Err(e) => {
+            error!("Tx proof not valid: {}", e);
+            Err(e)
+        }
+    }
+}
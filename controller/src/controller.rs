@@ -14,18 +14,16 @@
 
 //! Controller for wallet.. instantiates and handles listeners (or single-run
 //! invocations) as needed.
-use crate::api::{
-	self, boxed_body, ApiServer, BasicAuthMiddleware, BoxBodyType, ResponseFuture, Router,
-	TLSConfig,
-};
+use crate::api::{self, boxed_body, ApiServer, BoxBodyType, ResponseFuture, Router, TLSConfig};
 use crate::config::{EpicboxConfig, TorConfig};
 use crate::keychain::Keychain;
 use crate::libwallet::{
 	Error, NodeClient, NodeVersionInfo, Slate, WalletInst, WalletLCProvider,
 	EPIC_BLOCK_HEADER_VERSION,
 };
+use crate::tokens::{ApiAccess, OwnerApiAuth, OwnerEndpoint};
 use crate::util::secp::key::SecretKey;
-use crate::util::{from_hex, static_secp_instance, to_base64, Mutex};
+use crate::util::{from_hex, static_secp_instance, Mutex};
 use http_body_util::Full;
 
 use bytes::Bytes;
@@ -35,12 +33,20 @@ use hyper::{Request, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::collections::HashMap;
+use std::convert::Infallible;
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::mpsc::error::TrySendError;
 
 use crate::apiwallet::{
-	EncryptedRequest, EncryptedResponse, EncryptionErrorResponse, Foreign,
-	ForeignCheckMiddlewareFn, ForeignRpc, Owner, OwnerRpc, OwnerRpcS, RpcId,
+	fill_optional_params, EncryptedRequest, EncryptedResponse, EncryptionErrorResponse, Foreign,
+	ForeignCheckMiddlewareFn, ForeignRpc, Owner, OwnerEvent, OwnerEventStream, OwnerRpc, OwnerRpcS,
+	RpcId,
 };
 use easy_jsonrpc_mw;
 use easy_jsonrpc_mw::{Handler, MaybeReply};
@@ -120,11 +126,14 @@ where
 /// port and wrapping the calls
 /// Note keychain mask is only provided here in case the foreign listener is also being used
 /// in the same wallet instance
+/// Requests are authenticated with the API secret, or with one of the scoped
+/// tokens kept at `api_tokens_path`
 pub fn owner_listener<L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
 	keychain_mask: Arc<Mutex<Option<SecretKey>>>,
 	addr: &str,
 	api_secret: Option<String>,
+	api_tokens_path: Option<String>,
 	tls_config: Option<TLSConfig>,
 	owner_api_include_foreign: Option<bool>,
 	tor_config: Option<TorConfig>,
@@ -137,16 +146,12 @@ where
 	K: Keychain + 'static,
 {
 	let mut router = Router::new();
-	if api_secret.is_some() {
-		let api_basic_auth =
-			"Basic ".to_string() + &to_base64(&("epic:".to_string() + &api_secret.unwrap()));
-		let basic_auth_middleware = Arc::new(BasicAuthMiddleware::new(
-			api_basic_auth,
-			&EPIC_OWNER_BASIC_REALM,
-			Some("/v2/foreign".into()),
-		));
-		router.add_middleware(basic_auth_middleware);
-	}
+	// checked by the owner handlers themselves, as what a request may do
+	// depends on the method called
+	let auth = Arc::new(OwnerApiAuth::new(
+		api_secret,
+		api_tokens_path.map(PathBuf::from),
+	));
 	let mut running_foreign = false;
 	if owner_api_include_foreign.unwrap_or(false) {
 		running_foreign = true;
@@ -159,6 +164,7 @@ where
 		epicbox_config.clone(),
 		running_foreign,
 		is_node_synced.clone(),
+		auth.clone(),
 	);
 	let api_handler_v3 = OwnerAPIHandlerV3::new(
 		wallet.clone(),
@@ -167,16 +173,23 @@ where
 		epicbox_config,
 		running_foreign,
 		is_node_synced.clone(),
+		auth,
 	);
 
 	router
 		.add_route("/v2/owner", Arc::new(api_handler_v2))
 		.map_err(|_| Error::GenericError("Router failed to add route".to_string()))?;
 
+	let events_handler = api_handler_v3.events_handler();
+
 	router
 		.add_route("/v3/owner", Arc::new(api_handler_v3))
 		.map_err(|_| Error::GenericError("Router failed to add route".to_string()))?;
 
+	router
+		.add_route("/v3/owner/events", Arc::new(events_handler))
+		.map_err(|_| Error::GenericError("Router failed to add route".to_string()))?;
+
 	// If so configured, add the foreign API to the same port
 	if running_foreign {
 		warn!("Add Foreign API at {}.", addr);
@@ -262,6 +275,8 @@ where
 	/// have to store the mask in-process
 	pub running_foreign: bool,
 	pub is_node_synced: Arc<AtomicBool>,
+	/// Authentication of requests by API secret or token
+	auth: Arc<OwnerApiAuth>,
 }
 
 impl<L, C, K> OwnerAPIHandlerV2<L, C, K>
@@ -278,6 +293,7 @@ where
 		epicbox_config: Option<EpicboxConfig>,
 		running_foreign: bool,
 		is_node_synced: Arc<AtomicBool>,
+		auth: Arc<OwnerApiAuth>,
 	) -> OwnerAPIHandlerV2<L, C, K> {
 		let owner_api = Owner::new(wallet.clone(), None, is_node_synced.clone());
 		owner_api.set_tor_config(tor_config);
@@ -290,6 +306,7 @@ where
 			keychain_mask,
 			running_foreign,
 			is_node_synced,
+			auth,
 		}
 	}
 
@@ -297,11 +314,20 @@ where
 		req: Request<hyper::body::Incoming>,
 
 		api: Arc<Owner<L, C, K>>,
+		auth: Arc<OwnerApiAuth>,
+		access: ApiAccess,
 	) -> Result<serde_json::Value, Error> {
 		let val: serde_json::Value = parse_body(req).await?;
+		let reserved = match authorize_request(&auth, &access, OwnerEndpoint::V2, &val) {
+			Ok(r) => r,
+			Err(msg) => return Ok(unauthorized_call_response(val["id"].clone(), &msg)),
+		};
 
 		match <dyn OwnerRpc>::handle_request(&*api, val) {
-			MaybeReply::Reply(r) => Ok(r),
+			MaybeReply::Reply(r) => {
+				refund_failed_call(&auth, &access, reserved, &r);
+				Ok(r)
+			}
 			MaybeReply::DontReply => {
 				// Since it's http, we need to return something. We return [] because jsonrpc
 				// clients will parse it as an empty batch response.
@@ -313,8 +339,10 @@ where
 	async fn handle_post_request(
 		req: Request<hyper::body::Incoming>,
 		api: Arc<Owner<L, C, K>>,
+		auth: Arc<OwnerApiAuth>,
+		access: ApiAccess,
 	) -> Result<Response<BoxBodyType>, Error> {
-		let res = Self::call_api(req, api).await?;
+		let res = Self::call_api(req, api, auth, access).await?;
 		Ok(json_response_pretty(&res))
 	}
 }
@@ -327,9 +355,14 @@ where
 {
 	fn post(&self, req: hyper::Request<hyper::body::Incoming>) -> ResponseFuture {
 		let api = self.owner_api.clone();
+		let auth = self.auth.clone();
+		let access = match authenticate_request(&auth, &req) {
+			Some(a) => a,
+			None => return Box::pin(async { Ok(unauthorized_response()) }),
+		};
 
 		Box::pin(async move {
-			match Self::handle_post_request(req, api).await {
+			match Self::handle_post_request(req, api, auth, access).await {
 				Ok(r) => Ok(r),
 				Err(e) => {
 					error!("Request Error: {:?}", e);
@@ -369,6 +402,9 @@ where
 	pub running_foreign: bool,
 	/// Whether we're running the foreign API on the same port, and therefore
 	pub is_node_synced: Arc<AtomicBool>,
+
+	/// Authentication of requests by API secret or token
+	auth: Arc<OwnerApiAuth>,
 }
 
 pub struct OwnerV3Helpers;
@@ -600,6 +636,7 @@ where
 		epicbox_config: Option<EpicboxConfig>,
 		running_foreign: bool,
 		is_node_synced: Arc<AtomicBool>,
+		auth: Arc<OwnerApiAuth>,
 	) -> OwnerAPIHandlerV3<L, C, K> {
 		let owner_api = Owner::new(wallet.clone(), None, is_node_synced.clone());
 		owner_api.set_tor_config(tor_config);
@@ -612,6 +649,7 @@ where
 			keychain_mask,
 			running_foreign,
 			is_node_synced,
+			auth,
 		}
 	}
 
@@ -621,6 +659,8 @@ where
 		mask: Arc<Mutex<Option<SecretKey>>>,
 		running_foreign: bool,
 		api: Arc<Owner<L, C, K>>,
+		auth: Arc<OwnerApiAuth>,
+		access: ApiAccess,
 	) -> Result<serde_json::Value, Error> {
 		let mut val: serde_json::Value = parse_body(req).await?;
 		let mut is_init_secure_api = OwnerV3Helpers::is_init_secure_api(&val);
@@ -644,8 +684,18 @@ where
 		is_init_secure_api = OwnerV3Helpers::is_init_secure_api(&val);
 		// also need to intercept open/close wallet requests
 		let is_open_wallet = OwnerV3Helpers::is_open_wallet(&val);
+		fill_optional_params(&mut val);
+		let reserved = match authorize_request(&auth, &access, OwnerEndpoint::V3, &val) {
+			Ok(r) => r,
+			Err(msg) => {
+				return Ok(
+					EncryptionErrorResponse::new(encrypted_req_id, -32004, &msg).as_json_value()
+				)
+			}
+		};
 		match <dyn OwnerRpcS>::handle_request(&*api, val) {
 			MaybeReply::Reply(mut r) => {
+				refund_failed_call(&auth, &access, reserved, &r);
 				let (_was_error, unencrypted_intercept) =
 					OwnerV3Helpers::check_error_response(&r.clone());
 				if is_open_wallet && running_foreign {
@@ -687,10 +737,28 @@ where
 		mask: Arc<Mutex<Option<SecretKey>>>,
 		running_foreign: bool,
 		api: Arc<Owner<L, C, K>>,
+		auth: Arc<OwnerApiAuth>,
+		access: ApiAccess,
 	) -> Result<Response<BoxBodyType>, Error> {
-		let res = Self::call_api(req, key, mask, running_foreign, api).await?;
+		let res = Self::call_api(req, key, mask, running_foreign, api, auth, access).await?;
 		Ok(json_response_pretty(&res))
 	}
+
+	/// Handler streaming the events of this API, encrypted with the same
+	/// shared key
+	pub fn events_handler(&self) -> OwnerEventsHandler<L, C, K> {
+		OwnerEventsHandler {
+			hub: Arc::new(OwnerEventHub {
+				owner_api: self.owner_api.clone(),
+				shared_key: self.shared_key.clone(),
+				clients: Mutex::new(EventClients {
+					senders: vec![],
+					running: false,
+				}),
+			}),
+			auth: self.auth.clone(),
+		}
+	}
 }
 
 impl<L, C, K> api::Handler<Full<Bytes>> for OwnerAPIHandlerV3<L, C, K>
@@ -704,9 +772,16 @@ where
 		let mask = self.keychain_mask.clone();
 		let running_foreign = self.running_foreign;
 		let api = self.owner_api.clone();
+		let auth = self.auth.clone();
+		let access = match authenticate_request(&auth, &req) {
+			Some(a) => a,
+			None => return Box::pin(async { Ok(unauthorized_response()) }),
+		};
 
 		Box::pin(async move {
-			match Self::handle_post_request(req, key, mask, running_foreign, api).await {
+			let res =
+				Self::handle_post_request(req, key, mask, running_foreign, api, auth, access).await;
+			match res {
 				Ok(r) => Ok(r),
 				Err(e) => {
 					error!("Request Error: {:?}", e);
@@ -720,6 +795,209 @@ where
 		Box::pin(async { Ok(create_ok_response("{}")) })
 	}
 }
+
+/// Seconds to wait for updater messages before checking the wallet for changes
+const EVENT_POLL_SECS: u64 = 5;
+/// Number of event chunks buffered for a client. One that falls further
+/// behind is disconnected, rather than holding up the others.
+const EVENT_QUEUE_LEN: usize = 16;
+/// Sent when there is nothing to report, which is also how a client that went
+/// away is noticed
+const EVENT_KEEPALIVE: &str = ": keepalive\n\n";
+
+/// Clients streaming the events of an API
+struct EventClients {
+	senders: Vec<tokio::sync::mpsc::Sender<Bytes>>,
+	/// Whether the thread feeding them is running
+	running: bool,
+}
+
+/// Fans the events of an API out to every client streaming them. A single
+/// thread checks the wallet for changes and encrypts what it found, while
+/// there are clients; they all get the same chunks.
+struct OwnerEventHub<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	/// Handle to Owner API
+	owner_api: Arc<Owner<L, C, K>>,
+
+	/// ECDH shared key of the V3 handler
+	shared_key: Arc<Mutex<Option<SecretKey>>>,
+
+	clients: Mutex<EventClients>,
+}
+
+impl<L, C, K> OwnerEventHub<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	/// Add a client, starting the thread feeding clients if it isn't running
+	fn subscribe(hub: &Arc<Self>) -> Result<tokio::sync::mpsc::Receiver<Bytes>, Error> {
+		let (tx, rx) = tokio::sync::mpsc::channel(EVENT_QUEUE_LEN);
+		let mut clients = hub.clients.lock();
+		if !clients.running {
+			let h = hub.clone();
+			thread::Builder::new()
+				.name("owner-events".to_string())
+				.spawn(move || h.run())
+				.map_err(|e| Error::GenericError(format!("Unable to start event stream: {}", e)))?;
+			clients.running = true;
+		}
+		clients.senders.push(tx);
+		Ok(rx)
+	}
+
+	/// Send events to the clients until they all went away or the shared key
+	/// is gone
+	fn run(&self) {
+		let mut stream = OwnerEventStream::new(self.owner_api.clone());
+		let mut id = 0;
+		loop {
+			let events = stream.next_events(Duration::from_secs(EVENT_POLL_SECS));
+			let chunk = self.encode_events(events, &mut id);
+			let mut clients = self.clients.lock();
+			match chunk {
+				Some(c) => {
+					let chunk = Bytes::from(c);
+					clients.senders.retain(|s| match s.try_send(chunk.clone()) {
+						Ok(()) => true,
+						Err(TrySendError::Full(_)) => {
+							warn!("Owner event stream client is too slow, disconnecting it");
+							false
+						}
+						Err(TrySendError::Closed(_)) => {
+							debug!("Owner event stream closed by the client");
+							false
+						}
+					});
+				}
+				None => clients.senders.clear(),
+			}
+			if clients.senders.is_empty() {
+				clients.running = false;
+				return;
+			}
+		}
+	}
+
+	/// Server-sent events chunk of the events, each encrypted with the shared
+	/// key. None once there is no shared key anymore.
+	fn encode_events(&self, events: Vec<OwnerEvent>, id: &mut u64) -> Option<String> {
+		let key = self.shared_key.clone();
+		if !OwnerV3Helpers::encryption_enabled(key.clone()) {
+			return None;
+		}
+		let mut chunk = String::new();
+		for event in events {
+			*id += 1;
+			let res = serde_json::to_value(&event)
+				.map_err(|e| {
+					EncryptionErrorResponse::new(RpcId::Integer(*id), -32002, &e.to_string())
+						.as_json_value()
+				})
+				.and_then(|v| {
+					OwnerV3Helpers::encrypt_response(key.clone(), RpcId::Integer(*id), &v)
+				});
+			let data = match res {
+				Ok(v) => v,
+				Err(v) => v,
+			};
+			chunk.push_str(&format!("id: {}\ndata: {}\n\n", id, data));
+		}
+		if chunk.is_empty() {
+			chunk.push_str(EVENT_KEEPALIVE);
+		}
+		Some(chunk)
+	}
+}
+
+/// Streams owner API events to clients as server-sent events. Like every V3
+/// call, it requires the secure API to be initialized, and each event is sent
+/// encrypted with the shared key as a JSON-RPC response holding an
+/// [OwnerEvent](../../epic_wallet_api/enum.OwnerEvent.html). The wallet is
+/// checked for changes once for all clients.
+pub struct OwnerEventsHandler<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	/// Events shared by all clients
+	hub: Arc<OwnerEventHub<L, C, K>>,
+
+	/// Authentication of the V3 handler. Events only reveal what the read
+	/// methods do, so any token is good enough.
+	auth: Arc<OwnerApiAuth>,
+}
+
+impl<L, C, K> api::Handler<Full<Bytes>> for OwnerEventsHandler<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	fn get(&self, req: Request<hyper::body::Incoming>) -> ResponseFuture {
+		let hub = self.hub.clone();
+		if authenticate_request(&self.auth, &req).is_none() {
+			return Box::pin(async { Ok(unauthorized_response()) });
+		}
+
+		Box::pin(async move {
+			if let Err(v) = OwnerV3Helpers::check_encryption_started(hub.shared_key.clone()) {
+				return Ok(json_response_pretty(&v));
+			}
+			match OwnerEventHub::subscribe(&hub) {
+				Ok(rx) => Ok(event_stream_response(rx)),
+				Err(e) => Ok(create_error_response(e)),
+			}
+		})
+	}
+
+	fn options(&self, _req: Request<hyper::body::Incoming>) -> ResponseFuture {
+		Box::pin(async { Ok(create_ok_response("{}")) })
+	}
+}
+
+/// Response body fed by a channel, ending when the sender is dropped
+struct ChannelBody {
+	rx: tokio::sync::mpsc::Receiver<Bytes>,
+}
+
+impl hyper::body::Body for ChannelBody {
+	type Data = Bytes;
+	type Error = Infallible;
+
+	fn poll_frame(
+		mut self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<Option<Result<hyper::body::Frame<Bytes>, Infallible>>> {
+		self.rx
+			.poll_recv(cx)
+			.map(|chunk| chunk.map(|c| Ok(hyper::body::Frame::data(c))))
+	}
+}
+
+fn event_stream_response(rx: tokio::sync::mpsc::Receiver<Bytes>) -> Response<BoxBodyType> {
+	let body = ChannelBody { rx }.map_err(|never| match never {}).boxed();
+
+	hyper::Response::builder()
+		.status(StatusCode::OK)
+		.header("access-control-allow-origin", "*")
+		.header(
+			"access-control-allow-headers",
+			"Content-Type, Authorization",
+		)
+		.header(hyper::header::CONTENT_TYPE, "text/event-stream")
+		.header(hyper::header::CACHE_CONTROL, "no-cache")
+		.body(body)
+		.unwrap()
+}
+
 /// V2 API Handler/Wrapper for foreign functions
 pub struct ForeignAPIHandlerV2<L, C, K>
 where
@@ -815,6 +1093,73 @@ where
 	}
 }
 
+/// Access granted by the credentials of a request
+fn authenticate_request(
+	auth: &OwnerApiAuth,
+	req: &Request<hyper::body::Incoming>,
+) -> Option<ApiAccess> {
+	let header = req
+		.headers()
+		.get(hyper::header::AUTHORIZATION)
+		.and_then(|h| h.to_str().ok());
+	auth.authenticate(header)
+}
+
+/// Check the access allows a JSON-RPC call to `endpoint`. Batches can only
+/// be sent with full access, as they have no single method.
+fn authorize_request(
+	auth: &OwnerApiAuth,
+	access: &ApiAccess,
+	endpoint: OwnerEndpoint,
+	val: &serde_json::Value,
+) -> Result<Option<u64>, String> {
+	let method = val["method"].as_str().unwrap_or_default();
+	auth.authorize(access, endpoint, method, &val["params"])
+}
+
+/// Give back what a call counted against the daily limit of its token, if
+/// the call failed
+fn refund_failed_call(
+	auth: &OwnerApiAuth,
+	access: &ApiAccess,
+	reserved: Option<u64>,
+	reply: &serde_json::Value,
+) {
+	if let Some(amount) = reserved {
+		let (was_error, _) = OwnerV3Helpers::check_error_response(reply);
+		if was_error || !reply["error"].is_null() {
+			auth.refund(access, amount);
+		}
+	}
+}
+
+fn unauthorized_call_response(id: serde_json::Value, msg: &str) -> serde_json::Value {
+	serde_json::json!({
+		"jsonrpc": "2.0",
+		"id": id,
+		"error": {
+			"message": msg,
+			"code": -32004
+		}
+	})
+}
+
+fn unauthorized_response() -> Response<BoxBodyType> {
+	hyper::Response::builder()
+		.status(StatusCode::UNAUTHORIZED)
+		.header(
+			hyper::header::WWW_AUTHENTICATE,
+			EPIC_OWNER_BASIC_REALM.clone(),
+		)
+		.header("access-control-allow-origin", "*")
+		.header(
+			"access-control-allow-headers",
+			"Content-Type, Authorization",
+		)
+		.body(boxed_body(""))
+		.unwrap()
+}
+
 // Utility to serialize a struct into JSON and produce a sensible Response
 // out of it.
 fn _json_response<T>(s: &T) -> Response<BoxBodyType>
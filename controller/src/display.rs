@@ -12,11 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::batch::JournalEntry;
 use crate::core::core::{self, amount_to_hr_string};
 use crate::core::global;
 use crate::libwallet::{
-	address, AcctPathMapping, Error, OutputCommitMapping, OutputStatus, TxLogEntry, WalletInfo,
+	address, AcctPathMapping, Contact, Error, OutboxEntry, OutputCommitMapping, OutputStatus,
+	SelectionEstimate, TxLogEntry, WalletInfo,
 };
+use crate::tokens::ApiToken;
 use crate::util;
 use prettytable;
 use std::io::prelude::Write;
@@ -67,7 +70,8 @@ pub fn outputs(
 		bMG->"Coinbase?",
 		bMG->"# Confirms",
 		bMG->"Value",
-		bMG->"Tx"
+		bMG->"Tx",
+		bMG->"Label"
 	]);
 
 	for m in outputs {
@@ -83,6 +87,7 @@ pub fn outputs(
 		// Mark unconfirmed coinbase outputs as "Mining" instead of "Unconfirmed"
 		let status = match m.output.status {
 			OutputStatus::Unconfirmed if m.output.is_coinbase => "Mining".to_string(),
+			_ if m.output.frozen => format!("{} (Frozen)", m.output.status),
 			_ => format!("{}", m.output.status),
 		};
 
@@ -92,6 +97,7 @@ pub fn outputs(
 			None => "".to_owned(),
 			Some(t) => t.to_string(),
 		};
+		let label = m.output.label.clone().unwrap_or_default();
 
 		if dark_background_color_scheme {
 			table.add_row(row![
@@ -104,6 +110,7 @@ pub fn outputs(
 				bFB->num_confirmations,
 				bFG->value,
 				bFC->tx,
+				bFD->label,
 			]);
 		} else {
 			table.add_row(row![
@@ -116,6 +123,7 @@ pub fn outputs(
 				bFB->num_confirmations,
 				bFG->value,
 				bFD->tx,
+				bFD->label,
 			]);
 		}
 	}
@@ -140,6 +148,7 @@ pub fn txs(
 	cur_height: u64,
 	validated: bool,
 	txs: &[TxLogEntry],
+	outbox: &[OutboxEntry],
 	display_details: bool,
 
 	records_read: usize,  // Number of records returned after pagination
@@ -183,13 +192,17 @@ pub fn txs(
 		bMG->"Total Spent",
 		bMG->"Fee",
 		bMG->"Net Change",
+		bMG->"Delivery",
+		bMG->"Label",
 	]);
 
 	details_table.set_titles(row![
 		bMG->"Num. Inputs",
 		bMG->"Num. Outputs",
 		bMG->"TTL Cutoff Height",
+		bMG->"Lock Height",
 		bMG->"Payment Proof",
+		bMG->"Tx Proof",
 		bMG->"Kernel",
 		bMG->"Tx Data"
 	]);
@@ -229,6 +242,12 @@ pub fn txs(
 			Some(addr) => addr,
 			None => "None".to_owned(),
 		};
+		let label = t.label.clone().unwrap_or_default();
+		// only slates sent over epicbox are tracked
+		let delivery = match outbox.iter().find(|e| Some(e.slate_id) == t.tx_slate_id) {
+			Some(e) => format!("{}", e.status),
+			None => "None".to_owned(),
+		};
 
 		// Add the main row
 		table.add_row(row![
@@ -243,6 +262,8 @@ pub fn txs(
 			bFR->amount_debited_str,
 			bFR->fee,
 			bFY->net_diff,
+			bFC->delivery,
+			bFD->label,
 		]);
 
 		let num_inputs = format!("{}", t.num_inputs);
@@ -251,12 +272,21 @@ pub fn txs(
 			Some(b) => format!("{}", b),
 			None => "None".to_owned(),
 		};
+		let lock_height = match t.lock_height {
+			Some(h) => format!("{}", h),
+			None => "None".to_owned(),
+		};
 
 		let payment_proof = match t.payment_proof {
 			Some(_) => "Yes".to_owned(),
 			None => "None".to_owned(),
 		};
 
+		let tx_proof = match t.tx_proof {
+			Some(_) => "Yes".to_owned(),
+			None => "None".to_owned(),
+		};
+
 		let kernel_excess = match t.kernel_excess {
 			Some(e) => util::to_hex(e.0.to_vec()),
 			None => "None".to_owned(),
@@ -271,7 +301,9 @@ pub fn txs(
 			bFC->num_inputs,
 			bFC->num_outputs,
 			bFB->ttl_cutoff_height,
+			bFB->lock_height,
 			bFB->payment_proof,
+			bFB->tx_proof,
 			bFB->kernel_excess,
 			bFB->tx_data,
 		]);
@@ -336,6 +368,13 @@ pub fn info(
 			Fr->"Locked by transaction",
 			Fr->amount_to_hr_string(wallet_info.amount_locked, false)
 		]);
+		// Only display "Frozen" if the user has frozen any outputs.
+		if wallet_info.amount_frozen > 0 {
+			table.add_row(row![
+				Fr->"Frozen by user",
+				Fr->amount_to_hr_string(wallet_info.amount_frozen, false)
+			]);
+		}
 		table.add_row(row![
 			Fw->"--------------------------------",
 			Fw->"-------------"
@@ -365,6 +404,13 @@ pub fn info(
 			Fr->"Locked by transaction",
 			Fr->amount_to_hr_string(wallet_info.amount_locked, false)
 		]);
+		// Only display "Frozen" if the user has frozen any outputs.
+		if wallet_info.amount_frozen > 0 {
+			table.add_row(row![
+				Fr->"Frozen by user",
+				Fr->amount_to_hr_string(wallet_info.amount_frozen, false)
+			]);
+		}
 		table.add_row(row![
 			Fw->"--------------------------------",
 			Fw->"-------------"
@@ -389,11 +435,7 @@ pub fn info(
 /// Display summary info in a pretty way
 pub fn estimate(
 	amount: u64,
-	strategies: Vec<(
-		&str, // strategy
-		u64,  // total amount to be locked
-		u64,  // fee
-	)>,
+	estimates: Vec<SelectionEstimate>,
 	dark_background_color_scheme: bool,
 ) {
 	println!(
@@ -407,20 +449,69 @@ pub fn estimate(
 		bMG->"Selection strategy",
 		bMG->"Fee",
 		bMG->"Will be locked",
+		bMG->"Inputs",
 	]);
 
-	for (strategy, total, fee) in strategies {
+	for e in estimates {
 		if dark_background_color_scheme {
 			table.add_row(row![
-				bFC->strategy,
-				FR->amount_to_hr_string(fee, false),
-				FY->amount_to_hr_string(total, false),
+				bFC->e.strategy,
+				FR->amount_to_hr_string(e.fee, false),
+				FY->amount_to_hr_string(e.total, false),
+				FG->e.num_inputs,
 			]);
 		} else {
 			table.add_row(row![
-				bFD->strategy,
-				FR->amount_to_hr_string(fee, false),
-				FY->amount_to_hr_string(total, false),
+				bFD->e.strategy,
+				FR->amount_to_hr_string(e.fee, false),
+				FY->amount_to_hr_string(e.total, false),
+				FG->e.num_inputs,
+			]);
+		}
+	}
+	table.printstd();
+	println!();
+}
+
+/// Display the per-recipient outcome of a batch send
+pub fn batch_payouts(entries: &[JournalEntry], dark_background_color_scheme: bool) {
+	println!("\n____ Batch Payouts ____\n",);
+	let mut table = table!();
+
+	table.set_titles(row![
+		bMG->"#",
+		bMG->"Destination",
+		bMG->"Method",
+		bMG->"Amount",
+		bMG->"Status",
+		bMG->"Slate ID",
+		bMG->"Error",
+	]);
+	for (i, e) in entries.iter().enumerate() {
+		let slate_id = match e.slate_id {
+			Some(id) => id.to_string(),
+			None => "None".to_owned(),
+		};
+		let error = e.error.clone().unwrap_or_default();
+		if dark_background_color_scheme {
+			table.add_row(row![
+				bFC->i + 1,
+				bFC->e.dest,
+				bFB->e.method,
+				FY->amount_to_hr_string(e.amount, false),
+				FG->e.status,
+				bFD->slate_id,
+				FR->error,
+			]);
+		} else {
+			table.add_row(row![
+				bFD->i + 1,
+				bFD->e.dest,
+				bFB->e.method,
+				FY->amount_to_hr_string(e.amount, false),
+				FG->e.status,
+				bFD->slate_id,
+				FR->error,
 			]);
 		}
 	}
@@ -448,6 +539,58 @@ pub fn accounts(acct_mappings: Vec<AcctPathMapping>) {
 	println!();
 }
 
+/// Display the address book
+pub fn contacts(contacts: Vec<Contact>) {
+	println!("\n____ Contacts ____\n",);
+	let mut table = table!();
+
+	table.set_titles(row![
+		mMG->"Name",
+		bMG->"Method",
+		bMG->"Destination",
+		bMG->"Payment Proof Address",
+	]);
+	for c in contacts {
+		table.add_row(row![
+			bFC->c.name,
+			bFB->c.method,
+			bGC->c.destination,
+			bFD->c.proof_address.unwrap_or_else(|| "None".to_owned()),
+		]);
+	}
+	table.set_format(*prettytable::format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+	table.printstd();
+	println!();
+}
+
+/// Display the scoped owner API tokens
+pub fn api_tokens(tokens: &[ApiToken]) {
+	println!("\n____ Owner API Tokens ____\n",);
+	let mut table = table!();
+
+	table.set_titles(row![
+		mMG->"Name",
+		bMG->"Scope",
+		bMG->"Daily Limit",
+		bMG->"Spent Today",
+	]);
+	for t in tokens {
+		let limit = match t.daily_limit {
+			Some(l) => amount_to_hr_string(l, false),
+			None => "None".to_owned(),
+		};
+		table.add_row(row![
+			bFC->t.name,
+			bFB->t.scope,
+			bFD->limit,
+			bFD->amount_to_hr_string(t.spent_today(), false),
+		]);
+	}
+	table.set_format(*prettytable::format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+	table.printstd();
+	println!();
+}
+
 /// Display transaction log messages
 pub fn tx_messages(tx: &TxLogEntry, dark_background_color_scheme: bool) -> Result<(), Error> {
 	let title = format!("Transaction Messages - Transaction '{}'", tx.id,);
@@ -30,9 +30,11 @@ use epic_wallet_util::epic_core as core;
 use epic_wallet_util::epic_keychain as keychain;
 use epic_wallet_util::epic_util as util;
 
+pub mod batch;
 pub mod command;
 pub mod controller;
 pub mod display;
 mod error;
+pub mod tokens;
 
 pub use crate::error::Error;
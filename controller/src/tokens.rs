@@ -0,0 +1,566 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Scoped owner API tokens. Besides the API secret, which grants access to
+//! every method, the owner API accepts tokens limited to reading the wallet,
+//! receiving or spending, the latter optionally capped per day. Tokens are
+//! kept in a file next to the API secret, and only their hashes are stored.
+//! The file is read on every request, so tokens issued or revoked while the
+//! API runs take effect immediately. Setting up the V3 API's shared key
+//! replaces it for every client, so only the API secret may do so.
+
+use crate::libwallet::{Error, Slate, VersionedSlate};
+use crate::util::{to_base64, to_hex, Mutex};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{NaiveDate, Utc};
+use ring::digest;
+use serde_derive::{Deserialize, Serialize};
+use serde_json as json;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Methods any token may call, needed to use the V3 API at all
+const SESSION_METHODS: [&str; 1] = ["open_wallet"];
+
+/// Methods that only read the wallet
+const READ_METHODS: [&str; 23] = [
+	"accounts",
+	"contacts",
+	"estimate_selection_strategies",
+	"get_public_address",
+	"get_public_proof_address",
+	"get_stored_tx",
+	"get_top_level_directory",
+	"get_updater_messages",
+	"node_cache_stats",
+	"node_height",
+	"proof_address_from_onion_v3",
+	"retrieve_epicbox_status",
+	"retrieve_outbox",
+	"retrieve_outputs",
+	"retrieve_payment_proof",
+	"retrieve_summary_info",
+	"retrieve_tx_proof",
+	"retrieve_txs",
+	"scan_progress",
+	"search_labels",
+	"verify_payment_proof",
+	"verify_slate_messages",
+	"verify_tx_proof",
+];
+
+/// Methods receiving funds
+const RECEIVE_METHODS: [&str; 1] = ["issue_invoice_tx"];
+
+/// Methods spending funds
+const SPEND_METHODS: [&str; 6] = [
+	"init_send_tx",
+	"process_invoice_tx",
+	"tx_lock_outputs",
+	"finalize_tx",
+	"post_tx",
+	"cancel_tx",
+];
+
+/// Methods committing funds, counted against the daily limit of a token
+const LIMITED_METHODS: [&str; 2] = ["init_send_tx", "process_invoice_tx"];
+
+/// What a token allows. Each scope includes the ones before it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiScope {
+	/// Read balances, outputs and transactions
+	Read,
+	/// Also issue invoices
+	Receive,
+	/// Also send and finalize transactions
+	Spend,
+}
+
+impl ApiScope {
+	/// Parse a scope name as used on the command line
+	pub fn from_name(name: &str) -> Result<ApiScope, Error> {
+		match name {
+			"read" => Ok(ApiScope::Read),
+			"receive" => Ok(ApiScope::Receive),
+			"spend" => Ok(ApiScope::Spend),
+			_ => Err(Error::ArgumentError(format!(
+				"Unknown scope '{}', expected read, receive or spend",
+				name
+			))),
+		}
+	}
+
+	/// Whether the scope allows calling the method
+	pub fn allows(&self, method: &str) -> bool {
+		let required = if SESSION_METHODS.contains(&method) || READ_METHODS.contains(&method) {
+			ApiScope::Read
+		} else if RECEIVE_METHODS.contains(&method) {
+			ApiScope::Receive
+		} else if SPEND_METHODS.contains(&method) {
+			ApiScope::Spend
+		} else {
+			return false;
+		};
+		*self >= required
+	}
+}
+
+impl fmt::Display for ApiScope {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let s = match self {
+			ApiScope::Read => "read",
+			ApiScope::Receive => "receive",
+			ApiScope::Spend => "spend",
+		};
+		write!(f, "{}", s)
+	}
+}
+
+/// An issued token
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ApiToken {
+	/// Name the token is referred to by
+	pub name: String,
+	/// Hex encoded SHA-256 of the token
+	pub hash: String,
+	/// What the token allows
+	pub scope: ApiScope,
+	/// Most that can be spent in a day (UTC) with the token, in nanoepic
+	#[serde(default)]
+	pub daily_limit: Option<u64>,
+	/// Day the amount spent was counted on
+	#[serde(default)]
+	pub spent_day: Option<NaiveDate>,
+	/// Amount spent on `spent_day`
+	#[serde(default)]
+	pub spent: u64,
+}
+
+impl ApiToken {
+	/// Amount spent today
+	pub fn spent_today(&self) -> u64 {
+		match self.spent_day {
+			Some(d) if d == Utc::now().date_naive() => self.spent,
+			_ => 0,
+		}
+	}
+}
+
+/// All tokens issued for a wallet
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ApiTokenStore {
+	/// Issued tokens
+	pub tokens: Vec<ApiToken>,
+}
+
+impl ApiTokenStore {
+	/// Load the tokens at `path`, none if there is no file yet
+	pub fn load(path: &Path) -> Result<ApiTokenStore, Error> {
+		if !path.exists() {
+			return Ok(ApiTokenStore::default());
+		}
+		let data = fs::read_to_string(path)?;
+		json::from_str(&data)
+			.map_err(|e| Error::GenericError(format!("Invalid API token file: {}", e)))
+	}
+
+	/// Persist the tokens, written to a temporary file first and renamed so
+	/// a crash never leaves a truncated file behind
+	pub fn save(&self, path: &Path) -> Result<(), Error> {
+		let tmp = path.with_extension("tmp");
+		fs::write(&tmp, json::to_string_pretty(self)?)?;
+		fs::rename(&tmp, path)?;
+		Ok(())
+	}
+
+	/// Issue a new token, returning it. Only its hash is kept, so it can't
+	/// be shown again.
+	pub fn issue(
+		&mut self,
+		name: &str,
+		scope: ApiScope,
+		daily_limit: Option<u64>,
+	) -> Result<String, Error> {
+		if self.tokens.iter().any(|t| t.name == name) {
+			return Err(Error::ArgumentError(format!(
+				"A token named '{}' already exists",
+				name
+			)));
+		}
+		let token = to_hex(rand::random::<[u8; 32]>().to_vec());
+		self.tokens.push(ApiToken {
+			name: name.to_owned(),
+			hash: hash_token(&token),
+			scope,
+			daily_limit,
+			spent_day: None,
+			spent: 0,
+		});
+		Ok(token)
+	}
+
+	/// Revoke the token with the given name
+	pub fn revoke(&mut self, name: &str) -> Result<(), Error> {
+		let len = self.tokens.len();
+		self.tokens.retain(|t| t.name != name);
+		if self.tokens.len() == len {
+			return Err(Error::ArgumentError(format!("No token named '{}'", name)));
+		}
+		Ok(())
+	}
+
+	fn find_mut(&mut self, name: &str) -> Option<&mut ApiToken> {
+		self.tokens.iter_mut().find(|t| t.name == name)
+	}
+}
+
+fn hash_token(token: &str) -> String {
+	to_hex(
+		digest::digest(&digest::SHA256, token.as_bytes())
+			.as_ref()
+			.to_vec(),
+	)
+}
+
+/// Owner API endpoint a call was made to, which tells where its params are
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OwnerEndpoint {
+	/// `/v2/owner`, taking the params of each method as declared
+	V2,
+	/// `/v3/owner`, taking the keychain mask token before them
+	V3,
+}
+
+impl OwnerEndpoint {
+	/// Parameter of a call, given by name or by its position among the
+	/// declared params of the method
+	fn param<'a>(
+		&self,
+		params: &'a json::Value,
+		name: &str,
+		index: usize,
+	) -> Option<&'a json::Value> {
+		let offset = match self {
+			OwnerEndpoint::V2 => 0,
+			OwnerEndpoint::V3 => 1,
+		};
+		match params {
+			json::Value::Object(m) => m.get(name),
+			json::Value::Array(a) => a.get(index + offset),
+			_ => None,
+		}
+	}
+}
+
+/// Amount a call commits funds for: the `amount` of the transaction
+/// arguments when sending, the amount of the slate when paying an invoice.
+/// None if it can't be told.
+fn spend_amount(endpoint: OwnerEndpoint, method: &str, params: &json::Value) -> Option<u64> {
+	match method {
+		"init_send_tx" => match endpoint.param(params, "args", 0)?.get("amount")? {
+			json::Value::Number(n) => n.as_u64(),
+			json::Value::String(s) => s.parse().ok(),
+			_ => None,
+		},
+		"process_invoice_tx" => {
+			let slate = endpoint.param(params, "slate", 0)?;
+			let slate: VersionedSlate = json::from_value(slate.clone()).ok()?;
+			Some(Slate::from(slate).amount)
+		}
+		_ => None,
+	}
+}
+
+/// Access granted to an owner API request
+#[derive(Clone, Debug, PartialEq)]
+pub enum ApiAccess {
+	/// Authenticated with the API secret, or no secret is set
+	Full,
+	/// Authenticated with the named token
+	Token(String),
+}
+
+/// Authentication and authorization of owner API requests
+pub struct OwnerApiAuth {
+	/// Expected basic auth header, None if the API is open
+	basic_auth: Option<String>,
+	/// Location of the token file
+	tokens_path: Option<PathBuf>,
+	/// Serializes the updates of the token file
+	lock: Mutex<()>,
+}
+
+impl OwnerApiAuth {
+	/// Authentication by the API secret and the tokens at `tokens_path`
+	pub fn new(api_secret: Option<String>, tokens_path: Option<PathBuf>) -> OwnerApiAuth {
+		if api_secret.is_none() {
+			warn!(
+				"The owner API has no secret: every request gets full access, \
+				 whatever token it carries"
+			);
+		}
+		OwnerApiAuth {
+			basic_auth: api_secret.map(|s| format!("Basic {}", to_base64(&format!("epic:{}", s)))),
+			tokens_path,
+			lock: Mutex::new(()),
+		}
+	}
+
+	fn load(&self) -> Result<ApiTokenStore, Error> {
+		match self.tokens_path {
+			Some(ref p) => ApiTokenStore::load(p),
+			None => Ok(ApiTokenStore::default()),
+		}
+	}
+
+	fn save(&self, store: &ApiTokenStore) -> Result<(), Error> {
+		match self.tokens_path {
+			Some(ref p) => store.save(p),
+			None => Ok(()),
+		}
+	}
+
+	/// Access granted by the `Authorization` header of a request, None if it
+	/// grants none. Tokens are given as `Bearer <token>`, or as the basic
+	/// auth password of user `epic`.
+	pub fn authenticate(&self, header: Option<&str>) -> Option<ApiAccess> {
+		let expected = match self.basic_auth {
+			Some(ref a) => a,
+			None => return Some(ApiAccess::Full),
+		};
+		let header = header?;
+		if header == expected {
+			return Some(ApiAccess::Full);
+		}
+		let token = match header.strip_prefix("Bearer ") {
+			Some(t) => t.trim().to_owned(),
+			None => {
+				let encoded = header.strip_prefix("Basic ")?;
+				let decoded = general_purpose::STANDARD.decode(encoded.trim()).ok()?;
+				let credentials = String::from_utf8(decoded).ok()?;
+				credentials.strip_prefix("epic:")?.to_owned()
+			}
+		};
+		let hash = hash_token(&token);
+		let store = match self.load() {
+			Ok(s) => s,
+			Err(e) => {
+				error!("Unable to read API tokens: {}", e);
+				return None;
+			}
+		};
+		store
+			.tokens
+			.into_iter()
+			.find(|t| t.hash == hash)
+			.map(|t| ApiAccess::Token(t.name))
+	}
+
+	/// Check the access allows the call made to `endpoint`, counting what it
+	/// spends against the daily limit of the token. Returns the amount
+	/// counted, to be refunded if the call fails.
+	pub fn authorize(
+		&self,
+		access: &ApiAccess,
+		endpoint: OwnerEndpoint,
+		method: &str,
+		params: &json::Value,
+	) -> Result<Option<u64>, String> {
+		let name = match access {
+			ApiAccess::Full => return Ok(None),
+			ApiAccess::Token(n) => n,
+		};
+		let _guard = self.lock.lock();
+		let mut store = self.load().map_err(|e| e.to_string())?;
+		let token = store
+			.find_mut(name)
+			.ok_or_else(|| format!("Token '{}' was revoked", name))?;
+		if !token.scope.allows(method) {
+			return Err(format!(
+				"Token '{}' with scope '{}' can't call '{}'",
+				name, token.scope, method
+			));
+		}
+		let limit = match token.daily_limit {
+			Some(l) if LIMITED_METHODS.contains(&method) => l,
+			_ => return Ok(None),
+		};
+		let amount = spend_amount(endpoint, method, params).ok_or_else(|| {
+			format!(
+				"Token '{}' has a daily limit, and the amount '{}' spends can't be told",
+				name, method
+			)
+		})?;
+		let spent = token.spent_today();
+		if spent.saturating_add(amount) > limit {
+			return Err(format!(
+				"Token '{}' daily limit exceeded: {} of {} spent today",
+				name, spent, limit
+			));
+		}
+		token.spent_day = Some(Utc::now().date_naive());
+		token.spent = spent + amount;
+		self.save(&store).map_err(|e| e.to_string())?;
+		Ok(Some(amount))
+	}
+
+	/// Give back an amount counted by `authorize` for a call that failed
+	pub fn refund(&self, access: &ApiAccess, amount: u64) {
+		if let ApiAccess::Token(name) = access {
+			let _guard = self.lock.lock();
+			let res = self.load().and_then(|mut store| {
+				if let Some(token) = store.find_mut(name) {
+					token.spent = token.spent_today().saturating_sub(amount);
+				}
+				self.save(&store)
+			});
+			if let Err(e) = res {
+				error!("Unable to refund token '{}': {}", name, e);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::libwallet::SlateVersion;
+	use std::env;
+	use uuid::Uuid;
+
+	#[test]
+	fn scopes_and_daily_limit() {
+		assert!(ApiScope::Read.allows("retrieve_summary_info"));
+		assert!(ApiScope::Read.allows("open_wallet"));
+		assert!(!ApiScope::Spend.allows("init_secure_api"));
+		assert!(!ApiScope::Read.allows("issue_invoice_tx"));
+		assert!(ApiScope::Receive.allows("issue_invoice_tx"));
+		assert!(!ApiScope::Receive.allows("init_send_tx"));
+		assert!(ApiScope::Spend.allows("init_send_tx"));
+		assert!(!ApiScope::Spend.allows("get_mnemonic"));
+
+		let path = env::temp_dir().join(format!("api_tokens_{}.json", Uuid::new_v4()));
+		let mut store = ApiTokenStore::default();
+		let reader = store.issue("dashboard", ApiScope::Read, None).unwrap();
+		let spender = store.issue("payouts", ApiScope::Spend, Some(100)).unwrap();
+		assert!(store.issue("payouts", ApiScope::Read, None).is_err());
+		store.save(&path).unwrap();
+
+		let auth = OwnerApiAuth::new(Some("secret".to_owned()), Some(path.clone()));
+		let full = format!("Basic {}", to_base64("epic:secret"));
+		assert_eq!(auth.authenticate(Some(&full)), Some(ApiAccess::Full));
+		assert_eq!(auth.authenticate(None), None);
+		assert_eq!(auth.authenticate(Some("Bearer nope")), None);
+		let reading = auth
+			.authenticate(Some(&format!("Bearer {}", reader)))
+			.unwrap();
+		assert_eq!(reading, ApiAccess::Token("dashboard".to_owned()));
+		let spending = auth
+			.authenticate(Some(&format!(
+				"Basic {}",
+				to_base64(&format!("epic:{}", spender))
+			)))
+			.unwrap();
+
+		let send = |amount: &str| json::json!({ "token": null, "args": { "amount": amount } });
+		let authorize = |access: &ApiAccess, endpoint, method, params: &json::Value| {
+			auth.authorize(access, endpoint, method, params)
+		};
+		let v3 = OwnerEndpoint::V3;
+		assert!(authorize(&reading, v3, "retrieve_txs", &json::json!({})).is_ok());
+		assert!(authorize(&reading, v3, "init_send_tx", &send("1")).is_err());
+		assert_eq!(
+			authorize(&spending, v3, "init_send_tx", &send("60")),
+			Ok(Some(60))
+		);
+		assert!(authorize(&spending, v3, "init_send_tx", &send("60")).is_err());
+		auth.refund(&spending, 60);
+		assert!(authorize(&spending, v3, "init_send_tx", &send("60")).is_ok());
+		assert!(authorize(&spending, v3, "init_secure_api", &json::json!({})).is_err());
+
+		// invoices are paid the amount of their slate, whatever the
+		// arguments say, and calls whose amount can't be told are refused
+		let invoice = |amount: u64| {
+			let mut slate = Slate::blank(1);
+			slate.amount = amount;
+			json::to_value(VersionedSlate::into_version(slate, SlateVersion::V3)).unwrap()
+		};
+		let pay = |amount: u64| {
+			json::json!({
+				"token": null,
+				"slate": invoice(amount),
+				"args": { "amount": "0" }
+			})
+		};
+		auth.refund(&spending, 60);
+		assert_eq!(
+			authorize(&spending, v3, "process_invoice_tx", &pay(70)),
+			Ok(Some(70))
+		);
+		assert!(authorize(&spending, v3, "process_invoice_tx", &pay(40)).is_err());
+		let positional = json::json!([null, invoice(30), { "amount": "0" }]);
+		assert_eq!(
+			authorize(&spending, v3, "process_invoice_tx", &positional),
+			Ok(Some(30))
+		);
+		let unparsed = json::json!({
+			"token": null,
+			"slate": { "amount": "10" },
+			"args": { "amount": "0" }
+		});
+		auth.refund(&spending, 100);
+		assert!(authorize(&spending, v3, "process_invoice_tx", &unparsed).is_err());
+		assert!(authorize(
+			&spending,
+			v3,
+			"init_send_tx",
+			&json::json!({ "token": null })
+		)
+		.is_err());
+		assert!(authorize(&spending, v3, "init_send_tx", &send("many")).is_err());
+
+		// on the V2 API, positional params have no token before them
+		let v2 = OwnerEndpoint::V2;
+		let positional = json::json!([{ "amount": "60" }]);
+		assert_eq!(
+			authorize(&spending, v2, "init_send_tx", &positional),
+			Ok(Some(60))
+		);
+		let positional = json::json!([null, { "amount": "30" }]);
+		assert_eq!(
+			authorize(&spending, v3, "init_send_tx", &positional),
+			Ok(Some(30))
+		);
+		for amount in [200, 20] {
+			let positional = json::json!([invoice(amount), { "amount": "0" }]);
+			assert!(authorize(&spending, v2, "process_invoice_tx", &positional).is_err());
+		}
+		auth.refund(&spending, 90);
+		let positional = json::json!([invoice(20), { "amount": "0" }]);
+		assert_eq!(
+			authorize(&spending, v2, "process_invoice_tx", &positional),
+			Ok(Some(20))
+		);
+		let positional = json::json!([{ "amount": "0" }, { "amount": "0" }]);
+		assert!(authorize(&spending, v2, "process_invoice_tx", &positional).is_err());
+
+		let mut store = ApiTokenStore::load(&path).unwrap();
+		store.revoke("payouts").unwrap();
+		store.save(&path).unwrap();
+		assert!(authorize(&spending, v3, "post_tx", &json::json!({})).is_err());
+		let _ = fs::remove_file(&path);
+	}
+}
@@ -0,0 +1,323 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! tests batch payouts over a local epicbox relay, resumed after recipients
+//! were offline, never pay anyone twice
+#[macro_use]
+extern crate log;
+extern crate epic_wallet_controller as wallet;
+extern crate epic_wallet_impls as impls;
+
+use epic_wallet_config::{EpicboxConfig, TorConfig};
+use epic_wallet_libwallet as libwallet;
+use epic_wallet_util::epic_core::{consensus, core};
+use epic_wallet_util::epic_keychain::Keychain;
+use epic_wallet_util::epic_util::secp::key::SecretKey;
+use epic_wallet_util::epic_util::Mutex;
+use impls::epicbox::relay::EpicboxRelay;
+use impls::test_framework::{self, LocalWalletClient};
+use impls::EpicboxListenChannel;
+use libwallet::api_impl::owner as owner_impl;
+use libwallet::{NodeClient, SelectionStrategy, TxLogEntryType, WalletInst, WalletLCProvider};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use wallet::batch::{self, BatchJournal, PayoutStatus};
+use wallet::command::{self, BatchSendArgs};
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// Wait up to `secs` for `cond` to hold
+fn wait_for<F>(secs: u64, mut cond: F) -> bool
+where
+	F: FnMut() -> bool,
+{
+	let deadline = Instant::now() + Duration::from_secs(secs);
+	while Instant::now() < deadline {
+		if cond() {
+			return true;
+		}
+		thread::sleep(Duration::from_millis(100));
+	}
+	false
+}
+
+/// Listen for slates on the relay, as `listen` would
+fn listen<L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+	keychain_mask: Option<SecretKey>,
+	config: EpicboxConfig,
+) where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	thread::spawn(move || {
+		EpicboxListenChannel::new().unwrap().supervise(
+			wallet,
+			Arc::new(Mutex::new(keychain_mask)),
+			config,
+			Arc::new(AtomicBool::new(true)),
+			TorConfig::default(),
+		)
+	});
+}
+
+/// Confirmed transactions of the given type in the wallet's log
+fn confirmed_txs<L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+	keychain_mask: Option<&SecretKey>,
+	tx_type: TxLogEntryType,
+) -> usize
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	let mut count = 0;
+	wallet::controller::owner_single_use(
+		wallet,
+		keychain_mask,
+		|api, m| {
+			let txs = api.retrieve_txs(m, true, None, None, None, None, None)?;
+			count = txs
+				.txs
+				.iter()
+				.filter(|t| t.tx_type == tx_type && t.confirmed)
+				.count();
+			Ok(())
+		},
+		Arc::new(AtomicBool::new(true)),
+	)
+	.unwrap();
+	count
+}
+
+fn batch_args(input: &Path) -> BatchSendArgs {
+	BatchSendArgs {
+		input: input.to_str().unwrap().to_owned(),
+		format: "json".to_owned(),
+		journal: None,
+		method: "epicbox".to_owned(),
+		message: None,
+		minimum_confirmations: 1,
+		selection_strategy: SelectionStrategy::Smallest,
+		change_outputs: 1,
+		max_outputs: 500,
+		fluff: false,
+		concurrency: 2,
+	}
+}
+
+fn write_payouts(path: &Path, payouts: &[(String, u64)]) {
+	let payouts: Vec<_> = payouts
+		.iter()
+		.map(|(dest, amount)| batch::Payout {
+			dest: dest.clone(),
+			amount: core::amount_to_hr_string(*amount, false),
+			method: None,
+		})
+		.collect();
+	fs::write(path, serde_json::to_string(&payouts).unwrap()).unwrap();
+}
+
+fn journal(input: &Path) -> BatchJournal {
+	let payouts = batch::read_payouts(input, "json").unwrap();
+	let path = format!("{}.journal", input.display());
+	BatchJournal::load_or_create(Path::new(&path), &payouts, "epicbox").unwrap()
+}
+
+/// Refuse a batch the outputs can't cover before locking anything, then pay
+/// two recipients, one of them offline until the batch was run again
+fn batch_resume_test_impl(test_dir: &'static str) -> Result<(), wallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		_client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		_client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+	create_wallet_and_add!(
+		_client3,
+		wallet3,
+		mask3_i,
+		test_dir,
+		"wallet3",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask3 = (&mask3_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let relay = EpicboxRelay::bind("127.0.0.1:0", Duration::from_secs(60))?;
+	let relay_port = relay.local_addr()?.port();
+	thread::spawn(move || relay.run());
+	let config = EpicboxConfig {
+		epicbox_domain: Some("127.0.0.1".to_owned()),
+		epicbox_port: Some(relay_port),
+		epicbox_protocol_unsecure: Some(true),
+		epicbox_address_index: None,
+		epicbox_relays: None,
+	};
+	let dest2 = owner_impl::get_public_address(wallet2.clone(), mask2, config.clone(), 0)?;
+	let dest3 = owner_impl::get_public_address(wallet3.clone(), mask3, config.clone(), 0)?;
+
+	let reward = consensus::reward_at_height(1);
+	test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false)?;
+	let mut spendable = 0;
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+			spendable = (info.amount_currently_spendable / reward) as usize;
+			Ok(())
+		},
+		Arc::new(AtomicBool::new(true)),
+	)?;
+	let send = |input: &Path| {
+		command::batch_send(
+			wallet1.clone(),
+			mask1,
+			Some(TorConfig::default()),
+			Some(config.clone()),
+			batch_args(input),
+			false,
+			Arc::new(AtomicBool::new(true)),
+		)
+	};
+
+	// each payout takes an output of its own, one more than there are: the
+	// total fits the balance, the batch doesn't fit the outputs
+	let too_many = Path::new(test_dir).join("too_many.json");
+	let amount = reward / 2 + reward / 10;
+	write_payouts(&too_many, &vec![(dest2.to_string(), amount); spendable + 1]);
+	assert!(send(&too_many).is_err());
+	let j = journal(&too_many);
+	assert!(j.entries.iter().all(|e| e.status == PayoutStatus::Pending));
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			let txs = api.retrieve_txs(m, true, None, None, None, None, None)?;
+			assert!(txs
+				.txs
+				.iter()
+				.all(|t| t.tx_type == TxLogEntryType::ConfirmedCoinbase));
+			let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+			assert_eq!(info.amount_locked, 0);
+			Ok(())
+		},
+		Arc::new(AtomicBool::new(true)),
+	)?;
+
+	// the sender finalizes the responses, wallet2 is there to answer and
+	// wallet3 isn't
+	listen(wallet1.clone(), mask1_i.clone(), config.clone());
+	listen(wallet2.clone(), mask2_i.clone(), config.clone());
+	let input = Path::new(test_dir).join("payouts.json");
+	write_payouts(
+		&input,
+		&[
+			(dest2.to_string(), reward / 4),
+			(dest3.to_string(), reward / 5),
+		],
+	);
+	send(&input)?;
+	let j = journal(&input);
+	assert!(j.entries.iter().all(|e| e.status == PayoutStatus::Sent));
+	assert!(wait_for(60, || confirmed_txs(
+		wallet2.clone(),
+		mask2,
+		TxLogEntryType::TxReceived
+	) == 1));
+
+	// running it again records the first payout as posted and leaves the
+	// second waiting, without sending it again
+	send(&input)?;
+	let j = journal(&input);
+	assert_eq!(j.entries[0].status, PayoutStatus::Posted);
+	assert_eq!(j.entries[1].status, PayoutStatus::Sent);
+	assert!(!j.is_complete());
+
+	// wallet3 picks up the slate once it listens
+	listen(wallet3.clone(), mask3_i.clone(), config.clone());
+	assert!(wait_for(60, || confirmed_txs(
+		wallet3.clone(),
+		mask3,
+		TxLogEntryType::TxReceived
+	) == 1));
+	send(&input)?;
+	assert!(journal(&input).is_complete());
+
+	// everyone was paid exactly once
+	thread::sleep(Duration::from_secs(2));
+	assert_eq!(
+		confirmed_txs(wallet2.clone(), mask2, TxLogEntryType::TxReceived),
+		1
+	);
+	assert_eq!(
+		confirmed_txs(wallet3.clone(), mask3, TxLogEntryType::TxReceived),
+		1
+	);
+	assert_eq!(
+		confirmed_txs(wallet1.clone(), mask1, TxLogEntryType::TxSent),
+		2
+	);
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn batch_resume() {
+	let test_dir = "test_output/batch_resume";
+	setup(test_dir);
+	if let Err(e) = batch_resume_test_impl(test_dir) {
+		panic!("Libwallet Error: {}", e);
+	}
+	clean_output_dir(test_dir);
+}
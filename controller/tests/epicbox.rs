@@ -0,0 +1,368 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! tests paying over a local epicbox relay, and the epicbox listener
+//! reconnecting to relays that dropped it
+#[macro_use]
+extern crate log;
+extern crate epic_wallet_controller as wallet;
+extern crate epic_wallet_impls as impls;
+
+use epic_wallet_config::{EpicboxConfig, TorConfig};
+use epic_wallet_libwallet as libwallet;
+use epic_wallet_util::epic_core::consensus;
+use epic_wallet_util::epic_util::Mutex;
+use impls::epicbox::relay::EpicboxRelay;
+use impls::test_framework::{self, LocalWalletClient};
+use impls::{EpicboxChannel, EpicboxListenChannel};
+use libwallet::api_impl::owner as owner_impl;
+use libwallet::{EpicboxConnectionState, EpicboxListenerStatus, InitTxArgs, Slate, TxLogEntryType};
+use std::io;
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// Forwards connections to a relay, and drops them on demand
+struct Proxy {
+	port: u16,
+	accepted: Arc<AtomicUsize>,
+	streams: Arc<Mutex<Vec<TcpStream>>>,
+}
+
+impl Proxy {
+	fn start(relay: SocketAddr) -> Proxy {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let port = listener.local_addr().unwrap().port();
+		let accepted = Arc::new(AtomicUsize::new(0));
+		let streams = Arc::new(Mutex::new(vec![]));
+		{
+			let accepted = accepted.clone();
+			let streams = streams.clone();
+			thread::spawn(move || {
+				for client in listener.incoming() {
+					let client = client.unwrap();
+					let server = TcpStream::connect(relay).unwrap();
+					accepted.fetch_add(1, Ordering::SeqCst);
+					streams.lock().push(client.try_clone().unwrap());
+					streams.lock().push(server.try_clone().unwrap());
+					pipe(client.try_clone().unwrap(), server.try_clone().unwrap());
+					pipe(server, client);
+				}
+			});
+		}
+		Proxy {
+			port,
+			accepted,
+			streams,
+		}
+	}
+
+	/// Connections forwarded so far
+	fn accepted(&self) -> usize {
+		self.accepted.load(Ordering::SeqCst)
+	}
+
+	/// Drop the connections forwarded so far, as a relay going away would
+	fn drop_connections(&self) {
+		for s in self.streams.lock().drain(..) {
+			let _ = s.shutdown(Shutdown::Both);
+		}
+	}
+}
+
+fn pipe(mut from: TcpStream, mut to: TcpStream) {
+	thread::spawn(move || {
+		let _ = io::copy(&mut from, &mut to);
+		let _ = to.shutdown(Shutdown::Both);
+	});
+}
+
+/// Wait up to `secs` for `cond` to hold
+fn wait_for<F>(secs: u64, mut cond: F) -> bool
+where
+	F: FnMut() -> bool,
+{
+	let deadline = Instant::now() + Duration::from_secs(secs);
+	while Instant::now() < deadline {
+		if cond() {
+			return true;
+		}
+		thread::sleep(Duration::from_millis(50));
+	}
+	false
+}
+
+/// Send from one wallet to another over a relay. The recipient's listener
+/// receives the slate and sends the response back, the sender's finalizes and
+/// posts it.
+fn epicbox_send_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		_client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		_client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let relay = EpicboxRelay::bind("127.0.0.1:0", Duration::from_secs(60)).unwrap();
+	let relay_port = relay.local_addr().unwrap().port();
+	thread::spawn(move || relay.run());
+	let config = EpicboxConfig {
+		epicbox_domain: Some("127.0.0.1".to_owned()),
+		epicbox_port: Some(relay_port),
+		epicbox_protocol_unsecure: Some(true),
+		epicbox_address_index: None,
+		epicbox_relays: None,
+	};
+	let is_node_synced = Arc::new(AtomicBool::new(true));
+	for (wallet, mask) in [
+		(wallet1.clone(), mask1_i.clone()),
+		(wallet2.clone(), mask2_i.clone()),
+	] {
+		let config = config.clone();
+		let is_node_synced = is_node_synced.clone();
+		thread::spawn(move || {
+			EpicboxListenChannel::new().unwrap().supervise(
+				wallet,
+				Arc::new(Mutex::new(mask)),
+				config,
+				is_node_synced,
+				TorConfig::default(),
+			)
+		});
+	}
+
+	let reward = consensus::reward_at_height(1);
+	let amount = reward / 2;
+	test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false)?;
+	let dest = owner_impl::get_public_address(wallet2.clone(), mask2, config.clone(), 0)?;
+	let dest = dest.to_string();
+
+	let mut slate = Slate::blank(1);
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: false,
+				..Default::default()
+			};
+			slate = api.init_send_tx(m, args, is_node_synced.clone())?;
+			api.tx_lock_outputs(m, &slate, 0, Some(dest.clone()))?;
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+	EpicboxChannel::new(&dest, Some(config.clone()))?.send(
+		wallet1.clone(),
+		mask1_i.clone(),
+		&slate,
+		is_node_synced.clone(),
+		TorConfig::default(),
+	)?;
+
+	// both sides see the transaction confirmed once it's posted
+	let confirmed = |wallet, mask, tx_type| -> usize {
+		let mut count = 0;
+		wallet::controller::owner_single_use(
+			wallet,
+			mask,
+			|api, m| {
+				let txs = api.retrieve_txs(m, true, None, Some(slate.id), None, None, None)?;
+				count = txs
+					.txs
+					.iter()
+					.filter(|t| t.tx_type == tx_type && t.confirmed)
+					.count();
+				Ok(())
+			},
+			is_node_synced.clone(),
+		)
+		.unwrap();
+		count
+	};
+	assert!(wait_for(60, || confirmed(
+		wallet2.clone(),
+		mask2,
+		TxLogEntryType::TxReceived
+	) == 1));
+	assert!(wait_for(20, || confirmed(
+		wallet1.clone(),
+		mask1,
+		TxLogEntryType::TxSent
+	) == 1));
+	wallet::controller::owner_single_use(
+		wallet2.clone(),
+		mask2,
+		|api, m| {
+			let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+			assert_eq!(info.total, amount);
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+/// Listen on two relays, drop the connection to one and then to both, and
+/// check each is connected again on its own and the recorded state
+fn epicbox_reconnect_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+
+	create_wallet_and_add!(
+		_client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let relay = EpicboxRelay::bind("127.0.0.1:0", Duration::from_secs(60)).unwrap();
+	let relay_addr = relay.local_addr().unwrap();
+	thread::spawn(move || relay.run());
+	let first = Proxy::start(relay_addr);
+	let second = Proxy::start(relay_addr);
+
+	let config = EpicboxConfig {
+		epicbox_domain: Some("127.0.0.1".to_owned()),
+		epicbox_port: Some(first.port),
+		epicbox_protocol_unsecure: Some(true),
+		epicbox_address_index: None,
+		epicbox_relays: Some(vec![format!("127.0.0.1:{}", second.port)]),
+	};
+	{
+		let wallet = wallet1.clone();
+		let keychain_mask = Arc::new(Mutex::new(mask1_i.clone()));
+		let is_node_synced = Arc::new(AtomicBool::new(true));
+		thread::spawn(move || {
+			EpicboxListenChannel::new().unwrap().supervise(
+				wallet,
+				keychain_mask,
+				config,
+				is_node_synced,
+				TorConfig::default(),
+			)
+		});
+	}
+
+	let status = || -> Option<EpicboxListenerStatus> {
+		let mut w_lock = wallet1.lock();
+		let w = w_lock.lc_provider().unwrap().wallet_inst().unwrap();
+		w.epicbox_status().unwrap()
+	};
+	let is_listening = || status().map(|s| s.state) == Some(EpicboxConnectionState::Listening);
+
+	assert!(wait_for(20, || is_listening()
+		&& first.accepted() == 1
+		&& second.accepted() == 1));
+
+	// the wallet keeps listening on the first relay while the second is
+	// connected again
+	second.drop_connections();
+	assert!(wait_for(20, || {
+		assert!(is_listening());
+		second.accepted() == 2
+	}));
+	assert_eq!(first.accepted(), 1);
+
+	// with both gone, it's recorded as reconnecting until either is back
+	first.drop_connections();
+	second.drop_connections();
+	assert!(wait_for(10, || match status() {
+		Some(s) => s.state == EpicboxConnectionState::Reconnecting,
+		None => false,
+	}));
+	let s = status().unwrap();
+	assert!(s.reconnect_attempts >= 1);
+	assert!(s.next_attempt.is_some());
+	assert!(s.last_error.is_some());
+	assert!(wait_for(20, || is_listening()
+		&& first.accepted() == 2
+		&& second.accepted() == 3));
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn epicbox_send() {
+	let test_dir = "test_output/epicbox_send";
+	setup(test_dir);
+	if let Err(e) = epicbox_send_test_impl(test_dir) {
+		panic!("Libwallet Error: {}", e);
+	}
+	clean_output_dir(test_dir);
+}
+
+#[test]
+fn epicbox_reconnect() {
+	let test_dir = "test_output/epicbox_reconnect";
+	setup(test_dir);
+	if let Err(e) = epicbox_reconnect_test_impl(test_dir) {
+		panic!("Libwallet Error: {}", e);
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,141 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! tests for transaction and output labels
+#[macro_use]
+extern crate log;
+extern crate epic_wallet_controller as wallet;
+extern crate epic_wallet_impls as impls;
+
+use epic_wallet_libwallet as libwallet;
+use epic_wallet_util::epic_util::to_hex;
+use impls::test_framework::{self, LocalWalletClient};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// Set, clear, search and filter labels
+fn labels_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	// mine a few blocks, each one adds a coinbase tx log entry and output
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	let is_node_synced = Arc::new(AtomicBool::new(true));
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			// quotes must survive the trip through the database
+			api.set_tx_label(m, Some(0), None, Some("Rent for Bob's flat".to_owned()))?;
+			api.set_tx_label(m, Some(1), None, Some("  rent deposit \"May\" ".to_owned()))?;
+			api.set_tx_label(m, Some(2), None, Some("groceries".to_owned()))?;
+			api.set_tx_label(m, Some(2), None, None)?;
+			api.set_tx_label(m, Some(3), None, Some("   ".to_owned()))?;
+			assert!(api
+				.set_tx_label(m, Some(100), None, Some("x".to_owned()))
+				.is_err());
+			assert!(api
+				.set_tx_label(m, None, None, Some("x".to_owned()))
+				.is_err());
+
+			let txs = api
+				.retrieve_txs(m, false, None, None, None, None, None)?
+				.txs;
+			assert_eq!(txs[0].label, Some("Rent for Bob's flat".to_owned()));
+			assert_eq!(txs[1].label, Some("rent deposit \"May\"".to_owned()));
+			assert_eq!(txs[2].label, None);
+			assert_eq!(txs[3].label, None);
+
+			let outputs = api
+				.retrieve_outputs(m, false, false, false, None, None, None, None)?
+				.outputs;
+			let commit = to_hex(outputs[0].commit.as_ref().to_vec());
+			api.set_output_label(m, &commit, Some("cold storage rent".to_owned()))?;
+			assert!(api
+				.set_output_label(m, "deadbeef", Some("x".to_owned()))
+				.is_err());
+
+			// search is case insensitive and covers both txs and outputs
+			let found = api.search_labels(m, "RENT")?;
+			assert_eq!(found.txs.len(), 2);
+			assert_eq!(found.outputs.len(), 1);
+			assert_eq!(to_hex(found.outputs[0].commit.as_ref().to_vec()), commit);
+			let found = api.search_labels(m, "bob's")?;
+			assert_eq!(found.txs.len(), 1);
+			assert_eq!(found.txs[0].id, 0);
+			assert!(found.outputs.is_empty());
+			let found = api.search_labels(m, "groceries")?;
+			assert!(found.txs.is_empty());
+
+			// the 'txs --label' filter paginates the matching entries only
+			let mut result = api.retrieve_txs(m, false, None, None, None, None, None)?;
+			result.filter_by_label("rent", None, None);
+			assert_eq!(result.pager.total_records, 2);
+			assert_eq!(result.pager.records_read, 2);
+			let mut result = api.retrieve_txs(m, false, None, None, None, None, None)?;
+			result.filter_by_label("rent", Some(1), Some(1));
+			assert_eq!(result.pager.total_records, 2);
+			assert_eq!(result.pager.records_read, 1);
+			assert_eq!(result.pager.offset, 1);
+			assert_eq!(result.txs[0].id, 1);
+			let mut result = api.retrieve_txs(m, false, None, None, None, None, None)?;
+			result.filter_by_label("nothing", None, None);
+			assert!(result.txs.is_empty());
+			assert_eq!(result.pager.total_records, 0);
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn labels() {
+	let test_dir = "test_output/labels";
+	setup(test_dir);
+	if let Err(e) = labels_test_impl(test_dir) {
+		panic!("Libwallet Error: {}", e);
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,213 @@
+// Copyright 2019 The Epic Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! tests height locked transactions
+#[macro_use]
+extern crate log;
+extern crate epic_wallet_controller as wallet;
+extern crate epic_wallet_impls as impls;
+extern crate epic_wallet_util;
+
+use epic_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, Slate, TxLogEntryType};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// Test height locked sends
+fn lock_height_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+
+	let mask1 = (&mask1_i).as_ref();
+
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	// Do some mining
+	let bh = 10u64;
+	let _ =
+		test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, bh as usize, false);
+
+	let amount = 1457720000;
+	let is_node_synced = Arc::new(AtomicBool::new(true));
+
+	// A lock that can't be reached before the TTL is refused
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|sender_api, m| {
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: true,
+				ttl_blocks: Some(2),
+				lock_after_blocks: Some(5),
+				..Default::default()
+			};
+			let res = sender_api.init_send_tx(m, args, is_node_synced.clone());
+			assert!(res.is_err());
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+
+	// Lock the transaction for 5 blocks, it can't be posted yet
+	let mut slate = Slate::blank(1);
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|sender_api, m| {
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: true,
+				lock_after_blocks: Some(5),
+				..Default::default()
+			};
+			let slate_i = sender_api.init_send_tx(m, args, is_node_synced.clone())?;
+			assert_eq!(slate_i.lock_height, 15);
+
+			slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
+			sender_api.tx_lock_outputs(m, &slate, 0, None)?;
+			slate = sender_api.finalize_tx(m, &slate)?;
+
+			let res = sender_api.post_tx(m, &slate.tx, false);
+			match res {
+				Err(libwallet::Error::TransactionLocked(15, 10)) => {}
+				r => panic!("Unexpected post result: {:?}", r),
+			}
+
+			let txs = sender_api.retrieve_txs(m, true, None, Some(slate.id), None, None, None)?;
+			assert_eq!(txs.txs[0].lock_height, Some(15));
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+
+	// The receiver knows about the lock as well
+	wallet::controller::owner_single_use(
+		wallet2.clone(),
+		mask2,
+		|api, m| {
+			let txs = api.retrieve_txs(m, true, None, Some(slate.id), None, None, None)?;
+			assert_eq!(txs.txs[0].lock_height, Some(15));
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+
+	// Not yet valid, so it can be cancelled
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|sender_api, m| {
+			sender_api.cancel_tx(m, None, Some(slate.id))?;
+			let txs = sender_api.retrieve_txs(m, true, None, Some(slate.id), None, None, None)?;
+			assert!(txs.txs[0].tx_type == TxLogEntryType::TxSentCancelled);
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+
+	// Try again, and post once the lock height is reached
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|sender_api, m| {
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: true,
+				lock_height: Some(13),
+				..Default::default()
+			};
+			let slate_i = sender_api.init_send_tx(m, args, is_node_synced.clone())?;
+			slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
+			sender_api.tx_lock_outputs(m, &slate, 0, None)?;
+			slate = sender_api.finalize_tx(m, &slate)?;
+			assert!(sender_api.post_tx(m, &slate.tx, false).is_err());
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 2, false);
+
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|sender_api, m| {
+			sender_api.post_tx(m, &slate.tx, false)?;
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn lock_height() {
+	let test_dir = "test_output/lock_height";
+	setup(test_dir);
+	if let Err(e) = lock_height_test_impl(test_dir) {
+		panic!("Libwallet Error: {}", e);
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,303 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! tests the events pushed to owner API clients
+#[macro_use]
+extern crate log;
+extern crate epic_wallet_api as api;
+extern crate epic_wallet_controller as wallet;
+extern crate epic_wallet_impls as impls;
+extern crate epic_wallet_libwallet as libwallet;
+
+use api::{EncryptedResponse, OwnerEvent, OwnerEventStream};
+use epic_wallet_util::epic_util::secp::key::{PublicKey, SecretKey};
+use epic_wallet_util::epic_util::{from_hex, static_secp_instance, to_hex, Mutex};
+use impls::test_framework::{self, LocalWalletClient};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+const LISTENER_ADDR: &str = "127.0.0.1:13425";
+
+/// Only report what changed after the stream started
+fn owner_event_stream_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		_client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let is_node_synced = Arc::new(AtomicBool::new(true));
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+	let owner = Arc::new(api::Owner::new(wallet1.clone(), None, is_node_synced));
+	owner.retrieve_summary_info(mask1, true, 1)?;
+
+	let mut stream = OwnerEventStream::new(owner.clone());
+	assert!(stream.next_events(Duration::from_millis(100)).is_empty());
+
+	// new transactions, the balance they change and the messages of the
+	// update that found them
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 2, false);
+	owner.retrieve_summary_info(mask1, true, 1)?;
+	let events = stream.next_events(Duration::from_millis(100));
+	let txs: Vec<u32> = events
+		.iter()
+		.filter_map(|e| match e {
+			OwnerEvent::Transaction(tx) => Some(tx.id),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(txs, vec![5, 6]);
+	assert!(events.iter().any(|e| match e {
+		OwnerEvent::Balance(b) => b.total > 0,
+		_ => false,
+	}));
+	assert!(events.iter().any(|e| match e {
+		OwnerEvent::UpdaterStatus(_) => true,
+		_ => false,
+	}));
+	assert!(stream.next_events(Duration::from_millis(100)).is_empty());
+
+	// another account is a fresh start rather than a change
+	owner.create_account_path(mask1, "other")?;
+	owner.set_active_account(mask1, "other")?;
+	assert!(stream.next_events(Duration::from_millis(100)).is_empty());
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 1, false);
+	let events = stream.next_events(Duration::from_millis(100));
+	let txs: Vec<u32> = events
+		.iter()
+		.filter_map(|e| match e {
+			OwnerEvent::Transaction(tx) => Some(tx.id),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(txs, vec![0]);
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+/// Response to a request, once the listener closed the connection
+fn http_request(request: &str) -> String {
+	let mut stream = TcpStream::connect(LISTENER_ADDR).unwrap();
+	stream.write_all(request.as_bytes()).unwrap();
+	let mut response = String::new();
+	stream.read_to_string(&mut response).unwrap();
+	response
+}
+
+fn post_owner(body: &str) -> serde_json::Value {
+	let response = http_request(&format!(
+		"POST /v3/owner HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+		 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+		LISTENER_ADDR,
+		body.len(),
+		body
+	));
+	let body = &response[response.find("\r\n\r\n").unwrap() + 4..];
+	serde_json::from_str(body).unwrap()
+}
+
+const EVENTS_REQUEST: &str = "GET /v3/owner/events HTTP/1.1\r\nHost: localhost\r\n\r\n";
+const EVENTS_CLOSE_REQUEST: &str =
+	"GET /v3/owner/events HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+
+/// Shared key agreed with the listener through `init_secure_api`
+fn init_secure_api() -> SecretKey {
+	let secp_inst = static_secp_instance();
+	let secp = secp_inst.lock();
+	let sec_key = SecretKey::new(&secp, &mut rand::rng());
+	let pub_key = PublicKey::from_secret_key(&secp, &sec_key).unwrap();
+	let res = post_owner(
+		&serde_json::json!({
+			"jsonrpc": "2.0",
+			"method": "init_secure_api",
+			"params": { "ecdh_pubkey": to_hex(pub_key.serialize_vec(&secp, true).to_vec()) },
+			"id": 1
+		})
+		.to_string(),
+	);
+	let server_key = from_hex(res["result"]["Ok"].as_str().unwrap().to_owned()).unwrap();
+	let mut shared_pubkey = PublicKey::from_slice(&secp, &server_key).unwrap();
+	shared_pubkey.mul_assign(&secp, &sec_key).unwrap();
+	let x_coord = shared_pubkey.serialize_vec(&secp, true);
+	SecretKey::from_slice(&secp, &x_coord[1..]).unwrap()
+}
+
+/// Client of the event stream, past the response headers
+fn connect_events() -> BufReader<TcpStream> {
+	let mut stream = TcpStream::connect(LISTENER_ADDR).unwrap();
+	stream
+		.set_read_timeout(Some(Duration::from_secs(30)))
+		.unwrap();
+	stream.write_all(EVENTS_REQUEST.as_bytes()).unwrap();
+	let mut reader = BufReader::new(stream);
+	let mut headers = String::new();
+	loop {
+		let mut line = String::new();
+		reader.read_line(&mut line).unwrap();
+		if line == "\r\n" {
+			break;
+		}
+		headers.push_str(&line);
+	}
+	assert!(headers.starts_with("HTTP/1.1 200"));
+	assert!(headers.contains("text/event-stream"));
+	reader
+}
+
+/// Next transaction event streamed, with its event id
+fn next_tx_event(reader: &mut BufReader<TcpStream>, key: &SecretKey) -> (u64, u32) {
+	let mut id = 0;
+	loop {
+		let mut line = String::new();
+		reader.read_line(&mut line).unwrap();
+		if let Some(i) = line.strip_prefix("id: ") {
+			id = i.trim().parse().unwrap();
+		}
+		if let Some(data) = line.strip_prefix("data: ") {
+			let res: EncryptedResponse = serde_json::from_str(data).unwrap();
+			let event = res.decrypt(key).unwrap();
+			if event["type"] == "transaction" {
+				return (id, event["data"]["id"].as_u64().unwrap() as u32);
+			}
+		}
+	}
+}
+
+/// Stream the same events to several clients over the owner API
+fn owner_events_endpoint_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		_client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+
+	let listener_wallet = wallet1.clone();
+	let listener_mask = Arc::new(Mutex::new(mask1_i.clone()));
+	thread::spawn(move || {
+		let res = wallet::controller::owner_listener(
+			listener_wallet,
+			listener_mask,
+			LISTENER_ADDR,
+			None,
+			None,
+			None,
+			None,
+			None,
+			None,
+			Arc::new(AtomicBool::new(true)),
+		);
+		if let Err(e) = res {
+			error!("Owner listener error: {}", e);
+		}
+	});
+	let mut tries = 0;
+	while TcpStream::connect(LISTENER_ADDR).is_err() {
+		tries += 1;
+		assert!(tries < 100, "Owner listener didn't start");
+		thread::sleep(Duration::from_millis(100));
+	}
+
+	// events are encrypted, so there are none before the shared key
+	let response = http_request(EVENTS_CLOSE_REQUEST);
+	assert!(response.contains("-32001"));
+	let key = init_secure_api();
+
+	// every client is sent the same events
+	let mut client_a = connect_events();
+	let mut client_b = connect_events();
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 1, false);
+	let event_a = next_tx_event(&mut client_a, &key);
+	let event_b = next_tx_event(&mut client_b, &key);
+	assert_eq!(event_a, event_b);
+	assert_eq!(event_a.1, 3);
+
+	// clients can come and go
+	drop(client_a);
+	drop(client_b);
+	thread::sleep(Duration::from_secs(6));
+	let mut client_c = connect_events();
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 1, false);
+	let (_, tx_id) = next_tx_event(&mut client_c, &key);
+	assert_eq!(tx_id, 4);
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn owner_event_stream() {
+	let test_dir = "test_output/owner_event_stream";
+	setup(test_dir);
+	if let Err(e) = owner_event_stream_test_impl(test_dir) {
+		panic!("Libwallet Error: {}", e);
+	}
+	clean_output_dir(test_dir);
+}
+
+#[test]
+fn owner_events_endpoint() {
+	let test_dir = "test_output/owner_events_endpoint";
+	setup(test_dir);
+	if let Err(e) = owner_events_endpoint_test_impl(test_dir) {
+		panic!("Libwallet Error: {}", e);
+	}
+	clean_output_dir(test_dir);
+}
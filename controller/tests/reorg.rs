@@ -0,0 +1,253 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! tests rolling back confirmations after the chain forked under the wallet
+#[macro_use]
+extern crate log;
+extern crate epic_wallet_controller as wallet;
+extern crate epic_wallet_impls as impls;
+
+use epic_wallet_libwallet as libwallet;
+use epic_wallet_util::epic_core::consensus;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{
+	InitTxArgs, NodeClient, OutputData, OutputStatus, ScannedBlockHistory, Slate, StatusMessage,
+	TxLogEntryType,
+};
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// Fork height and transactions of the reorg reported on the channel, if any
+fn reorg_reported(rx: &Receiver<StatusMessage>) -> Option<(u64, Vec<u32>)> {
+	while let Ok(m) = rx.recv_timeout(Duration::from_secs(1)) {
+		if let StatusMessage::Reorg(_, height, tx_ids) = m {
+			return Some((height, tx_ids));
+		}
+	}
+	None
+}
+
+/// Roll back a transaction confirmed on a fork the node no longer follows
+fn reorg_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		_client2,
+		_wallet2,
+		_mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = consensus::reward_at_height(1);
+	let is_node_synced = Arc::new(AtomicBool::new(true));
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	// build a transaction, but hold it back from the node
+	let mut slate = Slate::blank(1);
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			api.retrieve_summary_info(m, true, 1)?;
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount: reward * 2,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: false,
+				..Default::default()
+			};
+			let slate_i = api.init_send_tx(m, args, is_node_synced.clone())?;
+			slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
+			api.tx_lock_outputs(m, &slate, 0, None)?;
+			slate = api.finalize_tx(m, &slate)?;
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+
+	// pretend the wallet saw it mined at the tip, on a branch the node
+	// then dropped
+	let (tip_height, _) = client1.get_chain_tip()?;
+	let below_tip = client1.get_header_hash(tip_height - 1)?;
+	let (tx_id, inputs) = {
+		wallet_inst!(wallet1, w);
+		let mut tx = w
+			.tx_log_iter()
+			.find(|t| t.tx_slate_id == Some(slate.id))
+			.unwrap();
+		let inputs: Vec<OutputData> = w
+			.iter()
+			.filter(|o| o.status == OutputStatus::Locked)
+			.collect();
+		assert!(!inputs.is_empty());
+		let mut history = w.scanned_block_history()?;
+		history.push(tip_height - 1, below_tip);
+		history.push(tip_height, "00".repeat(32));
+
+		let mut batch = w.batch(mask1)?;
+		for o in inputs.iter() {
+			let mut o = o.clone();
+			o.status = OutputStatus::Spent;
+			batch.save(o)?;
+		}
+		tx.confirmed = true;
+		tx.confirmation_height = Some(tip_height);
+		let parent_key_id = tx.parent_key_id.clone();
+		batch.save_tx_log_entry(tx.clone(), &parent_key_id)?;
+		batch.save_scanned_block_history(history)?;
+		batch.commit()?;
+		(tx.id, inputs)
+	};
+	let locked: u64 = inputs.iter().map(|o| o.value).sum();
+
+	// the transaction is pending again and its inputs are locked by it,
+	// rather than found unspent and the transaction cancelled
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			let rx = api.subscribe_updater_messages();
+			let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+			let (fork_height, tx_ids) = reorg_reported(&rx).unwrap();
+			assert_eq!(fork_height, tip_height);
+			assert!(tx_ids.contains(&tx_id));
+			assert_eq!(info.amount_locked, locked);
+
+			let txs = api
+				.retrieve_txs(m, false, Some(tx_id), None, None, None, None)?
+				.txs;
+			assert!(!txs[0].confirmed);
+			assert_eq!(txs[0].confirmation_height, None);
+			assert_ne!(txs[0].tx_type, TxLogEntryType::TxSentCancelled);
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+	{
+		wallet_inst!(wallet1, w);
+		for input in inputs.iter() {
+			let o = w.get(&input.key_id, &input.mmr_index)?;
+			assert_eq!(o.status, OutputStatus::Locked);
+		}
+	}
+
+	// once it's posted and mined, it's confirmed for good
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			api.post_tx(m, &slate.tx, false)?;
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+	let mut total = 0;
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			let rx = api.subscribe_updater_messages();
+			let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+			assert!(reorg_reported(&rx).is_none());
+			assert_eq!(info.amount_locked, 0);
+			let txs = api
+				.retrieve_txs(m, false, Some(tx_id), None, None, None, None)?
+				.txs;
+			assert!(txs[0].confirmed);
+			total = info.total;
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+
+	// a fork below every recorded tip rolls everything back, and the full
+	// rescan that follows finds it all again
+	let (tip_height, _) = client1.get_chain_tip()?;
+	{
+		wallet_inst!(wallet1, w);
+		let history = ScannedBlockHistory {
+			blocks: vec![(tip_height, "00".repeat(32))],
+		};
+		let mut batch = w.batch(mask1)?;
+		batch.save_scanned_block_history(history)?;
+		batch.commit()?;
+	}
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			let rx = api.subscribe_updater_messages();
+			let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+			let (fork_height, tx_ids) = reorg_reported(&rx).unwrap();
+			assert_eq!(fork_height, 0);
+			assert!(tx_ids.contains(&tx_id));
+			assert_eq!(info.total, total);
+			assert_eq!(info.amount_locked, 0);
+			let txs = api
+				.retrieve_txs(m, false, None, None, None, None, None)?
+				.txs;
+			assert!(txs.iter().all(|t| t.confirmed));
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn reorg() {
+	let test_dir = "test_output/reorg";
+	setup(test_dir);
+	if let Err(e) = reorg_test_impl(test_dir) {
+		panic!("Libwallet Error: {}", e);
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,181 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! tests resuming an interrupted scan from its checkpoint
+#[macro_use]
+extern crate log;
+extern crate epic_wallet_controller as wallet;
+extern crate epic_wallet_impls as impls;
+
+use epic_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{NodeClient, OutputCommitMapping, ScanCheckpoint, ScannedOutput};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+fn scanned(o: &OutputCommitMapping) -> ScannedOutput {
+	ScannedOutput {
+		commit: o.commit,
+		key_id: o.output.key_id.clone(),
+		n_child: o.output.n_child,
+		mmr_index: o.output.mmr_index.unwrap(),
+		value: o.output.value,
+		height: o.output.height,
+		lock_height: o.output.lock_height,
+		is_coinbase: o.output.is_coinbase,
+	}
+}
+
+/// Resume a scan from a checkpoint, and discard one that doesn't belong
+fn scan_checkpoint_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	let is_node_synced = Arc::new(AtomicBool::new(true));
+	let mut outputs = vec![];
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			outputs = api
+				.retrieve_outputs(m, false, true, false, None, None, None, None)?
+				.outputs;
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+	outputs.sort_by_key(|o| o.output.mmr_index);
+	assert_eq!(outputs.len(), 10);
+
+	// pretend a scan got interrupted after the fifth output and forget all
+	// outputs. The third one is left out of the checkpoint: as its range
+	// is past the checkpoint, resuming won't find it again.
+	let pmmr_range = client1.height_range_to_pmmr_indices(1, None)?;
+	let mut checkpoint = ScanCheckpoint::new(1, 10, pmmr_range);
+	checkpoint.last_pmmr_index = outputs[4].output.mmr_index;
+	checkpoint.outputs_found = 4;
+	{
+		wallet_inst!(wallet1, w);
+		let mut batch = w.batch(mask1)?;
+		for (i, o) in outputs.iter().enumerate() {
+			if i < 5 && i != 2 {
+				batch.save_scanned_output(scanned(o))?;
+			}
+			batch.delete(
+				&o.output.key_id,
+				&o.output.mmr_index,
+				&o.output.tx_log_entry,
+			)?;
+		}
+		batch.save_scan_checkpoint(checkpoint)?;
+		batch.commit()?;
+	}
+
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			let progress = api.scan_progress(m)?.unwrap();
+			assert_eq!(progress.outputs_found, 4);
+			assert_eq!(progress.last_pmmr_index, outputs[4].output.mmr_index);
+			assert!(progress.percentage_complete > 0);
+			assert!(progress.percentage_complete < 100);
+
+			api.scan(m, Some(1), false)?;
+			assert!(api.scan_progress(m)?.is_none());
+			let restored = api
+				.retrieve_outputs(m, false, false, false, None, None, None, None)?
+				.outputs;
+			assert_eq!(restored.len(), 9);
+			assert!(restored.iter().all(|o| o.commit != outputs[2].commit));
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+	{
+		wallet_inst!(wallet1, w);
+		assert_eq!(w.scanned_outputs().count(), 0);
+	}
+
+	// a checkpoint whose outputs don't match the keychain is discarded, and
+	// the scan starts over
+	let mut checkpoint = ScanCheckpoint::new(1, 10, pmmr_range);
+	checkpoint.last_pmmr_index = Some(pmmr_range.1);
+	checkpoint.outputs_found = 1;
+	let mut bogus = scanned(&outputs[0]);
+	bogus.commit = outputs[1].commit;
+	{
+		wallet_inst!(wallet1, w);
+		let mut batch = w.batch(mask1)?;
+		batch.save_scanned_output(bogus)?;
+		batch.save_scan_checkpoint(checkpoint)?;
+		batch.commit()?;
+	}
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			api.scan(m, Some(1), false)?;
+			let restored = api
+				.retrieve_outputs(m, false, false, false, None, None, None, None)?
+				.outputs;
+			assert_eq!(restored.len(), 10);
+			assert!(api.scan_progress(m)?.is_none());
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn scan_checkpoint() {
+	let test_dir = "test_output/scan_checkpoint";
+	setup(test_dir);
+	if let Err(e) = scan_checkpoint_test_impl(test_dir) {
+		panic!("Libwallet Error: {}", e);
+	}
+	clean_output_dir(test_dir);
+}
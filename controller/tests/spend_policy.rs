@@ -0,0 +1,214 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! tests the spending policy is enforced on the transactions the wallet creates
+#[macro_use]
+extern crate log;
+extern crate epic_wallet_controller as wallet;
+extern crate epic_wallet_impls as impls;
+
+use epic_wallet_config::SpendPolicy;
+use epic_wallet_libwallet as libwallet;
+use epic_wallet_util::epic_core::consensus;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{Error, InitTxArgs, IssueInvoiceTxArgs, Slate};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+fn send_args(amount: u64) -> InitTxArgs {
+	InitTxArgs {
+		src_acct_name: None,
+		amount,
+		minimum_confirmations: 2,
+		max_outputs: 500,
+		num_change_outputs: 1,
+		selection_strategy_is_use_all: false,
+		..Default::default()
+	}
+}
+
+fn is_violation<T>(res: &Result<T, Error>) -> bool {
+	matches!(res, Err(Error::PolicyViolation(_)))
+}
+
+/// Refuse transactions breaking the policy, however far they got
+fn spend_policy_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		_client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = consensus::reward_at_height(1);
+	let is_node_synced = Arc::new(AtomicBool::new(true));
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	let set_policy = |policy: SpendPolicy| -> Result<(), Error> {
+		let mut w_lock = wallet1.lock();
+		w_lock.lc_provider()?.set_spend_policy(policy)
+	};
+
+	// a single transaction over the maximum is refused
+	set_policy(SpendPolicy {
+		max_tx_amount: Some(reward),
+		..Default::default()
+	})?;
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			api.retrieve_summary_info(m, true, 1)?;
+			let res = api.init_send_tx(m, send_args(reward * 2), is_node_synced.clone());
+			assert!(is_violation(&res));
+			let res = api.init_send_tx_to(m, send_args(reward * 2), "wallet2");
+			assert!(is_violation(&res));
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+
+	// once a send went through, the daily limit counts it
+	set_policy(SpendPolicy {
+		daily_limit: Some(reward * 3),
+		..Default::default()
+	})?;
+	let mut slate = Slate::blank(1);
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			let slate_i = api.init_send_tx_to(m, send_args(reward * 2), "wallet2")?;
+			slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
+			api.tx_lock_outputs(m, &slate, 0, Some("wallet2".to_owned()))?;
+			slate = api.finalize_tx(m, &slate)?;
+
+			let res = api.init_send_tx_to(m, send_args(reward * 2), "wallet2");
+			assert!(is_violation(&res));
+
+			// still fits, until the limit is lowered before it's finalized
+			let slate_i = api.init_send_tx_to(m, send_args(reward), "wallet2")?;
+			slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
+			api.tx_lock_outputs(m, &slate, 0, Some("wallet2".to_owned()))?;
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+	set_policy(SpendPolicy {
+		daily_limit: Some(reward * 2),
+		..Default::default()
+	})?;
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			let res = api.finalize_tx(m, &slate);
+			assert!(is_violation(&res));
+			api.cancel_tx(m, None, Some(slate.id))?;
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+
+	// only declared, allowed destinations may be sent to
+	set_policy(SpendPolicy {
+		allowed_destinations: Some(vec!["wallet2".to_owned()]),
+		..Default::default()
+	})?;
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			api.init_send_tx_to(m, send_args(reward), "wallet2")?;
+			let res = api.init_send_tx_to(m, send_args(reward), "elsewhere");
+			assert!(is_violation(&res));
+			let res = api.init_send_tx(m, send_args(reward), is_node_synced.clone());
+			assert!(is_violation(&res));
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+
+	// invoices have no destination, so can't be paid
+	wallet::controller::owner_single_use(
+		wallet2.clone(),
+		mask2,
+		|api, m| {
+			let args = IssueInvoiceTxArgs {
+				amount: reward,
+				..Default::default()
+			};
+			slate = api.issue_invoice_tx(m, args)?;
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			let res = api.process_invoice_tx(m, &slate, send_args(slate.amount));
+			assert!(is_violation(&res));
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn spend_policy() {
+	let test_dir = "test_output/spend_policy";
+	setup(test_dir);
+	if let Err(e) = spend_policy_test_impl(test_dir) {
+		panic!("Libwallet Error: {}", e);
+	}
+	clean_output_dir(test_dir);
+}
@@ -103,6 +103,7 @@ fn updater_thread_test_impl(test_dir: &'static str) -> Result<(), libwallet::Err
 		test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, bh as usize, false);
 
 	let owner_api = api::Owner::new(wallet1, None, is_node_synced.clone());
+	let subscription = owner_api.subscribe_updater_messages();
 	owner_api.start_updater(mask1, Duration::from_secs(5))?;
 
 	// let updater thread run a bit
@@ -110,6 +111,8 @@ fn updater_thread_test_impl(test_dir: &'static str) -> Result<(), libwallet::Err
 
 	let messages = owner_api.get_updater_messages(1000)?;
 	assert_eq!(messages.len(), 32);
+	// subscribers are pushed the same messages
+	assert_eq!(subscription.try_iter().count(), 32);
 
 	owner_api.stop_updater()?;
 	thread::sleep(Duration::from_secs(2));
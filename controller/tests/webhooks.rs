@@ -0,0 +1,198 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! tests queueing webhook events and recording delivery attempts
+#[macro_use]
+extern crate log;
+extern crate epic_wallet_controller as wallet;
+extern crate epic_wallet_impls as impls;
+
+use epic_wallet_config::WebhookConfig;
+use epic_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{webhooks, WebhookEventType};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// Queue events for a wallet with history and a brand new one, then record
+/// deliveries that succeed, fail and run out of attempts
+fn webhooks_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		_client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		_client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let is_node_synced = Arc::new(AtomicBool::new(true));
+	let config = WebhookConfig {
+		urls: vec![
+			"http://127.0.0.1:1/a".to_owned(),
+			"http://127.0.0.1:1/b".to_owned(),
+		],
+		confirmations: 1,
+		..Default::default()
+	};
+
+	// history from before webhooks were enabled isn't replayed
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			api.retrieve_summary_info(m, true, 1)?;
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+	{
+		wallet_inst!(wallet1, w);
+		let tip_height = w.last_confirmed_height()?;
+		assert_eq!(w.webhooks_init_height()?, None);
+		let queued = webhooks::queue_events(&mut **w, mask1, &config, tip_height)?;
+		assert_eq!(queued, 0);
+		assert_eq!(w.webhooks_init_height()?, Some(tip_height));
+		assert_eq!(w.webhook_tx_state_iter().count(), 5);
+	}
+
+	// a wallet with nothing to report yet still reports its first
+	// transaction, even if it's settled by the time it's seen
+	{
+		wallet_inst!(wallet2, w);
+		let tip_height = w.last_confirmed_height()?;
+		let queued = webhooks::queue_events(&mut **w, mask2, &config, tip_height)?;
+		assert_eq!(queued, 0);
+		assert!(w.webhooks_init_height()?.is_some());
+	}
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet2.clone(), mask2, 1, false);
+	wallet::controller::owner_single_use(
+		wallet2.clone(),
+		mask2,
+		|api, m| {
+			api.retrieve_summary_info(m, true, 1)?;
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+	let due = {
+		wallet_inst!(wallet2, w);
+		let tip_height = w.last_confirmed_height()?;
+		let queued = webhooks::queue_events(&mut **w, mask2, &config, tip_height)?;
+		// received and confirmed, to each URL
+		assert_eq!(queued, 4);
+		assert_eq!(
+			webhooks::queue_events(&mut **w, mask2, &config, tip_height)?,
+			0
+		);
+		webhooks::due_deliveries(&mut **w)
+	};
+	assert_eq!(due.len(), 4);
+	assert_eq!(
+		due.iter()
+			.filter(|d| d.event == WebhookEventType::Confirmed)
+			.count(),
+		2
+	);
+
+	// delivered events leave the queue, failed ones wait for their retry
+	// and are dropped once out of attempts
+	{
+		wallet_inst!(wallet2, w);
+		let err = || Err(libwallet::Error::GenericError("refused".to_owned()));
+		webhooks::record_attempt(&mut **w, mask2, due[0].clone(), Ok(()), 3)?;
+		webhooks::record_attempt(&mut **w, mask2, due[1].clone(), err(), 3)?;
+		webhooks::record_attempt(&mut **w, mask2, due[2].clone(), err(), 1)?;
+
+		let queue: Vec<_> = w.webhook_delivery_iter().collect();
+		assert_eq!(queue.len(), 2);
+		assert!(queue.iter().all(|d| d.delivery_id != due[0].delivery_id));
+		assert!(queue.iter().all(|d| d.delivery_id != due[2].delivery_id));
+		let retried = queue
+			.iter()
+			.find(|d| d.delivery_id == due[1].delivery_id)
+			.unwrap();
+		assert_eq!(retried.attempts, 1);
+		assert_eq!(retried.payload, due[1].payload);
+		assert!(retried.next_attempt - due[1].next_attempt >= webhooks::retry_delay(1));
+
+		let still_due = webhooks::due_deliveries(&mut **w);
+		assert_eq!(still_due.len(), 1);
+		assert_eq!(still_due[0].delivery_id, due[3].delivery_id);
+	}
+
+	// the first wallet reports its new transactions too
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 1, false);
+	wallet::controller::owner_single_use(
+		wallet1.clone(),
+		mask1,
+		|api, m| {
+			api.retrieve_summary_info(m, true, 1)?;
+			Ok(())
+		},
+		is_node_synced.clone(),
+	)?;
+	{
+		wallet_inst!(wallet1, w);
+		let tip_height = w.last_confirmed_height()?;
+		let queued = webhooks::queue_events(&mut **w, mask1, &config, tip_height)?;
+		assert_eq!(queued, 4);
+	}
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn webhooks() {
+	let test_dir = "test_output/webhooks";
+	setup(test_dir);
+	if let Err(e) = webhooks_test_impl(test_dir) {
+		panic!("Libwallet Error: {}", e);
+	}
+	clean_output_dir(test_dir);
+}
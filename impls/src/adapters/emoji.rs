@@ -15,6 +15,7 @@
 use bitvec::prelude::*;
 use emoji::{self, Emoji};
 
+use crate::libwallet::slate_versions::v4::BINARY_MAGIC;
 use crate::libwallet::{Error, Slate, SlateVersion, VersionedSlate};
 
 #[derive(Clone)]
@@ -1147,6 +1148,14 @@ impl EmojiSlate {
 	}
 
 	pub fn encode(&self, slate: &Slate) -> String {
+		// compact slates travel in their binary form
+		if slate.version_info.orig_version >= 4 {
+			return match slate.serialize_binary() {
+				Ok(b) => self.encode_bytes(b),
+				Err(_) => "ERROR - Slate binary generation".to_string(),
+			};
+		}
+
 		let out_slate = {
 			if slate.payment_proof.is_some() || slate.ttl_cutoff_height.is_some() {
 				warn!("Transaction contains features that require epic-wallet 3.0.0 or later");
@@ -1166,6 +1175,10 @@ impl EmojiSlate {
 		}
 		.into_bytes();
 
+		self.encode_bytes(slate_str)
+	}
+
+	fn encode_bytes(&self, slate_str: Vec<u8>) -> String {
 		let mut bitstream: BitVec = BitVec::new();
 
 		for bit in self.set_extra_bits(slate_str.len()) {
@@ -1202,6 +1215,11 @@ impl EmojiSlate {
 	pub fn decode(&self, emoji_string: &str) -> Result<Slate, Error> {
 		let slate_string = self.translate2string(emoji_string);
 
+		// every char holds a single byte of the encoded slate
+		let slate_bin: Vec<u8> = slate_string.chars().map(|c| c as u8).collect();
+		if slate_bin.starts_with(BINARY_MAGIC) {
+			return Ok(Slate::deserialize_upgrade_binary(&slate_bin)?);
+		}
 		Ok(Slate::deserialize_upgrade(&slate_string)?)
 	}
 }
@@ -23,7 +23,8 @@ use crate::util::secp::key::PublicKey;
 
 use crate::libwallet::wallet_lock;
 use crate::libwallet::{
-	address, Address, EpicboxAddress, TxProof, DEFAULT_EPICBOX_PORT_443, DEFAULT_EPICBOX_PORT_80,
+	address, backoff_secs, Address, EpicboxAddress, EpicboxConnectionState, EpicboxListenerStatus,
+	TxProof, DEFAULT_EPICBOX_PORT_443, DEFAULT_EPICBOX_PORT_80,
 };
 use crate::libwallet::{NodeClient, WalletInst, WalletLCProvider};
 
@@ -33,7 +34,7 @@ use crate::libwallet::{Slate, SlateVersion, VersionedSlate};
 use crate::util::secp::key::SecretKey;
 use crate::util::Mutex;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::{self, Debug};
 
 use std::sync::Arc;
@@ -46,11 +47,16 @@ use epic_wallet_util::epic_core::core::amount_to_hr_string;
 use rand::rng;
 use rand::seq::SliceRandom;
 use std::env;
+use std::io;
 use std::net::TcpStream;
 use std::string::ToString;
 use std::sync::atomic::AtomicBool;
 use std::sync::mpsc::{channel, Receiver, Sender};
-use std::thread::spawn;
+use std::thread::{self, spawn};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use chrono::Utc;
 
 use tungstenite::connect;
 use tungstenite::Error as tungsteniteError;
@@ -70,11 +76,27 @@ use tungstenite::{Error as ErrorTungstenite, Message};
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-const CONNECTION_ERR_MSG: &str = "\nCan't connect to the epicbox server!\n\
-	Check your epic-wallet.toml settings and make sure epicbox domain is correct.\n";
-
 const EPICBOX_PROTOCOL_VERSION: &str = "3.0.0";
 
+/// How long to wait for the relay to accept a slate
+const RELAY_ACK_TIMEOUT_SECS: u64 = 10;
+/// How often the listener looks for outbox slates to send again
+const OUTBOX_POLL_SECS: u64 = 30;
+/// How long a listener read blocks, posts on the connection wait for it
+const LISTEN_READ_TIMEOUT_MS: u64 = 500;
+/// Number of received slates remembered to skip those received twice
+const SEEN_SLATES_LEN: usize = 1000;
+/// Delay before connecting again after the connection was lost
+const RECONNECT_BASE_SECS: u64 = 5;
+/// Longest delay between two attempts to connect
+const RECONNECT_MAX_SECS: u64 = 300;
+/// How often relays are pinged, to tell when a connection silently died
+const HEARTBEAT_SECS: u64 = 30;
+/// A relay that sent nothing for this long, pongs included, is gone
+const HEARTBEAT_TIMEOUT_SECS: u64 = 90;
+/// How long a relay has to send its challenge once connected
+const CHALLENGE_TIMEOUT_SECS: u64 = 30;
+
 /// Epicbox 'plugin' implementation
 pub enum CloseReason {
 	Normal,
@@ -119,12 +141,17 @@ impl EpicboxListenChannel {
 	pub fn new() -> Result<EpicboxListenChannel, Error> {
 		Ok(EpicboxListenChannel { _priv: () })
 	}
-	pub fn listen<L, C, K>(
+
+	/// Listen for as long as the process runs, on every relay at once. Each
+	/// relay is supervised on its own: whenever its connection drops or
+	/// stalls, the listener connects and subscribes to it again, waiting
+	/// longer after each attempt that fails. Its state is recorded in the
+	/// wallet for the owner API.
+	pub fn supervise<L, C, K>(
 		&self,
 		wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
 		keychain_mask: Arc<Mutex<Option<SecretKey>>>,
 		epicbox_config: EpicboxConfig,
-		reconnections: &mut u32,
 		is_node_synced: Arc<AtomicBool>,
 		tor_config: TorConfig,
 	) -> Result<(), Error>
@@ -133,68 +160,162 @@ impl EpicboxListenChannel {
 		C: NodeClient + 'static,
 		K: Keychain + 'static,
 	{
-		let (address, sec_key) = {
-			let a_keychain = keychain_mask.clone();
-			let a_wallet = wallet.clone();
-			let mask = a_keychain.lock();
-			let mut w_lock = a_wallet.lock();
-			let lc = w_lock.lc_provider()?;
-			let w_inst = lc.wallet_inst()?;
-			let k = w_inst.keychain((&mask).as_ref())?;
-			let parent_key_id = w_inst.parent_key_id();
-			let sec_key = address::address_from_derivation_path(&k, &parent_key_id, 0)?;
-			let pub_key = PublicKey::from_secret_key(k.secp(), &sec_key).unwrap();
-
-			let address = EpicboxAddress::new(
-				pub_key.clone(),
-				epicbox_config.epicbox_domain.clone(),
-				epicbox_config.epicbox_port,
-			);
+		// send what's left in the outbox while listening
+		{
+			let wallet = wallet.clone();
+			let keychain_mask = keychain_mask.clone();
+			let config = epicbox_config.clone();
+			spawn(move || loop {
+				let mask = keychain_mask.lock().clone();
+				let res =
+					epicbox_keys(&wallet, mask.as_ref(), &config).and_then(|(address, sec_key)| {
+						resend_outbox(&wallet, mask.as_ref(), &config, &address, &sec_key)
+					});
+				if let Err(e) = res {
+					warn!("Unable to send epicbox outbox: {}", e);
+				}
+				thread::sleep(Duration::from_secs(OUTBOX_POLL_SECS));
+			});
+		}
 
-			(address, sec_key)
-		};
-		let url = {
-			let cloned_address = address.clone();
-			match epicbox_config.epicbox_protocol_unsecure.unwrap_or(false) {
-				true => format!(
-					"ws://{}:{}",
-					cloned_address.domain,
-					cloned_address.port.unwrap_or(DEFAULT_EPICBOX_PORT_80)
-				),
-				false => format!(
-					"wss://{}:{}",
-					cloned_address.domain,
-					cloned_address.port.unwrap_or(DEFAULT_EPICBOX_PORT_443)
-				),
+		let statuses = Arc::new(Mutex::new(HashMap::new()));
+		let seen = Arc::new(Mutex::new(SeenSlates::default()));
+		thread::scope(|s| {
+			for url in relay_urls(&epicbox_config) {
+				let relay = Relay {
+					url,
+					reconnections: 0,
+					statuses: statuses.clone(),
+					seen: seen.clone(),
+				};
+				let wallet = wallet.clone();
+				let keychain_mask = keychain_mask.clone();
+				let epicbox_config = epicbox_config.clone();
+				let is_node_synced = is_node_synced.clone();
+				let tor_config = tor_config.clone();
+				s.spawn(move || {
+					self.supervise_relay(
+						relay,
+						wallet,
+						keychain_mask,
+						epicbox_config,
+						is_node_synced,
+						tor_config,
+					)
+				});
+			}
+		});
+		Ok(())
+	}
+
+	/// Listen on a relay for as long as the process runs, connecting again
+	/// whenever the connection is lost
+	fn supervise_relay<L, C, K>(
+		&self,
+		mut relay: Relay,
+		wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+		keychain_mask: Arc<Mutex<Option<SecretKey>>>,
+		epicbox_config: EpicboxConfig,
+		is_node_synced: Arc<AtomicBool>,
+		tor_config: TorConfig,
+	) where
+		L: WalletLCProvider<'static, C, K> + 'static,
+		C: NodeClient + 'static,
+		K: Keychain + 'static,
+	{
+		loop {
+			let res = relay.record_status(
+				&wallet,
+				keychain_mask.lock().as_ref(),
+				EpicboxConnectionState::Connecting,
+				None,
+				None,
+			);
+			if let Err(e) = res {
+				warn!("Unable to record the epicbox listener status: {}", e);
 			}
+			let res = self.listen(
+				&mut relay,
+				wallet.clone(),
+				keychain_mask.clone(),
+				epicbox_config.clone(),
+				is_node_synced.clone(),
+				tor_config.clone(),
+			);
+			let last_error = match res {
+				Ok(()) => None,
+				Err(e) => {
+					error!("Epicbox listener on {} stopped: {}", relay.url, e);
+					Some(format!("{}", e))
+				}
+			};
+			let delay = reconnect_delay(relay.reconnections);
+			info!(
+				"Reconnecting to epicbox relay {} in {}s",
+				relay.url,
+				delay.as_secs()
+			);
+			let next_attempt = Utc::now() + chrono::Duration::seconds(delay.as_secs() as i64);
+			let res = relay.record_status(
+				&wallet,
+				keychain_mask.lock().as_ref(),
+				EpicboxConnectionState::Reconnecting,
+				Some(next_attempt),
+				last_error,
+			);
+			if let Err(e) = res {
+				warn!("Unable to record the epicbox listener status: {}", e);
+			}
+			thread::sleep(delay);
+		}
+	}
+
+	/// Connect and subscribe to a relay, and receive slates from it until
+	/// the connection is lost
+	fn listen<L, C, K>(
+		&self,
+		relay: &mut Relay,
+		wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+		keychain_mask: Arc<Mutex<Option<SecretKey>>>,
+		epicbox_config: EpicboxConfig,
+		is_node_synced: Arc<AtomicBool>,
+		tor_config: TorConfig,
+	) -> Result<(), Error>
+	where
+		L: WalletLCProvider<'static, C, K> + 'static,
+		C: NodeClient + 'static,
+		K: Keychain + 'static,
+	{
+		let (address, sec_key) = {
+			let mask = keychain_mask.lock();
+			epicbox_keys(&wallet, mask.as_ref(), &epicbox_config)?
 		};
 		let (tx, _rx): (Sender<bool>, Receiver<bool>) = channel();
 
-		debug!("Connecting to the epicbox server at {} ..", url.clone());
-		let (socket, _response) = connect(url.clone()).map_err(|e| {
-			warn!("{}", Error::EpicboxTungstenite(format!("{}", e).into()));
-			*reconnections += 1;
-			Error::EpicboxTungstenite(format!("{}", e).into())
-		})?;
+		debug!("Connecting to the epicbox server at {} ..", relay.url);
+		let mut socket = match connect(relay.url.as_str()) {
+			Ok((socket, _response)) => socket,
+			Err(e) => {
+				relay.reconnections += 1;
+				return Err(Error::EpicboxTungstenite(format!("{}: {}", relay.url, e)));
+			}
+		};
+		set_read_timeout(&mut socket, Duration::from_millis(LISTEN_READ_TIMEOUT_MS))?;
 
 		let publisher =
 			EpicboxPublisher::new(address.clone(), sec_key, socket, tx, "listener".to_string())?;
-
 		let mut subscriber = EpicboxSubscriber::new(&publisher, is_node_synced)?;
 
 		let container = Container::new(epicbox_config.clone());
-		let cpublisher = publisher.clone();
-		let mask = keychain_mask.lock();
-		let km = mask.clone();
-		let controller = EpicboxController::new(
-			container,
-			cpublisher,
-			wallet,
-			km,
-			reconnections,
-			tor_config.clone(),
-		)
-		.expect("Could not init epicbox listener!");
+		let km = keychain_mask.lock().clone();
+		let controller = EpicboxController::new(container, wallet, km, relay, tor_config.clone());
+		let controller = match controller {
+			Ok(c) => c,
+			Err(e) => {
+				relay.reconnections += 1;
+				return Err(e);
+			}
+		};
 
 		info!("Starting epicbox listener for: {}", address);
 		subscriber.start(controller)
@@ -212,13 +333,16 @@ impl EpicboxChannel {
 		})
 	}
 
+	/// Send a slate, queueing it in the outbox first. A slate that can't be
+	/// handed to the relay stays queued, and is sent again by the listener,
+	/// which also takes care of the response.
 	pub fn send<L, C, K>(
 		&self,
 		wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
 		keychain_mask: Option<SecretKey>,
 		slate: &Slate,
-		is_node_synced: Arc<AtomicBool>,
-		tor_config: TorConfig,
+		_is_node_synced: Arc<AtomicBool>,
+		_tor_config: TorConfig,
 	) -> Result<Slate, Error>
 	where
 		L: WalletLCProvider<'static, C, K> + 'static,
@@ -229,121 +353,328 @@ impl EpicboxChannel {
 			None => EpicboxConfig::default(),
 			Some(epicbox_config) => epicbox_config,
 		};
-
-		let container = Container::new(config.clone());
-
-		let (tx, _rx): (Sender<bool>, Receiver<bool>) = channel();
-		let listener = start_epicbox(
-			container.clone(),
-			wallet,
-			keychain_mask,
-			config,
-			tx,
-			is_node_synced,
-			tor_config.clone(),
+		// older wallets only understand V2 over epicbox, compact slates are opt-in
+		let version = match slate.version_info.orig_version {
+			v if v >= 4 => SlateVersion::V4,
+			_ => SlateVersion::V2,
+		};
+		let vslate = VersionedSlate::into_version(slate.clone(), version.clone());
+		queue_and_deliver(
+			&wallet,
+			keychain_mask.as_ref(),
+			&config,
+			slate,
+			&vslate,
+			&self.dest,
 		)?;
 
-		container
-			.lock()
-			.listeners
-			.insert(ListenerInterface::Epicbox, listener);
-
-		let vslate = VersionedSlate::into_version(slate.clone(), SlateVersion::V2);
-
-		let _ = match container
-			.lock()
-			.listener(ListenerInterface::Epicbox)?
-			.publish(&vslate, &self.dest)
-		{
-			Ok(_) => (),
-			Err(e) => return Err(e),
+		let slate: Slate = match version {
+			SlateVersion::V4 => slate.clone(),
+			_ => VersionedSlate::into_version(slate.clone(), version).into(),
 		};
-
-		let slate: Slate = VersionedSlate::into_version(slate.clone(), SlateVersion::V2).into();
 		Ok(slate)
 	}
 }
 
-pub fn start_epicbox<L, C, K>(
-	container: Arc<Mutex<Container>>,
-	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
-	keychain_mask: Option<SecretKey>,
-	config: EpicboxConfig,
-	tx: Sender<bool>,
-	is_node_synced: Arc<AtomicBool>,
-	tor_config: TorConfig,
-) -> Result<Box<dyn Listener>, Error>
+/// Queue a slate for `dest` in the outbox and post it. A slate that can't be
+/// handed to a relay stays queued, the listener sends it again.
+fn queue_and_deliver<L, C, K>(
+	wallet: &Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	keychain_mask: Option<&SecretKey>,
+	config: &EpicboxConfig,
+	slate: &Slate,
+	vslate: &VersionedSlate,
+	dest: &str,
+) -> Result<(), Error>
 where
 	L: WalletLCProvider<'static, C, K> + 'static,
 	C: NodeClient + 'static,
 	K: Keychain + 'static,
 {
-	let (address, sec_key) = {
-		let a_wallet = wallet.clone();
-		let mut w_lock = a_wallet.lock();
-		let lc = w_lock.lc_provider()?;
-		let w_inst = lc.wallet_inst()?;
-		let k = w_inst.keychain(keychain_mask.as_ref())?;
-		let parent_key_id = w_inst.parent_key_id();
-		let sec_key = address::address_from_derivation_path(&k, &parent_key_id, 0)?;
-		let pub_key = PublicKey::from_secret_key(k.secp(), &sec_key).unwrap();
-
-		let address = EpicboxAddress::new(
-			pub_key.clone(),
-			config.epicbox_domain.clone(),
-			config.epicbox_port,
+	let (address, sec_key) = epicbox_keys(wallet, keychain_mask, config)?;
+	let to = EpicboxAddress::from_str(dest)?;
+	{
+		wallet_lock!(wallet, w);
+		let payload = serde_json::to_string(vslate)?;
+		owner::queue_epicbox_slate(&mut **w, keychain_mask, slate, dest, payload)?;
+	}
+	let res = deliver_slate_any(&post_urls(&to, config), &address, &sec_key, vslate, &to);
+	if res.is_err() {
+		warn!(
+			"Slate {} stays in the outbox until the listener sends it",
+			slate.id
 		);
-		(address, sec_key)
-	};
-	let url = {
-		let cloned_address = address.clone();
-		match config.epicbox_protocol_unsecure.unwrap_or(false) {
-			true => format!(
-				"ws://{}:{}",
-				cloned_address.domain,
-				cloned_address.port.unwrap_or(DEFAULT_EPICBOX_PORT_80)
-			),
-			false => format!(
-				"wss://{}:{}",
-				cloned_address.domain,
-				cloned_address.port.unwrap_or(DEFAULT_EPICBOX_PORT_443)
-			),
+	}
+	wallet_lock!(wallet, w);
+	let res = res.map_err(|e| e.into());
+	owner::record_epicbox_attempt(&mut **w, keychain_mask, &slate.id, res)?;
+	Ok(())
+}
+
+/// Address and key of the wallet on epicbox
+fn epicbox_keys<L, C, K>(
+	wallet: &Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	keychain_mask: Option<&SecretKey>,
+	config: &EpicboxConfig,
+) -> Result<(EpicboxAddress, SecretKey), Error>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	let mut w_lock = wallet.lock();
+	let lc = w_lock.lc_provider()?;
+	let w_inst = lc.wallet_inst()?;
+	let k = w_inst.keychain(keychain_mask)?;
+	let parent_key_id = w_inst.parent_key_id();
+	let sec_key = address::address_from_derivation_path(&k, &parent_key_id, 0)?;
+	let pub_key = PublicKey::from_secret_key(k.secp(), &sec_key).unwrap();
+
+	let address = EpicboxAddress::new(
+		pub_key.clone(),
+		config.epicbox_domain.clone(),
+		config.epicbox_port,
+	);
+	Ok((address, sec_key))
+}
+
+/// Delay before connecting again, after `attempts` attempts that failed
+fn reconnect_delay(attempts: u32) -> Duration {
+	Duration::from_secs(backoff_secs(
+		RECONNECT_BASE_SECS,
+		RECONNECT_MAX_SECS,
+		attempts,
+	))
+}
+
+/// A relay listened on, along with what the listeners of every relay share
+pub struct Relay {
+	/// Websocket URL of the relay
+	url: String,
+	/// Failed attempts to connect since the relay last sent anything
+	reconnections: u32,
+	/// State of the listener on each relay, by URL
+	statuses: Arc<Mutex<HashMap<String, EpicboxListenerStatus>>>,
+	/// Slates received lately on any of the relays
+	seen: Arc<Mutex<SeenSlates>>,
+}
+
+impl Relay {
+	/// Record the state of the listener on the relay. Slates are received
+	/// as long as any relay is listened on, so the state recorded for the
+	/// owner API is the one of the relay doing best.
+	fn record_status<L, C, K>(
+		&self,
+		wallet: &Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+		keychain_mask: Option<&SecretKey>,
+		state: EpicboxConnectionState,
+		next_attempt: Option<chrono::DateTime<Utc>>,
+		last_error: Option<String>,
+	) -> Result<(), Error>
+	where
+		L: WalletLCProvider<'static, C, K> + 'static,
+		C: NodeClient + 'static,
+		K: Keychain + 'static,
+	{
+		let status = EpicboxListenerStatus {
+			state,
+			reconnect_attempts: self.reconnections,
+			updated: Utc::now(),
+			next_attempt,
+			last_error,
+		};
+		let best = {
+			let mut statuses = self.statuses.lock();
+			statuses.insert(self.url.clone(), status);
+			statuses
+				.values()
+				.min_by_key(|s| {
+					let rank = match s.state {
+						EpicboxConnectionState::Listening => 0,
+						EpicboxConnectionState::Connecting => 1,
+						EpicboxConnectionState::Reconnecting => 2,
+					};
+					(rank, s.reconnect_attempts)
+				})
+				.cloned()
+		};
+		if let Some(status) = best {
+			wallet_lock!(wallet, w);
+			owner::record_epicbox_status(&mut **w, keychain_mask, status)?;
 		}
-	};
-	debug!("Connecting to the epicbox server at {} ..", url.clone());
-	let (socket, _) = connect(url.clone()).expect(CONNECTION_ERR_MSG);
+		Ok(())
+	}
+}
 
-	let publisher =
-		EpicboxPublisher::new(address.clone(), sec_key, socket, tx, "send".to_string())?;
-	let subscriber = EpicboxSubscriber::new(&publisher, is_node_synced)?;
+/// Websocket URL of a relay
+fn relay_url(domain: &str, port: Option<u16>, config: &EpicboxConfig) -> String {
+	match config.epicbox_protocol_unsecure.unwrap_or(false) {
+		true => format!(
+			"ws://{}:{}",
+			domain,
+			port.unwrap_or(DEFAULT_EPICBOX_PORT_80)
+		),
+		false => format!(
+			"wss://{}:{}",
+			domain,
+			port.unwrap_or(DEFAULT_EPICBOX_PORT_443)
+		),
+	}
+}
 
-	let mut csubscriber = subscriber.clone();
-	let cpublisher = publisher.clone();
-	let mut reconnections = 0;
+/// Websocket URLs of the configured relays, in order of priority
+fn relay_urls(config: &EpicboxConfig) -> Vec<String> {
+	let mut urls: Vec<String> = vec![];
+	for (domain, port) in config.relays() {
+		let url = relay_url(&domain, port, config);
+		if !urls.contains(&url) {
+			urls.push(url);
+		}
+	}
+	urls
+}
 
-	let handle = spawn(move || {
-		let controller = EpicboxController::new(
-			container,
-			cpublisher,
-			wallet,
-			keychain_mask,
-			&mut reconnections,
-			tor_config.clone(),
-		)
-		.expect("Could not init epicbox controller!");
-
-		csubscriber
-			.start(controller)
-			.expect("Could not start epicbox controller!");
-		()
-	});
-
-	Ok(Box::new(EpicboxListener {
-		address,
-		publisher,
-		subscriber,
-		handle,
-	}))
+/// Websocket URLs to post a slate to, the recipient's relay first and the
+/// configured ones in case it's down
+fn post_urls(to: &EpicboxAddress, config: &EpicboxConfig) -> Vec<String> {
+	let mut urls = vec![relay_url(&to.domain, to.port, config)];
+	for url in relay_urls(config) {
+		if !urls.contains(&url) {
+			urls.push(url);
+		}
+	}
+	urls
+}
+
+/// Whether a read gave up waiting, rather than failed
+fn is_timeout(e: &io::Error) -> bool {
+	match e.kind() {
+		io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => true,
+		_ => false,
+	}
+}
+
+/// Have reads on the socket give up after the timeout
+fn set_read_timeout(
+	socket: &mut WebSocket<MaybeTlsStream<TcpStream>>,
+	timeout: Duration,
+) -> Result<(), Error> {
+	match socket.get_mut() {
+		MaybeTlsStream::Plain(s) => s.set_read_timeout(Some(timeout))?,
+		MaybeTlsStream::NativeTls(s) => s.get_ref().set_read_timeout(Some(timeout))?,
+		_ => {}
+	}
+	Ok(())
+}
+
+/// Send a slate through the first relay that takes it
+fn deliver_slate_any(
+	urls: &[String],
+	from: &EpicboxAddress,
+	secret_key: &SecretKey,
+	slate: &VersionedSlate,
+	to: &EpicboxAddress,
+) -> Result<bool, Error> {
+	let mut res = Err(Error::EpicboxTungstenite("No epicbox relay".to_owned()));
+	for url in urls {
+		res = deliver_slate(url, from, secret_key, slate, to);
+		match res {
+			Ok(_) => break,
+			Err(ref e) => warn!("Unable to post slate to epicbox relay {}: {}", url, e),
+		}
+	}
+	res
+}
+
+/// Send a slate over a connection of its own and wait for the relay to
+/// accept it. Returns whether it did in time, a slate the relay didn't
+/// answer for may or may not have reached it.
+fn deliver_slate(
+	url: &str,
+	from: &EpicboxAddress,
+	secret_key: &SecretKey,
+	slate: &VersionedSlate,
+	to: &EpicboxAddress,
+) -> Result<bool, Error> {
+	debug!("Connecting to the epicbox server at {} ..", url);
+	let (mut socket, _) = connect(url).map_err(|e| Error::EpicboxTungstenite(format!("{}", e)))?;
+	let timeout = Duration::from_secs(RELAY_ACK_TIMEOUT_SECS);
+	set_read_timeout(&mut socket, timeout)?;
+	let (tx, _rx): (Sender<bool>, Receiver<bool>) = channel();
+	let broker = EpicboxBroker::new(socket, tx)?;
+	broker.post_slate(slate, to, from, secret_key)?;
+
+	let deadline = Instant::now() + timeout;
+	let res = loop {
+		if Instant::now() >= deadline {
+			break Ok(false);
+		}
+		let message = match broker.inner.lock().read() {
+			Ok(m) => m,
+			Err(ErrorTungstenite::Io(ref e)) if is_timeout(e) => break Ok(false),
+			Err(e) => break Err(Error::EpicboxTungstenite(format!("{}", e))),
+		};
+		match message {
+			Message::Text(_) | Message::Binary(_) => {
+				match serde_json::from_str::<ProtocolResponseV2>(&message.to_string()) {
+					Ok(ProtocolResponseV2::Ok) => break Ok(true),
+					Ok(ProtocolResponseV2::Error { kind, description }) => {
+						break Err(Error::EpicboxTungstenite(format!(
+							"{}: {}",
+							kind, description
+						)))
+					}
+					_ => {}
+				}
+			}
+			Message::Close(_) => break Ok(false),
+			_ => {}
+		}
+	};
+	let _ = broker.stop();
+	res
+}
+
+/// Send the slates of the outbox that are due, giving up on those that
+/// expired
+fn resend_outbox<L, C, K>(
+	wallet: &Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	keychain_mask: Option<&SecretKey>,
+	config: &EpicboxConfig,
+	address: &EpicboxAddress,
+	secret_key: &SecretKey,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	let due = {
+		wallet_lock!(wallet, w);
+		let tip_height = match w.w2n_client().get_chain_tip() {
+			Ok((height, _)) => height,
+			Err(_) => w.last_confirmed_height()?,
+		};
+		owner::due_epicbox_slates(&mut **w, keychain_mask, tip_height)?
+	};
+	for entry in due {
+		debug!(
+			"Sending slate {} to {}, attempt {}",
+			entry.slate_id,
+			entry.dest,
+			entry.attempts + 1
+		);
+		let res = serde_json::from_str::<VersionedSlate>(&entry.payload)
+			.map_err(Error::from)
+			.and_then(|slate| {
+				let to = EpicboxAddress::from_str(&entry.dest)?;
+				deliver_slate_any(&post_urls(&to, config), address, secret_key, &slate, &to)
+			});
+		wallet_lock!(wallet, w);
+		let res = res.map_err(|e| e.into());
+		owner::record_epicbox_attempt(&mut **w, keychain_mask, &entry.slate_id, res)?;
+	}
+	Ok(())
 }
 
 impl Listener for EpicboxListener {
@@ -417,19 +748,20 @@ impl EpicboxSubscriber {
 	}
 }
 
-pub struct EpicboxController<'a, P, L, C, K>
+pub struct EpicboxController<'a, L, C, K>
 where
-	P: Publisher,
 	L: WalletLCProvider<'static, C, K> + 'static,
 	C: NodeClient + 'static,
 	K: Keychain + 'static,
 {
-	publisher: P,
+	/// Relays and the wallet's address, to send responses
+	epicbox_config: EpicboxConfig,
 	/// Wallet instance
 	pub wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
 	/// Keychain mask
 	pub keychain_mask: Option<SecretKey>,
-	pub reconnections: &'a mut u32,
+	/// Relay the slates are received from
+	relay: &'a mut Relay,
 	pub tor_config: TorConfig,
 }
 pub struct Container {
@@ -474,27 +806,25 @@ impl fmt::Display for ListenerInterface {
 	}
 }
 
-impl<'a, P, L, C, K> EpicboxController<'a, P, L, C, K>
+impl<'a, L, C, K> EpicboxController<'a, L, C, K>
 where
-	P: Publisher,
 	L: WalletLCProvider<'static, C, K> + 'static,
 	C: NodeClient + 'static,
 	K: Keychain + 'static,
 {
 	pub fn new(
-		// TODO: check if container is required
-		_container: Arc<Mutex<Container>>,
-		publisher: P,
+		container: Arc<Mutex<Container>>,
 		wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
 		keychain_mask: Option<SecretKey>,
-		reconnections: &'a mut u32,
+		relay: &'a mut Relay,
 		tor_config: TorConfig,
 	) -> Result<Self, Error> {
+		let epicbox_config = container.lock().config.clone();
 		Ok(Self {
-			publisher,
+			epicbox_config,
 			wallet,
 			keychain_mask,
-			reconnections,
+			relay,
 			tor_config,
 		})
 	}
@@ -503,11 +833,13 @@ where
 		&self,
 		address: Option<String>,
 		slate: &mut Slate,
-		_tx_proof: Option<&mut TxProof>,
+		tx_proof: Option<&mut TxProof>,
 	) -> Result<bool, Error> {
 		// Case 1: Receiving a new transaction (not finalized)
 		if slate.num_participants > slate.participant_data.len() {
-			if slate.tx.inputs().is_empty() {
+			// compact slates don't carry the sender's inputs either, an invoice
+			// is told apart by the missing sender
+			if slate.tx.inputs().is_empty() && slate.participant_with_id(0).is_none() {
 				// TODO: invoicing
 			} else {
 				info!("Receive new transaction (foreign::receive_tx)");
@@ -526,6 +858,13 @@ where
 					}
 					Err(e) => return Err(Error::EpicboxReceiveTx(format!("{:?}", e)).into()),
 				};
+				if let Some(p) = tx_proof {
+					let res =
+						owner::store_tx_proof(&mut **w, self.keychain_mask.as_ref(), slate.id, p);
+					if let Err(e) = res {
+						error!("Unable to store tx proof of slate {}: {}", slate.id, e);
+					}
+				}
 			}
 			return Ok(false);
 		}
@@ -533,8 +872,23 @@ where
 		// Case 2: Finalizing and posting the transaction
 		info!("Finalize transaction (owner::finalize_tx)");
 		let (finalized_slate, mut onion_addresses, node_client) = {
-			wallet_lock!(self.wallet, w);
-			let finalized_slate = owner::finalize_tx(&mut **w, self.keychain_mask.as_ref(), slate)?;
+			let mut w_lock = self.wallet.lock();
+			let lc = w_lock.lc_provider()?;
+			let policy = lc.spend_policy();
+			let w = lc.wallet_inst()?;
+			let res =
+				owner::record_epicbox_response(&mut **w, self.keychain_mask.as_ref(), &slate.id);
+			if let Err(e) = res {
+				error!("Unable to update outbox for slate {}: {}", slate.id, e);
+			}
+			let finalized_slate =
+				owner::finalize_tx(&mut **w, self.keychain_mask.as_ref(), slate, &policy)?;
+			if let Some(p) = tx_proof {
+				let res = owner::store_tx_proof(&mut **w, self.keychain_mask.as_ref(), slate.id, p);
+				if let Err(e) = res {
+					error!("Unable to store tx proof of slate {}: {}", slate.id, e);
+				}
+			}
 			// Get onion addresses and node client while wallet is still locked
 			let onion_addresses = w.w2n_client().get_onion_addresses().unwrap_or_default();
 			let node_client = w.w2n_client().clone();
@@ -593,13 +947,20 @@ where
 	}
 }
 pub trait SubscriptionHandler: Send {
-	fn on_slate(&self, from: &EpicboxAddress, slate: &VersionedSlate, proof: Option<&mut TxProof>);
+	/// Process a slate, and send the response it calls for. The relay is
+	/// only told the slate was taken once this succeeded.
+	fn on_slate(
+		&self,
+		from: &EpicboxAddress,
+		slate: &VersionedSlate,
+		proof: Option<&mut TxProof>,
+	) -> Result<(), Error>;
 	fn on_close(&self, result: CloseReason);
+	fn on_status(&self, state: EpicboxConnectionState);
 }
 
-impl<'a, P, L, C, K> SubscriptionHandler for EpicboxController<'a, P, L, C, K>
+impl<'a, L, C, K> SubscriptionHandler for EpicboxController<'a, L, C, K>
 where
-	P: Publisher,
 	L: WalletLCProvider<'static, C, K> + 'static,
 	C: NodeClient + 'static,
 	K: Keychain + 'static,
@@ -609,7 +970,7 @@ where
 		from: &EpicboxAddress,
 		slate: &VersionedSlate,
 		tx_proof: Option<&mut TxProof>,
-	) {
+	) -> Result<(), Error> {
 		let version = slate.version();
 		let mut slate: Slate = slate.into();
 
@@ -629,30 +990,24 @@ where
 			);
 		};
 
-		let result = self
-			.process_incoming_slate(Some(from.to_string()), &mut slate, tx_proof)
-			.and_then(|is_finalized| {
-				if !is_finalized {
-					let _id = slate.id.clone();
-					let slate = VersionedSlate::into_version(slate, version);
-
-					self.publisher
-						.post_slate(&slate, from, false)
-						.map_err(|e| {
-							error!("{}: {}", "ERROR", e);
-							e
-						})
-						.expect("failed posting slate!");
-				} else {
-					info!("Slate [{}] finalized successfully", slate.id.to_string());
-				}
-				Ok(())
-			});
-
-		match result {
-			Ok(()) => {}
-			Err(e) => error!("Error process incoming slate. {:?}", e),
+		let is_finalized =
+			self.process_incoming_slate(Some(from.to_string()), &mut slate, tx_proof)?;
+		if is_finalized {
+			info!("Slate [{}] finalized successfully", slate.id.to_string());
+			return Ok(());
 		}
+
+		// the response goes through the outbox, so it's sent again if the
+		// relays can't take it now
+		let vslate = VersionedSlate::into_version(slate.clone(), version);
+		queue_and_deliver(
+			&self.wallet,
+			self.keychain_mask.as_ref(),
+			&self.epicbox_config,
+			&slate,
+			&vslate,
+			&from.to_string(),
+		)
 	}
 
 	fn on_close(&self, reason: CloseReason) {
@@ -665,12 +1020,20 @@ where
 			}
 		}
 	}
+
+	fn on_status(&self, state: EpicboxConnectionState) {
+		let res =
+			self.relay
+				.record_status(&self.wallet, self.keychain_mask.as_ref(), state, None, None);
+		if let Err(e) = res {
+			warn!("Unable to record the epicbox listener status: {}", e);
+		}
+	}
 }
 
 impl EpicboxSubscriber {
-	fn start<P, L, C, K>(&mut self, handler: EpicboxController<P, L, C, K>) -> Result<(), Error>
+	fn start<L, C, K>(&mut self, handler: EpicboxController<L, C, K>) -> Result<(), Error>
 	where
-		P: Publisher,
 		L: WalletLCProvider<'static, C, K> + 'static,
 		C: NodeClient + 'static,
 		K: Keychain + 'static,
@@ -718,27 +1081,24 @@ impl EpicboxBroker {
 	}
 
 	/// Start a listener, passing received messages to the wallet api directly
-	pub fn subscribe<P, L, C, K>(
+	pub fn subscribe<L, C, K>(
 		&mut self,
 		address: &EpicboxAddress,
 		secret_key: &SecretKey,
-		handler: EpicboxController<P, L, C, K>,
+		mut handler: EpicboxController<L, C, K>,
 		wallet_mode: &String,
 		is_node_synced: Arc<AtomicBool>,
 	) -> Result<(), Error>
 	where
-		P: Publisher,
 		L: WalletLCProvider<'static, C, K> + 'static,
 		C: NodeClient + 'static,
 		K: Keychain + 'static,
 	{
-		let handler = Arc::new(Mutex::new(handler));
 		let sender = self.inner.clone();
 		let mut first_run = true;
 
 		let mut client = EpicboxClient {
 			sender,
-			handler: handler.clone(),
 			challenge: None,
 			address: address.clone(),
 			secret_key: secret_key.clone(),
@@ -749,21 +1109,60 @@ impl EpicboxBroker {
 		let ver = EPICBOX_PROTOCOL_VERSION;
 		let wallet_mode = wallet_mode;
 
+		// the relay is expected to challenge the wallet first, then to
+		// answer heartbeats for as long as the connection is up
+		let mut connected = Instant::now();
+		let mut challenged = false;
+		let mut last_seen = Instant::now();
+		let mut last_ping = Instant::now();
+		// slates that couldn't be processed, left with the relay
+		let mut failed = SeenSlates::default();
+
 		let res = loop {
 			// Pause if node is not synced
 			if !is_node_synced.load(std::sync::atomic::Ordering::SeqCst) {
 				warn!("Node not synced, pausing Epicbox message processing...");
 				std::thread::sleep(std::time::Duration::from_secs(5));
+				connected = Instant::now();
+				last_seen = Instant::now();
 				continue;
 			}
 
+			let stalled = if !challenged
+				&& connected.elapsed() > Duration::from_secs(CHALLENGE_TIMEOUT_SECS)
+			{
+				Some("no challenge from the relay")
+			} else if last_seen.elapsed() > Duration::from_secs(HEARTBEAT_TIMEOUT_SECS) {
+				Some("heartbeats went unanswered")
+			} else {
+				None
+			};
+			if let Some(reason) = stalled {
+				handler.relay.reconnections += 1;
+				handler.on_close(CloseReason::Abnormal(Error::EpicboxStalled(
+					reason.to_owned(),
+				)));
+				let _ = client.sender.lock().close(None);
+				break Err(Error::EpicboxStalled(reason.to_owned()));
+			}
+			if last_ping.elapsed() > Duration::from_secs(HEARTBEAT_SECS) {
+				last_ping = Instant::now();
+				// a failure shows on the next read
+				let _ = client.sender.lock().send(Message::Ping(Default::default()));
+			}
+
 			let err = client.sender.lock().read();
+			if err.is_ok() {
+				last_seen = Instant::now();
+			}
 
 			match err {
+				// reads time out now and then to let posts through
+				Err(ErrorTungstenite::Io(ref e)) if is_timeout(e) => continue,
 				Err(e) => {
-					*handler.lock().reconnections += 1;
+					handler.relay.reconnections += 1;
 					error!("Error reading message {:?}", e);
-					handler.lock().on_close(CloseReason::Abnormal(
+					handler.on_close(CloseReason::Abnormal(
 						Error::EpicboxWebsocketAbnormalTermination,
 					));
 					match client.sender.lock().close(None) {
@@ -785,11 +1184,11 @@ impl EpicboxBroker {
 									e,
 									message.to_string()
 								);
-								return Ok(());
+								continue;
 							}
 						};
 
-						*handler.lock().reconnections = 0;
+						handler.relay.reconnections = 0;
 
 						match response {
 							ProtocolResponseV2::Challenge { str } => {
@@ -813,6 +1212,11 @@ impl EpicboxBroker {
 								let _ = client.send(&request_sub).map_err(|e| {
 									error!("Error attempting to send Subscribe {:?}", e)
 								});
+
+								if !challenged {
+									challenged = true;
+									handler.on_status(EpicboxConnectionState::Listening);
+								}
 							}
 							ProtocolResponseV2::Slate {
 								from,
@@ -831,17 +1235,36 @@ impl EpicboxBroker {
 								) {
 									Ok(x) => x,
 									Err(e) => {
-										error!("{}", e.to_string());
-										return Ok(());
+										error!("Invalid slate from the relay: {}", e);
+										continue;
 									}
 								};
 
 								let address = tx_proof.address.clone();
-								client.handler.lock().on_slate(
-									&address,
-									&slate,
-									Some(&mut tx_proof),
-								);
+								let received: Slate = (&slate).into();
+								let made = if failed.contains(&received) {
+									false
+								} else if handler.relay.seen.lock().insert(&received) {
+									let res =
+										handler.on_slate(&address, &slate, Some(&mut tx_proof));
+									// the relay isn't read while a slate is processed,
+									// which can take minutes when it's posted
+									last_seen = Instant::now();
+									match res {
+										Ok(()) => true,
+										Err(e) => {
+											error!("Error processing incoming slate: {}", e);
+											// processed again when the relay sends it anew,
+											// or another relay does
+											handler.relay.seen.lock().remove(&received);
+											failed.insert(&received);
+											false
+										}
+									}
+								} else {
+									debug!("Slate from [{}] already received", address);
+									true
+								};
 
 								let signature = sign_challenge(
 									&client.challenge.clone().unwrap(),
@@ -855,6 +1278,7 @@ impl EpicboxBroker {
 								};
 
 								match client.send(&request_sub) {
+									Ok(()) if !made => {}
 									Ok(()) => {
 										//send feedback to epicbox that we successfully finalize
 										match client.made_send(epicboxmsgid.clone()) {
@@ -898,11 +1322,16 @@ impl EpicboxBroker {
 						}
 					}
 					Message::Ping(_) => {}
-					Message::Pong(_) => {}
+					// keeps the recorded status fresh while listening
+					Message::Pong(_) => {
+						if challenged {
+							handler.on_status(EpicboxConnectionState::Listening);
+						}
+					}
 					Message::Frame(_) => {}
 					Message::Close(_) => {
 						info!("Close connection");
-						handler.lock().on_close(CloseReason::Normal);
+						handler.on_close(CloseReason::Normal);
 						let _ = client.sender.lock().close(None);
 						break Ok(());
 					}
@@ -947,7 +1376,7 @@ impl EpicboxBroker {
 			.send(Message::Text(
 				serde_json::to_string(&request).unwrap().into(),
 			))
-			.unwrap();
+			.map_err(|e| Error::EpicboxTungstenite(format!("{}", e)))?;
 
 		debug!("Slate sent successfully!");
 
@@ -958,15 +1387,45 @@ impl EpicboxBroker {
 	}
 }
 
-struct EpicboxClient<'a, P, L, C, K>
-where
-	L: WalletLCProvider<'static, C, K> + 'static,
-	C: NodeClient + 'static,
-	K: Keychain + 'static,
-	P: Publisher,
-{
+/// Slates received lately. The same slate comes in more than once when
+/// it's posted to several of the relays listened on.
+#[derive(Default)]
+struct SeenSlates {
+	slates: VecDeque<(Uuid, usize)>,
+}
+
+impl SeenSlates {
+	fn key(slate: &Slate) -> (Uuid, usize) {
+		(slate.id, slate.participant_data.len())
+	}
+
+	/// Whether the slate was received already
+	fn contains(&self, slate: &Slate) -> bool {
+		self.slates.contains(&SeenSlates::key(slate))
+	}
+
+	/// Record a slate, returns whether it wasn't received already
+	fn insert(&mut self, slate: &Slate) -> bool {
+		let key = SeenSlates::key(slate);
+		if self.slates.contains(&key) {
+			return false;
+		}
+		if self.slates.len() >= SEEN_SLATES_LEN {
+			self.slates.pop_front();
+		}
+		self.slates.push_back(key);
+		true
+	}
+
+	/// Forget a slate, so it's taken when it comes again
+	fn remove(&mut self, slate: &Slate) {
+		let key = SeenSlates::key(slate);
+		self.slates.retain(|k| k != &key);
+	}
+}
+
+struct EpicboxClient {
 	sender: Arc<Mutex<WebSocket<MaybeTlsStream<TcpStream>>>>,
-	handler: Arc<Mutex<EpicboxController<'a, P, L, C, K>>>,
 	challenge: Option<String>,
 	address: EpicboxAddress,
 	secret_key: SecretKey,
@@ -974,13 +1433,7 @@ where
 }
 
 /// client with handler from ws package
-impl<'a, P, L, C, K> EpicboxClient<'a, P, L, C, K>
-where
-	P: Publisher,
-	L: WalletLCProvider<'static, C, K> + 'static,
-	C: NodeClient + 'static,
-	K: Keychain + 'static,
-{
+impl EpicboxClient {
 	fn made_send(&self, epicboxmsgid: String) -> Result<(), Error> {
 		let signature = sign_challenge(&epicboxmsgid, &self.secret_key)?.to_hex();
 		let request = ProtocolRequestV2::Made {
@@ -1023,3 +1476,38 @@ where
 		self.sender.lock().send(Message::Text(request.into()))
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn relays_and_duplicates() {
+		let mut config = EpicboxConfig::default();
+		config.epicbox_relays = Some(vec![
+			"relay.example.org:8443".to_owned(),
+			"epicbox.epiccash.com".to_owned(),
+		]);
+		assert_eq!(
+			relay_urls(&config),
+			vec![
+				"wss://epicbox.epiccash.com:443",
+				"wss://relay.example.org:8443"
+			]
+		);
+
+		let mut seen = SeenSlates::default();
+		let slate = Slate::blank(2);
+		assert!(seen.insert(&slate));
+		assert!(!seen.insert(&slate));
+		assert!(seen.insert(&Slate::blank(2)));
+	}
+
+	#[test]
+	fn reconnect_backoff() {
+		assert_eq!(reconnect_delay(0).as_secs(), 5);
+		assert_eq!(reconnect_delay(1).as_secs(), 5);
+		assert_eq!(reconnect_delay(4).as_secs(), 40);
+		assert_eq!(reconnect_delay(100).as_secs(), 300);
+	}
+}
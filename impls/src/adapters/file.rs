@@ -27,7 +27,9 @@ impl SlatePutter for PathToSlate {
 	fn put_tx(&self, slate: &Slate) -> Result<(), Error> {
 		let mut pub_tx = File::create(&self.0)?;
 		let out_slate = {
-			if slate.payment_proof.is_some() || slate.ttl_cutoff_height.is_some() {
+			if slate.version_info.orig_version >= 4 {
+				VersionedSlate::into_version(slate.clone(), SlateVersion::V4)
+			} else if slate.payment_proof.is_some() || slate.ttl_cutoff_height.is_some() {
 				warn!("Transaction contains features that require epic-wallet 3.0.0 or later");
 				warn!("Please ensure the other party is running epic-wallet v3.0.0 or later before sending");
 				VersionedSlate::into_version(slate.clone(), SlateVersion::V3)
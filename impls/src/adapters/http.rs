@@ -137,7 +137,11 @@ impl SlateSender for HttpSlateSender {
         let url_str = format!("{}{}v2/foreign", self.base_url, trailing);
 
         let slate_send = match self.check_other_version(&url_str)? {
-            SlateVersion::V3 => VersionedSlate::into_version(slate.clone(), SlateVersion::V3),
+            // delta slates need the outputs locked before sending, so V4 isn't
+            // negotiated over http
+            SlateVersion::V4 | SlateVersion::V3 => {
+                VersionedSlate::into_version(slate.clone(), SlateVersion::V3)
+            }
             SlateVersion::V2 => {
                 let mut slate = slate.clone();
                 if let Some(_) = slate.payment_proof {
@@ -17,6 +17,7 @@ mod epicbox;
 mod file;
 pub mod http;
 mod keybase;
+mod webhook;
 
 pub use self::emoji::EmojiSlate;
 pub use self::epicbox::{
@@ -27,8 +28,9 @@ pub use self::epicbox::{EpicboxChannel, EpicboxListenChannel};
 pub use self::file::PathToSlate;
 pub use self::http::HttpSlateSender;
 pub use self::keybase::{KeybaseAllChannels, KeybaseChannel};
+pub use self::webhook::HttpWebhookSender;
 use crate::config::WalletConfig;
-use crate::libwallet::{Error, NodeClient, Slate, WalletInst, WalletLCProvider};
+use crate::libwallet::{Contact, Error, NodeClient, Slate, WalletInst, WalletLCProvider};
 use crate::tor::config::complete_tor_address;
 
 use crate::keychain::Keychain;
@@ -73,12 +75,41 @@ pub trait SlateGetter {
 	fn get_tx(&self) -> Result<Slate, Error>;
 }
 
+/// Find the address book contact referred to by an `@name` destination, if any
+pub fn find_contact<'a>(dest: &str, contacts: &'a [Contact]) -> Result<Option<&'a Contact>, Error> {
+	match dest.trim().strip_prefix('@') {
+		Some(name) => match contacts.iter().find(|c| c.name == name) {
+			Some(c) => Ok(Some(c)),
+			None => Err(Error::WalletComms(format!(
+				"No contact named \"{}\" in the address book",
+				name
+			))),
+		},
+		None => Ok(None),
+	}
+}
+
+/// Resolve an `@name` destination into the method and destination stored in
+/// the address book. Any other destination is returned as is.
+pub fn resolve_contact(
+	method: &str,
+	dest: &str,
+	contacts: &[Contact],
+) -> Result<(String, String), Error> {
+	Ok(match find_contact(dest, contacts)? {
+		Some(c) => (c.method.clone(), c.destination.clone()),
+		None => (method.to_owned(), dest.to_owned()),
+	})
+}
+
 /// select a SlateSender based on method and dest fields from, e.g., SendArgs
 pub fn create_sender(
 	method: &str,
 	dest: &str,
+	contacts: &[Contact],
 	is_node_synced: Arc<AtomicBool>,
 ) -> Result<Box<dyn SlateSender>, Error> {
+	let (method, dest) = resolve_contact(method, dest, contacts)?;
 	let invalid = || {
 		Error::WalletComms(format!(
 			"Invalid wallet comm type and destination. method: {}, dest: {}",
@@ -86,16 +117,16 @@ pub fn create_sender(
 		))
 	};
 
-	let mut method = method.into();
+	let mut method = method.as_str();
 
 	// will test if this is a tor address and fill out
 	// the http://[].onion if missing
-	let dest = match complete_tor_address(dest) {
+	let dest = match complete_tor_address(&dest) {
 		Ok(d) => {
 			method = "tor";
 			d
 		}
-		Err(_) => dest.into(),
+		Err(_) => dest.clone(),
 	};
 
 	Ok(match method {
@@ -128,3 +159,42 @@ pub fn create_sender(
 		}
 	})
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_resolve_contact() {
+		let contacts = vec![Contact {
+			name: "alice".to_owned(),
+			method: "epicbox".to_owned(),
+			destination: "esAlice@epicbox.epic.tech".to_owned(),
+			proof_address: None,
+		}];
+		let (method, dest) = resolve_contact("http", "@alice", &contacts).unwrap();
+		assert_eq!(method, "epicbox");
+		assert_eq!(dest, "esAlice@epicbox.epic.tech");
+
+		// plain destinations, including epicbox addresses, are left untouched
+		let (method, dest) =
+			resolve_contact("epicbox", "esBob@epicbox.epic.tech", &contacts).unwrap();
+		assert_eq!(method, "epicbox");
+		assert_eq!(dest, "esBob@epicbox.epic.tech");
+
+		assert!(resolve_contact("http", "@bob", &contacts).is_err());
+		assert!(contacts[0].matches_address(" esAlice@EpicBox.epic.tech "));
+		assert!(!contacts[0].matches_address("ESALICE@epicbox.epic.tech"));
+		assert!(!contacts[0].matches_address("esalice@epicbox.epic.tech"));
+
+		let http = Contact {
+			name: "carol".to_owned(),
+			method: "http".to_owned(),
+			destination: "http://Carol.example.com:3415/Wallet/".to_owned(),
+			proof_address: None,
+		};
+		assert!(http.matches_address("HTTP://carol.example.com:3415/Wallet"));
+		assert!(!http.matches_address("http://carol.example.com:3415/wallet"));
+		assert!(!http.matches_address(" "));
+	}
+}
@@ -0,0 +1,97 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Posts wallet events to webhooks over HTTP(S)
+
+use crate::client_utils::Client;
+use crate::libwallet::{Error, WebhookSender};
+
+/// Webhook sender posting JSON payloads with the regular HTTP client
+#[derive(Clone, Default)]
+pub struct HttpWebhookSender {}
+
+impl HttpWebhookSender {
+	/// New sender
+	pub fn new() -> HttpWebhookSender {
+		HttpWebhookSender {}
+	}
+}
+
+impl WebhookSender for HttpWebhookSender {
+	fn post(&self, url: &str, body: &str, headers: &[(String, String)]) -> Result<(), Error> {
+		let client = Client::new().map_err(|e| Error::GenericError(format!("{}", e)))?;
+		client
+			.post_raw(url, body.to_owned(), headers)
+			.map_err(|e| Error::ClientCallback(format!("Posting to webhook {}: {}", url, e)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::{BufRead, BufReader, Read, Write};
+	use std::net::TcpListener;
+	use std::sync::mpsc;
+	use std::thread;
+
+	/// Local stand-in for a webhook, answering a single request with the
+	/// given status line and handing back the headers and body it got
+	fn stand_in(status: &'static str) -> (String, mpsc::Receiver<(Vec<String>, String)>) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}/hook", listener.local_addr().unwrap());
+		let (tx, rx) = mpsc::channel();
+		thread::spawn(move || {
+			let (stream, _) = listener.accept().unwrap();
+			let mut reader = BufReader::new(stream.try_clone().unwrap());
+			let mut headers = vec![];
+			let mut len = 0;
+			loop {
+				let mut line = String::new();
+				reader.read_line(&mut line).unwrap();
+				let line = line.trim_end().to_owned();
+				if line.is_empty() {
+					break;
+				}
+				if let Some(v) = line.to_lowercase().strip_prefix("content-length:") {
+					len = v.trim().parse().unwrap();
+				}
+				headers.push(line);
+			}
+			let mut body = vec![0; len];
+			reader.read_exact(&mut body).unwrap();
+			let mut stream = stream;
+			write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status).unwrap();
+			tx.send((headers, String::from_utf8(body).unwrap()))
+				.unwrap();
+		});
+		(url, rx)
+	}
+
+	#[test]
+	fn posts_body_and_headers() {
+		let sender = HttpWebhookSender::new();
+		let headers = vec![("X-Epic-Event".to_owned(), "received".to_owned())];
+
+		let (url, rx) = stand_in("200 OK");
+		sender.post(&url, "{\"a\":1}", &headers).unwrap();
+		let (got_headers, body) = rx.recv().unwrap();
+		assert_eq!(body, "{\"a\":1}");
+		assert!(got_headers
+			.iter()
+			.any(|h| h.eq_ignore_ascii_case("x-epic-event: received")));
+
+		let (url, _rx) = stand_in("500 Internal Server Error");
+		assert!(sender.post(&url, "{}", &headers).is_err());
+	}
+}
@@ -19,7 +19,7 @@
 use crate::serialization as ser;
 use crate::serialization::Serializable;
 use crate::Error;
-use sqlite::{self, Connection};
+use sqlite::{self, Connection, State, Value};
 use std::path::PathBuf;
 use std::thread;
 use std::time::Duration;
@@ -150,9 +150,31 @@ impl Store {
     /// If the database is locked due to another writing process,
     /// The code will retry the same statement after 100 milliseconds
     pub fn execute(&self, statement: String) -> Result<(), sqlite::Error> {
+        self.with_retries(|| self.db.execute(statement.to_string()))
+    }
+
+    /// Executes an SQLite statement with values bound to its numbered parameters
+    /// Retries the same way as `execute` if the database is locked
+    pub fn execute_bound(
+        &self,
+        statement: &str,
+        params: Vec<(usize, Value)>,
+    ) -> Result<(), sqlite::Error> {
+        self.with_retries(|| {
+            let mut prepared = self.db.prepare(statement)?;
+            prepared.bind_iter(params.clone())?;
+            while prepared.next()? != State::Done {}
+            Ok(())
+        })
+    }
+
+    fn with_retries<F>(&self, mut run: F) -> Result<(), sqlite::Error>
+    where
+        F: FnMut() -> Result<(), sqlite::Error>,
+    {
         let mut retries = 0;
         loop {
-            match self.db.execute(statement.to_string()) {
+            match run() {
                 Ok(()) => break,
                 Err(e) => {
                     // e.code follows SQLite error types
@@ -184,91 +206,100 @@ impl<'a> Batch<'_> {
     /// Writes a single value to the db, given a key and a Serializable enum
     /// Specialized queries are used for TxLogEntry and OutputData to make best use of queriable columns
     pub fn put(&self, key: &[u8], value: Serializable) -> Result<(), Error> {
-        // serialize value to json
+        // serialize value to json, it is bound as a parameter so no escaping is needed
         let value_s = ser::serialize(&value).unwrap();
-        let prefix = key[0] as char;
-
-        // Insert on the database
-        // TxLogEntry and OutputData make use of queriable columns
-        let mut query = match &value {
-            Serializable::TxLogEntry(t) => format!(
-                r#"INSERT INTO data
-						(key, data, prefix, q_tx_id, q_confirmed, q_tx_status)
-					VALUES
-						("{:?}", '{}', "{}", {}, {}, "{}");
-				"#,
-                key, value_s, prefix, t.id, t.confirmed, t.tx_type
-            ),
-            Serializable::OutputData(o) => format!(
-                r#"INSERT INTO data
-						(key, data, prefix, q_tx_id, q_tx_status)
-					VALUES
-						("{:?}", '{}', "{}", "{}", "{}")
-				"#,
-                key,
-                value_s,
-                prefix,
-                match o.tx_log_entry {
-                    Some(entry) => entry.to_string(),
-                    None => "".to_string(),
-                },
-                o.status
-            ),
-            _ => format!(
-                r#"INSERT INTO data
-						(key, data, prefix)
-					VALUES
-						("{:?}", '{}', "{}");
-				"#,
-                key, value_s, prefix
-            ),
-        };
+        let key_s = format!("{:?}", key);
+        let prefix = (key[0] as char).to_string();
+        let exists = self.exists(&key).unwrap();
 
-        // Update if the current key exists on the database
+        // Insert on the database, or update if the current key already exists
         // TxLogEntry and OutputData make use of queriable columns
-        if self.exists(&key).unwrap() {
-            query = match &value {
-                Serializable::TxLogEntry(t) => format!(
+        let (query, params) = match &value {
+            Serializable::TxLogEntry(t) => {
+                let query = if exists {
                     r#"UPDATE data
 						SET
-							data = '{}',
-							q_tx_id = {},
-							q_confirmed = {},
-							q_tx_status = "{}"
+							data = ?2,
+							prefix = ?3,
+							q_tx_id = ?4,
+							q_confirmed = ?5,
+							q_tx_status = ?6
 						WHERE
-							key = "{:?}";
-					"#,
-                    value_s, t.id, t.confirmed, t.tx_type, key
-                ),
-                Serializable::OutputData(o) => format!(
+							key = ?1;
+					"#
+                } else {
+                    r#"INSERT INTO data
+						(key, data, prefix, q_tx_id, q_confirmed, q_tx_status)
+					VALUES
+						(?1, ?2, ?3, ?4, ?5, ?6);
+				"#
+                };
+                let params = vec![
+                    Value::Integer(t.id as i64),
+                    Value::Integer(t.confirmed as i64),
+                    Value::String(t.tx_type.to_string()),
+                ];
+                (query, params)
+            }
+            Serializable::OutputData(o) => {
+                let query = if exists {
                     r#"UPDATE data
 						SET
-							data = '{}',
-							q_tx_id = "{}",
-							q_tx_status = "{}"
+							data = ?2,
+							prefix = ?3,
+							q_tx_id = ?4,
+							q_tx_status = ?5
 						WHERE
-							key = "{:?}";
-					"#,
-                    value_s,
-                    match o.tx_log_entry {
+							key = ?1;
+					"#
+                } else {
+                    r#"INSERT INTO data
+						(key, data, prefix, q_tx_id, q_tx_status)
+					VALUES
+						(?1, ?2, ?3, ?4, ?5);
+				"#
+                };
+                let params = vec![
+                    Value::String(match o.tx_log_entry {
                         Some(entry) => entry.to_string(),
                         None => "".to_string(),
-                    },
-                    o.status,
-                    key
-                ),
-                _ => format!(
+                    }),
+                    Value::String(o.status.to_string()),
+                ];
+                (query, params)
+            }
+            _ => {
+                let query = if exists {
                     r#"UPDATE data
 						SET
-							data = '{}'
+							data = ?2,
+							prefix = ?3
 						WHERE
-							key = "{:?}";
-					"#,
-                    value_s, key
-                ),
-            };
-        }
-        Ok(self.store.execute(query).unwrap())
+							key = ?1;
+					"#
+                } else {
+                    r#"INSERT INTO data
+						(key, data, prefix)
+					VALUES
+						(?1, ?2, ?3);
+				"#
+                };
+                (query, vec![])
+            }
+        };
+
+        // key, data and prefix are always bound as ?1, ?2 and ?3
+        let params = vec![
+            Value::String(key_s),
+            Value::String(value_s),
+            Value::String(prefix),
+        ]
+        .into_iter()
+        .chain(params)
+        .enumerate()
+        .map(|(i, v)| (i + 1, v))
+        .collect();
+        Ok(self.store.execute_bound(query, params)?)
     }
 
     /// Writes a single value to the db, given a key and a Serializable enum
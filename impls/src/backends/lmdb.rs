@@ -18,8 +18,10 @@ use crate::core::core::Transaction;
 use crate::core::ser;
 use crate::keychain::{ChildNumber, ExtKeychain, Identifier, Keychain, SwitchCommitmentType};
 use crate::libwallet::{
-	AcctPathMapping, Context, Error, NodeClient, OutputData, OutputStatus, ScannedBlockInfo,
-	TxLogEntry, WalletBackend, WalletInitStatus, WalletOutputBatch,
+	AcctPathMapping, Contact, Context, EpicboxListenerStatus, Error, NodeClient, OutboxEntry,
+	OutputData, OutputStatus, ScanCheckpoint, ScannedBlockHistory, ScannedBlockInfo, ScannedOutput,
+	TxLogEntry, TxProof, WalletBackend, WalletInitStatus, WalletOutputBatch, WebhookDelivery,
+	WebhookTxState,
 };
 use crate::serialization::Serializable;
 use crate::store::{to_key, to_key_u64};
@@ -38,6 +40,7 @@ use std::{fs, path};
 pub const DB_DIR: &'static str = "db";
 const SQLITE_DIR: &'static str = "sqlite";
 pub const TX_SAVE_DIR: &'static str = "saved_txs";
+pub const TX_PROOF_SAVE_DIR: &'static str = "saved_proofs";
 
 const OUTPUT_HISTORY_PREFIX: u8 = 'h' as u8;
 const OUTPUT_HISTORY_ID_PREFIX: u8 = 'j' as u8;
@@ -48,10 +51,23 @@ const PRIVATE_TX_CONTEXT_PREFIX: u8 = 'p' as u8;
 const TX_LOG_ENTRY_PREFIX: u8 = 't' as u8;
 const TX_LOG_ID_PREFIX: u8 = 'i' as u8;
 const ACCOUNT_PATH_MAPPING_PREFIX: u8 = 'a' as u8;
+const CONTACT_PREFIX: u8 = 'b' as u8;
 const LAST_SCANNED_BLOCK: u8 = 'l' as u8;
 const LAST_SCANNED_KEY: &str = "LAST_SCANNED_KEY";
 const WALLET_INIT_STATUS: u8 = 'w' as u8;
 const WALLET_INIT_STATUS_KEY: &str = "WALLET_INIT_STATUS";
+const SCAN_CHECKPOINT: u8 = 'k' as u8;
+const SCAN_CHECKPOINT_KEY: &str = "SCAN_CHECKPOINT";
+const SCANNED_OUTPUT_PREFIX: u8 = 's' as u8;
+const SCANNED_BLOCK_HISTORY: u8 = 'r' as u8;
+const SCANNED_BLOCK_HISTORY_KEY: &str = "SCANNED_BLOCK_HISTORY";
+const WEBHOOK_TX_STATE_PREFIX: u8 = 'n' as u8;
+const WEBHOOK_DELIVERY_PREFIX: u8 = 'q' as u8;
+const WEBHOOKS_INIT_HEIGHT: u8 = 'g' as u8;
+const WEBHOOKS_INIT_HEIGHT_KEY: &str = "WEBHOOKS_INIT_HEIGHT";
+const OUTBOX_PREFIX: u8 = 'x' as u8;
+const EPICBOX_STATUS: u8 = 'e' as u8;
+const EPICBOX_STATUS_KEY: &str = "EPICBOX_STATUS";
 
 /// test to see if database files exist in the current directory. If so,
 /// use a DB backend for all operations
@@ -125,6 +141,10 @@ where
 		fs::create_dir_all(&stored_tx_path)
 			.expect("Couldn't create wallet backend tx storage directory!");
 
+		let stored_tx_proof_path = path::Path::new(data_file_dir).join(TX_PROOF_SAVE_DIR);
+		fs::create_dir_all(&stored_tx_proof_path)
+			.expect("Couldn't create wallet backend tx proof storage directory!");
+
 		let store = db::Store::new(db_path)?;
 
 		// Make sure default wallet derivation path always exists
@@ -400,6 +420,25 @@ where
 		})
 	}
 
+	fn contact_iter<'a>(&'a self) -> Box<dyn Iterator<Item = Contact> + 'a> {
+		let serializables: Vec<_> = self
+			.db
+			.iter(&[CONTACT_PREFIX])
+			.into_iter()
+			.filter_map(Serializable::as_contact)
+			.collect();
+		Box::new(serializables.into_iter().map(|x| x))
+	}
+
+	fn get_contact(&self, name: &str) -> Result<Option<Contact>, Error> {
+		let contact_key = to_key(CONTACT_PREFIX, &mut name.as_bytes().to_vec());
+
+		Ok(match self.db.get_ser(&contact_key) {
+			Some(s) => Serializable::as_contact(s),
+			None => None,
+		})
+	}
+
 	fn store_tx(&self, uuid: &str, tx: &Transaction) -> Result<(), Error> {
 		let filename = format!("{}.epictx", uuid);
 		let path = path::Path::new(&self.data_file_dir)
@@ -431,6 +470,34 @@ where
 		))
 	}
 
+	fn store_tx_proof(&self, uuid: &str, proof: &TxProof) -> Result<(), Error> {
+		let filename = format!("{}.proof", uuid);
+		let path = path::Path::new(&self.data_file_dir)
+			.join(TX_PROOF_SAVE_DIR)
+			.join(filename);
+		let mut stored_proof = File::create(path)?;
+		let proof_json =
+			serde_json::to_string_pretty(proof).map_err(|e| Error::TxProof(e.to_string()))?;
+		stored_proof.write_all(proof_json.as_bytes())?;
+		stored_proof.sync_all()?;
+		Ok(())
+	}
+
+	fn get_stored_tx_proof(&self, entry: &TxLogEntry) -> Result<Option<TxProof>, Error> {
+		let filename = match entry.tx_proof.clone() {
+			Some(f) => f,
+			None => return Ok(None),
+		};
+		let path = path::Path::new(&self.data_file_dir)
+			.join(TX_PROOF_SAVE_DIR)
+			.join(filename);
+		let mut proof_f = File::open(path)?;
+		let mut content = String::new();
+		proof_f.read_to_string(&mut content)?;
+		let proof = serde_json::from_str(&content).map_err(|e| Error::TxProof(e.to_string()))?;
+		Ok(Some(proof))
+	}
+
 	fn batch<'a>(
 		&'a mut self,
 		keychain_mask: Option<&SecretKey>,
@@ -530,6 +597,90 @@ where
 		Ok(last_scanned_block)
 	}
 
+	fn scan_checkpoint<'a>(&mut self) -> Result<Option<ScanCheckpoint>, Error> {
+		let batch = self.db.batch();
+		let checkpoint_key = to_key(
+			SCAN_CHECKPOINT,
+			&mut SCAN_CHECKPOINT_KEY.as_bytes().to_vec(),
+		);
+		Ok(match batch.get_ser(&checkpoint_key) {
+			Some(s) => Serializable::as_scan_checkpoint(s),
+			None => None,
+		})
+	}
+
+	fn scanned_outputs<'a>(&'a self) -> Box<dyn Iterator<Item = ScannedOutput> + 'a> {
+		let serializables: Vec<_> = self
+			.db
+			.iter(&[SCANNED_OUTPUT_PREFIX])
+			.into_iter()
+			.filter_map(Serializable::as_scanned_output)
+			.collect();
+		Box::new(serializables.into_iter().map(|x| x))
+	}
+
+	fn scanned_block_history<'a>(&mut self) -> Result<ScannedBlockHistory, Error> {
+		let batch = self.db.batch();
+		let history_key = to_key(
+			SCANNED_BLOCK_HISTORY,
+			&mut SCANNED_BLOCK_HISTORY_KEY.as_bytes().to_vec(),
+		);
+		Ok(batch
+			.get_ser(&history_key)
+			.and_then(Serializable::as_scanned_block_history)
+			.unwrap_or_default())
+	}
+
+	fn webhook_tx_state_iter<'a>(&'a self) -> Box<dyn Iterator<Item = WebhookTxState> + 'a> {
+		let serializables: Vec<_> = self
+			.db
+			.iter(&[WEBHOOK_TX_STATE_PREFIX])
+			.into_iter()
+			.filter_map(Serializable::as_webhook_tx_state)
+			.collect();
+		Box::new(serializables.into_iter().map(|x| x))
+	}
+
+	fn webhook_delivery_iter<'a>(&'a self) -> Box<dyn Iterator<Item = WebhookDelivery> + 'a> {
+		let serializables: Vec<_> = self
+			.db
+			.iter(&[WEBHOOK_DELIVERY_PREFIX])
+			.into_iter()
+			.filter_map(Serializable::as_webhook_delivery)
+			.collect();
+		Box::new(serializables.into_iter().map(|x| x))
+	}
+
+	fn webhooks_init_height<'a>(&mut self) -> Result<Option<u64>, Error> {
+		let batch = self.db.batch();
+		let init_key = to_key(
+			WEBHOOKS_INIT_HEIGHT,
+			&mut WEBHOOKS_INIT_HEIGHT_KEY.as_bytes().to_vec(),
+		);
+		Ok(match batch.get_ser(&init_key) {
+			Some(Serializable::Numeric(n)) => Some(n),
+			_ => None,
+		})
+	}
+
+	fn outbox_iter<'a>(&'a self) -> Box<dyn Iterator<Item = OutboxEntry> + 'a> {
+		let serializables: Vec<_> = self
+			.db
+			.iter(&[OUTBOX_PREFIX])
+			.into_iter()
+			.filter_map(Serializable::as_outbox_entry)
+			.collect();
+		Box::new(serializables.into_iter().map(|x| x))
+	}
+
+	fn epicbox_status<'a>(&mut self) -> Result<Option<EpicboxListenerStatus>, Error> {
+		let batch = self.db.batch();
+		let status_key = to_key(EPICBOX_STATUS, &mut EPICBOX_STATUS_KEY.as_bytes().to_vec());
+		Ok(batch
+			.get_ser(&status_key)
+			.and_then(Serializable::as_epicbox_listener_status))
+	}
+
 	fn init_status<'a>(&mut self) -> Result<WalletInitStatus, Error> {
 		let batch = self.db.batch();
 		let init_status_key = to_key(
@@ -778,6 +929,145 @@ where
 		Ok(())
 	}
 
+	fn save_scan_checkpoint(&mut self, checkpoint: ScanCheckpoint) -> Result<(), Error> {
+		let checkpoint_key = to_key(
+			SCAN_CHECKPOINT,
+			&mut SCAN_CHECKPOINT_KEY.as_bytes().to_vec(),
+		);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&checkpoint_key, Serializable::ScanCheckpoint(checkpoint))?;
+		Ok(())
+	}
+
+	fn save_scanned_output(&mut self, output: ScannedOutput) -> Result<(), Error> {
+		let output_key = to_key(SCANNED_OUTPUT_PREFIX, &mut output.commit.as_ref().to_vec());
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&output_key, Serializable::ScannedOutput(output))?;
+		Ok(())
+	}
+
+	fn delete_scan_checkpoint(&mut self) -> Result<(), Error> {
+		let checkpoint_key = to_key(
+			SCAN_CHECKPOINT,
+			&mut SCAN_CHECKPOINT_KEY.as_bytes().to_vec(),
+		);
+		let db = self.db.borrow();
+		let db = db.as_ref().unwrap();
+		let outputs: Vec<_> = db
+			.iter(&[SCANNED_OUTPUT_PREFIX])
+			.into_iter()
+			.filter_map(Serializable::as_scanned_output)
+			.collect();
+		for output in outputs {
+			let output_key = to_key(SCANNED_OUTPUT_PREFIX, &mut output.commit.as_ref().to_vec());
+			db.delete(&output_key)?;
+		}
+		db.delete(&checkpoint_key)
+			.map_err(|e| Error::Backend(format!("{}", e)))
+	}
+
+	fn save_scanned_block_history(&mut self, history: ScannedBlockHistory) -> Result<(), Error> {
+		let history_key = to_key(
+			SCANNED_BLOCK_HISTORY,
+			&mut SCANNED_BLOCK_HISTORY_KEY.as_bytes().to_vec(),
+		);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&history_key, Serializable::ScannedBlockHistory(history))?;
+		Ok(())
+	}
+
+	fn save_webhook_tx_state(&mut self, state: WebhookTxState) -> Result<(), Error> {
+		let state_key = to_key_u64(
+			WEBHOOK_TX_STATE_PREFIX,
+			&mut state.parent_key_id.to_bytes().to_vec(),
+			state.tx_id as u64,
+		);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&state_key, Serializable::WebhookTxState(state))?;
+		Ok(())
+	}
+
+	fn save_webhook_delivery(&mut self, delivery: WebhookDelivery) -> Result<(), Error> {
+		let delivery_key = to_key(
+			WEBHOOK_DELIVERY_PREFIX,
+			&mut delivery.delivery_id.as_bytes().to_vec(),
+		);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&delivery_key, Serializable::WebhookDelivery(delivery))?;
+		Ok(())
+	}
+
+	fn delete_webhook_delivery(&mut self, delivery_id: &uuid::Uuid) -> Result<(), Error> {
+		let delivery_key = to_key(
+			WEBHOOK_DELIVERY_PREFIX,
+			&mut delivery_id.as_bytes().to_vec(),
+		);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.delete(&delivery_key)
+			.map_err(|e| Error::Backend(format!("{}", e)))
+	}
+
+	fn save_webhooks_init_height(&mut self, height: u64) -> Result<(), Error> {
+		let init_key = to_key(
+			WEBHOOKS_INIT_HEIGHT,
+			&mut WEBHOOKS_INIT_HEIGHT_KEY.as_bytes().to_vec(),
+		);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&init_key, Serializable::Numeric(height))?;
+		Ok(())
+	}
+
+	fn save_outbox_entry(&mut self, entry: OutboxEntry) -> Result<(), Error> {
+		let entry_key = to_key(OUTBOX_PREFIX, &mut entry.slate_id.as_bytes().to_vec());
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&entry_key, Serializable::OutboxEntry(entry))?;
+		Ok(())
+	}
+
+	fn delete_outbox_entry(&mut self, slate_id: &uuid::Uuid) -> Result<(), Error> {
+		let entry_key = to_key(OUTBOX_PREFIX, &mut slate_id.as_bytes().to_vec());
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.delete(&entry_key)
+			.map_err(|e| Error::Backend(format!("{}", e)))
+	}
+
+	fn save_epicbox_status(&mut self, status: EpicboxListenerStatus) -> Result<(), Error> {
+		let status_key = to_key(EPICBOX_STATUS, &mut EPICBOX_STATUS_KEY.as_bytes().to_vec());
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&status_key, Serializable::EpicboxListenerStatus(status))?;
+		Ok(())
+	}
+
 	fn save_init_status(&mut self, value: WalletInitStatus) -> Result<(), Error> {
 		let init_status_key = to_key(
 			WALLET_INIT_STATUS,
@@ -846,6 +1136,26 @@ where
 		Box::new(serializables.into_iter().map(|x| x))
 	}
 
+	fn save_contact(&mut self, contact: Contact) -> Result<(), Error> {
+		let contact_key = to_key(CONTACT_PREFIX, &mut contact.name.as_bytes().to_vec());
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&contact_key, Serializable::Contact(contact))?;
+		Ok(())
+	}
+
+	fn delete_contact(&mut self, name: &str) -> Result<(), Error> {
+		let contact_key = to_key(CONTACT_PREFIX, &mut name.as_bytes().to_vec());
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.delete(&contact_key)
+			.map_err(|e| Error::Backend(format!("{}", e)))
+	}
+
 	fn lock_output(&mut self, out: &mut OutputData) -> Result<(), Error> {
 		out.lock();
 		self.save(out.clone())
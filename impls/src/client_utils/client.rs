@@ -20,6 +20,7 @@ use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYP
 use reqwest::{ClientBuilder, Method, Proxy, RequestBuilder};
 use serde::{Deserialize, Serialize};
 use serde_json;
+use std::future::Future;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::runtime::{Builder, Handle, Runtime};
@@ -203,6 +204,22 @@ impl Client {
 		Ok(())
 	}
 
+	/// Post a raw body with extra headers. Unlike the other helpers, any
+	/// response status but a success is an error.
+	pub fn post_raw(
+		&self,
+		url: &str,
+		body: String,
+		headers: &[(String, String)],
+	) -> Result<(), Error> {
+		let mut req = self.build_request(url, Method::POST, None, Some(body))?;
+		for (name, value) in headers {
+			req = req.header(name.as_str(), value.as_str());
+		}
+		let client = self.clone();
+		Self::block_on(async move { client.send_request_checked_async(req).await })
+	}
+
 	fn build_request(
 		&self,
 		url: &str,
@@ -268,25 +285,41 @@ impl Client {
 		Ok(text)
 	}
 
+	async fn send_request_checked_async(&self, req: RequestBuilder) -> Result<(), Error> {
+		let resp = req
+			.send()
+			.await
+			.map_err(|e| Error::RequestError(format!("Cannot make request: {}", e)))?;
+		if !resp.status().is_success() {
+			return Err(Error::ResponseError(format!(
+				"Request failed with status {}",
+				resp.status()
+			)));
+		}
+		Ok(())
+	}
+
 	pub fn send_request(&self, req: RequestBuilder) -> Result<String, Error> {
+		let client = self.clone();
+		Self::block_on(async move { client.send_request_async(req).await })
+	}
+
+	/// Run a request to completion on the global runtime
+	fn block_on<F, T>(request: F) -> T
+	where
+		F: Future<Output = T> + Send + 'static,
+		T: Send + 'static,
+	{
 		// This client is currently used both outside and inside of a tokio runtime
 		// context. In the latter case we are not allowed to do a blocking call to
 		// our global runtime, which unfortunately means we have to spawn a new thread
 		if Handle::try_current().is_ok() {
 			let rt = RUNTIME.clone();
-			let client = self.clone();
-			std::thread::spawn(move || {
-				rt.lock()
-					.unwrap()
-					.block_on(async { client.send_request_async(req).await })
-			})
-			.join()
-			.unwrap()
-		} else {
-			RUNTIME
-				.lock()
+			std::thread::spawn(move || rt.lock().unwrap().block_on(request))
+				.join()
 				.unwrap()
-				.block_on(self.send_request_async(req))
+		} else {
+			RUNTIME.lock().unwrap().block_on(request)
 		}
 	}
 }
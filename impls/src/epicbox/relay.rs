@@ -0,0 +1,833 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Minimal epicbox relay. Wallets subscribe to the slates sent to their
+//! address by signing the relay's challenge, and slates posted to an address
+//! are kept in its mailbox until the wallet confirms them with `Made` or
+//! they expire. Mailboxes, senders, slates and connections are limited by
+//! `RelayLimits`, so a relay can't be flooded. The relay speaks plain
+//! websockets, put it behind a TLS proxy to serve `wss://`.
+
+use crate::epicbox::protocol::{ProtocolError, ProtocolRequestV2, ProtocolResponseV2};
+use crate::libwallet::crypto::{verify_signature, Hex};
+use crate::libwallet::{Address, EpicboxAddress};
+use crate::util::secp::Signature;
+use crate::util::Mutex;
+use crate::Error;
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tungstenite::protocol::WebSocket;
+use tungstenite::{accept, Error as ErrorTungstenite, Message};
+use uuid::Uuid;
+
+/// How long a connection waits for a request before looking for new slates
+const POLL_INTERVAL_MS: u64 = 200;
+
+/// How long the websocket handshake, and then each response, may take
+const IO_TIMEOUT_SECS: u64 = 10;
+
+/// How long slates are kept by default
+pub const DEFAULT_EXPIRY_SECS: u64 = 24 * 60 * 60;
+
+/// Limits keeping a relay from being flooded
+#[derive(Clone, Copy, Debug)]
+pub struct RelayLimits {
+	/// Most slates kept for an address, more are refused until some are
+	/// picked up or expire
+	pub max_mailbox_len: usize,
+	/// Largest slate accepted, as the length of its encrypted message
+	pub max_slate_len: usize,
+	/// Most slates kept from one address, over all mailboxes
+	pub max_sender_len: usize,
+	/// Total length of the slates kept over all mailboxes, more are refused
+	/// until some are picked up or expire
+	pub max_stored_len: usize,
+	/// Most wallets connected at once, more are disconnected right away
+	pub max_connections: usize,
+	/// How long a connection may go without subscribing or posting a slate
+	/// before it's dropped
+	pub idle_timeout: Duration,
+}
+
+impl Default for RelayLimits {
+	fn default() -> Self {
+		RelayLimits {
+			max_mailbox_len: 1000,
+			max_slate_len: 1024 * 1024,
+			max_sender_len: 1000,
+			max_stored_len: 256 * 1024 * 1024,
+			max_connections: 1024,
+			idle_timeout: Duration::from_secs(30),
+		}
+	}
+}
+
+/// A slate waiting in a mailbox
+struct Mail {
+	id: String,
+	/// Public key the slate was signed with
+	sender: String,
+	from: String,
+	str: String,
+	signature: String,
+	posted: Instant,
+}
+
+/// Mailboxes and subscriptions, by public key. Subscribers are told about
+/// new slates through a channel, named by their connection's challenge.
+#[derive(Default)]
+struct Mailboxes {
+	mail: HashMap<String, Vec<Mail>>,
+	subscribers: HashMap<String, Vec<(String, Sender<()>)>>,
+	usage: Usage,
+}
+
+/// What all the mailboxes hold together
+#[derive(Default)]
+struct Usage {
+	/// Total length of the slates kept
+	stored: usize,
+	/// Slates kept, by sender public key
+	senders: HashMap<String, usize>,
+}
+
+impl Usage {
+	fn add(&mut self, mail: &Mail) {
+		self.stored += mail.str.len();
+		*self.senders.entry(mail.sender.clone()).or_default() += 1;
+	}
+
+	fn remove(&mut self, mail: &Mail) {
+		self.stored -= mail.str.len();
+		if let Some(n) = self.senders.get_mut(&mail.sender) {
+			*n -= 1;
+			if *n == 0 {
+				self.senders.remove(&mail.sender);
+			}
+		}
+	}
+}
+
+impl Mailboxes {
+	/// Drop slates older than `expiry`
+	fn expire(&mut self, expiry: Duration) {
+		let usage = &mut self.usage;
+		for mailbox in self.mail.values_mut() {
+			mailbox.retain(|m| {
+				let keep = m.posted.elapsed() < expiry;
+				if !keep {
+					usage.remove(m);
+				}
+				keep
+			});
+		}
+		self.mail.retain(|_, mailbox| !mailbox.is_empty());
+	}
+
+	/// Keep a slate for `to` and let its subscribers know. Slates going over
+	/// `limits` are refused, with the reason.
+	fn post(&mut self, to: &str, mail: Mail, limits: &RelayLimits) -> Result<(), &'static str> {
+		if self.usage.stored + mail.str.len() > limits.max_stored_len {
+			return Err("relay full");
+		}
+		if self.usage.senders.get(&mail.sender).copied().unwrap_or(0) >= limits.max_sender_len {
+			return Err("too many slates from sender");
+		}
+		let mailbox = self.mail.entry(to.to_owned()).or_default();
+		if mailbox.len() >= limits.max_mailbox_len {
+			return Err("mailbox full");
+		}
+		self.usage.add(&mail);
+		mailbox.push(mail);
+		if let Some(subscribers) = self.subscribers.get_mut(to) {
+			subscribers.retain(|(_, s)| s.send(()).is_ok());
+		}
+		Ok(())
+	}
+
+	/// Forget a slate its recipient confirmed, returns whether it was known
+	fn made(&mut self, address: &str, id: &str) -> bool {
+		let mailbox = match self.mail.get_mut(address) {
+			Some(m) => m,
+			None => return false,
+		};
+		let usage = &mut self.usage;
+		let len = mailbox.len();
+		mailbox.retain(|m| {
+			let keep = m.id != id;
+			if !keep {
+				usage.remove(m);
+			}
+			keep
+		});
+		len != mailbox.len()
+	}
+}
+
+/// Epicbox relay listening on a local socket
+pub struct EpicboxRelay {
+	listener: TcpListener,
+	mailboxes: Arc<Mutex<Mailboxes>>,
+	expiry: Duration,
+	limits: RelayLimits,
+}
+
+impl EpicboxRelay {
+	/// Listen on `addr`, keeping slates for `expiry`, with the default limits
+	pub fn bind(addr: &str, expiry: Duration) -> Result<Self, Error> {
+		let listener = TcpListener::bind(addr)?;
+		Ok(EpicboxRelay {
+			listener,
+			mailboxes: Arc::new(Mutex::new(Mailboxes::default())),
+			expiry,
+			limits: RelayLimits::default(),
+		})
+	}
+
+	/// Use other limits than the default ones
+	pub fn limits(mut self, limits: RelayLimits) -> Self {
+		self.limits = limits;
+		self
+	}
+
+	/// Address the relay is listening on
+	pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+		Ok(self.listener.local_addr()?)
+	}
+
+	/// Serve connections, each on a thread of its own
+	pub fn run(&self) -> Result<(), Error> {
+		info!("Epicbox relay listening on {}", self.local_addr()?);
+		let connections = Arc::new(AtomicUsize::new(0));
+		for stream in self.listener.incoming() {
+			// failing to accept one connection, e.g. because it was reset or
+			// the process is out of file descriptors, doesn't stop the relay
+			let stream = match stream {
+				Ok(s) => s,
+				Err(e) => {
+					warn!("Epicbox relay failed to accept a connection: {}", e);
+					thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+					continue;
+				}
+			};
+			let peer = stream.peer_addr().ok();
+			// only this loop adds connections, so the count can't go over
+			if connections.load(Ordering::SeqCst) >= self.limits.max_connections {
+				warn!(
+					"Epicbox relay refusing {:?}, {} connections open already",
+					peer, self.limits.max_connections
+				);
+				continue;
+			}
+			connections.fetch_add(1, Ordering::SeqCst);
+			let connections = connections.clone();
+			let mailboxes = self.mailboxes.clone();
+			let expiry = self.expiry;
+			let limits = self.limits;
+			thread::spawn(move || {
+				if let Err(e) = handle_connection(stream, mailboxes, expiry, limits) {
+					debug!("Epicbox relay connection {:?} closed: {}", peer, e);
+				}
+				connections.fetch_sub(1, Ordering::SeqCst);
+			});
+		}
+		Ok(())
+	}
+}
+
+/// A wallet connected to the relay
+struct Connection {
+	socket: WebSocket<TcpStream>,
+	challenge: String,
+	/// Public key the connection subscribed to, with the protocol version
+	subscription: Option<(String, String)>,
+	/// Slates sent on this connection and not confirmed yet
+	sent: HashSet<String>,
+	/// Whether the connection subscribed or posted a slate yet
+	active: bool,
+	notify_tx: Sender<()>,
+	notify_rx: Receiver<()>,
+}
+
+fn handle_connection(
+	stream: TcpStream,
+	mailboxes: Arc<Mutex<Mailboxes>>,
+	expiry: Duration,
+	limits: RelayLimits,
+) -> Result<(), Error> {
+	// a peer that never completes the handshake mustn't hold the thread
+	stream.set_read_timeout(Some(Duration::from_secs(IO_TIMEOUT_SECS)))?;
+	stream.set_write_timeout(Some(Duration::from_secs(IO_TIMEOUT_SECS)))?;
+	let socket = accept(stream).map_err(|e| Error::EpicboxTungstenite(format!("{}", e)))?;
+	socket
+		.get_ref()
+		.set_read_timeout(Some(Duration::from_millis(POLL_INTERVAL_MS)))?;
+	let opened = Instant::now();
+	let (notify_tx, notify_rx) = channel();
+	let mut conn = Connection {
+		socket,
+		challenge: Uuid::new_v4().simple().to_string(),
+		subscription: None,
+		sent: HashSet::new(),
+		active: false,
+		notify_tx,
+		notify_rx,
+	};
+	conn.respond(&ProtocolResponseV2::Challenge {
+		str: conn.challenge.clone(),
+	})?;
+
+	let res = loop {
+		if !conn.active && opened.elapsed() > limits.idle_timeout {
+			break Err(Error::EpicboxTungstenite(
+				"no subscription or slate in time".to_owned(),
+			));
+		}
+		if conn.notify_rx.try_iter().count() > 0 {
+			conn.deliver(&mailboxes, expiry)?;
+		}
+		let message = match conn.socket.read() {
+			Ok(m) => m,
+			Err(ErrorTungstenite::Io(ref e)) if is_timeout(e) => continue,
+			Err(ErrorTungstenite::ConnectionClosed) | Err(ErrorTungstenite::AlreadyClosed) => {
+				break Ok(());
+			}
+			Err(e) => break Err(Error::EpicboxTungstenite(format!("{}", e))),
+		};
+		match message {
+			Message::Text(_) | Message::Binary(_) => {
+				let request = message
+					.to_text()
+					.ok()
+					.and_then(|t| serde_json::from_str::<ProtocolRequestV2>(t).ok());
+				let res = match request {
+					Some(r) => conn.handle_request(r, &mailboxes, expiry, &limits),
+					None => conn.respond(&error(ProtocolError::InvalidRequest)),
+				};
+				if let Err(e) = res {
+					break Err(e);
+				}
+			}
+			Message::Close(_) => break Ok(()),
+			_ => {}
+		}
+	};
+	conn.unsubscribe(&mailboxes);
+	res
+}
+
+impl Connection {
+	fn handle_request(
+		&mut self,
+		request: ProtocolRequestV2,
+		mailboxes: &Mutex<Mailboxes>,
+		expiry: Duration,
+		limits: &RelayLimits,
+	) -> Result<(), Error> {
+		trace!("Epicbox relay request: {}", request);
+		match request {
+			ProtocolRequestV2::Challenge => self.respond(&ProtocolResponseV2::Challenge {
+				str: self.challenge.clone(),
+			}),
+			ProtocolRequestV2::Subscribe {
+				address,
+				ver,
+				signature,
+			} => {
+				let public_key = match check_signature(&address, &self.challenge, &signature) {
+					Ok(k) => k,
+					Err(e) => return self.respond(&e),
+				};
+				if self.subscription.as_ref().map(|(k, _)| k) != Some(&public_key) {
+					self.unsubscribe(mailboxes);
+					mailboxes
+						.lock()
+						.subscribers
+						.entry(public_key.clone())
+						.or_default()
+						.push((self.challenge.clone(), self.notify_tx.clone()));
+					self.sent.clear();
+				}
+				self.subscription = Some((public_key, ver));
+				self.active = true;
+				self.respond(&ProtocolResponseV2::Ok)?;
+				self.deliver(mailboxes, expiry)
+			}
+			ProtocolRequestV2::Unsubscribe { .. } => {
+				self.unsubscribe(mailboxes);
+				self.respond(&ProtocolResponseV2::Ok)
+			}
+			ProtocolRequestV2::PostSlate {
+				from,
+				to,
+				str,
+				signature,
+			} => {
+				if str.len() > limits.max_slate_len {
+					return self.respond(&ProtocolResponseV2::Error {
+						kind: ProtocolError::InvalidRequest,
+						description: format!("slate over {} bytes", limits.max_slate_len),
+					});
+				}
+				let sender = match check_signature(&from, &str, &signature) {
+					Ok(k) => k,
+					Err(e) => return self.respond(&e),
+				};
+				let to = match EpicboxAddress::from_str(&to) {
+					Ok(a) => a.public_key,
+					Err(_) => return self.respond(&error(ProtocolError::InvalidRequest)),
+				};
+				let mail = Mail {
+					id: Uuid::new_v4().to_string(),
+					sender,
+					from,
+					str,
+					signature,
+					posted: Instant::now(),
+				};
+				let id = mail.id.clone();
+				let kept = {
+					let mut mailboxes = mailboxes.lock();
+					mailboxes.expire(expiry);
+					mailboxes.post(&to, mail, limits)
+				};
+				if let Err(reason) = kept {
+					debug!("Epicbox relay refusing slate for {}: {}", to, reason);
+					return self.respond(&ProtocolResponseV2::Error {
+						kind: ProtocolError::UnknownError,
+						description: reason.to_owned(),
+					});
+				}
+				debug!("Epicbox relay keeping slate {} for {}", id, to);
+				self.active = true;
+				self.respond(&ProtocolResponseV2::Ok)
+			}
+			ProtocolRequestV2::Made {
+				address,
+				signature,
+				ver: _,
+				epicboxmsgid,
+			} => {
+				let public_key = match check_signature(&address, &epicboxmsgid, &signature) {
+					Ok(k) => k,
+					Err(e) => return self.respond(&e),
+				};
+				if mailboxes.lock().made(&public_key, &epicboxmsgid) {
+					debug!("Epicbox relay delivered slate {}", epicboxmsgid);
+				}
+				self.sent.remove(&epicboxmsgid);
+				self.respond(&ProtocolResponseV2::Ok)
+			}
+			ProtocolRequestV2::ClientDetails { .. } => Ok(()),
+		}
+	}
+
+	/// Send the subscribed address the slates it wasn't sent yet
+	fn deliver(&mut self, mailboxes: &Mutex<Mailboxes>, expiry: Duration) -> Result<(), Error> {
+		let (public_key, ver) = match self.subscription {
+			Some(ref s) => s.clone(),
+			None => return Ok(()),
+		};
+		let slates: Vec<ProtocolResponseV2> = {
+			let mut mailboxes = mailboxes.lock();
+			mailboxes.expire(expiry);
+			let mailbox = match mailboxes.mail.get(&public_key) {
+				Some(m) => m,
+				None => return Ok(()),
+			};
+			mailbox
+				.iter()
+				.filter(|m| !self.sent.contains(&m.id))
+				.map(|m| ProtocolResponseV2::Slate {
+					from: m.from.clone(),
+					str: m.str.clone(),
+					signature: m.signature.clone(),
+					challenge: String::new(),
+					ver: ver.clone(),
+					epicboxmsgid: m.id.clone(),
+				})
+				.collect()
+		};
+		for slate in slates {
+			if let ProtocolResponseV2::Slate {
+				ref epicboxmsgid, ..
+			} = slate
+			{
+				self.sent.insert(epicboxmsgid.clone());
+			}
+			self.respond(&slate)?;
+		}
+		Ok(())
+	}
+
+	fn unsubscribe(&mut self, mailboxes: &Mutex<Mailboxes>) {
+		if let Some((public_key, _)) = self.subscription.take() {
+			let mut mailboxes = mailboxes.lock();
+			if let Some(subscribers) = mailboxes.subscribers.get_mut(&public_key) {
+				subscribers.retain(|(c, _)| c != &self.challenge);
+				if subscribers.is_empty() {
+					mailboxes.subscribers.remove(&public_key);
+				}
+			}
+		}
+	}
+
+	fn respond(&mut self, response: &ProtocolResponseV2) -> Result<(), Error> {
+		let response = serde_json::to_string(response)?;
+		self.socket
+			.send(Message::Text(response.into()))
+			.map_err(|e| Error::EpicboxTungstenite(format!("{}", e)))
+	}
+}
+
+/// Check that `message` was signed by the key of `address`, which is either
+/// a public key or a full epicbox address. Returns the public key as written
+/// in addresses.
+fn check_signature(
+	address: &str,
+	message: &str,
+	signature: &str,
+) -> Result<String, ProtocolResponseV2> {
+	let address =
+		EpicboxAddress::from_str(address).map_err(|_| error(ProtocolError::InvalidRequest))?;
+	let public_key = address
+		.public_key()
+		.map_err(|_| error(ProtocolError::InvalidRequest))?;
+	let signature =
+		Signature::from_hex(signature).map_err(|_| error(ProtocolError::InvalidSignature))?;
+	verify_signature(message, &signature, &public_key)
+		.map_err(|_| error(ProtocolError::InvalidSignature))?;
+	Ok(address.public_key)
+}
+
+fn error(kind: ProtocolError) -> ProtocolResponseV2 {
+	ProtocolResponseV2::Error {
+		description: format!("{}", kind),
+		kind,
+	}
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+	matches!(
+		e.kind(),
+		io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+	)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::libwallet::crypto::sign_challenge;
+	use crate::util::secp::key::{PublicKey, SecretKey};
+	use crate::util::secp::Secp256k1;
+	use tungstenite::connect;
+	use tungstenite::stream::MaybeTlsStream;
+
+	type Client = WebSocket<MaybeTlsStream<TcpStream>>;
+
+	fn key(b: u8, port: u16) -> (SecretKey, EpicboxAddress) {
+		let secp = Secp256k1::new();
+		let sk = SecretKey::from_slice(&secp, &[b; 32]).unwrap();
+		let pk = PublicKey::from_secret_key(&secp, &sk).unwrap();
+		let address = EpicboxAddress::new(pk, Some("127.0.0.1".to_owned()), Some(port));
+		(sk, address)
+	}
+
+	fn send(client: &mut Client, request: &ProtocolRequestV2) {
+		let request = serde_json::to_string(request).unwrap();
+		client.send(Message::Text(request.into())).unwrap();
+	}
+
+	fn read(client: &mut Client) -> ProtocolResponseV2 {
+		loop {
+			let message = client.read().unwrap();
+			if let Message::Text(_) = message {
+				return serde_json::from_str(message.to_text().unwrap()).unwrap();
+			}
+		}
+	}
+
+	/// Connect and subscribe to the slates of `address`
+	fn subscribe(port: u16, sk: &SecretKey, address: &EpicboxAddress) -> Client {
+		let (mut client, _) = connect(format!("ws://127.0.0.1:{}", port)).unwrap();
+		let challenge = match read(&mut client) {
+			ProtocolResponseV2::Challenge { str } => str,
+			r => panic!("Unexpected response: {}", r),
+		};
+		send(
+			&mut client,
+			&ProtocolRequestV2::Subscribe {
+				address: address.public_key.clone(),
+				ver: "3.0.0".to_owned(),
+				signature: sign_challenge(&challenge, sk).unwrap().to_hex(),
+			},
+		);
+		client
+	}
+
+	fn post(client: &mut Client, sk: &SecretKey, from: &EpicboxAddress, to: &EpicboxAddress) {
+		post_str(
+			client,
+			sk,
+			from,
+			to,
+			format!("slate from {}", from.public_key),
+		);
+	}
+
+	fn post_str(
+		client: &mut Client,
+		sk: &SecretKey,
+		from: &EpicboxAddress,
+		to: &EpicboxAddress,
+		str: String,
+	) {
+		send(
+			client,
+			&ProtocolRequestV2::PostSlate {
+				from: from.stripped(),
+				to: to.stripped(),
+				signature: sign_challenge(&str, sk).unwrap().to_hex(),
+				str,
+			},
+		);
+	}
+
+	#[test]
+	fn relay_mailboxes() {
+		let relay = EpicboxRelay::bind("127.0.0.1:0", Duration::from_secs(60)).unwrap();
+		let port = relay.local_addr().unwrap().port();
+		thread::spawn(move || relay.run());
+
+		let (sender_sk, sender) = key(1, port);
+		let (recipient_sk, recipient) = key(2, port);
+		let (other_sk, other) = key(3, port);
+
+		// a slate has to be signed by the key of the address it's from
+		let mut posting = subscribe(port, &sender_sk, &sender);
+		assert!(matches!(read(&mut posting), ProtocolResponseV2::Ok));
+		post(&mut posting, &other_sk, &sender, &recipient);
+		match read(&mut posting) {
+			ProtocolResponseV2::Error {
+				kind: ProtocolError::InvalidSignature,
+				..
+			} => {}
+			r => panic!("Unexpected response: {}", r),
+		}
+
+		// kept until the recipient connects
+		post(&mut posting, &sender_sk, &sender, &recipient);
+		assert!(matches!(read(&mut posting), ProtocolResponseV2::Ok));
+		let mut listening = subscribe(port, &recipient_sk, &recipient);
+		assert!(matches!(read(&mut listening), ProtocolResponseV2::Ok));
+		let id = match read(&mut listening) {
+			ProtocolResponseV2::Slate {
+				from, epicboxmsgid, ..
+			} => {
+				assert_eq!(from, sender.stripped());
+				epicboxmsgid
+			}
+			r => panic!("Unexpected response: {}", r),
+		};
+
+		// and sent again until it's confirmed
+		let mut again = subscribe(port, &recipient_sk, &recipient);
+		assert!(matches!(read(&mut again), ProtocolResponseV2::Ok));
+		match read(&mut again) {
+			ProtocolResponseV2::Slate { epicboxmsgid, .. } => assert_eq!(epicboxmsgid, id),
+			r => panic!("Unexpected response: {}", r),
+		}
+		send(
+			&mut again,
+			&ProtocolRequestV2::Made {
+				address: recipient.public_key.clone(),
+				signature: sign_challenge(&id, &recipient_sk).unwrap().to_hex(),
+				ver: "3.0.0".to_owned(),
+				epicboxmsgid: id.clone(),
+			},
+		);
+		assert!(matches!(read(&mut again), ProtocolResponseV2::Ok));
+
+		// the next slate is pushed to the subscribers, the confirmed one is
+		// gone
+		let (_, other_recipient) = key(4, port);
+		post(&mut posting, &other_sk, &other, &other_recipient);
+		assert!(matches!(read(&mut posting), ProtocolResponseV2::Ok));
+		post(&mut posting, &sender_sk, &sender, &recipient);
+		assert!(matches!(read(&mut posting), ProtocolResponseV2::Ok));
+		let mut last = subscribe(port, &recipient_sk, &recipient);
+		assert!(matches!(read(&mut last), ProtocolResponseV2::Ok));
+		for client in [&mut listening, &mut last] {
+			match read(client) {
+				ProtocolResponseV2::Slate { epicboxmsgid, .. } => assert_ne!(epicboxmsgid, id),
+				r => panic!("Unexpected response: {}", r),
+			}
+		}
+	}
+
+	fn mail(id: &str, sender: &str, len: usize) -> Mail {
+		Mail {
+			id: id.to_owned(),
+			sender: sender.to_owned(),
+			from: String::new(),
+			str: "s".repeat(len),
+			signature: String::new(),
+			posted: Instant::now(),
+		}
+	}
+
+	#[test]
+	fn expiry() {
+		let mut mailboxes = Mailboxes::default();
+		let limits = RelayLimits {
+			max_mailbox_len: 2,
+			..RelayLimits::default()
+		};
+		assert!(mailboxes.post("key", mail("a", "", 0), &limits).is_ok());
+		assert!(mailboxes.post("key", mail("b", "", 0), &limits).is_ok());
+		assert!(mailboxes.post("key", mail("c", "", 0), &limits).is_err());
+		assert!(mailboxes.made("key", "a"));
+		assert!(!mailboxes.made("key", "a"));
+		mailboxes.expire(Duration::from_secs(60));
+		assert_eq!(mailboxes.mail["key"].len(), 1);
+		mailboxes.expire(Duration::from_secs(0));
+		assert!(mailboxes.mail.is_empty());
+	}
+
+	#[test]
+	fn relay_wide_limits() {
+		let mut mailboxes = Mailboxes::default();
+		let limits = RelayLimits {
+			max_sender_len: 2,
+			max_stored_len: 30,
+			..RelayLimits::default()
+		};
+
+		// a sender can't fill more than its share, whatever the mailboxes
+		assert!(mailboxes.post("1", mail("a", "x", 10), &limits).is_ok());
+		assert!(mailboxes.post("2", mail("b", "x", 10), &limits).is_ok());
+		assert_eq!(
+			mailboxes.post("3", mail("c", "x", 1), &limits),
+			Err("too many slates from sender")
+		);
+
+		// nor can all of them together fill more than the relay holds
+		assert!(mailboxes.post("3", mail("c", "y", 10), &limits).is_ok());
+		assert_eq!(
+			mailboxes.post("4", mail("d", "z", 1), &limits),
+			Err("relay full")
+		);
+
+		// slates picked up or expired make room again
+		assert!(mailboxes.made("1", "a"));
+		assert!(mailboxes.post("4", mail("d", "x", 10), &limits).is_ok());
+		mailboxes.expire(Duration::from_secs(0));
+		assert_eq!(mailboxes.usage.stored, 0);
+		assert!(mailboxes.usage.senders.is_empty());
+	}
+
+	#[test]
+	fn relay_limits() {
+		let limits = RelayLimits {
+			max_mailbox_len: 1,
+			max_slate_len: 100,
+			max_connections: 1,
+			..RelayLimits::default()
+		};
+		let relay = EpicboxRelay::bind("127.0.0.1:0", Duration::from_secs(60))
+			.unwrap()
+			.limits(limits);
+		let port = relay.local_addr().unwrap().port();
+		thread::spawn(move || relay.run());
+
+		let (sender_sk, sender) = key(1, port);
+		let (_, recipient) = key(2, port);
+
+		// slates over the size limit are refused, as are those that don't fit
+		// in the mailbox
+		let mut posting = subscribe(port, &sender_sk, &sender);
+		assert!(matches!(read(&mut posting), ProtocolResponseV2::Ok));
+		post_str(
+			&mut posting,
+			&sender_sk,
+			&sender,
+			&recipient,
+			"s".repeat(101),
+		);
+		assert!(matches!(
+			read(&mut posting),
+			ProtocolResponseV2::Error {
+				kind: ProtocolError::InvalidRequest,
+				..
+			}
+		));
+		post(&mut posting, &sender_sk, &sender, &recipient);
+		assert!(matches!(read(&mut posting), ProtocolResponseV2::Ok));
+		post(&mut posting, &sender_sk, &sender, &recipient);
+		assert!(matches!(
+			read(&mut posting),
+			ProtocolResponseV2::Error {
+				kind: ProtocolError::UnknownError,
+				..
+			}
+		));
+
+		// no more connections than the limit, until one closes
+		let url = format!("ws://127.0.0.1:{}", port);
+		assert!(connect(url.as_str()).is_err());
+		drop(posting);
+		let deadline = Instant::now() + Duration::from_secs(10);
+		while connect(url.as_str()).is_err() {
+			assert!(Instant::now() < deadline);
+			thread::sleep(Duration::from_millis(100));
+		}
+	}
+
+	#[test]
+	fn idle_connections() {
+		let limits = RelayLimits {
+			idle_timeout: Duration::from_millis(500),
+			..RelayLimits::default()
+		};
+		let relay = EpicboxRelay::bind("127.0.0.1:0", Duration::from_secs(60))
+			.unwrap()
+			.limits(limits);
+		let port = relay.local_addr().unwrap().port();
+		thread::spawn(move || relay.run());
+
+		// connections doing nothing are dropped, subscribed ones are kept
+		let (sk, address) = key(1, port);
+		let mut listening = subscribe(port, &sk, &address);
+		assert!(matches!(read(&mut listening), ProtocolResponseV2::Ok));
+		let (mut idle, _) = connect(format!("ws://127.0.0.1:{}", port)).unwrap();
+		assert!(matches!(
+			read(&mut idle),
+			ProtocolResponseV2::Challenge { .. }
+		));
+		thread::sleep(Duration::from_secs(1));
+		assert!(idle.read().is_err());
+		send(&mut listening, &ProtocolRequestV2::Challenge);
+		assert!(matches!(
+			read(&mut listening),
+			ProtocolResponseV2::Challenge { .. }
+		));
+	}
+}
@@ -107,6 +107,9 @@ pub enum Error {
 	#[error("Epicbox websocket terminated unexpectedly")]
 	EpicboxWebsocketAbnormalTermination,
 
+	#[error("Epicbox connection stalled: {0}")]
+	EpicboxStalled(String),
+
 	#[error("Epicbox ReceiveTx: {0}")]
 	EpicboxReceiveTx(String),
 }
@@ -44,15 +44,15 @@ pub mod test_framework;
 pub mod tor;
 
 pub use crate::adapters::{
-	create_sender, Container, EmojiSlate, EpicboxBroker, EpicboxChannel, EpicboxController,
-	EpicboxListenChannel, EpicboxListener, EpicboxPublisher, EpicboxSubscriber, HttpSlateSender,
-	KeybaseAllChannels, KeybaseChannel, Listener, ListenerInterface, PathToSlate, SlateGetter,
-	SlatePutter, SlateReceiver, SlateSender,
+	create_sender, find_contact, resolve_contact, Container, EmojiSlate, EpicboxBroker,
+	EpicboxChannel, EpicboxController, EpicboxListenChannel, EpicboxListener, EpicboxPublisher,
+	EpicboxSubscriber, HttpSlateSender, HttpWebhookSender, KeybaseAllChannels, KeybaseChannel,
+	Listener, ListenerInterface, PathToSlate, SlateGetter, SlatePutter, SlateReceiver, SlateSender,
 };
 pub use crate::backends::{wallet_db_exists, LMDBBackend};
 pub use crate::error::Error;
 pub use crate::lifecycle::DefaultLCProvider;
-pub use crate::node_clients::HTTPNodeClient;
+pub use crate::node_clients::{CachingNodeClient, HTTPNodeClient, MultiNodeClient};
 
 use crate::keychain::{ExtKeychain, Keychain};
 
@@ -15,8 +15,8 @@
 //! Default wallet lifecycle provider
 
 use crate::config::{
-	config, EpicboxConfig, GlobalWalletConfig, GlobalWalletConfigMembers, TorConfig, WalletConfig,
-	EPIC_WALLET_DIR,
+	config, EpicboxConfig, GlobalWalletConfig, GlobalWalletConfigMembers, ScanConfig,
+	SeedKdfConfig, SpendPolicy, TorConfig, WalletConfig, WebhookConfig, EPIC_WALLET_DIR,
 };
 use crate::core::global;
 use crate::keychain::Keychain;
@@ -37,6 +37,10 @@ where
 {
 	data_dir: String,
 	node_client: C,
+	seed_kdf: SeedKdfConfig,
+	scan: ScanConfig,
+	webhooks: WebhookConfig,
+	spend_policy: SpendPolicy,
 	backend: Option<Box<dyn WalletBackend<'a, C, K> + 'a>>,
 }
 
@@ -50,6 +54,10 @@ where
 		DefaultLCProvider {
 			node_client,
 			data_dir: "default".to_owned(),
+			seed_kdf: SeedKdfConfig::default(),
+			scan: ScanConfig::default(),
+			webhooks: WebhookConfig::default(),
+			spend_policy: SpendPolicy::default(),
 			backend: None,
 		}
 	}
@@ -69,6 +77,38 @@ where
 		Ok(self.data_dir.to_owned())
 	}
 
+	fn set_seed_kdf_config(&mut self, config: SeedKdfConfig) -> Result<(), Error> {
+		self.seed_kdf = config;
+		Ok(())
+	}
+
+	fn set_scan_config(&mut self, config: ScanConfig) -> Result<(), Error> {
+		self.scan = config;
+		Ok(())
+	}
+
+	fn scan_config(&self) -> ScanConfig {
+		self.scan
+	}
+
+	fn set_webhook_config(&mut self, config: WebhookConfig) -> Result<(), Error> {
+		self.webhooks = config;
+		Ok(())
+	}
+
+	fn webhook_config(&self) -> WebhookConfig {
+		self.webhooks.clone()
+	}
+
+	fn set_spend_policy(&mut self, policy: SpendPolicy) -> Result<(), Error> {
+		self.spend_policy = policy;
+		Ok(())
+	}
+
+	fn spend_policy(&self) -> SpendPolicy {
+		self.spend_policy.clone()
+	}
+
 	fn create_config(
 		&self,
 		chain_type: &global::ChainTypes,
@@ -180,6 +220,7 @@ where
 		mnemonic_length: usize,
 		password: ZeroingString,
 		test_mode: bool,
+		passphrase: Option<ZeroingString>,
 	) -> Result<(), Error> {
 		let mut data_dir_name = PathBuf::from(self.data_dir.clone());
 		data_dir_name.push(EPIC_WALLET_DIR);
@@ -191,7 +232,14 @@ where
 				return Err(Error::WalletSeedExists(msg))?;
 			}
 		}
-		let _ = WalletSeed::init_file(&data_dir_name, mnemonic_length, mnemonic.clone(), password);
+		let _ = WalletSeed::init_file(
+			&data_dir_name,
+			mnemonic_length,
+			mnemonic.clone(),
+			password,
+			passphrase,
+			&self.seed_kdf,
+		);
 		info!("Wallet seed file created");
 		let mut wallet: LMDBBackend<'a, C, K> =
 			match LMDBBackend::new(&data_dir_name, self.node_client.clone()) {
@@ -231,7 +279,7 @@ where
 				}
 				Ok(d) => d,
 			};
-		let wallet_seed = WalletSeed::from_file(&data_dir_name, password)
+		let wallet_seed = WalletSeed::open_file(&data_dir_name, password, &self.seed_kdf)
 			.map_err(|_| Error::Lifecycle("Error opening wallet (is password correct?)".into()))?;
 		let keychain = wallet_seed
 			.derive_keychain(global::is_floonet())
@@ -287,12 +335,19 @@ where
 		&self,
 		mnemonic: ZeroingString,
 		password: ZeroingString,
+		passphrase: Option<ZeroingString>,
 	) -> Result<(), Error> {
 		let mut data_dir_name = PathBuf::from(self.data_dir.clone());
 		data_dir_name.push(EPIC_WALLET_DIR);
 		let data_dir_name = data_dir_name.to_str().unwrap();
-		WalletSeed::recover_from_phrase(data_dir_name, mnemonic, password)
-			.map_err(|_| Error::Lifecycle("Error recovering from mnemonic".into()))?;
+		WalletSeed::recover_from_phrase(
+			data_dir_name,
+			mnemonic,
+			password,
+			passphrase,
+			&self.seed_kdf,
+		)
+		.map_err(|_| Error::Lifecycle("Error recovering from mnemonic".into()))?;
 		Ok(())
 	}
 
@@ -310,9 +365,6 @@ where
 
 		let orig_wallet_seed = WalletSeed::from_file(&data_dir_name, old)
 			.map_err(|_| Error::Lifecycle("Error opening wallet seed file".into()))?;
-		let orig_mnemonic = orig_wallet_seed
-			.to_mnemonic()
-			.map_err(|_| Error::Lifecycle("Error recovering mnemonic".into()))?;
 
 		// Back up existing seed, and keep track of filename as we're deleting it
 		// once the password change is confirmed
@@ -324,13 +376,9 @@ where
 			Error::Lifecycle("Unable to delete seed file for password change".into())
 		})?;
 
-		// Init a new file
-		let _ = WalletSeed::init_file(
-			data_dir_name,
-			0,
-			Some(ZeroingString::from(orig_mnemonic)),
-			new.clone(),
-		);
+		// Init a new file. The seed is re-encrypted as is, the mnemonic alone
+		// can't reproduce a seed derived with a passphrase
+		let _ = orig_wallet_seed.save_file(data_dir_name, new.clone(), &self.seed_kdf);
 		info!("Wallet seed file created");
 
 		let new_wallet_seed = WalletSeed::from_file(&data_dir_name, new)
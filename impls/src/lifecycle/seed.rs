@@ -18,24 +18,46 @@ use std::path::Path;
 use std::path::MAIN_SEPARATOR;
 
 use crate::blake2;
+use argon2::{Algorithm, Argon2, Params, Version};
 use rand::rng;
 use rand::Rng;
 use ring::aead;
 use ring::pbkdf2;
 use serde_json;
 
+use crate::config::SeedKdfConfig;
 use crate::keychain::{mnemonic, Keychain};
 use crate::util;
 use crate::Error;
 
 pub const SEED_FILE: &'static str = "wallet.seed";
 
+/// Seed files without a version tag, key derived with 100 rounds of PBKDF2
+const SEED_FILE_VERSION_PBKDF2: u16 = 1;
+/// Current seed file version, key derived with Argon2id
+pub const SEED_FILE_VERSION: u16 = 2;
+
+/// Length of a BIP39 seed
+const BIP39_SEED_LEN: usize = 64;
+/// PBKDF2 rounds BIP39 stretches the mnemonic and passphrase with
+const BIP39_PBKDF2_ROUNDS: u32 = 2048;
+
+/// The wallet seed. `entropy` is what the recovery phrase encodes. When the
+/// wallet was created with a mnemonic passphrase, the keychain is derived
+/// from the BIP39 seed of phrase and passphrase instead, so the phrase alone
+/// is not enough to restore the wallet.
 #[derive(Clone, Debug, PartialEq)]
-pub struct WalletSeed(Vec<u8>);
+pub struct WalletSeed {
+	entropy: Vec<u8>,
+	bip39_seed: Option<Vec<u8>>,
+}
 
 impl WalletSeed {
 	pub fn from_bytes(bytes: &[u8]) -> WalletSeed {
-		WalletSeed(bytes.to_vec())
+		WalletSeed {
+			entropy: bytes.to_vec(),
+			bip39_seed: None,
+		}
 	}
 
 	pub fn from_mnemonic(word_list: util::ZeroingString) -> Result<WalletSeed, Error> {
@@ -46,6 +68,46 @@ impl WalletSeed {
 		}
 	}
 
+	/// Seed from a recovery phrase and an optional mnemonic passphrase. An
+	/// empty passphrase is the same as none, which keeps the keychain of
+	/// wallets created without one unchanged.
+	pub fn from_mnemonic_with_passphrase(
+		word_list: util::ZeroingString,
+		passphrase: Option<util::ZeroingString>,
+	) -> Result<WalletSeed, Error> {
+		WalletSeed::from_mnemonic(word_list)?.with_passphrase(passphrase)
+	}
+
+	fn with_passphrase(
+		mut self,
+		passphrase: Option<util::ZeroingString>,
+	) -> Result<WalletSeed, Error> {
+		if let Some(p) = passphrase.filter(|p| !p.is_empty()) {
+			let words = util::ZeroingString::from(self.to_mnemonic()?);
+			self.bip39_seed = Some(WalletSeed::bip39_seed(&words, &p));
+		}
+		Ok(self)
+	}
+
+	/// BIP39 seed of a mnemonic and passphrase
+	fn bip39_seed(words: &str, passphrase: &str) -> Vec<u8> {
+		let salt = util::ZeroingString::from(format!("mnemonic{}", passphrase));
+		let mut seed = vec![0; BIP39_SEED_LEN];
+		pbkdf2::derive(
+			ring::pbkdf2::PBKDF2_HMAC_SHA512,
+			NonZeroU32::new(BIP39_PBKDF2_ROUNDS).unwrap(),
+			salt.as_bytes(),
+			words.as_bytes(),
+			&mut seed,
+		);
+		seed
+	}
+
+	/// Whether the keychain depends on a mnemonic passphrase
+	pub fn has_passphrase(&self) -> bool {
+		self.bip39_seed.is_some()
+	}
+
 	pub fn _from_hex(hex: &str) -> Result<WalletSeed, Error> {
 		let bytes = util::from_hex(hex.to_string())
 			.map_err(|_| Error::GenericError("Invalid hex".to_owned()))?;
@@ -53,11 +115,13 @@ impl WalletSeed {
 	}
 
 	pub fn _to_hex(&self) -> String {
-		util::to_hex(self.0.to_vec())
+		util::to_hex(self.entropy.to_vec())
 	}
 
+	/// The recovery phrase. Never includes the mnemonic passphrase, which is
+	/// not stored anywhere.
 	pub fn to_mnemonic(&self) -> Result<String, Error> {
-		let result = mnemonic::from_entropy(&self.0);
+		let result = mnemonic::from_entropy(&self.entropy);
 		match result {
 			Ok(r) => Ok(r),
 			Err(_) => Err(Error::Mnemonic.into()),
@@ -70,7 +134,8 @@ impl WalletSeed {
 	}
 
 	pub fn derive_keychain<K: Keychain>(&self, is_floonet: bool) -> Result<K, Error> {
-		let result = K::from_seed(&self.0, is_floonet)?;
+		let seed = self.bip39_seed.as_ref().unwrap_or(&self.entropy);
+		let result = K::from_seed(seed, is_floonet)?;
 		Ok(result)
 	}
 
@@ -80,7 +145,7 @@ impl WalletSeed {
 		for _ in 0..seed_length {
 			seed.push(rng.random());
 		}
-		WalletSeed(seed)
+		WalletSeed::from_bytes(&seed)
 	}
 
 	pub fn seed_file_exists(data_file_dir: &str) -> Result<bool, Error> {
@@ -120,6 +185,8 @@ impl WalletSeed {
 		data_file_dir: &str,
 		word_list: util::ZeroingString,
 		password: util::ZeroingString,
+		passphrase: Option<util::ZeroingString>,
+		kdf: &SeedKdfConfig,
 	) -> Result<(), Error> {
 		let seed_file_path = &format!("{}{}{}", data_file_dir, MAIN_SEPARATOR, SEED_FILE,);
 		debug!("data file dir: {}", data_file_dir);
@@ -134,8 +201,8 @@ impl WalletSeed {
 					.to_owned(),
 			))?;
 		}
-		let seed = WalletSeed::from_mnemonic(word_list)?;
-		let enc_seed = EncryptedWalletSeed::from_seed(&seed, password)?;
+		let seed = WalletSeed::from_mnemonic_with_passphrase(word_list, passphrase)?;
+		let enc_seed = EncryptedWalletSeed::from_seed(&seed, password, kdf)?;
 		let enc_seed_json = serde_json::to_string_pretty(&enc_seed)?;
 		let mut file = File::create(seed_file_path)?;
 		file.write_all(&enc_seed_json.as_bytes())?;
@@ -148,6 +215,8 @@ impl WalletSeed {
 		seed_length: usize,
 		recovery_phrase: Option<util::ZeroingString>,
 		password: util::ZeroingString,
+		passphrase: Option<util::ZeroingString>,
+		kdf: &SeedKdfConfig,
 	) -> Result<WalletSeed, Error> {
 		// create directory if it doesn't exist
 		fs::create_dir_all(data_file_dir)?;
@@ -162,11 +231,11 @@ impl WalletSeed {
 		}
 
 		let seed = match recovery_phrase {
-			Some(p) => WalletSeed::from_mnemonic(p)?,
-			None => WalletSeed::init_new(seed_length),
+			Some(p) => WalletSeed::from_mnemonic_with_passphrase(p, passphrase)?,
+			None => WalletSeed::init_new(seed_length).with_passphrase(passphrase)?,
 		};
 
-		let enc_seed = EncryptedWalletSeed::from_seed(&seed, password)?;
+		let enc_seed = EncryptedWalletSeed::from_seed(&seed, password, kdf)?;
 		let enc_seed_json = serde_json::to_string_pretty(&enc_seed)?;
 		let mut file = File::create(seed_file_path)?;
 		file.write_all(&enc_seed_json.as_bytes())?;
@@ -177,6 +246,42 @@ impl WalletSeed {
 		data_file_dir: &str,
 		password: util::ZeroingString,
 	) -> Result<WalletSeed, Error> {
+		let enc_seed = WalletSeed::read_file(data_file_dir)?;
+		enc_seed.decrypt(&password)
+	}
+
+	/// Decrypt the seed file, re-encrypting it with `kdf` if it was written
+	/// with an older format or weaker parameters
+	pub fn open_file(
+		data_file_dir: &str,
+		password: util::ZeroingString,
+		kdf: &SeedKdfConfig,
+	) -> Result<WalletSeed, Error> {
+		let enc_seed = WalletSeed::read_file(data_file_dir)?;
+		let wallet_seed = enc_seed.decrypt(&password)?;
+		if enc_seed.needs_upgrade(kdf) {
+			let upgraded = EncryptedWalletSeed::from_seed(&wallet_seed, password, kdf)?;
+			WalletSeed::write_file(data_file_dir, &upgraded)?;
+			warn!(
+				"Wallet seed file upgraded from version {} to {}",
+				enc_seed.version, upgraded.version
+			);
+		}
+		Ok(wallet_seed)
+	}
+
+	/// Overwrite the seed file with this seed, encrypted under `password`
+	pub fn save_file(
+		&self,
+		data_file_dir: &str,
+		password: util::ZeroingString,
+		kdf: &SeedKdfConfig,
+	) -> Result<(), Error> {
+		let enc_seed = EncryptedWalletSeed::from_seed(self, password, kdf)?;
+		WalletSeed::write_file(data_file_dir, &enc_seed)
+	}
+
+	fn read_file(data_file_dir: &str) -> Result<EncryptedWalletSeed, Error> {
 		// create directory if it doesn't exist
 		fs::create_dir_all(data_file_dir)?;
 
@@ -189,8 +294,7 @@ impl WalletSeed {
 			let mut buffer = String::new();
 			file.read_to_string(&mut buffer)?;
 			let enc_seed: EncryptedWalletSeed = serde_json::from_str(&buffer)?;
-			let wallet_seed = enc_seed.decrypt(&password)?;
-			Ok(wallet_seed)
+			Ok(enc_seed)
 		} else {
 			error!(
 				"wallet seed file {} could not be opened (epic wallet init). \
@@ -201,6 +305,19 @@ impl WalletSeed {
 		}
 	}
 
+	/// Replace the seed file. Written to a temporary file first and renamed,
+	/// so the seed is never lost to a crash halfway through.
+	fn write_file(data_file_dir: &str, enc_seed: &EncryptedWalletSeed) -> Result<(), Error> {
+		let seed_file_path = format!("{}{}{}", data_file_dir, MAIN_SEPARATOR, SEED_FILE,);
+		let tmp_file_path = format!("{}.tmp", seed_file_path);
+		let enc_seed_json = serde_json::to_string_pretty(enc_seed)?;
+		let mut file = File::create(&tmp_file_path)?;
+		file.write_all(&enc_seed_json.as_bytes())?;
+		file.sync_all()?;
+		fs::rename(&tmp_file_path, &seed_file_path)?;
+		Ok(())
+	}
+
 	pub fn delete_seed_file(data_file_dir: &str) -> Result<(), Error> {
 		let seed_file_path = &format!("{}{}{}", data_file_dir, MAIN_SEPARATOR, SEED_FILE,);
 		if Path::new(seed_file_path).exists() {
@@ -222,25 +339,49 @@ pub struct EncryptedWalletSeed {
 	pub salt: String,
 	/// Nonce
 	pub nonce: String,
+	/// Seed file format version, missing in files written before Argon2id
+	#[serde(default = "pbkdf2_version")]
+	pub version: u16,
+	/// Argon2id parameters the key is derived with
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub kdf: Option<SeedKdfConfig>,
+	/// Whether the BIP39 seed of a mnemonic passphrase is encrypted along
+	/// with the entropy. The passphrase itself is never stored.
+	#[serde(default, skip_serializing_if = "is_false")]
+	pub passphrase: bool,
+}
+
+fn is_false(b: &bool) -> bool {
+	!*b
+}
+
+fn pbkdf2_version() -> u16 {
+	SEED_FILE_VERSION_PBKDF2
 }
 
 impl EncryptedWalletSeed {
 	pub fn from_seed(
 		seed: &WalletSeed,
 		password: util::ZeroingString,
+		kdf: &SeedKdfConfig,
+	) -> Result<EncryptedWalletSeed, Error> {
+		let salt: [u8; 16] = rng().random();
+		EncryptedWalletSeed::seal(seed, &password, SEED_FILE_VERSION, Some(*kdf), &salt)
+	}
+
+	fn seal(
+		seed: &WalletSeed,
+		password: &str,
+		version: u16,
+		kdf: Option<SeedKdfConfig>,
+		salt: &[u8],
 	) -> Result<EncryptedWalletSeed, Error> {
-		let salt: [u8; 8] = rng().random();
 		let nonce: [u8; 12] = rng().random();
-		let password = password.as_bytes();
-		let mut key = [0; 32];
-		pbkdf2::derive(
-			ring::pbkdf2::PBKDF2_HMAC_SHA512,
-			NonZeroU32::new(100).unwrap(),
-			&salt,
-			password,
-			&mut key,
-		);
-		let content = seed.0.to_vec();
+		let key = EncryptedWalletSeed::derive_key(version, kdf.as_ref(), salt, password)?;
+		let mut content = seed.entropy.to_vec();
+		if let Some(ref b) = seed.bip39_seed {
+			content.extend_from_slice(b);
+		}
 		let mut enc_bytes = content;
 		/*let suffix_len = aead::CHACHA20_POLY1305.tag_len();
 		for _ in 0..suffix_len {
@@ -262,9 +403,63 @@ impl EncryptedWalletSeed {
 			encrypted_seed: util::to_hex(enc_bytes.to_vec()),
 			salt: util::to_hex(salt.to_vec()),
 			nonce: util::to_hex(nonce.to_vec()),
+			version,
+			kdf,
+			passphrase: seed.has_passphrase(),
 		})
 	}
 
+	/// Derive the encryption key the way the given seed file version does
+	fn derive_key(
+		version: u16,
+		kdf: Option<&SeedKdfConfig>,
+		salt: &[u8],
+		password: &str,
+	) -> Result<[u8; 32], Error> {
+		let password = password.as_bytes();
+		let mut key = [0; 32];
+		match version {
+			SEED_FILE_VERSION_PBKDF2 => pbkdf2::derive(
+				ring::pbkdf2::PBKDF2_HMAC_SHA512,
+				NonZeroU32::new(100).unwrap(),
+				salt,
+				password,
+				&mut key,
+			),
+			SEED_FILE_VERSION => {
+				let kdf = kdf.ok_or(Error::Encryption)?;
+				let params = Params::new(
+					kdf.memory_kib,
+					kdf.iterations,
+					kdf.parallelism,
+					Some(key.len()),
+				)
+				.map_err(|e| Error::GenericError(format!("Invalid seed KDF parameters: {}", e)))?;
+				Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+					.hash_password_into(password, salt, &mut key)
+					.map_err(|_| Error::Encryption)?;
+			}
+			v => {
+				return Err(Error::GenericError(format!(
+					"Unsupported wallet seed file version {}",
+					v
+				)))
+			}
+		}
+		Ok(key)
+	}
+
+	/// Whether the seed was encrypted with an older format or weaker
+	/// parameters than `kdf`
+	pub fn needs_upgrade(&self, kdf: &SeedKdfConfig) -> bool {
+		match self.kdf {
+			Some(ref k) if self.version == SEED_FILE_VERSION => {
+				k.memory_kib < kdf.memory_kib || k.iterations < kdf.iterations
+			}
+			_ => true,
+		}
+	}
+
 	/// Decrypt seed
 	pub fn decrypt(&self, password: &str) -> Result<WalletSeed, Error> {
 		let mut encrypted_seed = match util::from_hex(self.encrypted_seed.clone()) {
@@ -279,15 +474,8 @@ impl EncryptedWalletSeed {
 			Ok(s) => s,
 			Err(_) => return Err(Error::Encryption)?,
 		};
-		let password = password.as_bytes();
-		let mut key = [0; 32];
-		pbkdf2::derive(
-			ring::pbkdf2::PBKDF2_HMAC_SHA512,
-			NonZeroU32::new(100).unwrap(),
-			&salt,
-			password,
-			&mut key,
-		);
+		let key =
+			EncryptedWalletSeed::derive_key(self.version, self.kdf.as_ref(), &salt, password)?;
 
 		let mut n = [0u8; 12];
 		n.copy_from_slice(&nonce[0..12]);
@@ -306,7 +494,17 @@ impl EncryptedWalletSeed {
 			encrypted_seed.pop();
 		}
 
-		Ok(WalletSeed::from_bytes(&encrypted_seed))
+		if !self.passphrase {
+			return Ok(WalletSeed::from_bytes(&encrypted_seed));
+		}
+		if encrypted_seed.len() <= BIP39_SEED_LEN {
+			return Err(Error::Encryption)?;
+		}
+		let bip39_seed = encrypted_seed.split_off(encrypted_seed.len() - BIP39_SEED_LEN);
+		Ok(WalletSeed {
+			entropy: encrypted_seed,
+			bip39_seed: Some(bip39_seed),
+		})
 	}
 }
 
@@ -314,12 +512,24 @@ impl EncryptedWalletSeed {
 mod tests {
 	use super::*;
 	use crate::util::ZeroingString;
+	use std::env;
+	use uuid::Uuid;
+
+	// cheap parameters, so tests don't spend their time hashing
+	fn test_kdf() -> SeedKdfConfig {
+		SeedKdfConfig {
+			memory_kib: 1024,
+			iterations: 1,
+			parallelism: 1,
+		}
+	}
+
 	#[test]
 	fn wallet_seed_encrypt() {
 		let password = ZeroingString::from("passwoid");
 		let wallet_seed = WalletSeed::init_new(32);
 		let mut enc_wallet_seed =
-			EncryptedWalletSeed::from_seed(&wallet_seed, password.clone()).unwrap();
+			EncryptedWalletSeed::from_seed(&wallet_seed, password.clone(), &test_kdf()).unwrap();
 		println!("EWS: {:?}", enc_wallet_seed);
 		let decrypted_wallet_seed = enc_wallet_seed.decrypt(&password).unwrap();
 		assert_eq!(wallet_seed, decrypted_wallet_seed);
@@ -333,4 +543,79 @@ mod tests {
 		let decrypted_wallet_seed = enc_wallet_seed.decrypt(&password);
 		assert!(decrypted_wallet_seed.is_err());
 	}
+
+	#[test]
+	fn wallet_seed_legacy_upgrade() {
+		let password = ZeroingString::from("passwoid");
+		let wallet_seed = WalletSeed::init_new(32);
+		let salt: [u8; 8] = rng().random();
+		let legacy = EncryptedWalletSeed::seal(
+			&wallet_seed,
+			&password,
+			SEED_FILE_VERSION_PBKDF2,
+			None,
+			&salt,
+		)
+		.unwrap();
+
+		// files written before the version tag existed
+		let mut json = serde_json::to_value(&legacy).unwrap();
+		json.as_object_mut().unwrap().remove("version");
+		let legacy: EncryptedWalletSeed = serde_json::from_value(json).unwrap();
+		assert_eq!(legacy.version, SEED_FILE_VERSION_PBKDF2);
+		assert!(legacy.needs_upgrade(&test_kdf()));
+
+		let dir = env::temp_dir().join(format!("seed_upgrade_{}", Uuid::new_v4()));
+		let dir = dir.to_str().unwrap();
+		fs::create_dir_all(dir).unwrap();
+		WalletSeed::write_file(dir, &legacy).unwrap();
+
+		// wrong password leaves the file alone
+		assert!(WalletSeed::open_file(dir, ZeroingString::from(""), &test_kdf()).is_err());
+		assert_eq!(WalletSeed::read_file(dir).unwrap(), legacy);
+
+		let opened = WalletSeed::open_file(dir, password.clone(), &test_kdf()).unwrap();
+		assert_eq!(opened, wallet_seed);
+		let upgraded = WalletSeed::read_file(dir).unwrap();
+		assert_eq!(upgraded.version, SEED_FILE_VERSION);
+		assert!(!upgraded.needs_upgrade(&test_kdf()));
+		assert_eq!(WalletSeed::from_file(dir, password).unwrap(), wallet_seed);
+		let _ = fs::remove_dir_all(dir);
+	}
+
+	#[test]
+	fn wallet_seed_passphrase() {
+		let password = ZeroingString::from("passwoid");
+		let plain = WalletSeed::init_new(32);
+		let phrase = ZeroingString::from(plain.to_mnemonic().unwrap());
+
+		// an empty passphrase is no passphrase
+		let seed =
+			WalletSeed::from_mnemonic_with_passphrase(phrase.clone(), Some("".into())).unwrap();
+		assert_eq!(seed, plain);
+
+		let seed =
+			WalletSeed::from_mnemonic_with_passphrase(phrase.clone(), Some("25th".into())).unwrap();
+		assert!(seed.has_passphrase());
+		assert_ne!(seed, plain);
+		assert_eq!(seed.to_mnemonic().unwrap(), *phrase);
+		let other =
+			WalletSeed::from_mnemonic_with_passphrase(phrase.clone(), Some("26th".into())).unwrap();
+		assert_ne!(seed, other);
+
+		let enc_seed =
+			EncryptedWalletSeed::from_seed(&seed, password.clone(), &test_kdf()).unwrap();
+		assert!(enc_seed.passphrase);
+		assert!(!serde_json::to_string(&enc_seed).unwrap().contains("25th"));
+		assert_eq!(enc_seed.decrypt(&password).unwrap(), seed);
+
+		let enc_plain =
+			EncryptedWalletSeed::from_seed(&plain, password.clone(), &test_kdf()).unwrap();
+		assert!(!serde_json::to_value(&enc_plain)
+			.unwrap()
+			.as_object()
+			.unwrap()
+			.contains_key("passphrase"));
+		assert_eq!(enc_plain.decrypt(&password).unwrap(), plain);
+	}
 }
@@ -0,0 +1,459 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Node client wrapper caching kernel and output lookups. Answers are only
+//! valid for the chain tip they were given at, which is tracked through the
+//! tips returned by `get_chain_tip`. A new block drops everything a block can
+//! change, a tip that doesn't extend the last one is taken for a reorg and
+//! drops the whole cache. Transactions posted through the client are always
+//! looked up at the node until they are final.
+
+use crate::core::core::{Transaction, TxKernel};
+use crate::libwallet::{Error, NodeCacheStats, NodeClient, NodeStatus, NodeVersionInfo, PoolEntry};
+use crate::util::secp::pedersen::{Commitment, RangeProof};
+use crate::util::Mutex;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Confirmations after which a kernel is assumed to stay where it is
+const FINAL_CONFIRMATIONS: u64 = 10;
+
+/// How long a tip is trusted before lookups refresh it themselves
+const TIP_MAX_AGE: Duration = Duration::from_secs(30);
+
+#[derive(Default)]
+struct Cache {
+	/// Last tip seen and when
+	tip: Option<(u64, String)>,
+	tip_seen: Option<Instant>,
+	/// Kernels found, by excess
+	kernels: HashMap<Commitment, (TxKernel, u64, u64)>,
+	/// Kernel lookups that found nothing at the current tip
+	missing_kernels: HashSet<(Commitment, Option<u64>, Option<u64>)>,
+	/// Unspent status of outputs at the current tip
+	outputs: HashMap<Commitment, Option<(String, u64, u64)>>,
+	/// Kernels, inputs and outputs of posted transactions, by the height
+	/// they were posted at. These bypass the cache until they are final.
+	bypass: HashMap<Commitment, u64>,
+	stats: NodeCacheStats,
+}
+
+impl Cache {
+	fn clear(&mut self) {
+		self.tip = None;
+		self.tip_seen = None;
+		self.kernels.clear();
+		self.missing_kernels.clear();
+		self.outputs.clear();
+	}
+
+	/// Whether the tip is recent enough to answer from the cache
+	fn is_fresh(&self) -> bool {
+		self.tip_seen
+			.map(|t| t.elapsed() < TIP_MAX_AGE)
+			.unwrap_or(false)
+	}
+
+	fn update_tip(&mut self, tip: &(u64, String)) {
+		match self.tip.clone() {
+			Some(last) if last.1 == tip.1 => {}
+			Some(last) if tip.0 > last.0 => {
+				// new blocks can spend outputs and confirm kernels, and
+				// replace recent blocks with a reorg we can't see from here
+				self.outputs.clear();
+				self.missing_kernels.clear();
+				let final_height = tip.0.saturating_sub(FINAL_CONFIRMATIONS);
+				self.kernels.retain(|_, k| k.1 <= final_height);
+				self.bypass.retain(|_, posted| *posted > final_height);
+				debug!("Node cache at height {}: {:?}", tip.0, self.stats);
+			}
+			Some(last) => {
+				warn!(
+					"Chain reorg detected, tip moved from {} at {} to {} at {}. Clearing node cache",
+					last.1, last.0, tip.1, tip.0
+				);
+				self.clear();
+				self.stats.reorgs += 1;
+			}
+			None => {}
+		}
+		self.tip = Some(tip.clone());
+		self.tip_seen = Some(Instant::now());
+	}
+
+	/// Stop answering lookups about a transaction that was just posted,
+	/// and refresh the tip on the next lookup to notice it getting mined
+	fn bypass_tx(&mut self, tx: &Transaction) {
+		let height = self.tip.as_ref().map_or(0, |t| t.0);
+		let commits = tx
+			.inputs()
+			.iter()
+			.map(|i| i.commitment())
+			.chain(tx.outputs().iter().map(|o| o.commitment()))
+			.chain(tx.kernels().iter().map(|k| k.excess()));
+		for commit in commits {
+			self.kernels.remove(&commit);
+			self.missing_kernels.retain(|k| k.0 != commit);
+			self.outputs.remove(&commit);
+			self.bypass.insert(commit, height);
+		}
+		self.tip_seen = None;
+	}
+}
+
+/// Node client caching the kernel and output lookups of another one
+#[derive(Clone)]
+pub struct CachingNodeClient<C>
+where
+	C: NodeClient,
+{
+	inner: C,
+	cache: Arc<Mutex<Cache>>,
+	/// Whether lookups go through the cache at all
+	enabled: bool,
+}
+
+impl<C> CachingNodeClient<C>
+where
+	C: NodeClient,
+{
+	/// Layer a cache over the given client. If not `enabled`, all requests
+	/// go straight to the client.
+	pub fn new(inner: C, enabled: bool) -> CachingNodeClient<C> {
+		CachingNodeClient {
+			inner,
+			cache: Arc::new(Mutex::new(Cache::default())),
+			enabled,
+		}
+	}
+
+	/// Hit and miss counters since the client was created
+	pub fn stats(&self) -> NodeCacheStats {
+		self.cache.lock().stats
+	}
+
+	/// Current tip, refreshed from the node if it's too old to be trusted
+	fn current_tip(&self) -> Result<(u64, String), Error> {
+		{
+			let cache = self.cache.lock();
+			if cache.is_fresh() {
+				if let Some(tip) = cache.tip.clone() {
+					return Ok(tip);
+				}
+			}
+		}
+		self.get_chain_tip()
+	}
+}
+
+impl<C> NodeClient for CachingNodeClient<C>
+where
+	C: NodeClient,
+{
+	fn node_url(&self) -> &str {
+		self.inner.node_url()
+	}
+
+	fn node_api_secret(&self) -> Option<String> {
+		self.inner.node_api_secret()
+	}
+
+	fn set_node_url(&mut self, node_url: &str) {
+		self.inner.set_node_url(node_url);
+		self.cache.lock().clear();
+	}
+
+	fn set_node_api_secret(&mut self, node_api_secret: Option<String>) {
+		self.inner.set_node_api_secret(node_api_secret);
+	}
+
+	fn get_version_info(&mut self) -> Option<NodeVersionInfo> {
+		self.inner.get_version_info()
+	}
+
+	fn post_tx(&self, tx: &Transaction, fluff: bool) -> Result<(), Error> {
+		self.inner.post_tx(tx, fluff)?;
+		self.cache.lock().bypass_tx(tx);
+		Ok(())
+	}
+
+	fn post_tx_tor(&self, tx: &Transaction, tor_node_url: &str) -> Result<(), Error> {
+		self.inner.post_tx_tor(tx, tor_node_url)?;
+		self.cache.lock().bypass_tx(tx);
+		Ok(())
+	}
+
+	fn get_mempool(&self) -> Result<Vec<PoolEntry>, Error> {
+		self.inner.get_mempool()
+	}
+
+	fn get_chain_tip(&self) -> Result<(u64, String), Error> {
+		let tip = self.inner.get_chain_tip()?;
+		if self.enabled {
+			self.cache.lock().update_tip(&tip);
+		}
+		Ok(tip)
+	}
+
+	fn get_header_hash(&self, height: u64) -> Result<String, Error> {
+		self.inner.get_header_hash(height)
+	}
+
+	fn get_node_status(&self) -> Result<NodeStatus, Error> {
+		self.inner.get_node_status()
+	}
+
+	fn get_kernel(
+		&mut self,
+		excess: &Commitment,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Option<(TxKernel, u64, u64)>, Error> {
+		if !self.enabled {
+			return self.inner.get_kernel(excess, min_height, max_height);
+		}
+		let tip = self.current_tip()?;
+		let lookup = (*excess, min_height, max_height);
+		{
+			let mut cache = self.cache.lock();
+			if let Some(k) = cache.kernels.get(excess).cloned() {
+				cache.stats.kernel_hits += 1;
+				let in_range =
+					min_height.map_or(true, |h| k.1 >= h) && max_height.map_or(true, |h| k.1 <= h);
+				return Ok(if in_range { Some(k) } else { None });
+			}
+			if cache.missing_kernels.contains(&lookup) {
+				cache.stats.kernel_hits += 1;
+				return Ok(None);
+			}
+			cache.stats.kernel_misses += 1;
+		}
+
+		let res = self.inner.get_kernel(excess, min_height, max_height)?;
+		let mut cache = self.cache.lock();
+		// the answer is only good for the tip it was asked at
+		if cache.tip.as_ref() == Some(&tip) && !cache.bypass.contains_key(excess) {
+			match res {
+				Some(ref k) => {
+					cache.kernels.insert(*excess, k.clone());
+				}
+				None => {
+					cache.missing_kernels.insert(lookup);
+				}
+			}
+		}
+		Ok(res)
+	}
+
+	fn get_outputs_from_node(
+		&self,
+		wallet_outputs: Vec<Commitment>,
+	) -> Result<HashMap<Commitment, (String, u64, u64)>, Error> {
+		if !self.enabled {
+			return self.inner.get_outputs_from_node(wallet_outputs);
+		}
+		let tip = self.current_tip()?;
+		let mut api_outputs = HashMap::new();
+		let mut missing = vec![];
+		{
+			let mut cache = self.cache.lock();
+			let total = wallet_outputs.len();
+			for commit in wallet_outputs {
+				match cache.outputs.get(&commit) {
+					Some(Some(out)) => {
+						api_outputs.insert(commit, out.clone());
+					}
+					Some(None) => {}
+					None => missing.push(commit),
+				}
+			}
+			cache.stats.output_hits += (total - missing.len()) as u64;
+			cache.stats.output_misses += missing.len() as u64;
+		}
+		if missing.is_empty() {
+			return Ok(api_outputs);
+		}
+
+		let found = self.inner.get_outputs_from_node(missing.clone())?;
+		{
+			let mut cache = self.cache.lock();
+			if cache.tip.as_ref() == Some(&tip) {
+				for commit in missing {
+					if !cache.bypass.contains_key(&commit) {
+						cache.outputs.insert(commit, found.get(&commit).cloned());
+					}
+				}
+			}
+		}
+		api_outputs.extend(found);
+		Ok(api_outputs)
+	}
+
+	fn get_outputs_by_pmmr_index(
+		&self,
+		start_index: u64,
+		end_index: Option<u64>,
+		max_outputs: u64,
+	) -> Result<(u64, u64, Vec<(Commitment, RangeProof, bool, u64, u64)>), Error> {
+		self.inner
+			.get_outputs_by_pmmr_index(start_index, end_index, max_outputs)
+	}
+
+	fn height_range_to_pmmr_indices(
+		&self,
+		start_height: u64,
+		end_height: Option<u64>,
+	) -> Result<(u64, u64), Error> {
+		self.inner
+			.height_range_to_pmmr_indices(start_height, end_height)
+	}
+
+	fn get_onion_addresses(&self) -> Result<Vec<String>, Error> {
+		self.inner.get_onion_addresses()
+	}
+
+	fn cache_stats(&self) -> Option<NodeCacheStats> {
+		match self.enabled {
+			true => Some(self.stats()),
+			false => None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::core::core::{Input, KernelFeatures, OutputFeatures};
+	use crate::node_clients::stub::StubNode;
+
+	fn commit(b: u8) -> Commitment {
+		Commitment::from_vec(vec![b; 33])
+	}
+
+	#[test]
+	fn tip_changes_invalidate() {
+		let mut cache = Cache::default();
+		cache.update_tip(&(100, "a".to_owned()));
+		cache.outputs.insert(commit(1), None);
+		cache.missing_kernels.insert((commit(2), None, None));
+		assert!(cache.is_fresh());
+
+		// same tip keeps everything
+		cache.update_tip(&(100, "a".to_owned()));
+		assert_eq!(cache.outputs.len(), 1);
+		assert_eq!(cache.missing_kernels.len(), 1);
+
+		// a new block drops tip dependent answers
+		cache.update_tip(&(101, "b".to_owned()));
+		assert!(cache.outputs.is_empty());
+		assert!(cache.missing_kernels.is_empty());
+		assert_eq!(cache.stats.reorgs, 0);
+
+		// a different block at the same height is a reorg
+		cache.outputs.insert(commit(1), None);
+		cache.update_tip(&(101, "c".to_owned()));
+		assert!(cache.outputs.is_empty());
+		assert_eq!(cache.stats.reorgs, 1);
+		assert_eq!(cache.tip, Some((101, "c".to_owned())));
+	}
+	#[test]
+	fn lookups_are_cached() {
+		let mut stub = StubNode::new("a", 30, None).with_kernel(8);
+		stub.outputs.insert(commit(1), ("a".to_owned(), 29, 1));
+		let mut client = CachingNodeClient::new(stub, true);
+		let excess = commit(9);
+
+		for _ in 0..2 {
+			let outputs = client
+				.get_outputs_from_node(vec![commit(1), commit(2)])
+				.unwrap();
+			assert_eq!(outputs.len(), 1);
+			assert!(outputs.contains_key(&commit(1)));
+			assert!(client.get_kernel(&excess, None, None).unwrap().is_some());
+		}
+		assert_eq!(client.inner.lookups(), 2);
+		// the height range is checked against the cached kernel
+		assert!(client.get_kernel(&excess, Some(9), None).unwrap().is_none());
+		assert_eq!(client.inner.lookups(), 2);
+
+		// outputs are looked up again in a new block, final kernels aren't
+		client.inner.mine(1, None);
+		client.get_chain_tip().unwrap();
+		client.get_outputs_from_node(vec![commit(1)]).unwrap();
+		client.get_kernel(&excess, None, None).unwrap();
+		assert_eq!(client.inner.lookups(), 3);
+
+		// a reorg drops everything
+		client.inner.hashes.truncate(25);
+		client.inner.mine(7, Some(25));
+		client.get_chain_tip().unwrap();
+		client.get_kernel(&excess, None, None).unwrap();
+		assert_eq!(client.inner.lookups(), 4);
+
+		assert_eq!(
+			client.cache_stats(),
+			Some(NodeCacheStats {
+				kernel_hits: 3,
+				kernel_misses: 2,
+				output_hits: 2,
+				output_misses: 3,
+				reorgs: 1,
+			})
+		);
+	}
+
+	#[test]
+	fn posted_tx_bypasses_cache() {
+		let stub = StubNode::new("a", 30, None);
+		let mut client = CachingNodeClient::new(stub, true);
+		let mut kernel = TxKernel::with_features(KernelFeatures::Plain { fee: 0 });
+		kernel.excess = commit(3);
+		let tx = Transaction::empty()
+			.with_input(Input::new(OutputFeatures::Plain, commit(1)))
+			.with_kernel(kernel);
+
+		client.get_outputs_from_node(vec![commit(1)]).unwrap();
+		client.get_kernel(&commit(3), None, None).unwrap();
+		assert_eq!(client.inner.lookups(), 2);
+		client.post_tx(&tx, false).unwrap();
+
+		// until the transaction is final, the node is asked every time
+		for i in 1..=2 {
+			client.get_outputs_from_node(vec![commit(1)]).unwrap();
+			client.get_kernel(&commit(3), None, None).unwrap();
+			assert_eq!(client.inner.lookups(), 2 + 2 * i);
+		}
+		client.inner.mine(FINAL_CONFIRMATIONS, None);
+		client.get_chain_tip().unwrap();
+		for _ in 0..2 {
+			client.get_outputs_from_node(vec![commit(1)]).unwrap();
+			client.get_kernel(&commit(3), None, None).unwrap();
+		}
+		assert_eq!(client.inner.lookups(), 8);
+	}
+
+	#[test]
+	fn disabled_cache() {
+		let stub = StubNode::new("a", 30, None).with_kernel(8);
+		let mut client = CachingNodeClient::new(stub, false);
+		for _ in 0..2 {
+			client.get_outputs_from_node(vec![commit(1)]).unwrap();
+			client.get_kernel(&commit(3), None, None).unwrap();
+		}
+		assert_eq!(client.inner.lookups(), 4);
+		assert_eq!(client.cache_stats(), None);
+	}
+}
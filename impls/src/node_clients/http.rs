@@ -43,6 +43,12 @@ pub struct GetTipResp {
 	pub last_block_pushed: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct GetHeaderResp {
+	pub height: u64,
+	pub hash: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct GetVersionResp {
 	pub node_version: String,
@@ -203,6 +209,14 @@ impl NodeClient for HTTPNodeClient {
 		Ok((result.height, result.last_block_pushed))
 	}
 
+	/// Return the hash of the block header at the given height
+	fn get_header_hash(&self, height: u64) -> Result<String, Error> {
+		let params = json!([height, null, null]);
+		let result =
+			self.send_json_request::<GetHeaderResp>(FOREIGN_ENDPOINT, "get_header", &params)?;
+		Ok(result.hash)
+	}
+
 	// Retrieves the status of the node
 	fn get_node_status(&self) -> Result<NodeStatus, Error> {
 		let result = self.send_json_request::<NodeStatus>(
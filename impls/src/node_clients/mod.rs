@@ -12,6 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod caching;
 mod http;
+mod multi;
+#[cfg(test)]
+mod stub;
 
+pub use self::caching::CachingNodeClient;
 pub use self::http::HTTPNodeClient;
+pub use self::multi::MultiNodeClient;
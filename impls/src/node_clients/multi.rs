@@ -0,0 +1,543 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Node client talking to a list of nodes. Requests go to the active node
+//! and fail over to the next healthy one when it can't be reached. The chain
+//! tip and kernel lookups can optionally be cross-checked against a second
+//! node before they are trusted.
+
+use crate::core::core::{Transaction, TxKernel};
+use crate::libwallet::{Error, NodeClient, NodeStatus, NodeVersionInfo, PoolEntry};
+use crate::node_clients::HTTPNodeClient;
+use crate::util::secp::pedersen;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Sync status reported by a node that is fully synced
+const SYNCED: &str = "no_sync";
+
+/// Node client failing over between several nodes
+#[derive(Clone)]
+pub struct MultiNodeClient<C = HTTPNodeClient> {
+	nodes: Vec<C>,
+	/// Index of the node requests currently go to, shared between clones
+	active: Arc<AtomicUsize>,
+	/// Whether to confirm chain tips and kernels with a second node
+	cross_check: bool,
+}
+
+impl MultiNodeClient<HTTPNodeClient> {
+	/// Create a new client for the given nodes, the first one being the
+	/// preferred node
+	pub fn new(
+		node_urls: &[String],
+		node_api_secret: Option<String>,
+		cross_check: bool,
+	) -> Result<MultiNodeClient, Error> {
+		let nodes = node_urls
+			.iter()
+			.map(|url| HTTPNodeClient::new(url, node_api_secret.clone()))
+			.collect::<Result<Vec<_>, _>>()?;
+		MultiNodeClient::from_clients(nodes, cross_check)
+	}
+}
+
+impl<C> MultiNodeClient<C>
+where
+	C: NodeClient,
+{
+	/// Create a new client over the given node clients, the first one being
+	/// the preferred node
+	pub fn from_clients(nodes: Vec<C>, cross_check: bool) -> Result<MultiNodeClient<C>, Error> {
+		if nodes.is_empty() {
+			return Err(Error::ArgumentError("No node address given".to_owned()));
+		}
+		Ok(MultiNodeClient {
+			nodes,
+			active: Arc::new(AtomicUsize::new(0)),
+			cross_check,
+		})
+	}
+
+	/// Addresses of all nodes, in order of preference
+	pub fn node_urls(&self) -> Vec<String> {
+		self.nodes.iter().map(|n| n.node_url().to_owned()).collect()
+	}
+
+	fn active(&self) -> usize {
+		self.active.load(Ordering::Relaxed)
+	}
+
+	/// Indices of all nodes but `index`, starting after it
+	fn others(&self, index: usize) -> impl Iterator<Item = usize> {
+		let len = self.nodes.len();
+		(1..len).map(move |offset| (index + offset) % len)
+	}
+
+	/// Make `index` the active node
+	fn switch_to(&self, index: usize) {
+		let previous = self.active.swap(index, Ordering::Relaxed);
+		if previous != index {
+			warn!(
+				"Switching from node {} to node {}",
+				self.nodes[previous].node_url(),
+				self.nodes[index].node_url()
+			);
+		}
+	}
+
+	/// Status of the node if it answers and is fully synced
+	fn healthy_status(node: &C) -> Option<NodeStatus> {
+		match node.get_node_status() {
+			Ok(status) if status.sync_status == SYNCED => Some(status),
+			Ok(status) => {
+				debug!(
+					"Node {} is not synced: {}",
+					node.node_url(),
+					status.sync_status
+				);
+				None
+			}
+			Err(e) => {
+				debug!("Node {} is unavailable: {}", node.node_url(), e);
+				None
+			}
+		}
+	}
+
+	/// Whether the error means the node itself couldn't serve the request,
+	/// as opposed to the request being refused on its merits
+	fn is_node_failure(e: &Error) -> bool {
+		matches!(
+			e,
+			Error::ClientCallback(_)
+				| Error::Unauthorized
+				| Error::InternalServerError(_)
+				| Error::HttpError(_, _)
+		)
+	}
+
+	/// Run the request against the active node, failing over to the other
+	/// healthy nodes in turn if it can't be served. The error of the active
+	/// node is returned if no node succeeds.
+	fn call<T, F>(&self, f: F) -> Result<T, Error>
+	where
+		F: Fn(&C) -> Result<T, Error>,
+	{
+		let active = self.active();
+		let first_err = match f(&self.nodes[active]) {
+			Err(e) if Self::is_node_failure(&e) => e,
+			res => return res,
+		};
+		warn!(
+			"Node {} failed: {}",
+			self.nodes[active].node_url(),
+			first_err
+		);
+		for i in self.others(active) {
+			let node = &self.nodes[i];
+			if Self::healthy_status(node).is_none() {
+				continue;
+			}
+			match f(node) {
+				Err(e) if Self::is_node_failure(&e) => {
+					warn!("Node {} failed: {}", node.node_url(), e);
+				}
+				res => {
+					self.switch_to(i);
+					return res;
+				}
+			}
+		}
+		Err(first_err)
+	}
+
+	/// Run the request against the first node other than `index` able to
+	/// serve it. None if there is no such node.
+	fn call_peer<T, F>(&self, index: usize, f: F) -> Option<(usize, T)>
+	where
+		F: Fn(&C) -> Result<T, Error>,
+	{
+		self.others(index)
+			.find_map(|i| f(&self.nodes[i]).ok().map(|res| (i, res)))
+	}
+
+	/// Confirm the tip of the active node is on the same chain as a second
+	/// node, comparing the header hashes at the lower of both tips
+	fn check_chain_tip(&self, tip: &(u64, String)) -> Result<(), Error> {
+		let active = self.active();
+		let (peer, peer_tip) = match self.call_peer(active, |n| n.get_chain_tip()) {
+			Some(p) => p,
+			None => {
+				warn!("No second node available to cross-check the chain tip");
+				return Ok(());
+			}
+		};
+		let (hash, peer_hash) = if tip.0 == peer_tip.0 {
+			(tip.1.clone(), peer_tip.1)
+		} else {
+			let height = tip.0.min(peer_tip.0);
+			(
+				self.nodes[active].get_header_hash(height)?,
+				self.nodes[peer].get_header_hash(height)?,
+			)
+		};
+		if hash != peer_hash {
+			return Err(Error::NodeMismatch(format!(
+				"{} and {} are on different chains at height {}",
+				self.nodes[active].node_url(),
+				self.nodes[peer].node_url(),
+				tip.0.min(peer_tip.0)
+			)));
+		}
+		Ok(())
+	}
+
+	/// Confirm a kernel lookup of the active node with a second node. A
+	/// kernel only one of them knows is fine as long as it is above the tip
+	/// of the other.
+	fn check_kernel(
+		&self,
+		excess: &pedersen::Commitment,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+		kernel: &Option<(TxKernel, u64, u64)>,
+	) -> Result<(), Error> {
+		let active = self.active();
+		// the lookup doesn't modify the client, it's only the trait asking for it
+		let lookup = |n: &C| n.clone().get_kernel(excess, min_height, max_height);
+		let (peer, peer_kernel) = match self.call_peer(active, lookup) {
+			Some(p) => p,
+			None => {
+				warn!("No second node available to cross-check the kernel lookup");
+				return Ok(());
+			}
+		};
+		let agree = match (kernel, &peer_kernel) {
+			(Some(k), Some(p)) => k == p,
+			(None, None) => true,
+			(Some((_, height, _)), None) => {
+				let (peer_height, _) = self.nodes[peer].get_chain_tip()?;
+				*height > peer_height
+			}
+			(None, Some((_, height, _))) => {
+				let (active_height, _) = self.nodes[active].get_chain_tip()?;
+				*height > active_height
+			}
+		};
+		if !agree {
+			return Err(Error::NodeMismatch(format!(
+				"{} and {} disagree on kernel {}",
+				self.nodes[active].node_url(),
+				self.nodes[peer].node_url(),
+				crate::util::to_hex(excess.0.to_vec())
+			)));
+		}
+		Ok(())
+	}
+}
+
+impl<C> NodeClient for MultiNodeClient<C>
+where
+	C: NodeClient,
+{
+	fn node_url(&self) -> &str {
+		self.nodes[self.active()].node_url()
+	}
+
+	fn node_api_secret(&self) -> Option<String> {
+		self.nodes[self.active()].node_api_secret()
+	}
+
+	fn set_node_url(&mut self, node_url: &str) {
+		let active = self.active();
+		self.nodes[active].set_node_url(node_url);
+	}
+
+	fn set_node_api_secret(&mut self, node_api_secret: Option<String>) {
+		for node in self.nodes.iter_mut() {
+			node.set_node_api_secret(node_api_secret.clone());
+		}
+	}
+
+	fn get_version_info(&mut self) -> Option<NodeVersionInfo> {
+		let active = self.active();
+		if let Some(v) = self.nodes[active].get_version_info() {
+			return Some(v);
+		}
+		for i in self.others(active) {
+			if let Some(v) = self.nodes[i].get_version_info() {
+				self.switch_to(i);
+				return Some(v);
+			}
+		}
+		None
+	}
+
+	fn post_tx(&self, tx: &Transaction, fluff: bool) -> Result<(), Error> {
+		self.call(|n| n.post_tx(tx, fluff))
+	}
+
+	fn post_tx_tor(&self, tx: &Transaction, tor_node_url: &str) -> Result<(), Error> {
+		self.nodes[self.active()].post_tx_tor(tx, tor_node_url)
+	}
+
+	fn get_mempool(&self) -> Result<Vec<PoolEntry>, Error> {
+		self.call(|n| n.get_mempool())
+	}
+
+	fn get_chain_tip(&self) -> Result<(u64, String), Error> {
+		let tip = self.call(|n| n.get_chain_tip())?;
+		if self.cross_check {
+			self.check_chain_tip(&tip)?;
+		}
+		Ok(tip)
+	}
+
+	fn get_header_hash(&self, height: u64) -> Result<String, Error> {
+		self.call(|n| n.get_header_hash(height))
+	}
+
+	/// Status of the active node. A node that is syncing, e.g. after a
+	/// restart, is swapped for a synced one if there is any.
+	fn get_node_status(&self) -> Result<NodeStatus, Error> {
+		let status = self.call(|n| n.get_node_status())?;
+		if status.sync_status == SYNCED {
+			return Ok(status);
+		}
+		for i in self.others(self.active()) {
+			if let Some(s) = Self::healthy_status(&self.nodes[i]) {
+				self.switch_to(i);
+				return Ok(s);
+			}
+		}
+		Ok(status)
+	}
+
+	fn get_kernel(
+		&mut self,
+		excess: &pedersen::Commitment,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Option<(TxKernel, u64, u64)>, Error> {
+		let kernel = self.call(|n| n.clone().get_kernel(excess, min_height, max_height))?;
+		if self.cross_check {
+			self.check_kernel(excess, min_height, max_height, &kernel)?;
+		}
+		Ok(kernel)
+	}
+
+	fn get_outputs_from_node(
+		&self,
+		wallet_outputs: Vec<pedersen::Commitment>,
+	) -> Result<HashMap<pedersen::Commitment, (String, u64, u64)>, Error> {
+		self.call(|n| n.get_outputs_from_node(wallet_outputs.clone()))
+	}
+
+	fn get_outputs_by_pmmr_index(
+		&self,
+		start_index: u64,
+		end_index: Option<u64>,
+		max_outputs: u64,
+	) -> Result<
+		(
+			u64,
+			u64,
+			Vec<(pedersen::Commitment, pedersen::RangeProof, bool, u64, u64)>,
+		),
+		Error,
+	> {
+		self.call(|n| n.get_outputs_by_pmmr_index(start_index, end_index, max_outputs))
+	}
+
+	fn height_range_to_pmmr_indices(
+		&self,
+		start_height: u64,
+		end_height: Option<u64>,
+	) -> Result<(u64, u64), Error> {
+		self.call(|n| n.height_range_to_pmmr_indices(start_height, end_height))
+	}
+
+	fn get_onion_addresses(&self) -> Result<Vec<String>, Error> {
+		self.call(|n| n.get_onion_addresses())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::node_clients::stub::StubNode;
+
+	fn is_mismatch<T>(res: Result<T, Error>) -> bool {
+		matches!(res, Err(Error::NodeMismatch(_)))
+	}
+
+	#[test]
+	fn node_order() {
+		assert!(MultiNodeClient::new(&[], None, false).is_err());
+
+		let urls: Vec<String> = (1..=3)
+			.map(|i| format!("http://127.0.0.{}:3413", i))
+			.collect();
+		let client = MultiNodeClient::new(&urls, None, true).unwrap();
+		assert_eq!(client.node_url(), urls[0]);
+		assert_eq!(client.others(1).collect::<Vec<_>>(), vec![2, 0]);
+
+		// clones follow a switch of the active node
+		let clone = client.clone();
+		client.switch_to(2);
+		assert_eq!(clone.node_url(), urls[2]);
+		assert_eq!(clone.node_urls(), urls);
+	}
+	#[test]
+	fn failover() {
+		// the second node answers but isn't synced, so it's skipped
+		let nodes = vec![
+			StubNode::new("a", 10, None).down(),
+			StubNode::new("b", 8, None).syncing(),
+			StubNode::new("c", 10, None),
+		];
+		let client = MultiNodeClient::from_clients(nodes, false).unwrap();
+		assert_eq!(client.get_chain_tip().unwrap().0, 10);
+		assert_eq!(client.node_url(), "c");
+
+		// the node that took over keeps serving requests
+		assert_eq!(client.get_header_hash(3).unwrap(), "main3");
+		assert_eq!(client.active(), 2);
+
+		// a request refused on its merits doesn't trigger a switch
+		let nodes = vec![StubNode::new("a", 10, None), StubNode::new("b", 10, None)];
+		let client = MultiNodeClient::from_clients(nodes, false).unwrap();
+		assert!(client.get_header_hash(11).is_err());
+		assert_eq!(client.active(), 0);
+
+		// the error of the active node is returned when all nodes are down
+		let nodes = vec![
+			StubNode::new("a", 10, None).down(),
+			StubNode::new("b", 10, None).down(),
+		];
+		let client = MultiNodeClient::from_clients(nodes, false).unwrap();
+		match client.get_chain_tip() {
+			Err(Error::ClientCallback(e)) => assert_eq!(e, "a is down"),
+			res => panic!("unexpected result {:?}", res),
+		}
+		assert_eq!(client.active(), 0);
+	}
+
+	#[test]
+	fn syncing_node_is_swapped() {
+		let nodes = vec![
+			StubNode::new("a", 5, None).syncing(),
+			StubNode::new("b", 10, None).down(),
+			StubNode::new("c", 10, None),
+		];
+		let client = MultiNodeClient::from_clients(nodes, false).unwrap();
+		let status = client.get_node_status().unwrap();
+		assert_eq!(status.sync_status, SYNCED);
+		assert_eq!(client.node_url(), "c");
+
+		// with no synced node around the active one's status is reported
+		let nodes = vec![
+			StubNode::new("a", 5, None).syncing(),
+			StubNode::new("b", 5, None).syncing(),
+		];
+		let client = MultiNodeClient::from_clients(nodes, false).unwrap();
+		assert_eq!(client.get_node_status().unwrap().sync_status, "body_sync");
+		assert_eq!(client.node_url(), "a");
+	}
+
+	#[test]
+	fn cross_check_chain_tip() {
+		let check = |a: StubNode, b: StubNode| {
+			let client = MultiNodeClient::from_clients(vec![a, b], true).unwrap();
+			client.get_chain_tip()
+		};
+		assert!(check(StubNode::new("a", 10, None), StubNode::new("b", 10, None)).is_ok());
+		assert!(is_mismatch(check(
+			StubNode::new("a", 10, None),
+			StubNode::new("b", 10, Some(10))
+		)));
+
+		// at different heights, the hashes at the lower tip are compared
+		assert!(check(StubNode::new("a", 12, None), StubNode::new("b", 10, None)).is_ok());
+		assert!(check(
+			StubNode::new("a", 10, None),
+			StubNode::new("b", 12, Some(11))
+		)
+		.is_ok());
+		assert!(is_mismatch(check(
+			StubNode::new("a", 12, Some(9)),
+			StubNode::new("b", 10, None)
+		)));
+
+		// no second node to ask isn't an error
+		assert!(check(
+			StubNode::new("a", 10, None),
+			StubNode::new("b", 10, None).down()
+		)
+		.is_ok());
+
+		// without cross-checking a forked node is trusted
+		let nodes = vec![
+			StubNode::new("a", 10, None),
+			StubNode::new("b", 10, Some(5)),
+		];
+		let client = MultiNodeClient::from_clients(nodes, false).unwrap();
+		assert!(client.get_chain_tip().is_ok());
+	}
+
+	#[test]
+	fn cross_check_kernel() {
+		let excess = pedersen::Commitment::from_vec(vec![0; 33]);
+		let check = |a: StubNode, b: StubNode| {
+			let mut client = MultiNodeClient::from_clients(vec![a, b], true).unwrap();
+			client.get_kernel(&excess, None, None)
+		};
+		let res = check(
+			StubNode::new("a", 10, None).with_kernel(8),
+			StubNode::new("b", 10, None).with_kernel(8),
+		);
+		assert_eq!(res.unwrap().unwrap().1, 8);
+		let res = check(StubNode::new("a", 10, None), StubNode::new("b", 10, None));
+		assert!(res.unwrap().is_none());
+		assert!(is_mismatch(check(
+			StubNode::new("a", 10, None).with_kernel(8),
+			StubNode::new("b", 10, None).with_kernel(9),
+		)));
+
+		// a kernel above the tip of the other node is yet to reach it
+		let res = check(
+			StubNode::new("a", 12, None).with_kernel(12),
+			StubNode::new("b", 10, None),
+		);
+		assert!(res.unwrap().is_some());
+		let res = check(
+			StubNode::new("a", 10, None),
+			StubNode::new("b", 12, None).with_kernel(11),
+		);
+		assert!(res.unwrap().is_none());
+
+		// but one below it should be known to both
+		assert!(is_mismatch(check(
+			StubNode::new("a", 12, None).with_kernel(9),
+			StubNode::new("b", 10, None),
+		)));
+		assert!(is_mismatch(check(
+			StubNode::new("a", 12, None),
+			StubNode::new("b", 12, None).with_kernel(11),
+		)));
+	}
+}
@@ -0,0 +1,197 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Node stub for testing the node client wrappers without a chain
+
+use crate::core::core::{KernelFeatures, Transaction, TxKernel};
+use crate::libwallet::{Error, NodeClient, NodeStatus, NodeVersionInfo, PoolEntry, Tip};
+use crate::util::secp::pedersen;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Node answering from a fixed list of header hashes, the last one being
+/// its tip
+#[derive(Clone)]
+pub struct StubNode {
+	pub url: String,
+	pub up: bool,
+	pub sync_status: &'static str,
+	pub hashes: Vec<String>,
+	pub kernel: Option<(TxKernel, u64, u64)>,
+	pub outputs: HashMap<pedersen::Commitment, (String, u64, u64)>,
+	/// Kernel and output lookups served, shared between clones
+	lookups: Arc<AtomicUsize>,
+}
+
+impl StubNode {
+	/// Synced node with a chain up to `tip`, forked from `fork` on
+	pub fn new(url: &str, tip: u64, fork: Option<u64>) -> StubNode {
+		let mut node = StubNode {
+			url: url.to_owned(),
+			up: true,
+			sync_status: "no_sync",
+			hashes: vec![],
+			kernel: None,
+			outputs: HashMap::new(),
+			lookups: Arc::new(AtomicUsize::new(0)),
+		};
+		node.mine(tip + 1, fork);
+		node
+	}
+
+	pub fn down(mut self) -> StubNode {
+		self.up = false;
+		self
+	}
+
+	pub fn syncing(mut self) -> StubNode {
+		self.sync_status = "body_sync";
+		self
+	}
+
+	pub fn with_kernel(mut self, height: u64) -> StubNode {
+		let kernel = TxKernel::with_features(KernelFeatures::Plain { fee: 0 });
+		self.kernel = Some((kernel, height, 1));
+		self
+	}
+
+	/// Add `blocks` blocks to the chain, those from `fork` on being on a
+	/// fork
+	pub fn mine(&mut self, blocks: u64, fork: Option<u64>) {
+		for _ in 0..blocks {
+			let h = self.hashes.len() as u64;
+			self.hashes.push(match fork {
+				Some(f) if h >= f => format!("fork{}", h),
+				_ => format!("main{}", h),
+			});
+		}
+	}
+
+	/// Kernel and output lookups served so far
+	pub fn lookups(&self) -> usize {
+		self.lookups.load(Ordering::Relaxed)
+	}
+
+	fn check_up(&self) -> Result<(), Error> {
+		match self.up {
+			true => Ok(()),
+			false => Err(Error::ClientCallback(format!("{} is down", self.url))),
+		}
+	}
+}
+
+impl NodeClient for StubNode {
+	fn node_url(&self) -> &str {
+		&self.url
+	}
+	fn set_node_url(&mut self, node_url: &str) {
+		self.url = node_url.to_owned();
+	}
+	fn node_api_secret(&self) -> Option<String> {
+		None
+	}
+	fn set_node_api_secret(&mut self, _node_api_secret: Option<String>) {}
+	fn post_tx(&self, _tx: &Transaction, _fluff: bool) -> Result<(), Error> {
+		self.check_up()
+	}
+	fn post_tx_tor(&self, _tx: &Transaction, _tor_url: &str) -> Result<(), Error> {
+		self.check_up()
+	}
+	fn get_mempool(&self) -> Result<Vec<PoolEntry>, Error> {
+		self.check_up()?;
+		Ok(vec![])
+	}
+	fn get_version_info(&mut self) -> Option<NodeVersionInfo> {
+		None
+	}
+	fn get_chain_tip(&self) -> Result<(u64, String), Error> {
+		self.check_up()?;
+		let height = self.hashes.len() as u64 - 1;
+		Ok((height, self.hashes[height as usize].clone()))
+	}
+	fn get_header_hash(&self, height: u64) -> Result<String, Error> {
+		self.check_up()?;
+		self.hashes
+			.get(height as usize)
+			.cloned()
+			.ok_or_else(|| Error::GenericError(format!("No header at {}", height)))
+	}
+	fn get_kernel(
+		&mut self,
+		_excess: &pedersen::Commitment,
+		_min_height: Option<u64>,
+		_max_height: Option<u64>,
+	) -> Result<Option<(TxKernel, u64, u64)>, Error> {
+		self.check_up()?;
+		self.lookups.fetch_add(1, Ordering::Relaxed);
+		Ok(self.kernel.clone())
+	}
+	fn get_outputs_from_node(
+		&self,
+		wallet_outputs: Vec<pedersen::Commitment>,
+	) -> Result<HashMap<pedersen::Commitment, (String, u64, u64)>, Error> {
+		self.check_up()?;
+		self.lookups.fetch_add(1, Ordering::Relaxed);
+		Ok(wallet_outputs
+			.iter()
+			.filter_map(|c| self.outputs.get(c).map(|o| (*c, o.clone())))
+			.collect())
+	}
+	fn get_node_status(&self) -> Result<NodeStatus, Error> {
+		let (height, hash) = self.get_chain_tip()?;
+		Ok(NodeStatus {
+			protocol_version: 1,
+			user_agent: "stub".to_owned(),
+			connections: 1,
+			tip: Tip {
+				height,
+				last_block_pushed: hash,
+				prev_block_to_last: String::new(),
+				total_difficulty: HashMap::new(),
+			},
+			sync_status: self.sync_status.to_owned(),
+			sync_info: None,
+		})
+	}
+	fn get_outputs_by_pmmr_index(
+		&self,
+		_start_index: u64,
+		_end_index: Option<u64>,
+		_max_outputs: u64,
+	) -> Result<
+		(
+			u64,
+			u64,
+			Vec<(pedersen::Commitment, pedersen::RangeProof, bool, u64, u64)>,
+		),
+		Error,
+	> {
+		self.check_up()?;
+		Ok((0, 0, vec![]))
+	}
+	fn height_range_to_pmmr_indices(
+		&self,
+		_start_height: u64,
+		_end_height: Option<u64>,
+	) -> Result<(u64, u64), Error> {
+		self.check_up()?;
+		Ok((0, 0))
+	}
+	fn get_onion_addresses(&self) -> Result<Vec<String>, Error> {
+		self.check_up()?;
+		Ok(vec![])
+	}
+}
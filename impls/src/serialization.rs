@@ -15,7 +15,9 @@
 //! Responsible for handling the serialization and deserialization of structs common to the database
 
 use epic_wallet_libwallet::{
-	AcctPathMapping, Context, OutputData, ScannedBlockInfo, TxLogEntry, WalletInitStatus,
+	AcctPathMapping, Contact, Context, EpicboxListenerStatus, OutboxEntry, OutputData,
+	ScanCheckpoint, ScannedBlockHistory, ScannedBlockInfo, ScannedOutput, TxLogEntry,
+	WalletInitStatus, WebhookDelivery, WebhookTxState,
 };
 use serde::Serialize;
 use serde_json::Result;
@@ -31,6 +33,14 @@ pub enum Serializable {
 	ScannedBlockInfo(ScannedBlockInfo),
 	WalletInitStatus(WalletInitStatus),
 	Context(Context),
+	Contact(Contact),
+	ScanCheckpoint(ScanCheckpoint),
+	ScannedOutput(ScannedOutput),
+	ScannedBlockHistory(ScannedBlockHistory),
+	WebhookTxState(WebhookTxState),
+	WebhookDelivery(WebhookDelivery),
+	OutboxEntry(OutboxEntry),
+	EpicboxListenerStatus(EpicboxListenerStatus),
 	Numeric(u64),
 }
 
@@ -79,4 +89,68 @@ impl Serializable {
 			_ => None,
 		}
 	}
+
+	/// Converts a Serializable into a Contact
+	pub fn as_contact(self) -> Option<Contact> {
+		match self {
+			Serializable::Contact(contact) => Some(contact),
+			_ => None,
+		}
+	}
+
+	/// Converts a Serializable into a ScanCheckpoint
+	pub fn as_scan_checkpoint(self) -> Option<ScanCheckpoint> {
+		match self {
+			Serializable::ScanCheckpoint(checkpoint) => Some(checkpoint),
+			_ => None,
+		}
+	}
+
+	/// Converts a Serializable into a ScannedOutput
+	pub fn as_scanned_output(self) -> Option<ScannedOutput> {
+		match self {
+			Serializable::ScannedOutput(output) => Some(output),
+			_ => None,
+		}
+	}
+
+	/// Converts a Serializable into a ScannedBlockHistory
+	pub fn as_scanned_block_history(self) -> Option<ScannedBlockHistory> {
+		match self {
+			Serializable::ScannedBlockHistory(history) => Some(history),
+			_ => None,
+		}
+	}
+
+	/// Converts a Serializable into a WebhookTxState
+	pub fn as_webhook_tx_state(self) -> Option<WebhookTxState> {
+		match self {
+			Serializable::WebhookTxState(state) => Some(state),
+			_ => None,
+		}
+	}
+
+	/// Converts a Serializable into a WebhookDelivery
+	pub fn as_webhook_delivery(self) -> Option<WebhookDelivery> {
+		match self {
+			Serializable::WebhookDelivery(delivery) => Some(delivery),
+			_ => None,
+		}
+	}
+
+	/// Converts a Serializable into an OutboxEntry
+	pub fn as_outbox_entry(self) -> Option<OutboxEntry> {
+		match self {
+			Serializable::OutboxEntry(entry) => Some(entry),
+			_ => None,
+		}
+	}
+
+	/// Converts a Serializable into an EpicboxListenerStatus
+	pub fn as_epicbox_listener_status(self) -> Option<EpicboxListenerStatus> {
+		match self {
+			Serializable::EpicboxListenerStatus(status) => Some(status),
+			_ => None,
+		}
+	}
 }
@@ -274,7 +274,9 @@ where
 {
 	let slate = {
 		let mut w_lock = wallet.lock();
-		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let lc = w_lock.lc_provider()?;
+		let policy = lc.spend_policy();
+		let w = lc.wallet_inst()?;
 		let args = InitTxArgs {
 			src_acct_name: None,
 			amount,
@@ -284,10 +286,17 @@ where
 			selection_strategy_is_use_all: true,
 			..Default::default()
 		};
-		let slate_i = owner::init_send_tx(&mut **w, keychain_mask, args, test_mode)?;
+		let slate_i = owner::init_send_tx(
+			&mut **w,
+			keychain_mask,
+			args,
+			&policy,
+			Some(dest),
+			test_mode,
+		)?;
 		let slate = client.send_tx_slate_direct(dest, &slate_i)?;
 		owner::tx_lock_outputs(&mut **w, keychain_mask, &slate, 0, Some(dest.to_string()))?;
-		let slate = owner::finalize_tx(&mut **w, keychain_mask, &slate)?;
+		let slate = owner::finalize_tx(&mut **w, keychain_mask, &slate, &policy)?;
 		slate
 	};
 	let client = {
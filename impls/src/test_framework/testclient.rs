@@ -20,6 +20,7 @@ use crate::api::{self, LocatedTxKernel};
 use crate::chain::types::NoopAdapter;
 use crate::chain::Chain;
 
+use crate::core::core::hash::Hashed;
 use crate::core::core::{Transaction, TxKernel};
 use crate::core::global::{set_mining_mode, ChainTypes};
 use crate::core::pow;
@@ -155,6 +156,7 @@ where
 			println!("Wallet Client Proxy Received: {:?}", m);
 			let resp = match m.method.as_ref() {
 				"get_chain_tip" => self.get_chain_tip(m)?,
+				"get_header_hash" => self.get_header_hash(m)?,
 				"get_outputs_from_node" => self.get_outputs_from_node(m)?,
 				"get_outputs_by_pmmr_index" => self.get_outputs_by_pmmr_index(m)?,
 				"height_range_to_pmmr_indices" => self.height_range_to_pmmr_indices(m)?,
@@ -299,6 +301,25 @@ where
 		})
 	}
 
+	/// get the hash of the header at a height
+	fn get_header_hash(
+		&mut self,
+		m: WalletProxyMessage,
+	) -> Result<WalletProxyMessage, libwallet::Error> {
+		let height = m.body.parse::<u64>().unwrap();
+		let hash = match self.chain.get_header_by_height(height) {
+			Ok(h) => util::to_hex(h.hash().to_vec()),
+			Err(_) => "".to_owned(),
+		};
+
+		Ok(WalletProxyMessage {
+			sender_id: "node".to_owned(),
+			dest: m.sender_id,
+			method: m.method,
+			body: hash,
+		})
+	}
+
 	/// get api outputs
 	fn get_outputs_from_node(
 		&mut self,
@@ -608,6 +629,28 @@ impl NodeClient for LocalWalletClient {
 		Ok((split[0].parse::<u64>().unwrap(), split[1].to_owned()))
 	}
 
+	/// Return the hash of the header at a given height
+	fn get_header_hash(&self, height: u64) -> Result<String, libwallet::Error> {
+		let m = WalletProxyMessage {
+			sender_id: self.id.clone(),
+			dest: self.node_url().to_owned(),
+			method: "get_header_hash".to_owned(),
+			body: format!("{}", height),
+		};
+		{
+			let p = self.proxy_tx.lock();
+			p.send(m)
+				.map_err(|_| libwallet::Error::ClientCallback("Get header hash send".to_owned()))?;
+		}
+		let r = self.rx.lock();
+		let m = r.recv().unwrap();
+		trace!("Received get_header_hash response: {:?}", m.clone());
+		if m.body.is_empty() {
+			return Err(libwallet::Error::NotFound);
+		}
+		Ok(m.body)
+	}
+
 	/// Retrieve outputs from node
 	fn get_outputs_from_node(
 		&self,
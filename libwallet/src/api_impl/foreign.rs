@@ -88,6 +88,7 @@ where
 {
     let mut ret_slate = slate.clone();
     check_ttl(w, &ret_slate)?;
+    tx::check_lock_height(&ret_slate)?;
     let parent_key_id = match dest_acct_name {
         Some(d) => {
             let pm = w.get_acct_path(d.to_owned())?;
@@ -134,6 +135,24 @@ where
         use_test_rng,
     )?;
     tx::update_message(&mut *w, keychain_mask, &mut ret_slate)?;
+
+    // record known counterparties by their address book name
+    let sender_address = ret_slate.payment_proof.as_ref().map(|p| p.sender_address);
+    let contact = w.contact_iter().find(|c| {
+        let by_addr = match addr_from.as_ref() {
+            Some(a) => c.matches_address(a),
+            None => false,
+        };
+        let by_proof = match (c.proof_address.as_ref(), sender_address) {
+            (Some(p), Some(s)) => address::ed25519_parse_pubkey(p).ok() == Some(s),
+            _ => false,
+        };
+        by_addr || by_proof
+    });
+    let addr_from = match contact {
+        Some(c) => Some(c.name),
+        None => addr_from,
+    };
     tx::update_public_addr(
         &mut *w,
         keychain_mask,
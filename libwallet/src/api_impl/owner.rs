@@ -17,22 +17,28 @@
 use uuid::Uuid;
 
 use crate::epic_core::core::hash::Hashed;
-use crate::epic_core::core::Transaction;
+use crate::epic_core::core::{KernelFeatures, Transaction};
 use crate::epic_util::secp::key::SecretKey;
 use crate::epic_util::Mutex;
 
 use crate::api_impl::owner_updater::StatusMessage;
-use crate::config::EpicboxConfig;
-use crate::epic_keychain::{Identifier, Keychain};
+use crate::config::{EpicboxConfig, SpendPolicy};
+use crate::epic_keychain::{Identifier, Keychain, SwitchCommitmentType};
 use crate::epic_util::secp::key::PublicKey;
+use crate::epic_util::to_hex;
 use crate::epicbox_address::EpicboxAddress;
-use crate::internal::{keys, scan, selection, tx, updater};
+use crate::internal::{keys, outbox, policy, scan, selection, tx, updater};
 use crate::slate::{PaymentInfo, Slate};
-use crate::types::{AcctPathMapping, NodeClient, TxLogEntry, WalletBackend, WalletInfo};
+use crate::types::{
+    AcctPathMapping, CoinSelectionStrategy, Contact, EpicboxListenerStatus, NodeClient,
+    OutboxEntry, OutputData, OutputStatus, ScanProgress, SelectionStrategy, TxLogEntry,
+    WalletBackend, WalletInfo,
+};
 use crate::{
-    address, wallet_lock, InitTxArgs, IssueInvoiceTxArgs, NodeHeightResult, Pager, PaymentProof,
-    RetrieveOutputsResult, RetrieveTxsResult, ScannedBlockInfo, TxLogEntryType, WalletInitStatus,
-    WalletInst, WalletLCProvider,
+    address, wallet_lock, InitTxArgs, IssueInvoiceTxArgs, LabelSearchResult, NodeHeightResult,
+    Pager, PaymentProof, RetrieveOutputsResult, RetrieveTxsResult, ScannedBlockInfo,
+    SelectionEstimate, TxLogEntryType, TxProof, VerifiedTxProof, WalletInitStatus, WalletInst,
+    WalletLCProvider,
 };
 
 use crate::Error;
@@ -45,6 +51,8 @@ use std::sync::Arc;
 use std::{thread, time::Duration};
 
 const USER_MESSAGE_MAX_LEN: usize = 256;
+const LABEL_MAX_LEN: usize = 256;
+const CONTACT_METHODS: [&str; 4] = ["http", "tor", "epicbox", "keybase"];
 
 /// List of accounts
 pub fn accounts<'a, T: ?Sized, C, K>(w: &mut T) -> Result<Vec<AcctPathMapping>, Error>
@@ -80,6 +88,82 @@ where
     w.set_parent_key_id_by_name(label)
 }
 
+/// List of address book contacts, ordered by name
+pub fn contacts<'a, T: ?Sized, C, K>(w: &mut T) -> Result<Vec<Contact>, Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let mut contacts: Vec<Contact> = w.contact_iter().collect();
+    contacts.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(contacts)
+}
+
+/// Add an address book contact, replacing any existing contact of the same name
+pub fn add_contact<'a, T: ?Sized, C, K>(
+    w: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    mut contact: Contact,
+) -> Result<(), Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    contact.name = contact.name.trim().trim_start_matches('@').to_owned();
+    contact.method = contact.method.trim().to_lowercase();
+    contact.destination = contact.destination.trim().to_owned();
+    if contact.name.is_empty() || contact.name.contains(char::is_whitespace) {
+        return Err(Error::ArgumentError(format!(
+            "Invalid contact name '{}'",
+            contact.name
+        )));
+    }
+    if !CONTACT_METHODS.contains(&contact.method.as_str()) {
+        return Err(Error::ArgumentError(format!(
+            "Unsupported contact method '{}', expected one of {:?}",
+            contact.method, CONTACT_METHODS
+        )));
+    }
+    if contact.destination.is_empty() {
+        return Err(Error::ArgumentError(
+            "Contact destination is required".to_owned(),
+        ));
+    }
+    if let Some(p) = contact.proof_address.as_ref() {
+        address::ed25519_parse_pubkey(p)?;
+    }
+    let mut batch = w.batch(keychain_mask)?;
+    batch.save_contact(contact)?;
+    batch.commit()?;
+    Ok(())
+}
+
+/// Remove an address book contact
+pub fn remove_contact<'a, T: ?Sized, C, K>(
+    w: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    name: &str,
+) -> Result<(), Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let name = name.trim().trim_start_matches('@');
+    if w.get_contact(name)?.is_none() {
+        return Err(Error::ArgumentError(format!(
+            "Contact '{}' not found",
+            name
+        )));
+    }
+    let mut batch = w.batch(keychain_mask)?;
+    batch.delete_contact(name)?;
+    batch.commit()?;
+    Ok(())
+}
+
 /// Retrieve the payment proof address for the current parent key at
 /// the given index
 /// set active account
@@ -184,6 +268,190 @@ where
     })
 }
 
+/// Find the output with the given (hex encoded) commitment
+fn find_output<'a, T: ?Sized, C, K>(
+    w: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    commit: &str,
+) -> Result<OutputData, Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let commit = commit.trim().to_lowercase();
+    let keychain = w.keychain(keychain_mask)?;
+    let output = w.iter().find(|out| {
+        let out_commit = match out.commit.clone() {
+            Some(c) => c,
+            None => match keychain.commit(out.value, &out.key_id, &SwitchCommitmentType::Regular) {
+                Ok(c) => to_hex(c.as_ref().to_vec()),
+                Err(_) => return false,
+            },
+        };
+        out_commit == commit
+    });
+    match output {
+        Some(o) => Ok(o),
+        None => Err(Error::ArgumentError(format!(
+            "Output {} not found in wallet",
+            commit
+        ))),
+    }
+}
+
+/// Trim and truncate a user supplied label, treating an empty label as none
+fn normalize_label(label: Option<String>) -> Option<String> {
+    label
+        .map(|l| l.trim().chars().take(LABEL_MAX_LEN).collect::<String>())
+        .filter(|l| !l.is_empty())
+}
+
+/// Freeze or unfreeze the output with the given commitment. Frozen outputs
+/// are never selected for spending.
+pub fn set_output_frozen<'a, L, C, K>(
+    wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+    keychain_mask: Option<&SecretKey>,
+    commit: &str,
+    frozen: bool,
+) -> Result<(), Error>
+where
+    L: WalletLCProvider<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    wallet_lock!(wallet_inst, w);
+    let mut output = find_output(&mut **w, keychain_mask, commit)?;
+    if output.frozen == frozen {
+        return Ok(());
+    }
+    if frozen && ![OutputStatus::Unspent, OutputStatus::Unconfirmed].contains(&output.status) {
+        return Err(Error::ArgumentError(format!(
+            "Output {} can't be frozen (status: {})",
+            commit, output.status
+        )));
+    }
+    output.frozen = frozen;
+    let mut batch = w.batch(keychain_mask)?;
+    batch.save(output)?;
+    batch.commit()?;
+    Ok(())
+}
+
+/// Set or clear (if `label` is `None` or empty) the label of an output
+pub fn set_output_label<'a, L, C, K>(
+    wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+    keychain_mask: Option<&SecretKey>,
+    commit: &str,
+    label: Option<String>,
+) -> Result<(), Error>
+where
+    L: WalletLCProvider<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    wallet_lock!(wallet_inst, w);
+    let mut output = find_output(&mut **w, keychain_mask, commit)?;
+    output.label = normalize_label(label);
+    let mut batch = w.batch(keychain_mask)?;
+    batch.save(output)?;
+    batch.commit()?;
+    Ok(())
+}
+
+/// Set or clear (if `label` is `None` or empty) the label of a transaction
+/// log entry in the active account
+pub fn set_tx_label<'a, L, C, K>(
+    wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+    keychain_mask: Option<&SecretKey>,
+    tx_id: Option<u32>,
+    tx_slate_id: Option<Uuid>,
+    label: Option<String>,
+) -> Result<(), Error>
+where
+    L: WalletLCProvider<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    wallet_lock!(wallet_inst, w);
+    let parent_key_id = w.parent_key_id();
+    let tx_id_string = match (tx_id, tx_slate_id) {
+        (Some(id), None) => id.to_string(),
+        (None, Some(slate_id)) => slate_id.to_string(),
+        _ => {
+            return Err(Error::ArgumentError(
+                "Exactly one of tx_id or tx_slate_id must be provided".to_owned(),
+            ));
+        }
+    };
+    let (_, _, txs) = updater::retrieve_txs(
+        &mut **w,
+        tx_id,
+        tx_slate_id,
+        Some(&parent_key_id),
+        false,
+        None,
+        None,
+        None,
+    )?;
+    if txs.is_empty() {
+        return Err(Error::TransactionDoesntExist(tx_id_string));
+    }
+    let label = normalize_label(label);
+    let mut batch = w.batch(keychain_mask)?;
+    // both the sending and receiving entries of a self-send share a slate id
+    for mut tx in txs {
+        tx.label = label.clone();
+        batch.save_tx_log_entry(tx, &parent_key_id)?;
+    }
+    batch.commit()?;
+    Ok(())
+}
+
+/// Return all transactions and outputs in the active account whose label
+/// contains `query` (case insensitive)
+pub fn search_labels<'a, L, C, K>(
+    wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+    keychain_mask: Option<&SecretKey>,
+    query: &str,
+) -> Result<LabelSearchResult, Error>
+where
+    L: WalletLCProvider<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    wallet_lock!(wallet_inst, w);
+    let parent_key_id = w.parent_key_id();
+    let (_, _, txs) = updater::retrieve_txs(
+        &mut **w,
+        None,
+        None,
+        Some(&parent_key_id),
+        false,
+        None,
+        None,
+        None,
+    )?;
+    let (_, _, outputs) = updater::retrieve_outputs(
+        &mut **w,
+        keychain_mask,
+        true,
+        false,
+        None,
+        Some(&parent_key_id),
+        None,
+        None,
+        None,
+    )?;
+    Ok(LabelSearchResult {
+        txs: txs.into_iter().filter(|t| t.label_matches(query)).collect(),
+        outputs: outputs
+            .into_iter()
+            .filter(|o| o.output.label_matches(query))
+            .collect(),
+    })
+}
+
 /// Retrieve txs
 pub fn retrieve_txs<'a, L, C, K>(
     wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
@@ -368,11 +636,61 @@ where
     })
 }
 
-/// Initiate tx as sender
+/// Retrieve the epicbox proof stored for a transaction
+pub fn retrieve_tx_proof<'a, T: ?Sized, C, K>(
+    w: &T,
+    tx_id: Option<u32>,
+    tx_slate_id: Option<Uuid>,
+) -> Result<TxProof, Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    if tx_id.is_none() && tx_slate_id.is_none() {
+        return Err(Error::TxProof(
+            "Transaction ID or Slate UUID must be specified".into(),
+        ));
+    }
+    let parent_key_id = w.parent_key_id();
+    let tx = w.tx_log_iter().find(|t| match (tx_id, tx_slate_id) {
+        (Some(id), _) => t.id == id && t.parent_key_id == parent_key_id,
+        (None, slate_id) => t.tx_slate_id == slate_id && t.tx_proof.is_some(),
+    });
+    let tx = match tx {
+        Some(t) => t,
+        None => return Err(Error::TxProof("Transaction doesn't exist".into())),
+    };
+    match w.get_stored_tx_proof(&tx)? {
+        Some(p) => Ok(p),
+        None => Err(Error::TxProof(
+            "Transaction was not received with a proof".into(),
+        )),
+    }
+}
+
+/// Verify an epicbox proof, independently of any wallet
+pub fn verify_tx_proof(proof: &TxProof) -> Result<VerifiedTxProof, Error> {
+    let (from, to, slate) = proof.verify().map_err(|e| Error::TxProof(e.to_string()))?;
+    Ok(VerifiedTxProof {
+        from_address: from.to_string(),
+        to_address: to.to_string(),
+        slate_id: slate.id,
+        amount: slate.amount,
+        fee: slate.fee,
+        num_inputs: slate.tx.inputs().len(),
+        num_outputs: slate.tx.outputs().len(),
+    })
+}
+
+/// Initiate tx as sender, once checked against the spending policy. `dest`
+/// is where the caller is going to send the slate, if known.
 pub fn init_send_tx<'a, T: ?Sized, C, K>(
     w: &mut T,
     keychain_mask: Option<&SecretKey>,
     args: InitTxArgs,
+    spend_policy: &SpendPolicy,
+    dest: Option<&str>,
     use_test_rng: bool,
 ) -> Result<Slate, Error>
 where
@@ -380,6 +698,8 @@ where
     C: NodeClient + 'a,
     K: Keychain + 'a,
 {
+    policy::check_send_policy(&mut *w, spend_policy, &args, dest)?;
+    let selection_strategy = args.effective_selection_strategy();
     let parent_key_id = match args.src_acct_name {
         Some(d) => {
             let pm = w.get_acct_path(d)?;
@@ -390,6 +710,16 @@ where
         }
         None => w.parent_key_id(),
     };
+    let selection_strategy: Box<dyn CoinSelectionStrategy> = match args.inputs {
+        Some(commits) => Box::new(selection::manual_selection(
+            &mut *w,
+            keychain_mask,
+            commits,
+            args.minimum_confirmations,
+            &parent_key_id,
+        )?),
+        None => selection_strategy.selector(),
+    };
 
     let message = match args.message {
         Some(mut m) => {
@@ -400,18 +730,20 @@ where
     };
 
     let mut slate = tx::new_tx_slate(&mut *w, args.amount, 2, use_test_rng, args.ttl_blocks)?;
+    slate.lock_height = tx::lock_height(slate.height, args.lock_height, args.lock_after_blocks)?;
+    tx::check_lock_height(&slate)?;
 
     // if we just want to estimate, don't save a context, just send the results
     // back
     if let Some(true) = args.estimate_only {
-        let (total, fee) = tx::estimate_send_tx(
+        let (total, fee, _num_inputs) = tx::estimate_send_tx(
             &mut *w,
             keychain_mask,
             args.amount,
             args.minimum_confirmations,
             args.max_outputs as usize,
             args.num_change_outputs as usize,
-            args.selection_strategy_is_use_all,
+            &*selection_strategy,
             &parent_key_id,
         )?;
         slate.amount = total;
@@ -426,7 +758,7 @@ where
         args.minimum_confirmations,
         args.max_outputs as usize,
         args.num_change_outputs as usize,
-        args.selection_strategy_is_use_all,
+        &*selection_strategy,
         &parent_key_id,
         0,
         message,
@@ -468,6 +800,109 @@ where
     Ok(slate)
 }
 
+/// Estimate the fee, amount locked and number of inputs of a transaction
+/// for each of the built-in coin selection strategies
+pub fn estimate_selection_strategies<'a, T: ?Sized, C, K>(
+    w: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    args: InitTxArgs,
+) -> Result<Vec<SelectionEstimate>, Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let parent_key_id = match args.src_acct_name {
+        Some(d) => {
+            let pm = w.get_acct_path(d)?;
+            match pm {
+                Some(p) => p.path,
+                None => w.parent_key_id(),
+            }
+        }
+        None => w.parent_key_id(),
+    };
+
+    let mut estimates = vec![];
+    for strategy in SelectionStrategy::all() {
+        let (total, fee, num_inputs) = tx::estimate_send_tx(
+            &mut *w,
+            keychain_mask,
+            args.amount,
+            args.minimum_confirmations,
+            args.max_outputs as usize,
+            args.num_change_outputs as usize,
+            &*strategy.selector(),
+            &parent_key_id,
+        )?;
+        estimates.push(SelectionEstimate {
+            strategy,
+            total,
+            fee,
+            num_inputs,
+        });
+    }
+    Ok(estimates)
+}
+
+/// Select the inputs of a transaction for each of `amounts` at once, so they
+/// can all be created one after the other. Returns the commitments each
+/// transaction is to spend, as the `inputs` of its arguments. Only the
+/// amounts of `args` differ between the transactions.
+pub fn plan_inputs<'a, T: ?Sized, C, K>(
+    w: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    args: InitTxArgs,
+    amounts: &[u64],
+) -> Result<Vec<Vec<String>>, Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let selection_strategy = args.effective_selection_strategy();
+    let parent_key_id = match args.src_acct_name {
+        Some(d) => {
+            let pm = w.get_acct_path(d)?;
+            match pm {
+                Some(p) => p.path,
+                None => w.parent_key_id(),
+            }
+        }
+        None => w.parent_key_id(),
+    };
+    updater::refresh_outputs(&mut *w, keychain_mask, &parent_key_id, false)?;
+    let current_height = w.w2n_client().get_chain_tip()?.0;
+
+    let selections = selection::select_coins_for_all(
+        &mut *w,
+        amounts,
+        current_height,
+        args.minimum_confirmations,
+        args.max_outputs as usize,
+        args.num_change_outputs as usize,
+        &*selection_strategy.selector(),
+        &parent_key_id,
+    )?;
+    let mut plan = vec![];
+    for coins in selections {
+        let mut commits = vec![];
+        for c in coins {
+            match c.commit {
+                Some(commit) => commits.push(commit),
+                None => {
+                    return Err(Error::GenericError(format!(
+                        "Output {:?} has no commitment",
+                        c.key_id
+                    )))
+                }
+            }
+        }
+        plan.push(commits);
+    }
+    Ok(plan)
+}
+
 /// Initiate a transaction as the recipient (invoicing)
 pub fn issue_invoice_tx<'a, T: ?Sized, C, K>(
     w: &mut T,
@@ -527,12 +962,13 @@ where
 }
 
 /// Receive an invoice tx, essentially adding inputs to whatever
-/// output was specified
+/// output was specified, once checked against the spending policy
 pub fn process_invoice_tx<'a, T: ?Sized, C, K>(
     w: &mut T,
     keychain_mask: Option<&SecretKey>,
     slate: &Slate,
     args: InitTxArgs,
+    spend_policy: &SpendPolicy,
     use_test_rng: bool,
 ) -> Result<Slate, Error>
 where
@@ -540,6 +976,8 @@ where
     C: NodeClient + 'a,
     K: Keychain + 'a,
 {
+    policy::check_invoice_policy(&mut *w, spend_policy, slate, &args)?;
+    let selection_strategy = args.effective_selection_strategy();
     let mut ret_slate = slate.clone();
     check_ttl(w, &ret_slate)?;
     let parent_key_id = match args.src_acct_name {
@@ -568,6 +1006,16 @@ where
             return Err(Error::TransactionAlreadyReceived(ret_slate.id.to_string()).into());
         }
     }
+    let selection_strategy: Box<dyn CoinSelectionStrategy> = match args.inputs {
+        Some(commits) => Box::new(selection::manual_selection(
+            &mut *w,
+            keychain_mask,
+            commits,
+            args.minimum_confirmations,
+            &parent_key_id,
+        )?),
+        None => selection_strategy.selector(),
+    };
 
     let message = match args.message {
         Some(mut m) => {
@@ -592,7 +1040,7 @@ where
         args.minimum_confirmations,
         args.max_outputs as usize,
         args.num_change_outputs as usize,
-        args.selection_strategy_is_use_all,
+        &*selection_strategy,
         &parent_key_id,
         0,
         message,
@@ -632,21 +1080,26 @@ where
     selection::lock_tx_context(&mut *w, keychain_mask, slate, &context, addr_to)
 }
 
-/// Finalize slate
+/// Finalize slate, once checked against the spending policy
 pub fn finalize_tx<'a, T: ?Sized, C, K>(
     w: &mut T,
     keychain_mask: Option<&SecretKey>,
     slate: &Slate,
+    spend_policy: &SpendPolicy,
 ) -> Result<Slate, Error>
 where
     T: WalletBackend<'a, C, K>,
     C: NodeClient + 'a,
     K: Keychain + 'a,
 {
+    policy::check_finalize_policy(&mut *w, spend_policy, slate)?;
     let mut sl = slate.clone();
     check_ttl(w, &sl)?;
     let context = w.get_private_context(keychain_mask, sl.id.as_bytes(), 0)?;
     let parent_key_id = w.parent_key_id();
+    if sl.tx.inputs().is_empty() {
+        tx::restore_sender_elements(&mut *w, &mut sl)?;
+    }
     tx::complete_tx(&mut *w, keychain_mask, &mut sl, 0, &context)?;
     tx::verify_slate_payment_proof(&mut *w, keychain_mask, &parent_key_id, &context, &sl)?;
     tx::update_stored_tx(&mut *w, keychain_mask, &context, &mut sl, false)?;
@@ -675,6 +1128,130 @@ where
     tx::update_mempool_status(wallet, keychain_mask, slate)
 }
 
+/// Wrapper for tx::store_tx_proof
+pub fn store_tx_proof<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    slate_id: Uuid,
+    proof: &TxProof,
+) -> Result<(), Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    tx::store_tx_proof(wallet, keychain_mask, slate_id, proof)
+}
+
+/// Slates sent over epicbox along with their delivery state, oldest first
+pub fn retrieve_outbox<'a, T: ?Sized, C, K>(w: &T) -> Result<Vec<OutboxEntry>, Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let mut entries: Vec<OutboxEntry> = w.outbox_iter().collect();
+    entries.sort_by_key(|e| e.created);
+    Ok(entries)
+}
+
+/// Wrapper for outbox::queue_slate
+pub fn queue_epicbox_slate<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    slate: &Slate,
+    dest: &str,
+    payload: String,
+) -> Result<(), Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    outbox::queue_slate(wallet, keychain_mask, slate, dest, payload)
+}
+
+/// Wrapper for outbox::record_attempt
+pub fn record_epicbox_attempt<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    slate_id: &Uuid,
+    result: Result<bool, Error>,
+) -> Result<(), Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    outbox::record_attempt(wallet, keychain_mask, slate_id, result)
+}
+
+/// Wrapper for outbox::record_response
+pub fn record_epicbox_response<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    slate_id: &Uuid,
+) -> Result<(), Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    outbox::record_response(wallet, keychain_mask, slate_id)
+}
+
+/// Give up on epicbox slates that expired at the given tip, remove those kept
+/// long enough, and return those due to be sent again
+pub fn due_epicbox_slates<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    tip_height: u64,
+) -> Result<Vec<OutboxEntry>, Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let expired = outbox::expire_entries(wallet, keychain_mask, tip_height)?;
+    if expired > 0 {
+        info!("Gave up on {} epicbox slates", expired);
+    }
+    let pruned = outbox::prune_entries(wallet, keychain_mask)?;
+    if pruned > 0 {
+        debug!("Removed {} epicbox slates from the outbox", pruned);
+    }
+    Ok(outbox::due_entries(wallet))
+}
+
+/// Status of the epicbox listener, as last recorded by it
+pub fn retrieve_epicbox_status<'a, T: ?Sized, C, K>(
+    w: &mut T,
+) -> Result<Option<EpicboxListenerStatus>, Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    w.epicbox_status()
+}
+
+/// Record the status of the epicbox listener
+pub fn record_epicbox_status<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    status: EpicboxListenerStatus,
+) -> Result<(), Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let mut batch = wallet.batch(keychain_mask)?;
+    batch.save_epicbox_status(status)?;
+    batch.commit()?;
+    Ok(())
+}
+
 /// Polls the node mempool for the given transaction and marks it as TxSentMempool if found.
 pub fn wait_for_tx_in_mempool<'a, L, C, K>(
     wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
@@ -789,6 +1366,7 @@ pub fn post_tx<'a, C>(client: &C, tx: &Transaction, fluff: bool) -> Result<(), E
 where
     C: NodeClient + 'a,
 {
+    check_tx_lock_height(client, tx)?;
     let res = client.post_tx(&tx, fluff);
 
     if let Err(e) = res {
@@ -806,6 +1384,7 @@ pub fn post_tx_tor<'a, C>(client: &C, tx: &Transaction, tor_node_url: &str) -> R
 where
     C: NodeClient + 'a,
 {
+    check_tx_lock_height(client, tx)?;
     let res = client.post_tx_tor(tx, tor_node_url);
 
     if let Err(e) = res {
@@ -821,6 +1400,30 @@ where
     }
 }
 
+/// Refuse to post a transaction with a height locked kernel before the chain
+/// reaches its lock height, the node would only reject it
+fn check_tx_lock_height<'a, C>(client: &C, tx: &Transaction) -> Result<(), Error>
+where
+    C: NodeClient + 'a,
+{
+    let lock_height = tx
+        .kernels()
+        .iter()
+        .filter_map(|k| match k.features {
+            KernelFeatures::HeightLocked { lock_height, .. } => Some(lock_height),
+            _ => None,
+        })
+        .max();
+    if let Some(lock_height) = lock_height {
+        // the transaction can go in the next block at the earliest
+        let tip = client.get_chain_tip()?.0;
+        if lock_height > tip + 1 {
+            return Err(Error::TransactionLocked(lock_height, tip));
+        }
+    }
+    Ok(())
+}
+
 /// verify slate messages
 pub fn verify_slate_messages(slate: &Slate) -> Result<(), Error> {
     slate.verify_messages()
@@ -863,13 +1466,27 @@ where
     info.hash = tip.1;
 
     wallet_lock!(wallet_inst, w);
+    let mut history = w.scanned_block_history()?;
+    history.push(info.height, info.hash.clone());
     let mut batch = w.batch(keychain_mask)?;
     batch.save_last_scanned_block(info)?;
+    batch.save_scanned_block_history(history)?;
+    batch.delete_scan_checkpoint()?;
     batch.commit()?;
 
     Ok(())
 }
 
+/// Progress of a running or interrupted scan, if any
+pub fn scan_progress<'a, T: ?Sized, C, K>(w: &mut T) -> Result<Option<ScanProgress>, Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    Ok(w.scan_checkpoint()?.map(|c| c.progress()))
+}
+
 /// node height
 pub fn node_height<'a, L, C, K>(
     wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
@@ -936,6 +1553,14 @@ where
         w.w2n_client().clone()
     };
 
+    // Step 0: Roll back confirmations the chain may have lost since the last update
+    let fork_height = check_reorg(
+        wallet_inst.clone(),
+        keychain_mask,
+        &client,
+        status_send_channel,
+    )?;
+
     // Step 1: Update outputs and transactions purely based on UTXO state
     if let Some(ref s) = status_send_channel {
         let _ = s.send(StatusMessage::UpdatingOutputs(
@@ -1018,7 +1643,11 @@ where
         }
     };
 
-    let start_index = last_scanned_block.height.saturating_sub(100);
+    // rescan everything above a fork, however deep
+    let start_index = match fork_height {
+        Some(h) => h.min(last_scanned_block.height.saturating_sub(100)),
+        None => last_scanned_block.height.saturating_sub(100),
+    };
 
     if last_scanned_block.height == 0 {
         let msg = format!("This wallet has not been scanned against the current chain. Beginning full scan... (this first scan may take a while, but subsequent scans will be much quicker)");
@@ -1040,8 +1669,12 @@ where
 
     {
         wallet_lock!(wallet_inst, w);
+        let mut history = w.scanned_block_history()?;
+        history.push(info.height, info.hash.clone());
         let mut batch = w.batch(keychain_mask)?;
         batch.save_last_scanned_block(info)?;
+        batch.save_scanned_block_history(history)?;
+        batch.delete_scan_checkpoint()?;
         // init considered complete after first successful update
         batch.save_init_status(WalletInitStatus::InitComplete)?;
         batch.commit()?;
@@ -1170,6 +1803,62 @@ where
     }
 }
 
+/// Check the chain tips recent updates scanned up to are still on the node's
+/// chain. If the chain forked under them, confirmations at or above the fork
+/// are rolled back and the fork height returned, so it can be rescanned.
+fn check_reorg<'a, L, C, K>(
+    wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+    keychain_mask: Option<&SecretKey>,
+    client: &C,
+    status_send_channel: &Option<Sender<StatusMessage>>,
+) -> Result<Option<u64>, Error>
+where
+    L: WalletLCProvider<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    // a node we can't reach is reported by the steps after this one
+    let tip = match client.get_chain_tip() {
+        Ok(t) => t,
+        Err(_) => return Ok(None),
+    };
+    let mut history = {
+        wallet_lock!(wallet_inst, w);
+        w.scanned_block_history()?
+    };
+    let fork_height = match updater::find_fork_height(client, &history, tip.0) {
+        Ok(Some(h)) => h,
+        Ok(None) => return Ok(None),
+        Err(e) => {
+            warn!(
+                "Unable to check the wallet for a chain reorganisation: {}",
+                e
+            );
+            return Ok(None);
+        }
+    };
+
+    let txs = {
+        wallet_lock!(wallet_inst, w);
+        let txs = updater::rollback_confirmations(&mut **w, keychain_mask, fork_height)?;
+        history.truncate(fork_height);
+        let mut batch = w.batch(keychain_mask)?;
+        batch.save_scanned_block_history(history)?;
+        batch.commit()?;
+        txs
+    };
+
+    let tx_ids: Vec<u32> = txs.iter().map(|t| t.id).collect();
+    let msg = format!(
+        "Chain reorganisation detected at height {}, confirmation of transactions {:?} rolled back. Rescanning from the fork",
+        fork_height, tx_ids
+    );
+    if let Some(ref s) = status_send_channel {
+        let _ = s.send(StatusMessage::Reorg(msg, fork_height, tx_ids));
+    }
+    Ok(Some(fork_height))
+}
+
 /// Update transactions that need to be validated via kernel lookup
 fn update_txs_via_kernel<'a, L, C, K>(
     wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
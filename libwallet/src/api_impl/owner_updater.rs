@@ -13,6 +13,8 @@
 // limitations under the License.
 
 //! A threaded persistent Updater that can be controlled by a epic wallet
+use chrono::Utc;
+use std::fs;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{Receiver, Sender};
 use std::sync::Arc;
@@ -24,7 +26,8 @@ use crate::epic_util::secp::key::SecretKey;
 use crate::epic_util::Mutex;
 
 use crate::api_impl::owner;
-use crate::types::NodeClient;
+use crate::internal::webhooks;
+use crate::types::{EpicboxConnectionState, EpicboxListenerStatus, NodeClient, WebhookSender};
 use crate::Error;
 use crate::{WalletInst, WalletLCProvider};
 
@@ -48,12 +51,23 @@ pub enum StatusMessage {
 	ScanningComplete(String),
 	/// Warning of issues that may have occured during an update
 	UpdateWarning(String),
+	/// The chain forked under the wallet. Holds the height of the fork and
+	/// the ids of the transactions whose confirmation was rolled back
+	Reorg(String, u64, Vec<u32>),
+	/// The epicbox listener connected, lost its connection or is trying
+	/// to connect again
+	EpicboxListener(String, EpicboxConnectionState),
 }
 
+/// Channels each updater message is forwarded to as it arrives. Listeners
+/// that hung up are dropped on the next message.
+pub type StatusListeners = Arc<Mutex<Vec<Sender<StatusMessage>>>>;
+
 /// Helper function that starts a simple log thread for updater messages
 pub fn start_updater_log_thread(
 	rx: Receiver<StatusMessage>,
 	queue: Arc<Mutex<Vec<StatusMessage>>>,
+	listeners: StatusListeners,
 ) -> Result<(), Error> {
 	let _ = thread::Builder::new()
 		.name("wallet-updater-status".to_string())
@@ -67,6 +81,7 @@ pub fn start_updater_log_thread(
 						q.pop();
 					}
 				}
+				listeners.lock().retain(|l| l.send(m.clone()).is_ok());
 				match m {
 					StatusMessage::UpdatingOutputs(s) => debug!("{}", s),
 					StatusMessage::UpdatingTransactions(s) => debug!("{}", s),
@@ -77,6 +92,8 @@ pub fn start_updater_log_thread(
 					}
 					StatusMessage::ScanningComplete(s) => warn!("{}", s),
 					StatusMessage::UpdateWarning(s) => warn!("{}", s),
+					StatusMessage::Reorg(s, _, _) => warn!("{}", s),
+					StatusMessage::EpicboxListener(s, _) => info!("{}", s),
 				}
 			}
 		})?;
@@ -93,6 +110,7 @@ where
 {
 	wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
 	is_running: Arc<AtomicBool>,
+	webhook_sender: Option<Box<dyn WebhookSender>>,
 }
 
 impl<'a, L, C, K> Updater<'a, L, C, K>
@@ -110,9 +128,16 @@ where
 		Updater {
 			wallet_inst,
 			is_running,
+			webhook_sender: None,
 		}
 	}
 
+	/// Set what posts wallet events to the configured webhooks. Without
+	/// one, no webhook is ever notified.
+	pub fn set_webhook_sender(&mut self, sender: Option<Box<dyn WebhookSender>>) {
+		self.webhook_sender = sender;
+	}
+
 	/// Start the updater at the given frequency
 	pub fn run(
 		&self,
@@ -121,6 +146,7 @@ where
 		status_send_channel: &Option<Sender<StatusMessage>>,
 	) -> Result<(), Error> {
 		self.is_running.store(true, Ordering::Relaxed);
+		let mut epicbox_status = None;
 		loop {
 			// Business goes here
 			owner::update_wallet_state(
@@ -129,6 +155,17 @@ where
 				status_send_channel,
 				false,
 			)?;
+			if let Some(ref sender) = self.webhook_sender {
+				// a failing webhook must not stop the wallet from updating
+				if let Err(e) = self.notify_webhooks(sender.as_ref(), (&keychain_mask).as_ref()) {
+					warn!("Unable to notify webhooks: {}", e);
+				}
+			}
+			if let Some(ref sender) = status_send_channel {
+				if let Err(e) = self.report_epicbox_status(sender, &mut epicbox_status) {
+					warn!("Unable to read the epicbox listener status: {}", e);
+				}
+			}
 			if !self.is_running.load(Ordering::Relaxed) {
 				break;
 			}
@@ -136,4 +173,97 @@ where
 		}
 		Ok(())
 	}
+
+	/// Pass on the changes of the epicbox listener's status. The listener
+	/// runs in a process of its own and records its status in the wallet.
+	fn report_epicbox_status(
+		&self,
+		sender: &Sender<StatusMessage>,
+		last: &mut Option<EpicboxListenerStatus>,
+	) -> Result<(), Error> {
+		let status = {
+			wallet_lock!(self.wallet_inst, w);
+			owner::retrieve_epicbox_status(&mut **w)?
+		};
+		let status = match status {
+			Some(s) => s,
+			None => return Ok(()),
+		};
+		let changed = match last {
+			Some(l) => l.state != status.state || l.reconnect_attempts != status.reconnect_attempts,
+			None => true,
+		};
+		if !changed {
+			return Ok(());
+		}
+		let msg = match status.state {
+			EpicboxConnectionState::Connecting => "Epicbox listener connecting".to_owned(),
+			EpicboxConnectionState::Listening => "Epicbox listener subscribed".to_owned(),
+			EpicboxConnectionState::Reconnecting => format!(
+				"Epicbox listener lost its connection ({}), reconnecting at {} (attempt {})",
+				status
+					.last_error
+					.as_deref()
+					.unwrap_or("closed by the relay"),
+				status
+					.next_attempt
+					.map(|t| t.to_rfc3339())
+					.unwrap_or_default(),
+				status.reconnect_attempts,
+			),
+		};
+		let _ = sender.send(StatusMessage::EpicboxListener(msg, status.state));
+		*last = Some(status);
+		Ok(())
+	}
+
+	/// Queue webhook events for what the last update changed and post those
+	/// that are due. The wallet is only locked while the queue is read or
+	/// updated, not while waiting on the webhooks.
+	fn notify_webhooks(
+		&self,
+		sender: &dyn WebhookSender,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<(), Error> {
+		let config = {
+			let mut w_lock = self.wallet_inst.lock();
+			w_lock.lc_provider()?.webhook_config()
+		};
+		if !config.is_enabled() {
+			return Ok(());
+		}
+		let due = {
+			wallet_lock!(self.wallet_inst, w);
+			let tip_height = w.last_confirmed_height()?;
+			let queued = webhooks::queue_events(&mut **w, keychain_mask, &config, tip_height)?;
+			if queued > 0 {
+				debug!("Queued {} webhook deliveries", queued);
+			}
+			webhooks::due_deliveries(&mut **w)
+		};
+		if due.is_empty() {
+			return Ok(());
+		}
+
+		let secret = match config.secret_path {
+			Some(ref path) => {
+				let secret = fs::read_to_string(path).map_err(|e| {
+					Error::GenericError(format!("Unable to read webhook secret {}: {}", path, e))
+				})?;
+				Some(secret.trim().to_owned())
+			}
+			None => None,
+		};
+		for delivery in due {
+			let headers = webhooks::headers(
+				&delivery,
+				secret.as_ref().map(|s| s.as_bytes()),
+				Utc::now().timestamp(),
+			);
+			let res = sender.post(&delivery.url, &delivery.payload, &headers);
+			wallet_lock!(self.wallet_inst, w);
+			webhooks::record_attempt(&mut **w, keychain_mask, delivery, res, config.max_attempts)?;
+		}
+		Ok(())
+	}
 }
@@ -19,9 +19,11 @@ use crate::epic_util::secp::pedersen;
 use crate::slate_versions::ser as dalek_ser;
 use crate::slate_versions::SlateVersion;
 use crate::types::OutputData;
+use crate::types::SelectionStrategy;
 use crate::types::TxLogEntry;
 use ed25519_dalek::Signature as DalekSignature;
 use ed25519_dalek::VerifyingKey as DalekPublicKey;
+use uuid::Uuid;
 
 pub use crate::epic_core::core::block_fees::BlockFees;
 /// Send TX API Args
@@ -78,8 +80,17 @@ pub struct InitTxArgs {
 	/// minimizes fees. This will generally result in many inputs and a large change output(s),
 	/// usually much larger than the amount being sent. If `false`, the transaction will include
 	/// as many outputs as are needed to meet the amount, (and no more) starting with the smallest
-	/// value outputs.
+	/// value outputs. Ignored if `selection_strategy` is set.
 	pub selection_strategy_is_use_all: bool,
+	/// The coin selection strategy to use, one of `all`, `smallest`, `branch_and_bound`
+	/// (spend a set of outputs matching amount + fee exactly, so no change output is created),
+	/// `largest`, `oldest` or `privacy` (avoid linking outputs from unrelated transactions).
+	/// If `None`, the strategy is determined by `selection_strategy_is_use_all`.
+	pub selection_strategy: Option<SelectionStrategy>,
+	/// If set, spend exactly the outputs with these (hex encoded) commitments, and no others.
+	/// The outputs must be unspent, mature and belong to the source account. Overrides the
+	/// selection strategy.
+	pub inputs: Option<Vec<String>>,
 	/// An optional participant message to include alongside the sender's public
 	/// ParticipantData within the slate. This message will include a signature created with the
 	/// sender's private excess value, and will be publically verifiable. Note this message is for
@@ -93,6 +104,16 @@ pub struct InitTxArgs {
 	/// Number of blocks from current after which TX should be ignored
 	#[serde(with = "secp_ser::opt_string_or_u64")]
 	pub ttl_blocks: Option<u64>,
+	/// Height before which the transaction can't be mined, giving it a height locked
+	/// kernel. Can't be combined with `lock_after_blocks`.
+	#[serde(with = "secp_ser::opt_string_or_u64")]
+	#[serde(default)]
+	pub lock_height: Option<u64>,
+	/// Number of blocks from current before which the transaction can't be mined.
+	/// Can't be combined with `lock_height`.
+	#[serde(with = "secp_ser::opt_string_or_u64")]
+	#[serde(default)]
+	pub lock_after_blocks: Option<u64>,
 	/// If set, require a payment proof for the particular recipient
 	#[serde(with = "dalek_ser::option_dalek_pubkey_serde")]
 	pub payment_proof_recipient_address: Option<DalekPublicKey>,
@@ -131,9 +152,13 @@ impl Default for InitTxArgs {
 			max_outputs: 500,
 			num_change_outputs: 1,
 			selection_strategy_is_use_all: true,
+			selection_strategy: None,
+			inputs: None,
 			message: None,
 			target_slate_version: None,
 			ttl_blocks: None,
+			lock_height: None,
+			lock_after_blocks: None,
 			estimate_only: Some(false),
 			payment_proof_recipient_address: None,
 			send_args: None,
@@ -141,6 +166,35 @@ impl Default for InitTxArgs {
 	}
 }
 
+impl InitTxArgs {
+	/// The coin selection strategy to use, taking the legacy
+	/// `selection_strategy_is_use_all` flag into account
+	pub fn effective_selection_strategy(&self) -> SelectionStrategy {
+		match self.selection_strategy {
+			Some(s) => s,
+			None => match self.selection_strategy_is_use_all {
+				true => SelectionStrategy::All,
+				false => SelectionStrategy::Smallest,
+			},
+		}
+	}
+}
+
+/// Fee and inputs resulting from a particular coin selection strategy
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SelectionEstimate {
+	/// The selection strategy
+	pub strategy: SelectionStrategy,
+	/// Total amount that would be locked
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub total: u64,
+	/// Transaction fee
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub fee: u64,
+	/// Number of inputs that would be spent
+	pub num_inputs: usize,
+}
+
 /// V2 Issue Invoice Tx Args
 #[derive(Clone, Serialize, Deserialize)]
 pub struct IssueInvoiceTxArgs {
@@ -191,6 +245,37 @@ pub struct RetrieveTxsResult {
 	pub txs: Vec<TxLogEntry>,
 }
 
+impl RetrieveTxsResult {
+	/// Keeps only the entries whose label matches `query`, then paginates what's left.
+	/// Expects the result to have been retrieved without a limit or offset
+	pub fn filter_by_label(&mut self, query: &str, limit: Option<usize>, offset: Option<usize>) {
+		let matching: Vec<_> = self
+			.txs
+			.drain(..)
+			.filter(|t| t.label_matches(query))
+			.collect();
+		let offset = offset.unwrap_or(0);
+		self.pager.total_records = matching.len();
+		self.txs = matching
+			.into_iter()
+			.skip(offset)
+			.take(limit.unwrap_or(usize::MAX))
+			.collect();
+		self.pager.records_read = self.txs.len();
+		self.pager.limit = limit.unwrap_or(0);
+		self.pager.offset = offset;
+	}
+}
+
+/// Struct to encapsulate the result of `search_labels`
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LabelSearchResult {
+	/// Transaction log entries with a matching label
+	pub txs: Vec<TxLogEntry>,
+	/// Outputs with a matching label
+	pub outputs: Vec<OutputCommitMapping>,
+}
+
 /// Struct to encapsulate pagination metadata
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Pager {
@@ -262,3 +347,24 @@ pub struct PaymentProof {
 	#[serde(with = "dalek_ser::dalek_sig_serde")]
 	pub sender_sig: DalekSignature,
 }
+
+/// Contents of a verified epicbox transaction proof
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VerifiedTxProof {
+	/// Epicbox address the slate was sent from
+	pub from_address: String,
+	/// Epicbox address the slate was sent to
+	pub to_address: String,
+	/// Slate ID
+	pub slate_id: Uuid,
+	/// Amount
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount: u64,
+	/// Fee
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub fee: u64,
+	/// Number of inputs in the slate
+	pub num_inputs: usize,
+	/// Number of outputs in the slate
+	pub num_outputs: usize,
+}
@@ -0,0 +1,193 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ASCII armored slates. The slate is base58check encoded, split into short
+//! words and framed by a header and footer, so it survives being pasted into
+//! chat or email. The payload can be encrypted to the recipient's epicbox key
+//! with the same scheme epicbox uses for its messages, which keeps amounts
+//! and participant data private in transit. Armored slates are always
+//! compact (V4) slates, unencrypted ones in their binary form.
+
+use crate::base58::{FromBase58, ToBase58};
+use crate::epic_util::secp::key::{PublicKey, SecretKey};
+use crate::epic_util::secp::Secp256k1;
+use crate::epicbox_address::EpicboxAddress;
+use crate::message::EncryptedMessage;
+use crate::{Error, Slate, SlateVersion, VersionedSlate};
+
+/// First line of an armored slate
+pub const ARMOR_HEADER: &str = "BEGINEPICSLATE.";
+/// Last line of an armored slate
+pub const ARMOR_FOOTER: &str = "ENDEPICSLATE.";
+
+/// Version byte of the base58check payload
+const ARMOR_VERSION: u8 = 1;
+/// Payload holds the slate json as is
+const MODE_PLAIN: u8 = 0;
+/// Payload holds the sender's public key followed by the encrypted slate
+const MODE_ENCRYPTED: u8 = 1;
+/// Payload holds the binary encoded slate
+const MODE_BINARY: u8 = 2;
+/// Length of a compressed public key
+const PUBLIC_KEY_LEN: usize = 33;
+
+const WORD_LEN: usize = 15;
+const WORDS_PER_LINE: usize = 6;
+
+/// Whether the text looks like an armored slate
+pub fn is_armored(text: &str) -> bool {
+	text.trim_start().starts_with(ARMOR_HEADER)
+}
+
+/// Armor a slate. When `encryption` holds our secret key and the recipient's
+/// public key, the slate is encrypted so that only the recipient can read it.
+pub fn armor_slate(
+	slate: &Slate,
+	encryption: Option<(&SecretKey, &PublicKey)>,
+) -> Result<String, Error> {
+	let mut payload = vec![];
+	match encryption {
+		None => {
+			payload.push(MODE_BINARY);
+			payload.extend_from_slice(&slate.serialize_binary()?);
+		}
+		Some((secret_key, recipient)) => {
+			let vslate = VersionedSlate::into_version(slate.clone(), SlateVersion::V4);
+			let json = serde_json::to_string(&vslate)?;
+			let secp = Secp256k1::new();
+			let sender = PublicKey::from_secret_key(&secp, secret_key)?;
+			let destination = EpicboxAddress::new(recipient.clone(), None, None);
+			let message = EncryptedMessage::new(json, &destination, recipient, secret_key)?;
+			payload.push(MODE_ENCRYPTED);
+			payload.extend_from_slice(&sender.serialize_vec(&secp, true)[..]);
+			payload.extend_from_slice(serde_json::to_string(&message)?.as_bytes());
+		}
+	}
+
+	let encoded: Vec<char> = payload
+		.to_base58_check(vec![ARMOR_VERSION])
+		.chars()
+		.collect();
+	let words: Vec<String> = encoded
+		.chunks(WORD_LEN)
+		.map(|w| w.iter().collect())
+		.collect();
+	let lines: Vec<String> = words.chunks(WORDS_PER_LINE).map(|l| l.join(" ")).collect();
+	Ok(format!(
+		"{}\n{}\n{}",
+		ARMOR_HEADER,
+		lines.join("\n"),
+		ARMOR_FOOTER
+	))
+}
+
+/// Decode an armored slate, decrypting it with our secret key if needed.
+/// Encrypted slates also return the sender's public key, so the response can
+/// be encrypted back to them.
+pub fn unarmor_slate(
+	text: &str,
+	secret_key: Option<&SecretKey>,
+) -> Result<(Slate, Option<PublicKey>), Error> {
+	let body = text
+		.trim()
+		.strip_prefix(ARMOR_HEADER)
+		.and_then(|t| t.strip_suffix(ARMOR_FOOTER))
+		.ok_or_else(|| Error::ArmoredSlate("missing header or footer".to_owned()))?;
+	let body: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+
+	let (version, payload) = body.from_base58_check(1)?;
+	if version != [ARMOR_VERSION] {
+		return Err(Error::ArmoredSlate(format!(
+			"unsupported version {:?}",
+			version
+		)));
+	}
+
+	match payload.split_first() {
+		Some((&MODE_PLAIN, content)) => {
+			let json = String::from_utf8(content.to_vec())
+				.map_err(|_| Error::ArmoredSlate("invalid slate encoding".to_owned()))?;
+			Ok((Slate::deserialize_upgrade(&json)?, None))
+		}
+		Some((&MODE_BINARY, content)) => Ok((Slate::deserialize_upgrade_binary(content)?, None)),
+		Some((&MODE_ENCRYPTED, content)) => {
+			let secret_key =
+				secret_key.ok_or_else(|| Error::ArmoredSlate("slate is encrypted".to_owned()))?;
+			if content.len() < PUBLIC_KEY_LEN {
+				return Err(Error::ArmoredSlate("payload too short".to_owned()));
+			}
+			let secp = Secp256k1::new();
+			let sender = PublicKey::from_slice(&secp, &content[..PUBLIC_KEY_LEN])
+				.map_err(|_| Error::ArmoredSlate("invalid sender key".to_owned()))?;
+			let message: EncryptedMessage = serde_json::from_slice(&content[PUBLIC_KEY_LEN..])?;
+			let key = message.key(&sender, secret_key)?;
+			let json = message.decrypt_with_key(&key)?;
+			Ok((Slate::deserialize_upgrade(&json)?, Some(sender)))
+		}
+		Some((mode, _)) => Err(Error::ArmoredSlate(format!(
+			"unknown payload type {}",
+			mode
+		))),
+		None => Err(Error::ArmoredSlate("empty payload".to_owned())),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn key(b: u8) -> (SecretKey, PublicKey) {
+		let secp = Secp256k1::new();
+		let sk = SecretKey::from_slice(&secp, &[b; 32]).unwrap();
+		let pk = PublicKey::from_secret_key(&secp, &sk).unwrap();
+		(sk, pk)
+	}
+
+	#[test]
+	fn armor_plain_roundtrip() {
+		let slate = Slate::blank(2);
+		let armored = armor_slate(&slate, None).unwrap();
+		assert!(is_armored(&armored));
+		assert!(armored
+			.lines()
+			.all(|l| l.len() <= WORDS_PER_LINE * (WORD_LEN + 1)));
+
+		// whitespace mangled by a mail client doesn't matter
+		let mangled = armored.replace('\n', "  \r\n ");
+		let (res, sender) = unarmor_slate(&mangled, None).unwrap();
+		assert_eq!(res.id, slate.id);
+		assert!(sender.is_none());
+
+		let mut corrupted = armored.clone().into_bytes();
+		let i = ARMOR_HEADER.len() + 5;
+		corrupted[i] = if corrupted[i] == b'a' { b'b' } else { b'a' };
+		assert!(unarmor_slate(&String::from_utf8(corrupted).unwrap(), None).is_err());
+	}
+
+	#[test]
+	fn armor_encrypted_roundtrip() {
+		let slate = Slate::blank(2);
+		let (sender_sk, sender_pk) = key(1);
+		let (recipient_sk, recipient_pk) = key(2);
+		let (other_sk, _) = key(3);
+
+		let armored = armor_slate(&slate, Some((&sender_sk, &recipient_pk))).unwrap();
+		assert!(unarmor_slate(&armored, None).is_err());
+		assert!(unarmor_slate(&armored, Some(&other_sk)).is_err());
+
+		let (res, sender) = unarmor_slate(&armored, Some(&recipient_sk)).unwrap();
+		assert_eq!(res.id, slate.id);
+		assert_eq!(sender, Some(sender_pk));
+	}
+}
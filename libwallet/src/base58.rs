@@ -79,78 +79,31 @@ impl ToBase58 for [u8] {
 
 impl FromBase58 for str {
 	fn from_base58(&self) -> Result<Vec<u8>, Error> {
-		let mut bin = [0u8; 132];
-		let mut out = [0u32; (132 + 3) / 4];
-		let bytesleft = (bin.len() % 4) as u8;
-		let zeromask = match bytesleft {
-			0 => 0u32,
-			_ => 0xffffffff << (bytesleft * 8),
-		};
-
-		let zcount = self.chars().take_while(|x| *x == '1').count();
-		let mut i = zcount;
-		let b58: Vec<u8> = self.bytes().collect();
-
-		while i < self.len() {
-			if (b58[i] & 0x80) != 0 {
-				// High-bit set on invalid digit
-				Err(Error::InvalidBase58Character(b58[i] as char, i))?;
-			}
-
-			if B58_DIGITS_MAP[b58[i] as usize] == -1 {
-				// // Invalid base58 digit
-				Err(Error::InvalidBase58Character(b58[i] as char, i))?;
-			}
-
-			let mut c = B58_DIGITS_MAP[b58[i] as usize] as u64;
-			let mut j = out.len();
-			while j != 0 {
-				j -= 1;
-				let t = out[j] as u64 * 58 + c;
-				c = (t & 0x3f00000000) >> 32;
-				out[j] = (t & 0xffffffff) as u32;
+		let zcount = self.bytes().take_while(|x| *x == b'1').count();
+
+		// little endian base256 accumulator, so input of any length decodes
+		let mut bin: Vec<u8> = Vec::with_capacity(self.len() * 733 / 1000 + 1);
+		for (i, c) in self.bytes().enumerate().skip(zcount) {
+			if (c & 0x80) != 0 || B58_DIGITS_MAP[c as usize] == -1 {
+				// Invalid base58 digit
+				Err(Error::InvalidBase58Character(c as char, i))?;
 			}
 
-			if c != 0 {
-				// Output number too big (carry to the next int32)
-				Err(Error::InvalidBase58Length)?;
+			let mut carry = B58_DIGITS_MAP[c as usize] as u32;
+			for b in bin.iter_mut() {
+				carry += *b as u32 * 58;
+				*b = (carry & 0xff) as u8;
+				carry >>= 8;
 			}
-
-			if (out[0] & zeromask) != 0 {
-				// Output number too big (last int32 filled too far)
-				Err(Error::InvalidBase58Length)?;
+			while carry != 0 {
+				bin.push((carry & 0xff) as u8);
+				carry >>= 8;
 			}
-
-			i += 1;
 		}
 
-		let mut i = 1;
-		let mut j = 0;
-
-		bin[0] = match bytesleft {
-			3 => ((out[0] & 0xff0000) >> 16) as u8,
-			2 => ((out[0] & 0xff00) >> 8) as u8,
-			1 => {
-				j = 1;
-				(out[0] & 0xff) as u8
-			}
-			_ => {
-				i = 0;
-				bin[0]
-			}
-		};
-
-		while j < out.len() {
-			bin[i] = ((out[j] >> 0x18) & 0xff) as u8;
-			bin[i + 1] = ((out[j] >> 0x10) & 0xff) as u8;
-			bin[i + 2] = ((out[j] >> 8) & 0xff) as u8;
-			bin[i + 3] = ((out[j] >> 0) & 0xff) as u8;
-			i += 4;
-			j += 1;
-		}
-
-		let leading_zeros = bin.iter().take_while(|x| **x == 0).count();
-		Ok(bin[leading_zeros - zcount..].to_vec())
+		bin.extend(std::iter::repeat(0).take(zcount));
+		bin.reverse();
+		Ok(bin)
 	}
 
 	fn from_base58_check(&self, version_bytes: usize) -> Result<(Vec<u8>, Vec<u8>), Error> {
@@ -180,3 +133,25 @@ fn double_sha256(payload: &[u8]) -> Vec<u8> {
 	let hash = hasher.finalize();
 	hash.to_vec()
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn base58_roundtrip() {
+		let short = vec![0u8, 0, 1, 2, 3, 255];
+		assert_eq!(short.to_base58().from_base58().unwrap(), short);
+
+		// longer than any key or address
+		let long: Vec<u8> = (0..2048).map(|i| (i * 7 % 256) as u8).collect();
+		assert_eq!(long.to_base58().from_base58().unwrap(), long);
+
+		let checked = long.to_base58_check(vec![1]);
+		let (version, payload) = checked.from_base58_check(1).unwrap();
+		assert_eq!(version, vec![1]);
+		assert_eq!(payload, long);
+
+		assert!("0OIl".from_base58().is_err());
+	}
+}
@@ -245,6 +245,10 @@ pub enum Error {
 	#[error("Payment Proof parsing error: {0}")]
 	PaymentProofParsing(String),
 
+	/// Storing, retrieving or verifying an epicbox transaction proof
+	#[error("Tx Proof error: {0}")]
+	TxProof(String),
+
 	/// Decoding OnionV3 addresses to payment proof addresses
 	#[error("Proof Address decoding: {0}")]
 	AddressDecoding(String),
@@ -253,6 +257,14 @@ pub enum Error {
 	#[error("Transaction Expired")]
 	TransactionExpired,
 
+	/// Transaction kernel is height locked above the chain tip
+	#[error("Transaction is locked until height {0}, the chain is at {1}")]
+	TransactionLocked(u64, u64),
+
+	/// Lock height of a transaction is unusable
+	#[error("Invalid lock height: {0}")]
+	InvalidLockHeight(String),
+
 	/// From sqlite::Error
 	#[error("SQLite Error: {0}")]
 	#[serde(skip)]
@@ -288,6 +300,17 @@ pub enum Error {
 	#[error("Could not parse '{0}' to a epicbox address")]
 	EpicboxAddressParsingError(String),
 
+	#[error("Armored slate error: {0}")]
+	ArmoredSlate(String),
+
+	/// Two nodes returned conflicting answers to the same query
+	#[error("Node mismatch: {0}")]
+	NodeMismatch(String),
+
+	/// A transaction breaks the spending policy of the wallet
+	#[error("Spending policy violation: {0}")]
+	PolicyViolation(String),
+
 	/// Other
 	#[error("Generic error: {0}")]
 	GenericError(String),
@@ -21,8 +21,12 @@
 #![deny(unused_mut)]
 #![warn(missing_docs)]
 
+pub mod backoff;
 pub mod keys;
+pub mod outbox;
+pub mod policy;
 pub mod scan;
 pub mod selection;
 pub mod tx;
 pub mod updater;
+pub mod webhooks;
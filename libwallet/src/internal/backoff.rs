@@ -0,0 +1,38 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exponential backoff for whatever the wallet retries
+
+/// Seconds to wait after `attempts` failed attempts: `base_secs` after the
+/// first one, doubling with every further failure up to `max_secs`
+pub fn backoff_secs(base_secs: u64, max_secs: u64, attempts: u32) -> u64 {
+    let exp = attempts.saturating_sub(1).min(16);
+    (base_secs << exp).min(max_secs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn doubles_up_to_max() {
+        assert_eq!(backoff_secs(10, 3600, 0), 10);
+        assert_eq!(backoff_secs(10, 3600, 1), 10);
+        assert_eq!(backoff_secs(10, 3600, 2), 20);
+        assert_eq!(backoff_secs(10, 3600, 3), 40);
+        assert_eq!(backoff_secs(10, 3600, 9), 2560);
+        assert_eq!(backoff_secs(10, 3600, 10), 3600);
+        assert_eq!(backoff_secs(10, 3600, u32::MAX), 3600);
+    }
+}
@@ -0,0 +1,320 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Outbox of slates sent over epicbox. A slate is queued in the wallet
+//! database before it's sent, so it isn't lost when the relay can't be
+//! reached. Until the relay accepts it, the listener sends it again with
+//! exponential backoff, and gives up once the slate's TTL is reached, or
+//! after `MAX_AGE_DAYS` for slates without one, or when its transaction was
+//! cancelled. The recipient confirms a slate to the relay with `Made`, which
+//! isn't passed on to the sender, so a slate counts as acknowledged once the
+//! relay accepted it. Slates nothing more is sent for are kept for
+//! `KEEP_DAYS` to be looked up, then removed.
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::epic_keychain::Keychain;
+use crate::epic_util::secp::key::SecretKey;
+use crate::error::Error;
+use crate::internal::backoff::backoff_secs;
+use crate::slate::Slate;
+use crate::types::{DeliveryStatus, NodeClient, OutboxEntry, TxLogEntryType, WalletBackend};
+
+/// Delay before the first retransmission of a slate
+const RETRY_BASE_SECS: u64 = 30;
+/// Longest delay between two attempts
+const RETRY_MAX_SECS: u64 = 3600;
+/// Age at which slates without a TTL are given up on
+const MAX_AGE_DAYS: i64 = 7;
+/// How long slates are kept once nothing more is sent for them
+const KEEP_DAYS: i64 = 7;
+
+/// Delay before the next attempt of a slate sent `attempts` times
+pub fn retry_delay(attempts: u32) -> Duration {
+    Duration::seconds(backoff_secs(RETRY_BASE_SECS, RETRY_MAX_SECS, attempts) as i64)
+}
+
+/// State of a slate after an attempt to send it, which either tells whether
+/// the relay confirmed it or why it failed. A slate never goes back to an
+/// earlier state.
+pub fn status_after_attempt(
+    status: DeliveryStatus,
+    result: &Result<bool, Error>,
+) -> DeliveryStatus {
+    match (status, result) {
+        (s, _) if s.is_final() => s,
+        (_, Ok(true)) => DeliveryStatus::Acknowledged,
+        (DeliveryStatus::Queued, Ok(false)) => DeliveryStatus::Posted,
+        (s, _) => s,
+    }
+}
+
+/// Whether the slate is past its TTL at the given tip, or too old to be
+/// sent at `now` if it has none
+pub fn is_expired(entry: &OutboxEntry, tip_height: u64, now: DateTime<Utc>) -> bool {
+    match entry.ttl_cutoff_height {
+        Some(h) => tip_height >= h,
+        None => now - entry.created >= Duration::days(MAX_AGE_DAYS),
+    }
+}
+
+/// Whether the slate was kept long enough at `now` since it was last sent
+pub fn is_prunable(entry: &OutboxEntry, now: DateTime<Utc>) -> bool {
+    let last = entry.last_attempt.unwrap_or(entry.created);
+    entry.status.is_final() && now - last >= Duration::days(KEEP_DAYS)
+}
+
+/// Queue a slate about to be sent to `dest`, as the serialized payload
+pub fn queue_slate<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    slate: &Slate,
+    dest: &str,
+    payload: String,
+) -> Result<(), Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let now = Utc::now();
+    let mut batch = wallet.batch(keychain_mask)?;
+    batch.save_outbox_entry(OutboxEntry {
+        slate_id: slate.id,
+        dest: dest.to_owned(),
+        payload,
+        status: DeliveryStatus::Queued,
+        attempts: 0,
+        created: now,
+        last_attempt: None,
+        next_attempt: now,
+        ttl_cutoff_height: slate.ttl_cutoff_height,
+        last_error: None,
+    })?;
+    batch.commit()?;
+    Ok(())
+}
+
+/// Record the outcome of an attempt to send a slate, and when to send it
+/// again
+pub fn record_attempt<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    slate_id: &Uuid,
+    result: Result<bool, Error>,
+) -> Result<(), Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let mut entry = match wallet.outbox_iter().find(|e| &e.slate_id == slate_id) {
+        Some(e) => e,
+        None => return Ok(()),
+    };
+    let now = Utc::now();
+    entry.attempts += 1;
+    entry.last_attempt = Some(now);
+    entry.next_attempt = now + retry_delay(entry.attempts);
+    entry.status = status_after_attempt(entry.status, &result);
+    entry.last_error = match result {
+        Ok(_) => None,
+        Err(e) => {
+            warn!(
+                "Unable to send slate {} to {}, retrying in {}s: {}",
+                entry.slate_id,
+                entry.dest,
+                retry_delay(entry.attempts).num_seconds(),
+                e
+            );
+            Some(format!("{}", e))
+        }
+    };
+    let mut batch = wallet.batch(keychain_mask)?;
+    batch.save_outbox_entry(entry)?;
+    batch.commit()?;
+    Ok(())
+}
+
+/// Record that the recipient's response to a slate came back
+pub fn record_response<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    slate_id: &Uuid,
+) -> Result<(), Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let mut entry = match wallet.outbox_iter().find(|e| &e.slate_id == slate_id) {
+        Some(e) => e,
+        None => return Ok(()),
+    };
+    if entry.status == DeliveryStatus::ResponseReceived {
+        return Ok(());
+    }
+    entry.status = DeliveryStatus::ResponseReceived;
+    let mut batch = wallet.batch(keychain_mask)?;
+    batch.save_outbox_entry(entry)?;
+    batch.commit()?;
+    Ok(())
+}
+
+/// Give up on slates past their TTL at the given tip, or whose transaction
+/// was cancelled, returning how many were given up on
+pub fn expire_entries<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    tip_height: u64,
+) -> Result<usize, Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let now = Utc::now();
+    let cancelled: HashSet<Uuid> = wallet
+        .tx_log_iter()
+        .filter(|tx| tx.tx_type == TxLogEntryType::TxSentCancelled)
+        .filter_map(|tx| tx.tx_slate_id)
+        .collect();
+    let expired: Vec<OutboxEntry> = wallet
+        .outbox_iter()
+        .filter(|e| !e.status.is_final())
+        .filter(|e| is_expired(e, tip_height, now) || cancelled.contains(&e.slate_id))
+        .collect();
+    if expired.is_empty() {
+        return Ok(0);
+    }
+    let count = expired.len();
+    let mut batch = wallet.batch(keychain_mask)?;
+    for mut entry in expired {
+        debug!("Giving up on slate {} to {}", entry.slate_id, entry.dest);
+        entry.status = DeliveryStatus::Expired;
+        batch.save_outbox_entry(entry)?;
+    }
+    batch.commit()?;
+    Ok(count)
+}
+
+/// Remove the slates kept long enough since they were delivered or given up
+/// on, returning how many were removed
+pub fn prune_entries<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    keychain_mask: Option<&SecretKey>,
+) -> Result<usize, Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let now = Utc::now();
+    let pruned: Vec<Uuid> = wallet
+        .outbox_iter()
+        .filter(|e| is_prunable(e, now))
+        .map(|e| e.slate_id)
+        .collect();
+    if pruned.is_empty() {
+        return Ok(0);
+    }
+    let mut batch = wallet.batch(keychain_mask)?;
+    for slate_id in pruned.iter() {
+        batch.delete_outbox_entry(slate_id)?;
+    }
+    batch.commit()?;
+    Ok(pruned.len())
+}
+
+/// Slates due to be sent, oldest first
+pub fn due_entries<'a, T: ?Sized, C, K>(wallet: &mut T) -> Vec<OutboxEntry>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let now = Utc::now();
+    let mut due: Vec<OutboxEntry> = wallet
+        .outbox_iter()
+        .filter(|e| !e.status.is_final() && e.next_attempt <= now)
+        .collect();
+    due.sort_by_key(|e| e.next_attempt);
+    due
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn status_and_backoff() {
+        let failed = Err(Error::GenericError("relay unreachable".to_owned()));
+        let queued = DeliveryStatus::Queued;
+        assert_eq!(status_after_attempt(queued, &failed), queued);
+        assert_eq!(
+            status_after_attempt(queued, &Ok(false)),
+            DeliveryStatus::Posted
+        );
+        assert_eq!(
+            status_after_attempt(queued, &Ok(true)),
+            DeliveryStatus::Acknowledged
+        );
+        // nothing more is sent once the relay acknowledged it
+        let acked = DeliveryStatus::Acknowledged;
+        assert!(acked.is_final());
+        assert_eq!(status_after_attempt(acked, &failed), acked);
+        assert_eq!(status_after_attempt(acked, &Ok(false)), acked);
+        let responded = DeliveryStatus::ResponseReceived;
+        assert_eq!(status_after_attempt(responded, &Ok(true)), responded);
+
+        assert_eq!(retry_delay(1).num_seconds(), 30);
+        assert_eq!(retry_delay(3).num_seconds(), 120);
+        assert_eq!(retry_delay(40).num_seconds(), 3600);
+    }
+
+    #[test]
+    fn expiry_and_pruning() {
+        let now = Utc::now();
+        let mut entry = OutboxEntry {
+            slate_id: Uuid::new_v4(),
+            dest: "epicbox@epicbox.epiccash.com".to_owned(),
+            payload: "{}".to_owned(),
+            status: DeliveryStatus::Queued,
+            attempts: 0,
+            created: now,
+            last_attempt: None,
+            next_attempt: now,
+            ttl_cutoff_height: None,
+            last_error: None,
+        };
+        // without a TTL, slates are given up on once old enough
+        let later = now + Duration::days(MAX_AGE_DAYS);
+        assert!(!is_expired(&entry, u64::MAX, now));
+        assert!(is_expired(&entry, 0, later));
+        entry.ttl_cutoff_height = Some(100);
+        assert!(!is_expired(&entry, 99, later));
+        assert!(is_expired(&entry, 100, now));
+
+        // only slates nothing more is sent for are removed, once kept long enough
+        let later = now + Duration::days(KEEP_DAYS);
+        assert!(!is_prunable(&entry, later));
+        entry.status = DeliveryStatus::Acknowledged;
+        assert!(!is_prunable(&entry, now));
+        assert!(is_prunable(&entry, later));
+        entry.last_attempt = Some(later);
+        assert!(!is_prunable(&entry, later));
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Spending policy. Transactions are checked against the limits of the wallet
+//! configuration when they are created, when an invoice is paid and again when
+//! they are finalized, so nothing that goes through the owner API can get
+//! around them. What an account has sent is taken from its transaction log.
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::api_impl::types::InitTxArgs;
+use crate::config::SpendPolicy;
+use crate::epic_keychain::{Identifier, Keychain};
+use crate::error::Error;
+use crate::slate::Slate;
+use crate::types::{NodeClient, TxLogEntry, TxLogEntryType, WalletBackend};
+
+/// Amount a logged transaction sends out of the wallet, fee excluded. None
+/// if it doesn't send anything, or was cancelled.
+fn sent_amount(tx: &TxLogEntry) -> Option<u64> {
+    match tx.tx_type {
+        TxLogEntryType::TxSent | TxLogEntryType::TxSentMempool | TxLogEntryType::TxSentCreated => {
+            Some(
+                tx.amount_debited
+                    .saturating_sub(tx.amount_credited)
+                    .saturating_sub(tx.fee.unwrap_or(0)),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// Amount the account sent since `since`, leaving out the transaction of
+/// the given slate
+fn sent_since<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    parent_key_id: &Identifier,
+    since: DateTime<Utc>,
+    exclude: Option<Uuid>,
+) -> u64
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    wallet
+        .tx_log_iter()
+        .filter(|tx| &tx.parent_key_id == parent_key_id && tx.creation_ts >= since)
+        .filter(|tx| exclude.is_none() || tx.tx_slate_id != exclude)
+        .filter_map(|tx| sent_amount(&tx))
+        .sum()
+}
+
+fn check_amount(policy: &SpendPolicy, amount: u64) -> Result<(), Error> {
+    match policy.max_tx_amount {
+        Some(max) if amount > max => Err(Error::PolicyViolation(format!(
+            "amount {} is above the limit of {} per transaction",
+            amount, max
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn check_velocity<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    policy: &SpendPolicy,
+    parent_key_id: &Identifier,
+    amount: u64,
+    exclude: Option<Uuid>,
+) -> Result<(), Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let caps = [
+        ("daily", policy.daily_limit, Duration::days(1)),
+        ("weekly", policy.weekly_limit, Duration::days(7)),
+    ];
+    for (name, limit, window) in caps.iter() {
+        if let Some(limit) = limit {
+            let sent = sent_since(wallet, parent_key_id, Utc::now() - *window, exclude);
+            if sent.saturating_add(amount) > *limit {
+                return Err(Error::PolicyViolation(format!(
+                    "amount {} would exceed the {} limit of {}, {} already sent",
+                    amount, name, limit, sent
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_destination(policy: &SpendPolicy, destination: Option<&str>) -> Result<(), Error> {
+    let allowed = match policy.allowed_destinations {
+        Some(ref a) => a,
+        None => return Ok(()),
+    };
+    match destination {
+        Some(d) if allowed.iter().any(|a| a == d) => Ok(()),
+        Some(d) => Err(Error::PolicyViolation(format!(
+            "destination {} is not allowed",
+            d
+        ))),
+        None => Err(Error::PolicyViolation(
+            "transactions must be sent to an allowed destination".to_owned(),
+        )),
+    }
+}
+
+fn check_ttl_blocks(policy: &SpendPolicy, ttl_blocks: Option<u64>) -> Result<(), Error> {
+    match (policy.min_ttl_blocks, ttl_blocks) {
+        (Some(min), Some(ttl)) if ttl < min => Err(Error::PolicyViolation(format!(
+            "ttl_blocks {} is below the minimum of {}",
+            ttl, min
+        ))),
+        (Some(min), None) => Err(Error::PolicyViolation(format!(
+            "ttl_blocks of at least {} is required",
+            min
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Account the transaction arguments spend from
+fn source_account<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    src_acct_name: &Option<String>,
+) -> Result<Identifier, Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    Ok(match src_acct_name {
+        Some(name) => match wallet.get_acct_path(name.to_owned())? {
+            Some(p) => p.path,
+            None => wallet.parent_key_id(),
+        },
+        None => wallet.parent_key_id(),
+    })
+}
+
+/// Check a transaction about to be created by `init_send_tx`, to be sent to
+/// `dest`. Destinations are compared after address book names were resolved,
+/// as contacts can be changed through the owner API as well.
+pub fn check_send_policy<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    policy: &SpendPolicy,
+    args: &InitTxArgs,
+    dest: Option<&str>,
+) -> Result<(), Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    if !policy.is_enabled() || args.estimate_only == Some(true) {
+        return Ok(());
+    }
+    let parent_key_id = source_account(wallet, &args.src_acct_name)?;
+    check_amount(policy, args.amount)?;
+    check_destination(policy, dest)?;
+    check_ttl_blocks(policy, args.ttl_blocks)?;
+    check_velocity(wallet, policy, &parent_key_id, args.amount, None)
+}
+
+/// Check an invoice about to be paid by `process_invoice_tx`. Invoices have
+/// no destination, so they can't be paid when destinations are restricted.
+pub fn check_invoice_policy<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    policy: &SpendPolicy,
+    slate: &Slate,
+    args: &InitTxArgs,
+) -> Result<(), Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    if !policy.is_enabled() {
+        return Ok(());
+    }
+    let parent_key_id = source_account(wallet, &args.src_acct_name)?;
+    let height = wallet.last_confirmed_height()?;
+    let ttl_blocks = slate
+        .ttl_cutoff_height
+        .map(|cutoff| cutoff.saturating_sub(height));
+    check_amount(policy, slate.amount)?;
+    check_destination(policy, None)?;
+    check_ttl_blocks(policy, ttl_blocks)?;
+    check_velocity(wallet, policy, &parent_key_id, slate.amount, Some(slate.id))
+}
+
+/// Check a transaction about to be finalized. The limits may have been lowered,
+/// or other transactions sent, since it was created. Its destination was
+/// checked then, and its TTL can't have changed.
+pub fn check_finalize_policy<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    policy: &SpendPolicy,
+    slate: &Slate,
+) -> Result<(), Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    if !policy.is_enabled() {
+        return Ok(());
+    }
+    let parent_key_id = wallet
+        .tx_log_iter()
+        .find(|tx| tx.tx_slate_id == Some(slate.id) && sent_amount(tx).is_some())
+        .map(|tx| tx.parent_key_id)
+        .unwrap_or_else(|| wallet.parent_key_id());
+    check_amount(policy, slate.amount)?;
+    check_velocity(wallet, policy, &parent_key_id, slate.amount, Some(slate.id))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn limits() {
+        let mut policy = SpendPolicy::default();
+        assert!(!policy.is_enabled());
+        assert!(check_amount(&policy, u64::MAX).is_ok());
+        assert!(check_destination(&policy, None).is_ok());
+        assert!(check_ttl_blocks(&policy, None).is_ok());
+
+        policy.max_tx_amount = Some(100);
+        policy.allowed_destinations = Some(vec!["https://exchange.example".to_owned()]);
+        policy.min_ttl_blocks = Some(60);
+        assert!(policy.is_enabled());
+        assert!(check_amount(&policy, 100).is_ok());
+        assert!(check_amount(&policy, 101).is_err());
+        assert!(check_destination(&policy, Some("https://exchange.example")).is_ok());
+        assert!(check_destination(&policy, Some("https://elsewhere.example")).is_err());
+        assert!(check_destination(&policy, None).is_err());
+        assert!(check_ttl_blocks(&policy, Some(60)).is_ok());
+        assert!(check_ttl_blocks(&policy, Some(59)).is_err());
+        assert!(check_ttl_blocks(&policy, None).is_err());
+    }
+}
@@ -27,9 +27,11 @@ use crate::{wallet_lock, Error, OutputCommitMapping};
 use crate::internal::scan::api::CommitmentWrapper;
 use epic_wallet_util::epic_api as api;
 use std::cmp;
-use std::collections::HashMap;
-use std::sync::mpsc::Sender;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
 use std::sync::Arc;
+use std::thread;
 
 /// Utility struct for return values from below
 #[derive(Debug, Clone)]
@@ -52,6 +54,36 @@ struct OutputResult {
 	pub is_coinbase: bool,
 }
 
+impl From<&OutputResult> for ScannedOutput {
+	fn from(o: &OutputResult) -> ScannedOutput {
+		ScannedOutput {
+			commit: o.commit,
+			key_id: o.key_id.clone(),
+			n_child: o.n_child,
+			mmr_index: o.mmr_index,
+			value: o.value,
+			height: o.height,
+			lock_height: o.lock_height,
+			is_coinbase: o.is_coinbase,
+		}
+	}
+}
+
+impl From<ScannedOutput> for OutputResult {
+	fn from(o: ScannedOutput) -> OutputResult {
+		OutputResult {
+			commit: o.commit,
+			key_id: o.key_id,
+			n_child: o.n_child,
+			mmr_index: o.mmr_index,
+			value: o.value,
+			height: o.height,
+			lock_height: o.lock_height,
+			is_coinbase: o.is_coinbase,
+		}
+	}
+}
+
 #[derive(Debug, Clone)]
 /// Collect stats in case we want to just output a single tx log entry
 /// for restored non-coinbase outputs
@@ -143,56 +175,322 @@ where
 	Ok(wallet_outputs)
 }
 
-/// Collect outputs from the chain, starting at a given start and end index
-fn collect_chain_outputs<'a, C, K>(
+/// Outputs as returned by the node: commit, proof, is_coinbase, height and
+/// mmr_index
+type ChainOutput = (pedersen::Commitment, pedersen::RangeProof, bool, u64, u64);
+
+/// Outputs of a window of PMMR indices, identified by its position in the
+/// scan: window, last index of the window, number of outputs checked and
+/// outputs belonging to the wallet
+type ScannedWindow = (u64, u64, usize, Vec<OutputResult>);
+
+/// Fetch all outputs within the PMMR index window `[start, end]`
+fn fetch_window<C>(
+	client: &C,
+	start: u64,
+	end: u64,
+	batch_size: u64,
+) -> Result<Vec<ChainOutput>, Error>
+where
+	C: NodeClient,
+{
+	let mut outputs = vec![];
+	let mut start_index = start;
+	loop {
+		let (highest_index, last_retrieved_index, mut batch) =
+			client.get_outputs_by_pmmr_index(start_index, Some(end), batch_size)?;
+		let done = batch.is_empty()
+			|| last_retrieved_index >= end
+			|| highest_index <= last_retrieved_index;
+		outputs.append(&mut batch);
+		if done {
+			break;
+		}
+		start_index = last_retrieved_index + 1;
+	}
+	Ok(outputs)
+}
+
+/// Runs `fetch` for windows `0..windows` on `fetch_threads` threads, hands
+/// what it returns to `process` on `workers` threads, and passes the results
+/// to `apply` in window order, a run of consecutive windows at a time. No more
+/// than `max_ahead` windows are fetched ahead of the next one to apply, so a
+/// slow window can't make the others pile up in memory. Stops at the first
+/// error, without starting any further window.
+fn run_windows<T, U, F, P, A>(
+	windows: u64,
+	fetch_threads: usize,
+	workers: usize,
+	max_ahead: usize,
+	fetch: F,
+	process: P,
+	mut apply: A,
+) -> Result<(), Error>
+where
+	T: Send,
+	U: Send,
+	F: Fn(u64) -> Result<T, Error> + Sync,
+	P: Fn(u64, T) -> Result<U, Error> + Sync,
+	A: FnMut(Vec<(u64, U)>) -> Result<(), Error>,
+{
+	let max_ahead = cmp::max(max_ahead, 1);
+	let next_window = AtomicU64::new(0);
+	let abort = AtomicBool::new(false);
+	thread::scope(|s| {
+		// a permit is taken for each window fetched, and handed back once
+		// the window has been applied
+		let (permit_send, permit_recv) = mpsc::sync_channel::<()>(max_ahead);
+		for _ in 0..max_ahead {
+			let _ = permit_send.send(());
+		}
+		let permit_recv = Arc::new(Mutex::new(permit_recv));
+		let (fetched_send, fetched_recv) = mpsc::sync_channel::<(u64, T)>(fetch_threads);
+		let fetched_recv = Arc::new(Mutex::new(fetched_recv));
+		let (result_send, result_recv) = mpsc::sync_channel::<Result<(u64, U), Error>>(max_ahead);
+
+		for _ in 0..fetch_threads {
+			let permit_recv = permit_recv.clone();
+			let fetched_send = fetched_send.clone();
+			let result_send = result_send.clone();
+			let (next_window, abort, fetch) = (&next_window, &abort, &fetch);
+			s.spawn(move || loop {
+				let permit = permit_recv.lock().recv();
+				if permit.is_err() || abort.load(Ordering::Relaxed) {
+					break;
+				}
+				let window = next_window.fetch_add(1, Ordering::SeqCst);
+				if window >= windows {
+					break;
+				}
+				match fetch(window) {
+					Ok(fetched) => {
+						if fetched_send.send((window, fetched)).is_err() {
+							break;
+						}
+					}
+					Err(e) => {
+						let _ = result_send.send(Err(e));
+						break;
+					}
+				}
+			});
+		}
+		drop(fetched_send);
+
+		for _ in 0..workers {
+			let fetched_recv = fetched_recv.clone();
+			let result_send = result_send.clone();
+			let process = &process;
+			s.spawn(move || loop {
+				let next = fetched_recv.lock().recv();
+				let (window, fetched) = match next {
+					Ok(f) => f,
+					Err(_) => break,
+				};
+				let res = process(window, fetched).map(|r| (window, r));
+				if result_send.send(res).is_err() {
+					break;
+				}
+			});
+		}
+		drop(result_send);
+
+		let mut apply_results = || -> Result<(), Error> {
+			let mut pending = BTreeMap::new();
+			let mut next_expected = 0;
+			while next_expected < windows {
+				let (window, result) = match result_recv.recv() {
+					Ok(r) => r?,
+					Err(_) => {
+						return Err(Error::GenericError(
+							"Scan workers stopped unexpectedly".to_owned(),
+						))
+					}
+				};
+				pending.insert(window, result);
+				let mut ready = vec![];
+				while let Some(result) = pending.remove(&next_expected) {
+					ready.push((next_expected, result));
+					next_expected += 1;
+				}
+				if ready.is_empty() {
+					continue;
+				}
+				let applied = ready.len();
+				apply(ready)?;
+				for _ in 0..applied {
+					let _ = permit_send.send(());
+				}
+			}
+			Ok(())
+		};
+		let res = apply_results();
+		// stop the fetchers, the channels close as this scope returns and
+		// every thread blocked on one of them exits
+		abort.store(true, Ordering::Relaxed);
+		res
+	})
+}
+
+/// Collect outputs from the chain, continuing from the checkpoint up to its
+/// end index, into `outputs`. The range is split into windows of `batch_size`
+/// indices, which are fetched by several concurrent node requests and handed
+/// to a pool of workers rewinding their range proofs. Results are applied to
+/// the checkpoint in order, and the checkpoint saved as it advances along
+/// with the outputs found since it was last saved.
+fn collect_chain_outputs<'a, L, C, K>(
+	wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	keychain_mask: Option<&SecretKey>,
 	keychain: &K,
 	client: C,
-	start_index: u64,
-	end_index: Option<u64>,
+	checkpoint: &mut ScanCheckpoint,
+	outputs: &mut Vec<ScannedOutput>,
 	status_send_channel: &Option<Sender<StatusMessage>>,
-) -> Result<(Vec<OutputResult>, u64), Error>
+) -> Result<(), Error>
 where
+	L: WalletLCProvider<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let batch_size = 1000;
-	let start_index_stat = start_index;
-	let mut start_index = start_index;
-	let mut result_vec: Vec<OutputResult> = vec![];
-	let last_retrieved_return_index;
-	loop {
-		let (highest_index, last_retrieved_index, outputs) =
-			client.get_outputs_by_pmmr_index(start_index, end_index, batch_size)?;
+	let config = {
+		let mut w_lock = wallet_inst.lock();
+		w_lock.lc_provider()?.scan_config()
+	};
+	let batch_size = cmp::max(config.batch_size, 1);
+	let start_index_stat = checkpoint.start_pmmr_index;
+	let first_index = checkpoint.next_pmmr_index();
+	let end_index = checkpoint.end_pmmr_index;
+	if first_index > end_index {
+		return Ok(());
+	}
+	let windows = (end_index - first_index) / batch_size + 1;
+	let window_end = |window: u64| cmp::min(first_index + (window + 1) * batch_size - 1, end_index);
+	let perc_complete = move |index: u64| {
+		let range = cmp::max(end_index.saturating_sub(start_index_stat), 1);
+		let progress = index.saturating_sub(start_index_stat);
+		cmp::min(progress * 100 / range, 99) as u8
+	};
 
-		let range = highest_index as f64 - start_index_stat as f64;
-		let progress = last_retrieved_index as f64 - start_index_stat as f64;
-		let perc_complete = cmp::min(((progress / range) * 100.0) as u8, 99);
+	let fetch_threads = cmp::max(config.fetch_threads, 1);
+	let workers = cmp::max(config.rewind_workers(), 1);
+	run_windows(
+		windows,
+		fetch_threads,
+		workers,
+		2 * (fetch_threads + workers),
+		|window| {
+			let start = first_index + window * batch_size;
+			fetch_window(&client, start, window_end(window), batch_size)
+		},
+		|window, fetched: Vec<ChainOutput>| {
+			let count = fetched.len();
+			let found = identify_utxo_outputs(
+				keychain,
+				fetched,
+				status_send_channel,
+				perc_complete(window_end(window)),
+			)?;
+			Ok((count, found))
+		},
+		|ready| {
+			let mut checked = 0;
+			let mut found_now = vec![];
+			for (window, (count, found)) in ready {
+				found_now.extend(found.iter().map(ScannedOutput::from));
+				checkpoint.last_pmmr_index = Some(window_end(window));
+				checked += count;
+			}
+			checkpoint.outputs_found += found_now.len();
+			let last_index = checkpoint.last_pmmr_index.unwrap_or(first_index);
+			let msg = format!(
+				"Checked {} outputs (up to PMMR index {}, highest: {}).",
+				checked, last_index, end_index,
+			);
+			if let Some(ref s) = status_send_channel {
+				let _ = s.send(StatusMessage::Scanning(msg, perc_complete(last_index)));
+			}
+			wallet_lock!(wallet_inst, w);
+			let mut batch = w.batch(keychain_mask)?;
+			for o in found_now.iter() {
+				batch.save_scanned_output(o.clone())?;
+			}
+			batch.save_scan_checkpoint(checkpoint.clone())?;
+			batch.commit()?;
+			outputs.append(&mut found_now);
+			Ok(())
+		},
+	)
+}
 
-		let msg = format!(
-			"Checking {} outputs (from PMMR index {} to {}, highest: {}).",
-			outputs.len(),
-			start_index,
-			last_retrieved_index,
-			highest_index,
-		);
-		if let Some(ref s) = status_send_channel {
-			let _ = s.send(StatusMessage::Scanning(msg, perc_complete));
+/// Checkpoint to continue a scan from, with the outputs found before it.
+/// An earlier checkpoint is only picked up if it covers the requested start
+/// height and was made with this keychain, otherwise it is discarded. Outputs
+/// it found that have been spent since are dropped.
+fn resume_checkpoint<'a, L, C, K>(
+	wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	keychain: &K,
+	client: &C,
+	start_height: u64,
+	end_height: u64,
+	pmmr_range: (u64, u64),
+	status_send_channel: &Option<Sender<StatusMessage>>,
+) -> Result<(ScanCheckpoint, Vec<ScannedOutput>), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let (checkpoint, mut outputs) = {
+		wallet_lock!(wallet_inst, w);
+		let outputs: Vec<_> = w.scanned_outputs().collect();
+		(w.scan_checkpoint()?, outputs)
+	};
+	let mut same_keychain = true;
+	for o in outputs.iter() {
+		let commit = keychain.commit(o.value, &o.key_id, &SwitchCommitmentType::Regular)?;
+		if commit != o.commit {
+			same_keychain = false;
+			break;
 		}
+	}
+	let mut checkpoint = match checkpoint {
+		Some(c) if c.start_height <= start_height && same_keychain => c,
+		_ => {
+			if !same_keychain {
+				warn!("Discarding scan checkpoint made with a different keychain");
+			}
+			// clear whatever an earlier scan left behind
+			wallet_lock!(wallet_inst, w);
+			let mut batch = w.batch_no_mask()?;
+			batch.delete_scan_checkpoint()?;
+			batch.commit()?;
+			let checkpoint = ScanCheckpoint::new(start_height, end_height, pmmr_range);
+			return Ok((checkpoint, vec![]));
+		}
+	};
 
-		result_vec.append(&mut identify_utxo_outputs(
-			keychain,
-			outputs.clone(),
-			status_send_channel,
-			perc_complete as u8,
-		)?);
+	if !outputs.is_empty() {
+		let commits = outputs.iter().map(|o| o.commit).collect();
+		let unspent = client.get_outputs_from_node(commits)?;
+		outputs.retain(|o| unspent.contains_key(&o.commit));
+	}
+	checkpoint.outputs_found = outputs.len();
+	checkpoint.end_height = cmp::max(checkpoint.end_height, end_height);
+	checkpoint.end_pmmr_index = cmp::max(checkpoint.end_pmmr_index, pmmr_range.1);
 
-		if highest_index <= last_retrieved_index {
-			last_retrieved_return_index = last_retrieved_index;
-			break;
-		}
-		start_index = last_retrieved_index + 1;
+	let msg = format!(
+		"Resuming scan from block {} at PMMR index {} ({} outputs found so far)",
+		checkpoint.start_height,
+		checkpoint.next_pmmr_index(),
+		checkpoint.outputs_found,
+	);
+	if let Some(ref s) = status_send_channel {
+		let _ = s.send(StatusMessage::Scanning(
+			msg,
+			checkpoint.progress().percentage_complete,
+		));
 	}
-	Ok((result_vec, last_retrieved_return_index))
+	Ok((checkpoint, outputs))
 }
 
 ///
@@ -271,6 +569,8 @@ where
 		lock_height: output.lock_height,
 		is_coinbase: output.is_coinbase,
 		tx_log_entry: Some(log_id),
+		frozen: false,
+		label: None,
 	});
 
 	let max_child_index = found_parents.get(&parent_key_id).unwrap().clone();
@@ -330,7 +630,9 @@ where
 
 /// Check / repair wallet contents by scanning against chain
 /// assume wallet contents have been freshly updated with contents
-/// of latest block
+/// of latest block. Progress is checkpointed in the wallet, and an
+/// interrupted scan is resumed by the next call. The caller removes the
+/// checkpoint once the returned scan info is saved.
 pub fn scan<'a, L, C, K>(
 	wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
 	keychain_mask: Option<&SecretKey>,
@@ -355,13 +657,25 @@ where
 	// Retrieve the actual PMMR index range we're looking for
 	let pmmr_range = client.height_range_to_pmmr_indices(start_height, Some(end_height))?;
 
-	let (chain_outs, last_index) = collect_chain_outputs(
+	let (mut checkpoint, mut scanned) = resume_checkpoint(
+		wallet_inst.clone(),
+		&keychain,
+		&client,
+		start_height,
+		end_height,
+		pmmr_range,
+		status_send_channel,
+	)?;
+	collect_chain_outputs(
+		wallet_inst.clone(),
+		keychain_mask,
 		&keychain,
 		client,
-		pmmr_range.0,
-		Some(pmmr_range.1),
+		&mut checkpoint,
+		&mut scanned,
 		status_send_channel,
 	)?;
+	let chain_outs: Vec<OutputResult> = scanned.into_iter().map(OutputResult::from).collect();
 	let msg = format!(
 		"Identified {} wallet_outputs as belonging to this wallet",
 		chain_outs.len(),
@@ -555,7 +869,96 @@ where
 	Ok(ScannedBlockInfo {
 		height: end_height,
 		hash: "".to_owned(),
-		start_pmmr_index: pmmr_range.0,
-		last_pmmr_index: last_index,
+		start_pmmr_index: checkpoint.start_pmmr_index,
+		last_pmmr_index: checkpoint
+			.last_pmmr_index
+			.unwrap_or(checkpoint.start_pmmr_index),
 	})
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use std::sync::atomic::AtomicUsize;
+	use std::time::Duration;
+
+	#[test]
+	fn windows_applied_in_order() {
+		let max_ahead = 4;
+		let in_flight = AtomicUsize::new(0);
+		let mut applied = vec![];
+		run_windows(
+			50,
+			3,
+			2,
+			max_ahead,
+			|window| {
+				let n = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+				assert!(n <= max_ahead, "{} windows in flight", n);
+				// early windows are the slowest, so later ones finish first
+				thread::sleep(Duration::from_millis((50 - window) % 7));
+				Ok(window)
+			},
+			|window, fetched| {
+				assert_eq!(window, fetched);
+				Ok(fetched * 10)
+			},
+			|ready| {
+				for (window, result) in ready {
+					assert_eq!(result, window * 10);
+					applied.push(window);
+					in_flight.fetch_sub(1, Ordering::SeqCst);
+				}
+				Ok(())
+			},
+		)
+		.unwrap();
+		assert_eq!(applied, (0..50).collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn windows_stop_on_error() {
+		let max_ahead = 4;
+		let fetched = AtomicUsize::new(0);
+		let mut applied = 0;
+		let res = run_windows(
+			1000,
+			3,
+			2,
+			max_ahead,
+			|window| {
+				fetched.fetch_add(1, Ordering::SeqCst);
+				Ok(window)
+			},
+			|_, w| Ok(w),
+			|ready| {
+				for (window, _) in ready {
+					if window == 5 {
+						return Err(Error::GenericError("cancelled".to_owned()));
+					}
+					applied += 1;
+				}
+				Ok(())
+			},
+		);
+		assert!(res.is_err());
+		assert_eq!(applied, 5);
+		// only the windows allowed ahead of the failing one were started
+		assert!(fetched.load(Ordering::SeqCst) <= 6 + max_ahead);
+
+		// a failed fetch stops the run as well
+		let res = run_windows(
+			1000,
+			3,
+			2,
+			max_ahead,
+			|window| match window {
+				3 => Err(Error::GenericError("node went away".to_owned())),
+				w => Ok(w),
+			},
+			|_, w| Ok(w),
+			|_| Ok(()),
+		);
+		assert!(res.is_err());
+	}
+}
@@ -24,7 +24,7 @@ use crate::epic_core::libtx::{
 use crate::epic_keychain::{Identifier, Keychain};
 use crate::epic_util::secp::key::SecretKey;
 use crate::error::Error;
-use crate::internal::keys;
+use crate::internal::{keys, updater};
 use crate::slate::Slate;
 use crate::types::*;
 use std::collections::HashMap;
@@ -42,7 +42,7 @@ pub fn build_send_tx<'a, T: ?Sized, C, K>(
     minimum_confirmations: u64,
     max_outputs: usize,
     change_outputs: usize,
-    selection_strategy_is_use_all: bool,
+    selection_strategy: &dyn CoinSelectionStrategy,
     parent_key_id: Identifier,
     use_test_nonce: bool,
 ) -> Result<Context, Error>
@@ -59,7 +59,7 @@ where
         minimum_confirmations,
         max_outputs,
         change_outputs,
-        selection_strategy_is_use_all,
+        selection_strategy,
         &parent_key_id,
     )?;
 
@@ -145,6 +145,9 @@ where
         t.stored_tx = Some(filename);
         t.fee = Some(slate.fee);
         t.ttl_cutoff_height = slate.ttl_cutoff_height;
+        if slate.lock_height > 0 {
+            t.lock_height = Some(slate.lock_height);
+        }
 
         match slate.calc_excess(&keychain) {
             Ok(e) => t.kernel_excess = Some(e),
@@ -207,6 +210,8 @@ where
                 lock_height: 0,
                 is_coinbase: false,
                 tx_log_entry: Some(log_id),
+                frozen: false,
+                label: None,
             })?;
         }
         batch.save_tx_log_entry(t.clone(), &parent_key_id)?;
@@ -269,6 +274,9 @@ where
     t.num_outputs = 1;
     t.messages = messages;
     t.ttl_cutoff_height = slate.ttl_cutoff_height;
+    if slate.lock_height > 0 {
+        t.lock_height = Some(slate.lock_height);
+    }
     // when invoicing, this will be invalid
     match slate.calc_excess(&keychain) {
         Ok(e) => t.kernel_excess = Some(e),
@@ -287,6 +295,8 @@ where
         lock_height: 0,
         is_coinbase: false,
         tx_log_entry: Some(log_id),
+        frozen: false,
+        label: None,
     })?;
     batch.save_tx_log_entry(t, &parent_key_id)?;
     batch.commit()?;
@@ -305,7 +315,7 @@ pub fn select_send_tx<'a, T: ?Sized, C, K, B>(
     minimum_confirmations: u64,
     max_outputs: usize,
     change_outputs: usize,
-    selection_strategy_is_use_all: bool,
+    selection_strategy: &dyn CoinSelectionStrategy,
     parent_key_id: &Identifier,
 ) -> Result<
     (
@@ -329,7 +339,7 @@ where
         minimum_confirmations,
         max_outputs,
         change_outputs,
-        selection_strategy_is_use_all,
+        selection_strategy,
         &parent_key_id,
     )?;
 
@@ -348,7 +358,7 @@ pub fn select_coins_and_fee<'a, T: ?Sized, C, K>(
     minimum_confirmations: u64,
     max_outputs: usize,
     change_outputs: usize,
-    selection_strategy_is_use_all: bool,
+    selection_strategy: &dyn CoinSelectionStrategy,
     parent_key_id: &Identifier,
 ) -> Result<
     (
@@ -371,7 +381,7 @@ where
         current_height,
         minimum_confirmations,
         max_outputs,
-        selection_strategy_is_use_all,
+        selection_strategy,
         parent_key_id,
     );
 
@@ -433,7 +443,7 @@ where
                 current_height,
                 minimum_confirmations,
                 max_outputs,
-                selection_strategy_is_use_all,
+                selection_strategy,
                 parent_key_id,
             )
             .1;
@@ -445,6 +455,86 @@ where
     Ok((coins, total, amount, fee))
 }
 
+/// Select the outputs of several transactions at once, one per amount, so
+/// that no output is selected twice. Each selection covers its amount and
+/// fee as `select_coins_and_fee` would, and nothing is selected unless every
+/// amount can be covered.
+pub fn select_coins_for_all<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    amounts: &[u64],
+    current_height: u64,
+    minimum_confirmations: u64,
+    max_outputs: usize,
+    change_outputs: usize,
+    selection_strategy: &dyn CoinSelectionStrategy,
+    parent_key_id: &Identifier,
+) -> Result<Vec<Vec<OutputData>>, Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let mut eligible = wallet
+        .iter()
+        .filter(|out| {
+            out.root_key_id == *parent_key_id
+                && out.eligible_to_spend(current_height, minimum_confirmations)
+                && selection_strategy.can_spend(out)
+        })
+        .collect::<Vec<OutputData>>();
+    eligible.sort_by_key(|out| out.value);
+
+    let mut selections = vec![];
+    for amount in amounts {
+        let coins = coins_and_fee_from(
+            &eligible,
+            *amount,
+            max_outputs,
+            change_outputs,
+            selection_strategy,
+        )?;
+        eligible.retain(|out| !coins.iter().any(|c| c.key_id == out.key_id));
+        selections.push(coins);
+    }
+    Ok(selections)
+}
+
+/// Outputs among `eligible` covering `amount` and the fee of a transaction
+/// spending them
+fn coins_and_fee_from(
+    eligible: &[OutputData],
+    amount: u64,
+    max_outputs: usize,
+    change_outputs: usize,
+    selection_strategy: &dyn CoinSelectionStrategy,
+) -> Result<Vec<OutputData>, Error> {
+    let mut coins = selection_strategy
+        .select(amount, max_outputs, eligible)
+        .unwrap_or_default();
+    let mut total: u64 = coins.iter().map(|c| c.value).sum();
+    if total == amount + tx_fee(coins.len(), 1, 1, None) {
+        return Ok(coins);
+    }
+    let mut fee = tx_fee(coins.len(), change_outputs + 1, 1, None);
+    while total < amount + fee {
+        coins = match selection_strategy.select(amount + fee, max_outputs, eligible) {
+            Some(c) => c,
+            None => {
+                let available: u64 = eligible.iter().map(|c| c.value).sum();
+                return Err(Error::NotEnoughFunds {
+                    available,
+                    available_disp: amount_to_hr_string(available, false),
+                    needed: amount + fee,
+                    needed_disp: amount_to_hr_string(amount + fee, false),
+                });
+            }
+        };
+        fee = tx_fee(coins.len(), change_outputs + 1, 1, None);
+        total = coins.iter().map(|c| c.value).sum();
+    }
+    Ok(coins)
+}
+
 /// Selects inputs and change for a transaction
 pub fn inputs_and_change<'a, T: ?Sized, C, K, B>(
     coins: &Vec<OutputData>,
@@ -516,12 +606,9 @@ where
     Ok((parts, change_amounts_derivations))
 }
 
-/// Select spendable coins from a wallet.
-/// Default strategy is to spend the maximum number of outputs (up to
-/// max_outputs). Alternative strategy is to spend smallest outputs first
-/// but only as many as necessary. When we introduce additional strategies
-/// we should pass something other than a bool in.
-/// TODO: Possibly move this into another trait to be owned by a wallet?
+/// Select spendable coins from a wallet, using the given selection strategy.
+/// If the strategy can't cover the amount, the largest `max_outputs` outputs
+/// are returned so callers can provide guidance on what is possible.
 
 pub fn select_coins<'a, T: ?Sized, C, K>(
     wallet: &mut T,
@@ -529,7 +616,7 @@ pub fn select_coins<'a, T: ?Sized, C, K>(
     current_height: u64,
     minimum_confirmations: u64,
     max_outputs: usize,
-    select_all: bool,
+    selection_strategy: &dyn CoinSelectionStrategy,
     parent_key_id: &Identifier,
 ) -> (usize, Vec<OutputData>)
 //    max_outputs_available, Outputs
@@ -544,6 +631,7 @@ where
         .filter(|out| {
             out.root_key_id == *parent_key_id
                 && out.eligible_to_spend(current_height, minimum_confirmations)
+                && selection_strategy.can_spend(out)
         })
         .collect::<Vec<OutputData>>();
 
@@ -552,45 +640,361 @@ where
     // sort eligible outputs by increasing value
     eligible.sort_by_key(|out| out.value);
 
-    // use a sliding window to identify potential sets of possible outputs to spend
-    // Case of amount > total amount of max_outputs(500):
-    // The limit exists because by default, we always select as many inputs as
-    // possible in a transaction, to reduce both the Output set and the fees.
-    // But that only makes sense up to a point, hence the limit to avoid being too
-    // greedy. But if max_outputs(500) is actually not enough to cover the whole
-    // amount, the wallet should allow going over it to satisfy what the user
-    // wants to send. So the wallet considers max_outputs more of a soft limit.
+    if let Some(outputs) = selection_strategy.select(amount, max_outputs, &eligible) {
+        return (max_available, outputs);
+    }
+
+    // we failed to find a suitable set of outputs to spend,
+    // so return the largest amount we can so we can provide guidance on what is
+    // possible
+    eligible.reverse();
+    (
+        max_available,
+        eligible.iter().take(max_outputs).cloned().collect(),
+    )
+}
+
+impl SelectionStrategy {
+    /// Returns the coin selection implementation for this strategy
+    pub fn selector(&self) -> Box<dyn CoinSelectionStrategy> {
+        match *self {
+            SelectionStrategy::All => Box::new(UseAll),
+            SelectionStrategy::Smallest => Box::new(SmallestFirst),
+            SelectionStrategy::BranchAndBound => Box::new(BranchAndBound::default()),
+            SelectionStrategy::Largest => Box::new(LargestFirst),
+            SelectionStrategy::Oldest => Box::new(OldestFirst),
+            SelectionStrategy::Privacy => Box::new(MinimizeLinkage),
+        }
+    }
+}
+
+/// Check that the outputs with the given commitments exist, are unspent,
+/// mature and belong to the given account, and return a selection that
+/// spends exactly those outputs
+pub fn manual_selection<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    commits: Vec<String>,
+    minimum_confirmations: u64,
+    parent_key_id: &Identifier,
+) -> Result<ManualSelection, Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let mut commits: Vec<String> = commits.iter().map(|c| c.trim().to_lowercase()).collect();
+    commits.sort();
+    commits.dedup();
+    if commits.is_empty() {
+        return Err(Error::ArgumentError("No inputs provided".to_owned()));
+    }
+
+    // make sure output statuses are current before checking them
+    updater::refresh_outputs(wallet, keychain_mask, parent_key_id, false)?;
+    let current_height = wallet.w2n_client().get_chain_tip()?.0;
+
+    for commit in commits.iter() {
+        let out = match wallet
+            .iter()
+            .find(|out| out.commit.as_ref() == Some(commit))
+        {
+            Some(o) => o,
+            None => {
+                return Err(Error::ArgumentError(format!(
+                    "Output {} not found in wallet",
+                    commit
+                )));
+            }
+        };
+        if out.root_key_id != *parent_key_id {
+            return Err(Error::ArgumentError(format!(
+                "Output {} does not belong to the source account",
+                commit
+            )));
+        }
+        if out.status != OutputStatus::Unspent {
+            return Err(Error::ArgumentError(format!(
+                "Output {} is not spendable (status: {})",
+                commit, out.status
+            )));
+        }
+        if out.frozen {
+            return Err(Error::ArgumentError(format!("Output {} is frozen", commit)));
+        }
+        if !out.eligible_to_spend(current_height, minimum_confirmations) {
+            return Err(Error::ArgumentError(format!(
+                "Output {} does not have enough confirmations or is locked until height {}",
+                commit, out.lock_height
+            )));
+        }
+    }
+    Ok(ManualSelection { commits })
+}
+
+/// Spend exactly the outputs with the given commitments (manual coin
+/// control). All of them are spent, even if fewer would cover the amount.
+pub struct ManualSelection {
+    /// Commitments of the outputs to spend, hex encoded
+    pub commits: Vec<String>,
+}
+
+impl CoinSelectionStrategy for ManualSelection {
+    fn can_spend(&self, output: &OutputData) -> bool {
+        match output.commit {
+            Some(ref c) => self.commits.contains(c),
+            None => false,
+        }
+    }
+
+    fn select(
+        &self,
+        amount: u64,
+        _max_outputs: usize,
+        eligible: &[OutputData],
+    ) -> Option<Vec<OutputData>> {
+        select_from(amount, true, eligible.to_vec())
+    }
+}
+
+/// Spend as many outputs as possible, up to `max_outputs`
+pub struct UseAll;
+
+impl CoinSelectionStrategy for UseAll {
+    fn select(
+        &self,
+        amount: u64,
+        max_outputs: usize,
+        eligible: &[OutputData],
+    ) -> Option<Vec<OutputData>> {
+        select_windowed(amount, max_outputs, true, eligible)
+    }
+}
+
+/// Spend the smallest outputs first, only as many as needed
+pub struct SmallestFirst;
+
+impl CoinSelectionStrategy for SmallestFirst {
+    fn select(
+        &self,
+        amount: u64,
+        max_outputs: usize,
+        eligible: &[OutputData],
+    ) -> Option<Vec<OutputData>> {
+        select_windowed(amount, max_outputs, false, eligible)
+    }
+}
+
+/// Spend the largest outputs first, resulting in the fewest inputs
+pub struct LargestFirst;
+
+impl CoinSelectionStrategy for LargestFirst {
+    fn select(
+        &self,
+        amount: u64,
+        _max_outputs: usize,
+        eligible: &[OutputData],
+    ) -> Option<Vec<OutputData>> {
+        select_from(amount, false, eligible.iter().rev().cloned().collect())
+    }
+}
+
+/// Spend the outputs with the lowest block height first
+pub struct OldestFirst;
+
+impl CoinSelectionStrategy for OldestFirst {
+    fn select(
+        &self,
+        amount: u64,
+        _max_outputs: usize,
+        eligible: &[OutputData],
+    ) -> Option<Vec<OutputData>> {
+        let mut outputs = eligible.to_vec();
+        // stable sort, so outputs at the same height stay ordered by value
+        outputs.sort_by_key(|out| out.height);
+        select_from(amount, false, outputs)
+    }
+}
+
+/// Avoid linking previously unrelated transactions together. Outputs created
+/// by the same transaction are already linked on chain, so if a single
+/// transaction's outputs can cover the amount, only those are spent.
+/// Otherwise the largest outputs are spent first, to link as few
+/// transactions as possible.
+pub struct MinimizeLinkage;
+
+impl CoinSelectionStrategy for MinimizeLinkage {
+    fn select(
+        &self,
+        amount: u64,
+        max_outputs: usize,
+        eligible: &[OutputData],
+    ) -> Option<Vec<OutputData>> {
+        let mut by_tx: HashMap<u32, Vec<OutputData>> = HashMap::new();
+        let mut groups = vec![];
+        for out in eligible.iter().rev() {
+            match out.tx_log_entry {
+                Some(id) => by_tx.entry(id).or_insert_with(Vec::new).push(out.clone()),
+                // outputs we don't know the origin of can't be grouped
+                None => groups.push(vec![out.clone()]),
+            }
+        }
+        groups.extend(by_tx.into_iter().map(|(_, outputs)| outputs));
+
+        // of the groups able to cover the amount, pick the one using the fewest
+        // outputs, then the one with the smallest total
+        let best = groups
+            .into_iter()
+            .filter_map(|outputs| select_from(amount, false, outputs))
+            .filter(|outputs| outputs.len() <= max_outputs)
+            .min_by_key(|outputs| {
+                (
+                    outputs.len(),
+                    outputs.iter().fold(0, |acc, x| acc + x.value),
+                )
+            });
+        match best {
+            Some(outputs) => Some(outputs),
+            None => LargestFirst.select(amount, max_outputs, eligible),
+        }
+    }
+}
+
+/// Search for a set of outputs whose value matches the amount plus the fee
+/// of a transaction without change exactly, so no change output is needed.
+/// Falls back to smallest first if no such set is found.
+pub struct BranchAndBound {
+    /// Maximum number of search steps before giving up on an exact match
+    pub max_tries: usize,
+}
+
+impl Default for BranchAndBound {
+    fn default() -> BranchAndBound {
+        BranchAndBound { max_tries: 100_000 }
+    }
+}
+
+impl CoinSelectionStrategy for BranchAndBound {
+    fn select(
+        &self,
+        amount: u64,
+        max_outputs: usize,
+        eligible: &[OutputData],
+    ) -> Option<Vec<OutputData>> {
+        // explore largest outputs first to find small sets quickly
+        let outputs: Vec<OutputData> = eligible.iter().rev().cloned().collect();
+        let mut remaining = vec![0; outputs.len() + 1];
+        for i in (0..outputs.len()).rev() {
+            remaining[i] = remaining[i + 1] + outputs[i].value;
+        }
+
+        let mut search = BranchAndBoundSearch {
+            amount,
+            max_outputs,
+            max_tries: self.max_tries,
+            outputs: &outputs,
+            remaining,
+            selected: vec![],
+            tries: 0,
+        };
+        if search.search(0, 0) {
+            return Some(
+                search
+                    .selected
+                    .iter()
+                    .map(|i| outputs[*i].clone())
+                    .collect(),
+            );
+        }
+        SmallestFirst.select(amount, max_outputs, eligible)
+    }
+}
+
+/// Depth first search state for `BranchAndBound`
+struct BranchAndBoundSearch<'a> {
+    amount: u64,
+    max_outputs: usize,
+    max_tries: usize,
+    /// Candidate outputs, by decreasing value
+    outputs: &'a [OutputData],
+    /// Sum of the values of outputs[i..]
+    remaining: Vec<u64>,
+    /// Indices of the currently selected outputs
+    selected: Vec<usize>,
+    tries: usize,
+}
+
+impl<'a> BranchAndBoundSearch<'a> {
+    fn search(&mut self, index: usize, selected_total: u64) -> bool {
+        self.tries += 1;
+        if self.tries > self.max_tries {
+            return false;
+        }
+        if !self.selected.is_empty() {
+            let target = self.amount + tx_fee(self.selected.len(), 1, 1, None);
+            if selected_total == target {
+                return true;
+            }
+            // adding inputs only increases the total and never increases the
+            // fee, so there's no exact match down this branch
+            if selected_total > target {
+                return false;
+            }
+        }
+        if index == self.outputs.len() || self.selected.len() == self.max_outputs {
+            return false;
+        }
+        // even taking all remaining outputs can't reach the target
+        let max_inputs = self.selected.len() + self.outputs.len() - index;
+        if selected_total + self.remaining[index] < self.amount + tx_fee(max_inputs, 1, 1, None) {
+            return false;
+        }
+
+        // first try with the output at index included, then without it
+        self.selected.push(index);
+        if self.search(index + 1, selected_total + self.outputs[index].value) {
+            return true;
+        }
+        self.selected.pop();
+        self.search(index + 1, selected_total)
+    }
+}
+
+// use a sliding window to identify potential sets of possible outputs to spend
+// Case of amount > total amount of max_outputs(500):
+// The limit exists because by default, we always select as many inputs as
+// possible in a transaction, to reduce both the Output set and the fees.
+// But that only makes sense up to a point, hence the limit to avoid being too
+// greedy. But if max_outputs(500) is actually not enough to cover the whole
+// amount, the wallet should allow going over it to satisfy what the user
+// wants to send. So the wallet considers max_outputs more of a soft limit.
+fn select_windowed(
+    amount: u64,
+    max_outputs: usize,
+    select_all: bool,
+    eligible: &[OutputData],
+) -> Option<Vec<OutputData>> {
     if eligible.len() > max_outputs {
         for window in eligible.windows(max_outputs) {
             let windowed_eligibles = window.iter().cloned().collect::<Vec<_>>();
             if let Some(outputs) = select_from(amount, select_all, windowed_eligibles) {
-                return (max_available, outputs);
+                return Some(outputs);
             }
         }
         // Not exist in any window of which total amount >= amount.
         // Then take coins from the smallest one up to the total amount of selected
         // coins = the amount.
-        if let Some(outputs) = select_from(amount, false, eligible.clone()) {
+        if let Some(outputs) = select_from(amount, false, eligible.to_vec()) {
             debug!(
                 "Extending maximum number of outputs. {} outputs selected.",
                 outputs.len()
             );
-            return (max_available, outputs);
+            return Some(outputs);
         }
+        None
     } else {
-        if let Some(outputs) = select_from(amount, select_all, eligible.clone()) {
-            return (max_available, outputs);
-        }
+        select_from(amount, select_all, eligible.to_vec())
     }
-
-    // we failed to find a suitable set of outputs to spend,
-    // so return the largest amount we can so we can provide guidance on what is
-    // possible
-    eligible.reverse();
-    (
-        max_available,
-        eligible.iter().take(max_outputs).cloned().collect(),
-    )
 }
 
 fn select_from(amount: u64, select_all: bool, outputs: Vec<OutputData>) -> Option<Vec<OutputData>> {
@@ -616,3 +1020,105 @@ fn select_from(amount: u64, select_all: bool, outputs: Vec<OutputData>) -> Optio
         None
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::epic_keychain::ExtKeychainPath;
+
+    fn output(value: u64, height: u64, tx_log_entry: Option<u32>) -> OutputData {
+        let key_id = ExtKeychainPath::new(1, height as u32, 0, 0, 0).to_identifier();
+        OutputData {
+            root_key_id: key_id.parent_path(),
+            key_id,
+            n_child: height as u32,
+            commit: None,
+            mmr_index: None,
+            value,
+            status: OutputStatus::Unspent,
+            height,
+            lock_height: 0,
+            is_coinbase: false,
+            tx_log_entry,
+            frozen: false,
+            label: None,
+        }
+    }
+
+    fn values(outputs: &[OutputData]) -> Vec<u64> {
+        outputs.iter().map(|o| o.value).collect()
+    }
+
+    #[test]
+    fn branch_and_bound_finds_exact_match() {
+        let amount = 10 * tx_fee(1, 1, 1, None);
+        let exact = amount + tx_fee(2, 1, 1, None) - 3;
+        let eligible = vec![
+            output(3, 1, None),
+            output(amount, 2, None),
+            output(exact, 3, None),
+            output(amount * 3, 4, None),
+        ];
+        let selected = BranchAndBound::default()
+            .select(amount, 500, &eligible)
+            .unwrap();
+        assert_eq!(values(&selected), vec![exact, 3]);
+
+        // no exact match, fall back to smallest first
+        let eligible = vec![output(amount, 1, None), output(amount * 3, 2, None)];
+        let selected = BranchAndBound::default()
+            .select(amount, 500, &eligible)
+            .unwrap();
+        assert_eq!(values(&selected), vec![amount]);
+    }
+
+    #[test]
+    fn largest_and_oldest_first() {
+        let eligible = vec![output(1, 3, None), output(2, 1, None), output(5, 2, None)];
+        let selected = LargestFirst.select(4, 500, &eligible).unwrap();
+        assert_eq!(values(&selected), vec![5]);
+        let selected = OldestFirst.select(4, 500, &eligible).unwrap();
+        assert_eq!(values(&selected), vec![2, 5]);
+        assert!(OldestFirst.select(9, 500, &eligible).is_none());
+    }
+
+    #[test]
+    fn frozen_outputs_are_not_eligible() {
+        let mut out = output(5, 1, None);
+        assert!(out.eligible_to_spend(20, 10));
+        out.frozen = true;
+        assert!(!out.eligible_to_spend(20, 10));
+    }
+
+    #[test]
+    fn manual_selection_spends_only_chosen_outputs() {
+        let mut eligible = vec![output(1, 1, None), output(2, 2, None), output(5, 3, None)];
+        eligible[0].commit = Some("aa".to_owned());
+        eligible[1].commit = Some("bb".to_owned());
+        let manual = ManualSelection {
+            commits: vec!["aa".to_owned(), "bb".to_owned()],
+        };
+        let chosen: Vec<OutputData> = eligible
+            .iter()
+            .filter(|o| manual.can_spend(o))
+            .cloned()
+            .collect();
+        assert_eq!(values(&manual.select(1, 500, &chosen).unwrap()), vec![1, 2]);
+        assert!(manual.select(4, 500, &chosen).is_none());
+    }
+
+    #[test]
+    fn privacy_prefers_single_transaction() {
+        let eligible = vec![
+            output(1, 1, Some(1)),
+            output(2, 2, Some(2)),
+            output(3, 3, Some(2)),
+            output(4, 4, Some(3)),
+        ];
+        let selected = MinimizeLinkage.select(5, 500, &eligible).unwrap();
+        assert_eq!(values(&selected), vec![3, 2]);
+        // no single transaction covers the amount
+        let selected = MinimizeLinkage.select(8, 500, &eligible).unwrap();
+        assert_eq!(values(&selected), vec![4, 3, 2]);
+    }
+}
@@ -26,8 +26,10 @@ use crate::epic_util::secp::pedersen;
 use crate::epic_util::Mutex;
 use crate::internal::{selection, updater};
 use crate::slate::Slate;
-use crate::types::{Context, NodeClient, StoredProofInfo, TxLogEntryType, WalletBackend};
-use crate::{address, Error};
+use crate::types::{
+    CoinSelectionStrategy, Context, NodeClient, StoredProofInfo, TxLogEntryType, WalletBackend,
+};
+use crate::{address, Error, TxProof};
 
 use ed25519_dalek::Signature as DalekSignature;
 use ed25519_dalek::Signer;
@@ -85,7 +87,45 @@ where
     Ok(slate)
 }
 
-/// Estimates locked amount and fee for the transaction without creating one
+/// Absolute lock height for a transaction created at `current_height`, given
+/// either an absolute height or a number of blocks from now. 0 means the
+/// kernel isn't height locked.
+pub fn lock_height(
+    current_height: u64,
+    lock_height: Option<u64>,
+    lock_after_blocks: Option<u64>,
+) -> Result<u64, Error> {
+    match (lock_height, lock_after_blocks) {
+        (Some(_), Some(_)) => Err(Error::InvalidLockHeight(
+            "only one of lock_height and lock_after_blocks can be given".to_owned(),
+        )),
+        (Some(h), None) if h <= current_height => Err(Error::InvalidLockHeight(format!(
+            "lock height {} is not above the current height {}",
+            h, current_height
+        ))),
+        (Some(h), None) => Ok(h),
+        (None, Some(0)) | (None, None) => Ok(0),
+        (None, Some(b)) => Ok(current_height + b),
+    }
+}
+
+/// Check the lock height of a slate against its TTL. A transaction that is
+/// locked until its TTL has passed could never be mined.
+pub fn check_lock_height(slate: &Slate) -> Result<(), Error> {
+    if slate.lock_height == 0 {
+        return Ok(());
+    }
+    match slate.ttl_cutoff_height {
+        Some(cutoff) if cutoff <= slate.lock_height => Err(Error::InvalidLockHeight(format!(
+            "lock height {} is not below the TTL cutoff height {}",
+            slate.lock_height, cutoff
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Estimates locked amount, fee and number of inputs for the transaction
+/// without creating one
 pub fn estimate_send_tx<'a, T: ?Sized, C, K>(
     wallet: &mut T,
     keychain_mask: Option<&SecretKey>,
@@ -93,12 +133,13 @@ pub fn estimate_send_tx<'a, T: ?Sized, C, K>(
     minimum_confirmations: u64,
     max_outputs: usize,
     num_change_outputs: usize,
-    selection_strategy_is_use_all: bool,
+    selection_strategy: &dyn CoinSelectionStrategy,
     parent_key_id: &Identifier,
 ) -> Result<
     (
-        u64, // total
-        u64, // fee
+        u64,   // total
+        u64,   // fee
+        usize, // number of inputs
     ),
     Error,
 >
@@ -119,17 +160,17 @@ where
     // according to plan
     // This function is just a big helper to do all of that, in theory
     // this process can be split up in any way
-    let (_coins, total, _amount, fee) = selection::select_coins_and_fee(
+    let (coins, total, _amount, fee) = selection::select_coins_and_fee(
         wallet,
         amount,
         current_height,
         minimum_confirmations,
         max_outputs,
         num_change_outputs,
-        selection_strategy_is_use_all,
+        selection_strategy,
         parent_key_id,
     )?;
-    Ok((total, fee))
+    Ok((total, fee, coins.len()))
 }
 
 /// Add inputs to the slate (effectively becoming the sender)
@@ -140,7 +181,7 @@ pub fn add_inputs_to_slate<'a, T: ?Sized, C, K>(
     minimum_confirmations: u64,
     max_outputs: usize,
     num_change_outputs: usize,
-    selection_strategy_is_use_all: bool,
+    selection_strategy: &dyn CoinSelectionStrategy,
     parent_key_id: &Identifier,
     participant_id: usize,
     message: Option<String>,
@@ -170,7 +211,7 @@ where
         minimum_confirmations,
         max_outputs,
         num_change_outputs,
-        selection_strategy_is_use_all,
+        selection_strategy,
         parent_key_id.clone(),
         use_test_rng,
     )?;
@@ -273,6 +314,40 @@ where
     Ok(())
 }
 
+/// Compact (V4) slates leave the sender's inputs and change outputs out of
+/// the initial slate, as nobody but the sender needs them. Add them back from
+/// the transaction stored when the sender locked its outputs.
+pub fn restore_sender_elements<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    slate: &mut Slate,
+) -> Result<(), Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let tx_vec =
+        updater::retrieve_txs(wallet, None, Some(slate.id), None, false, None, None, None)?;
+    let entry = tx_vec
+        .2
+        .into_iter()
+        .find(|t| t.tx_type == TxLogEntryType::TxSentCreated || t.tx_type == TxLogEntryType::TxSent)
+        .ok_or_else(|| Error::TransactionDoesntExist(slate.id.to_string()))?;
+    let stored = wallet.get_stored_tx(&entry)?.ok_or_else(|| {
+        Error::GenericError(format!(
+            "No stored transaction for slate {}, outputs must be locked before finalizing",
+            slate.id
+        ))
+    })?;
+    for input in stored.inputs() {
+        slate.tx = slate.tx.clone().with_input(*input);
+    }
+    for output in stored.outputs() {
+        slate.tx = slate.tx.clone().with_output(*output);
+    }
+    Ok(())
+}
+
 /// Rollback outputs associated with a transaction in the wallet
 pub fn cancel_tx<'a, T: ?Sized, C, K>(
     wallet: &mut T,
@@ -325,6 +400,13 @@ where
         None,
     )?;
     let outputs = res.2.iter().map(|m| m.output.clone()).collect();
+    if let Some(lock_height) = tx.lock_height {
+        warn!(
+            "Cancelled transaction {} is height locked until {}, it can still be posted \
+             after that height unless its inputs are spent first",
+            tx_id_string, lock_height
+        );
+    }
     updater::cancel_tx_and_outputs(wallet, keychain_mask, tx, outputs, parent_key_id)?;
     Ok(())
 }
@@ -395,6 +477,37 @@ where
     Ok(())
 }
 
+/// Store the epicbox proof a slate was received with, and record it against
+/// the transactions of that slate
+pub fn store_tx_proof<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    slate_id: Uuid,
+    proof: &TxProof,
+) -> Result<(), Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let txs: Vec<_> = wallet
+        .tx_log_iter()
+        .filter(|t| t.tx_slate_id == Some(slate_id))
+        .collect();
+    if txs.is_empty() {
+        return Err(Error::TransactionDoesntExist(slate_id.to_string()));
+    }
+    wallet.store_tx_proof(&slate_id.to_string(), proof)?;
+    let mut batch = wallet.batch(keychain_mask)?;
+    for mut t in txs {
+        t.tx_proof = Some(format!("{}.proof", slate_id));
+        let parent_key_id = t.parent_key_id.clone();
+        batch.save_tx_log_entry(t, &parent_key_id)?;
+    }
+    batch.commit()?;
+    Ok(())
+}
+
 /// Update the transaction from/to address
 pub fn update_public_addr<'a, T: ?Sized, C, K>(
     wallet: &mut T,
@@ -15,7 +15,7 @@
 //! Utilities to check the status of all the outputs we have stored in
 //! the wallet storage and update them.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 use crate::epic_core::consensus::{cumulative_reward_foundation, reward};
@@ -31,7 +31,8 @@ use crate::epic_util::static_secp_instance;
 use crate::error::Error;
 use crate::internal::keys;
 use crate::types::{
-    NodeClient, OutputData, OutputStatus, TxLogEntry, TxLogEntryType, WalletBackend, WalletInfo,
+    NodeClient, OutputData, OutputStatus, ScannedBlockHistory, TxLogEntry, TxLogEntryType,
+    WalletBackend, WalletInfo,
 };
 use crate::{BlockFees, CbData, OutputCommitMapping};
 
@@ -330,8 +331,12 @@ where
             if let Ok(mut output) = batch.get(id, mmr_index) {
                 match api_outputs.get(&commit) {
                     Some(o) => {
-                        // if this is a coinbase tx being confirmed, it's recordable in tx log
-                        if output.is_coinbase && output.status == OutputStatus::Unconfirmed {
+                        // if this is a coinbase tx being confirmed, it's recordable in tx log,
+                        // unless it already is and a reorg rolled its confirmation back
+                        let new_coinbase = output.is_coinbase
+                            && output.status == OutputStatus::Unconfirmed
+                            && output.tx_log_entry.is_none();
+                        if new_coinbase {
                             let log_id = batch.next_tx_log_id(parent_key_id)?;
                             let mut t = TxLogEntry::new(
                                 parent_key_id.clone(),
@@ -350,24 +355,24 @@ where
                                 let excess =
                                     secp.commit_sum(vec![commit.clone()], vec![over_commit])?;
                                 t.kernel_excess = Some(excess);
-                                t.kernel_lookup_min_height = Some(height);
+                                t.kernel_lookup_min_height = Some(o.1);
                             }
                             t.update_confirmation_ts();
-                            t.confirmation_height = Some(height);
+                            t.confirmation_height = Some(o.1);
                             output.tx_log_entry = Some(log_id);
                             batch.save_tx_log_entry(t, &parent_key_id)?;
                         }
                         // also mark the transaction in which this output is involved as confirmed
                         // note that one involved input/output confirmation SHOULD be enough
                         // to reliably confirm the tx
-                        if !output.is_coinbase && output.status == OutputStatus::Unconfirmed {
+                        if !new_coinbase && output.status == OutputStatus::Unconfirmed {
                             let tx = batch.tx_log_iter().find(|t| {
                                 Some(t.id) == output.tx_log_entry
                                     && t.parent_key_id == *parent_key_id
                             });
                             if let Some(mut t) = tx {
                                 t.update_confirmation_ts();
-                                t.confirmation_height = Some(height);
+                                t.confirmation_height = Some(o.1);
                                 t.confirmed = true;
                                 // If it was SentMempool or SentCreated, update to Sent
                                 if t.tx_type == TxLogEntryType::TxSentMempool
@@ -463,6 +468,111 @@ where
     Ok(())
 }
 
+/// Check the recorded scanned tips against the node's chain, newest first.
+/// Returns the height the chain forked at if the newest tip at or below the
+/// node's height is no longer on it. Tips above the node's height are
+/// skipped, the node may just be lagging behind. If none of the tips are on
+/// the chain the fork is deeper than the history goes, and everything from
+/// the genesis on has to be checked again.
+pub fn find_fork_height<C>(
+    client: &C,
+    history: &ScannedBlockHistory,
+    tip_height: u64,
+) -> Result<Option<u64>, Error>
+where
+    C: NodeClient,
+{
+    let mut forked = false;
+    for (height, hash) in history.blocks.iter().rev().filter(|b| b.0 <= tip_height) {
+        if client.get_header_hash(*height)? == *hash {
+            return Ok(if forked { Some(height + 1) } else { None });
+        }
+        forked = true;
+    }
+    // forked below anything we recorded, only a full rescan can tell
+    Ok(if forked { Some(0) } else { None })
+}
+
+/// Roll back everything confirmed at or above the height the chain forked
+/// at. Outputs go back to unconfirmed, and so do their transactions, until
+/// the updater finds them on the new chain. Outputs spent by a transaction
+/// rolled back, or not confirmed yet, go back to locked by it until the
+/// updater finds them spent again. Returns the transactions whose
+/// confirmation was rolled back.
+pub fn rollback_confirmations<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    fork_height: u64,
+) -> Result<Vec<TxLogEntry>, Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let outputs: Vec<OutputData> = wallet
+        .iter()
+        .filter(|o| o.status == OutputStatus::Unspent && o.height >= fork_height)
+        .collect();
+    let output_txs: HashSet<(Identifier, u32)> = outputs
+        .iter()
+        .filter_map(|o| o.tx_log_entry.map(|id| (o.root_key_id.clone(), id)))
+        .collect();
+    let txs: Vec<TxLogEntry> = wallet
+        .tx_log_iter()
+        .filter(|t| {
+            t.confirmed
+                && (output_txs.contains(&(t.parent_key_id.clone(), t.id))
+                    || t.confirmation_height.map_or(false, |h| h >= fork_height))
+        })
+        .collect();
+    // the spending transaction of a spent output is the one it's recorded
+    // with, its spend only holds if that transaction is still confirmed
+    let spending_txs: HashSet<(Identifier, u32)> = wallet
+        .tx_log_iter()
+        .filter(|t| {
+            !t.confirmed
+                && t.tx_type != TxLogEntryType::TxSentCancelled
+                && t.tx_type != TxLogEntryType::TxReceivedCancelled
+        })
+        .chain(txs.iter().cloned())
+        .map(|t| (t.parent_key_id, t.id))
+        .collect();
+    let spent: Vec<OutputData> = wallet
+        .iter()
+        .filter(|o| {
+            o.status == OutputStatus::Spent
+                && o.tx_log_entry.map_or(false, |id| {
+                    spending_txs.contains(&(o.root_key_id.clone(), id))
+                })
+        })
+        .collect();
+    let accounts: Vec<Identifier> = wallet.acct_path_iter().map(|a| a.path).collect();
+
+    let mut batch = wallet.batch(keychain_mask)?;
+    for mut o in outputs {
+        o.status = OutputStatus::Unconfirmed;
+        batch.save(o)?;
+    }
+    for mut o in spent {
+        o.status = OutputStatus::Locked;
+        batch.save(o)?;
+    }
+    for t in txs.iter() {
+        let mut t = t.clone();
+        t.confirmed = false;
+        t.confirmation_ts = None;
+        t.confirmation_height = None;
+        let parent_key_id = t.parent_key_id.clone();
+        batch.save_tx_log_entry(t, &parent_key_id)?;
+    }
+    // outputs are only refreshed against a chain at least this high
+    for parent_key_id in accounts {
+        batch.save_last_confirmed_height(&parent_key_id, fork_height.saturating_sub(1))?;
+    }
+    batch.commit()?;
+    Ok(txs)
+}
+
 /// Retrieve summary info about the wallet
 /// caller should refresh first if desired
 pub fn retrieve_info<'a, T: ?Sized, C, K>(
@@ -486,8 +596,14 @@ where
     let mut unconfirmed_total = 0;
     let mut locked_total = 0;
     let mut unspent_unconfirmed_total = 0;
+    let mut frozen_total = 0;
 
     for out in outputs {
+        // Frozen outputs are counted in their own bucket
+        if out.frozen && out.status == OutputStatus::Unspent {
+            frozen_total += out.value;
+            continue;
+        }
         match out.status {
             OutputStatus::Unspent => {
                 if out.is_coinbase && out.lock_height > current_height {
@@ -521,12 +637,17 @@ where
     Ok(WalletInfo {
         last_confirmed_height: current_height,
         minimum_confirmations,
-        total: unspent_total + immature_total + locked_total + unspent_unconfirmed_total,
+        total: unspent_total
+            + immature_total
+            + locked_total
+            + unspent_unconfirmed_total
+            + frozen_total,
         amount_awaiting_finalization: awaiting_finalization_total,
         amount_awaiting_confirmation: unconfirmed_total,
         amount_immature: immature_total,
         amount_locked: locked_total,
         amount_currently_spendable: unspent_total,
+        amount_frozen: frozen_total,
     })
 }
 
@@ -622,6 +743,8 @@ where
             lock_height,
             is_coinbase: true,
             tx_log_entry: None,
+            frozen: false,
+            label: None,
         })?;
         batch.commit()?;
     }
@@ -692,6 +815,8 @@ where
             lock_height,
             is_coinbase: true,
             tx_log_entry: None,
+            frozen: false,
+            label: None,
         })?;
         batch.commit()?;
     }
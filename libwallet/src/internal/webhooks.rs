@@ -0,0 +1,351 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Webhook notifications of wallet events. After every update the transaction
+//! log is compared with the events already queued for each transaction, and
+//! new events are queued once per configured URL in the wallet database. The
+//! queue survives restarts; failed deliveries are retried with exponential
+//! backoff until they succeed or run out of attempts. Signed deliveries cover
+//! the time of the attempt too, so receivers can turn away replays.
+
+use chrono::{Duration, Utc};
+use ring::hmac;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::config::WebhookConfig;
+use crate::epic_keychain::{Identifier, Keychain};
+use crate::epic_util as util;
+use crate::epic_util::secp::key::SecretKey;
+use crate::error::Error;
+use crate::internal::backoff::backoff_secs;
+use crate::types::{
+    NodeClient, TxLogEntry, TxLogEntryType, WalletBackend, WebhookDelivery, WebhookEvent,
+    WebhookEventType, WebhookTxState,
+};
+
+/// Header holding the hex encoded HMAC-SHA256 of `<timestamp>.<body>`,
+/// prefixed by `sha256=`
+pub const SIGNATURE_HEADER: &str = "X-Epic-Signature";
+/// Header holding the unix time of the attempt, in seconds
+pub const TIMESTAMP_HEADER: &str = "X-Epic-Timestamp";
+/// Header holding the kind of event
+pub const EVENT_HEADER: &str = "X-Epic-Event";
+/// Header holding the delivery id, the same for every retry
+pub const DELIVERY_HEADER: &str = "X-Epic-Delivery";
+
+/// Delay before the first retry of a failed delivery
+const RETRY_BASE_SECS: u64 = 10;
+/// Longest delay between two attempts
+const RETRY_MAX_SECS: u64 = 3600;
+
+/// Number of confirmations of a transaction at the given tip
+pub fn confirmations(tx: &TxLogEntry, tip_height: u64) -> u64 {
+    match tx.confirmation_height {
+        Some(h) if tx.confirmed && tip_height >= h => tip_height - h + 1,
+        _ => 0,
+    }
+}
+
+/// Events a transaction calls for, given those already queued for it. The
+/// state is updated to include them.
+pub fn tx_events(
+    state: &mut WebhookTxState,
+    tx: &TxLogEntry,
+    confirmations: u64,
+    min_confirmations: u64,
+) -> Vec<WebhookEventType> {
+    let mut events = vec![];
+    let cancelled = match tx.tx_type {
+        TxLogEntryType::TxReceivedCancelled | TxLogEntryType::TxSentCancelled => true,
+        _ => false,
+    };
+    let received = match tx.tx_type {
+        TxLogEntryType::TxReceived
+        | TxLogEntryType::TxReceivedMempool
+        | TxLogEntryType::ConfirmedCoinbase => true,
+        _ => false,
+    };
+
+    if received && !state.received {
+        state.received = true;
+        events.push(WebhookEventType::Received);
+    }
+    if cancelled && !state.cancelled {
+        state.cancelled = true;
+        events.push(WebhookEventType::Cancelled);
+    }
+    if state.confirmed && !tx.confirmed {
+        state.confirmed = false;
+        events.push(WebhookEventType::Reorged);
+    }
+    if !state.confirmed && !cancelled && tx.confirmed && confirmations >= min_confirmations {
+        state.confirmed = true;
+        events.push(WebhookEventType::Confirmed);
+    }
+    events
+}
+
+/// Whether nothing more is expected to happen to the transaction
+fn is_settled(tx: &TxLogEntry, confirmations: u64, min_confirmations: u64) -> bool {
+    match tx.tx_type {
+        TxLogEntryType::TxReceivedCancelled | TxLogEntryType::TxSentCancelled => true,
+        _ => tx.confirmed && confirmations >= min_confirmations,
+    }
+}
+
+/// Hex encoded HMAC-SHA256 of the payload
+pub fn sign(secret: &[u8], payload: &str) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+    let tag = hmac::sign(&key, payload.as_bytes());
+    util::to_hex(tag.as_ref().to_vec())
+}
+
+/// Message signed for a delivery attempted at `timestamp`
+pub fn signed_payload(timestamp: i64, payload: &str) -> String {
+    format!("{}.{}", timestamp, payload)
+}
+
+/// Headers posted along with a delivery attempted at `timestamp`, signed when
+/// there is a secret
+pub fn headers(
+    delivery: &WebhookDelivery,
+    secret: Option<&[u8]>,
+    timestamp: i64,
+) -> Vec<(String, String)> {
+    let mut headers = vec![
+        (EVENT_HEADER.to_owned(), delivery.event.to_string()),
+        (DELIVERY_HEADER.to_owned(), delivery.delivery_id.to_string()),
+        (TIMESTAMP_HEADER.to_owned(), timestamp.to_string()),
+    ];
+    if let Some(s) = secret {
+        let message = signed_payload(timestamp, &delivery.payload);
+        headers.push((
+            SIGNATURE_HEADER.to_owned(),
+            format!("sha256={}", sign(s, &message)),
+        ));
+    }
+    headers
+}
+
+/// Delay before the next attempt of a delivery that failed `attempts` times
+pub fn retry_delay(attempts: u32) -> Duration {
+    Duration::seconds(backoff_secs(RETRY_BASE_SECS, RETRY_MAX_SECS, attempts) as i64)
+}
+
+/// Queue the events called for by the changes to the transaction log since
+/// the last call, returning the number of deliveries queued. The first time
+/// round, transactions that were already settled are recorded without being
+/// reported, so enabling webhooks doesn't replay the whole history. That
+/// first run is recorded, so later transactions are always reported.
+pub fn queue_events<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    config: &WebhookConfig,
+    tip_height: u64,
+) -> Result<usize, Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let states: HashMap<(Identifier, u32), WebhookTxState> = wallet
+        .webhook_tx_state_iter()
+        .map(|s| ((s.parent_key_id.clone(), s.tx_id), s))
+        .collect();
+    // states saved before the marker existed count as a first run done
+    let first_run = wallet.webhooks_init_height()?.is_none() && states.is_empty();
+    let txs: Vec<TxLogEntry> = wallet.tx_log_iter().collect();
+
+    let mut queued = 0;
+    let mut batch = wallet.batch(keychain_mask)?;
+    for tx in txs {
+        let known = states.get(&(tx.parent_key_id.clone(), tx.id));
+        let mut state = known.cloned().unwrap_or_else(|| WebhookTxState::new(&tx));
+        let confs = confirmations(&tx, tip_height);
+        let events = tx_events(&mut state, &tx, confs, config.confirmations);
+        if known == Some(&state) {
+            continue;
+        }
+        if !(first_run && is_settled(&tx, confs, config.confirmations)) {
+            for event in events {
+                let payload = serde_json::to_string(&WebhookEvent {
+                    event_id: Uuid::new_v4(),
+                    event,
+                    created: Utc::now(),
+                    confirmations: confs,
+                    tx: tx.clone(),
+                })?;
+                for url in config.urls.iter() {
+                    batch.save_webhook_delivery(WebhookDelivery {
+                        delivery_id: Uuid::new_v4(),
+                        url: url.clone(),
+                        event,
+                        payload: payload.clone(),
+                        attempts: 0,
+                        next_attempt: Utc::now(),
+                    })?;
+                    queued += 1;
+                }
+            }
+        }
+        batch.save_webhook_tx_state(state)?;
+    }
+    if first_run {
+        batch.save_webhooks_init_height(tip_height)?;
+    }
+    batch.commit()?;
+    Ok(queued)
+}
+
+/// Deliveries due for an attempt, oldest first
+pub fn due_deliveries<'a, T: ?Sized, C, K>(wallet: &mut T) -> Vec<WebhookDelivery>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let now = Utc::now();
+    let mut due: Vec<WebhookDelivery> = wallet
+        .webhook_delivery_iter()
+        .filter(|d| d.next_attempt <= now)
+        .collect();
+    due.sort_by_key(|d| d.next_attempt);
+    due
+}
+
+/// Record the outcome of a delivery attempt. Successful deliveries leave the
+/// queue, failed ones are rescheduled until `max_attempts` is reached.
+pub fn record_attempt<'a, T: ?Sized, C, K>(
+    wallet: &mut T,
+    keychain_mask: Option<&SecretKey>,
+    mut delivery: WebhookDelivery,
+    result: Result<(), Error>,
+    max_attempts: u32,
+) -> Result<(), Error>
+where
+    T: WalletBackend<'a, C, K>,
+    C: NodeClient + 'a,
+    K: Keychain + 'a,
+{
+    let mut batch = wallet.batch(keychain_mask)?;
+    match result {
+        Ok(()) => {
+            debug!(
+                "Webhook {} delivered {} event to {}",
+                delivery.delivery_id, delivery.event, delivery.url
+            );
+            batch.delete_webhook_delivery(&delivery.delivery_id)?;
+        }
+        Err(e) => {
+            delivery.attempts += 1;
+            if delivery.attempts >= max_attempts {
+                error!(
+                    "Webhook {} to {} failed {} times, dropping {} event: {}",
+                    delivery.delivery_id, delivery.url, delivery.attempts, delivery.event, e
+                );
+                batch.delete_webhook_delivery(&delivery.delivery_id)?;
+            } else {
+                let delay = retry_delay(delivery.attempts);
+                warn!(
+                    "Webhook {} to {} failed, retrying in {}s: {}",
+                    delivery.delivery_id,
+                    delivery.url,
+                    delay.num_seconds(),
+                    e
+                );
+                delivery.next_attempt = Utc::now() + delay;
+                batch.save_webhook_delivery(delivery)?;
+            }
+        }
+    }
+    batch.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::epic_keychain::ExtKeychain;
+
+    fn tx(tx_type: TxLogEntryType, confirmation_height: Option<u64>) -> TxLogEntry {
+        let mut tx = TxLogEntry::new(ExtKeychain::derive_key_id(2, 0, 0, 0, 0), tx_type, 1);
+        tx.confirmed = confirmation_height.is_some();
+        tx.confirmation_height = confirmation_height;
+        tx
+    }
+
+    #[test]
+    fn events_follow_tx_lifecycle() {
+        let pending = tx(TxLogEntryType::TxReceived, None);
+        let mut state = WebhookTxState::new(&pending);
+        let events = tx_events(&mut state, &pending, 0, 10);
+        assert_eq!(events, vec![WebhookEventType::Received]);
+        assert!(tx_events(&mut state, &pending, 0, 10).is_empty());
+
+        // not confirmed until enough blocks are on top
+        let mined = tx(TxLogEntryType::TxReceived, Some(100));
+        assert_eq!(confirmations(&mined, 105), 6);
+        assert!(tx_events(&mut state, &mined, 6, 10).is_empty());
+        let events = tx_events(&mut state, &mined, confirmations(&mined, 109), 10);
+        assert_eq!(events, vec![WebhookEventType::Confirmed]);
+
+        // a reorg unconfirms it, and it's reported again once back
+        let events = tx_events(&mut state, &pending, 0, 10);
+        assert_eq!(events, vec![WebhookEventType::Reorged]);
+        let events = tx_events(&mut state, &mined, 10, 10);
+        assert_eq!(events, vec![WebhookEventType::Confirmed]);
+
+        let cancelled = tx(TxLogEntryType::TxSentCancelled, None);
+        let mut state = WebhookTxState::new(&cancelled);
+        let events = tx_events(&mut state, &cancelled, 0, 10);
+        assert_eq!(events, vec![WebhookEventType::Cancelled]);
+        assert!(is_settled(&cancelled, 0, 10));
+    }
+
+    #[test]
+    fn signature_and_backoff() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign(b"Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        let delivery = WebhookDelivery {
+            delivery_id: Uuid::new_v4(),
+            url: "http://127.0.0.1:8080".to_owned(),
+            event: WebhookEventType::Received,
+            payload: "{}".to_owned(),
+            attempts: 0,
+            next_attempt: Utc::now(),
+        };
+        let unsigned = headers(&delivery, None, 1600000000);
+        assert_eq!(unsigned.len(), 3);
+        assert_eq!(
+            unsigned[2],
+            (TIMESTAMP_HEADER.to_owned(), "1600000000".to_owned())
+        );
+        let signed = headers(&delivery, Some(b"secret"), 1600000000);
+        assert_eq!(
+            signed[3].1,
+            format!("sha256={}", sign(b"secret", "1600000000.{}"))
+        );
+        // a replay at another time doesn't carry a valid signature
+        let later = headers(&delivery, Some(b"secret"), 1600000060);
+        assert_ne!(signed[3].1, later[3].1);
+
+        assert_eq!(retry_delay(1).num_seconds(), 10);
+        assert_eq!(retry_delay(3).num_seconds(), 40);
+        assert_eq!(retry_delay(40).num_seconds(), 3600);
+    }
+}
@@ -43,6 +43,7 @@ extern crate strum_macros;
 
 pub mod address;
 pub mod api_impl;
+pub mod armor;
 mod base58;
 pub mod crypto;
 mod epicbox_address;
@@ -63,21 +64,26 @@ pub use crate::slate_versions::{
 pub use crate::tx_proof::TxProof;
 pub use api_impl::owner_updater::StatusMessage;
 pub use api_impl::types::{
-	BlockFees, InitTxArgs, InitTxSendArgs, IssueInvoiceTxArgs, NodeHeightResult,
-	OutputCommitMapping, Pager, PaymentProof, RetrieveOutputsResult, RetrieveTxsResult, SendTXArgs,
-	VersionInfo,
+	BlockFees, InitTxArgs, InitTxSendArgs, IssueInvoiceTxArgs, LabelSearchResult, NodeHeightResult,
+	OutputCommitMapping, Pager, PaymentProof, RetrieveOutputsResult, RetrieveTxsResult,
+	SelectionEstimate, SendTXArgs, VerifiedTxProof, VersionInfo,
 };
 pub use epicbox_address::{
 	version_bytes, Address, AddressType, EpicboxAddress, DEFAULT_EPICBOX_PORT_443,
 	DEFAULT_EPICBOX_PORT_80,
 };
+pub use internal::backoff::backoff_secs;
 pub use internal::scan::scan;
+pub use internal::webhooks;
 pub use slate_versions::ser as dalek_ser;
 pub use types::{
-	AcctPathMapping, BlockIdentifier, CbData, Context, NodeClient, NodeStatus, NodeVersionInfo,
-	OutputData, OutputStatus, PoolEntry, ScannedBlockInfo, StoredProofInfo, Tip, TxLogEntry,
-	TxLogEntryType, TxSource, TxWrapper, WalletBackend, WalletInfo, WalletInitStatus, WalletInst,
-	WalletLCProvider, WalletOutputBatch,
+	AcctPathMapping, BlockIdentifier, CbData, CoinSelectionStrategy, Contact, Context,
+	DeliveryStatus, EpicboxConnectionState, EpicboxListenerStatus, NodeCacheStats, NodeClient,
+	NodeStatus, NodeVersionInfo, OutboxEntry, OutputData, OutputStatus, PoolEntry, ScanCheckpoint,
+	ScanProgress, ScannedBlockHistory, ScannedBlockInfo, ScannedOutput, SelectionStrategy,
+	StoredProofInfo, Tip, TxLogEntry, TxLogEntryType, TxSource, TxWrapper, WalletBackend,
+	WalletInfo, WalletInitStatus, WalletInst, WalletLCProvider, WalletOutputBatch, WebhookDelivery,
+	WebhookEvent, WebhookEventType, WebhookSender, WebhookTxState,
 };
 
 /// Helper for taking a lock on the wallet instance
@@ -47,6 +47,7 @@ use crate::slate_versions::v3::{
 	CoinbaseV3, InputV3, OutputV3, ParticipantDataV3, PaymentInfoV3, SlateV3, TransactionBodyV3,
 	TransactionV3, TxKernelV3, VersionCompatInfoV3,
 };
+use crate::slate_versions::v4::{SlateV4, VersionCompatInfoV4};
 use crate::slate_versions::{CURRENT_SLATE_VERSION, EPIC_BLOCK_HEADER_VERSION};
 use crate::types::CbData;
 
@@ -236,6 +237,11 @@ impl Slate {
 	pub fn deserialize_upgrade(slate_json: &str) -> Result<Slate, Error> {
 		let version = Slate::parse_slate_version(slate_json)?;
 		let v3: SlateV3 = match version {
+			4 => {
+				let v4: SlateV4 =
+					serde_json::from_str(slate_json).map_err(|_| Error::SlateDeser)?;
+				SlateV3::from(v4)
+			}
 			3 => serde_json::from_str(slate_json).map_err(|_| Error::SlateDeser)?,
 			2 => {
 				let v2: SlateV2 =
@@ -247,6 +253,19 @@ impl Slate {
 		Ok(v3.into())
 	}
 
+	/// Recieve a slate in the compact binary format
+	pub fn deserialize_upgrade_binary(slate_bin: &[u8]) -> Result<Slate, Error> {
+		let v4 = SlateV4::from_binary(slate_bin)?;
+		Ok(SlateV3::from(v4).into())
+	}
+
+	/// Encode the slate in the compact binary format, which is only defined
+	/// for V4 slates
+	pub fn serialize_binary(&self) -> Result<Vec<u8>, Error> {
+		let v3 = SlateV3::from(self);
+		SlateV4::from(&v3).to_binary()
+	}
+
 	/// Create a new slate
 	pub fn blank(num_participants: usize) -> Slate {
 		Slate {
@@ -729,6 +748,7 @@ impl Serialize for Slate {
 
 		let v3 = SlateV3::from(self);
 		match self.version_info.orig_version {
+			4 => SlateV4::from(&v3).serialize(serializer),
 			3 => v3.serialize(serializer),
 			// left as a reminder
 			2 => {
@@ -747,6 +767,8 @@ pub struct SlateVersionProbe {
 	version: Option<u64>,
 	#[serde(default)]
 	version_info: Option<VersionCompatInfo>,
+	#[serde(default)]
+	ver: Option<String>,
 }
 
 impl SlateVersionProbe {
@@ -754,9 +776,16 @@ impl SlateVersionProbe {
 	pub fn version(&self) -> u16 {
 		match &self.version_info {
 			Some(v) => v.version,
-			None => match self.version {
-				Some(_) => 1,
-				None => 0,
+			None => match self
+				.ver
+				.as_ref()
+				.and_then(|v| VersionCompatInfoV4::parse(v))
+			{
+				Some(v) => v.version,
+				None => match self.version {
+					Some(_) => 1,
+					None => 0,
+				},
 			},
 		}
 	}
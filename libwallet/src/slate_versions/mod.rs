@@ -20,6 +20,7 @@
 use crate::slate::Slate;
 use crate::slate_versions::v2::{CoinbaseV2, SlateV2};
 use crate::slate_versions::v3::{CoinbaseV3, SlateV3};
+use crate::slate_versions::v4::SlateV4;
 use crate::types::CbData;
 
 pub mod ser;
@@ -28,6 +29,8 @@ pub mod ser;
 pub mod v2;
 #[allow(missing_docs)]
 pub mod v3;
+#[allow(missing_docs)]
+pub mod v4;
 
 /// The most recent version of the slate
 pub const CURRENT_SLATE_VERSION: u16 = 3;
@@ -38,6 +41,8 @@ pub const EPIC_BLOCK_HEADER_VERSION: u16 = 6;
 /// Existing versions of the slate
 #[derive(EnumIter, Serialize, Deserialize, Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
 pub enum SlateVersion {
+	/// V4 (compact, opt-in)
+	V4,
 	/// V3 (most current)
 	V3,
 	/// V2 (2.0.0 - Onwards)
@@ -49,6 +54,8 @@ pub enum SlateVersion {
 /// Versions are ordered newest to oldest so serde attempts to
 /// deserialize newer versions first, then falls back to older versions.
 pub enum VersionedSlate {
+	/// Compact, with optional binary encoding
+	V4(SlateV4),
 	/// Current (3.0.0 Onwards )
 	V3(SlateV3),
 	/// V2 (2.0.0 - Onwards)
@@ -59,6 +66,7 @@ impl VersionedSlate {
 	/// Return slate version
 	pub fn version(&self) -> SlateVersion {
 		match *self {
+			VersionedSlate::V4(_) => SlateVersion::V4,
 			VersionedSlate::V3(_) => SlateVersion::V3,
 			VersionedSlate::V2(_) => SlateVersion::V2,
 		}
@@ -67,6 +75,10 @@ impl VersionedSlate {
 	/// convert this slate type to a specified older version
 	pub fn into_version(slate: Slate, version: SlateVersion) -> VersionedSlate {
 		match version {
+			SlateVersion::V4 => {
+				let s = SlateV3::from(slate);
+				VersionedSlate::V4(SlateV4::from(&s))
+			}
 			SlateVersion::V3 => VersionedSlate::V3(slate.into()),
 			// Left here as a reminder of what needs to be inserted on
 			// the release of a new slate
@@ -82,6 +94,10 @@ impl VersionedSlate {
 impl From<VersionedSlate> for Slate {
 	fn from(slate: VersionedSlate) -> Slate {
 		match slate {
+			VersionedSlate::V4(s) => {
+				let s = SlateV3::from(s);
+				Slate::from(s)
+			}
 			VersionedSlate::V3(s) => {
 				let s = SlateV3::from(s);
 				Slate::from(s)
@@ -97,6 +113,10 @@ impl From<VersionedSlate> for Slate {
 impl From<&VersionedSlate> for Slate {
 	fn from(slate: &VersionedSlate) -> Slate {
 		match slate {
+			VersionedSlate::V4(s) => {
+				let s = SlateV3::from(s.clone());
+				Slate::from(s)
+			}
 			VersionedSlate::V3(s) => {
 				let s = SlateV3::from(s.clone());
 				Slate::from(s)
@@ -124,7 +144,8 @@ impl VersionedCoinbase {
 	/// convert this coinbase data to a specific versioned representation for the json api.
 	pub fn into_version(cb: CbData, version: SlateVersion) -> VersionedCoinbase {
 		match version {
-			SlateVersion::V3 => VersionedCoinbase::V3(cb.into()),
+			// coinbase data didn't change in V4
+			SlateVersion::V4 | SlateVersion::V3 => VersionedCoinbase::V3(cb.into()),
 			SlateVersion::V2 => VersionedCoinbase::V2(cb.into()),
 		}
 	}
@@ -0,0 +1,837 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Contains V4 of the slate, a compact version of V3
+//! Changes from V3:
+//! * Short field names, fields holding their default value are left out
+//! * Version info collapsed into a single `ver` string
+//! * The kernel only carries its excess and signature once they exist, its
+//!   features, fee and lock height are derived from the slate
+//! * The initial slate of a standard send doesn't carry the sender's inputs
+//!   and change outputs, the sender adds them back from its stored
+//!   transaction when finalizing. Only each party's additions travel.
+//! * Optional binary encoding
+
+use crate::epic_core::core::transaction::{Input, Output, OutputFeatures};
+use crate::epic_core::libtx::secp_ser;
+use crate::epic_core::map_vec;
+use crate::epic_core::ser as core_ser;
+use crate::epic_keychain::BlindingFactor;
+use crate::epic_util::secp;
+use crate::epic_util::secp::key::PublicKey;
+use crate::epic_util::secp::pedersen::{Commitment, RangeProof};
+use crate::epic_util::secp::Signature;
+use crate::error::Error;
+use crate::slate::CompatKernelFeatures;
+use crate::slate_versions::ser as dalek_ser;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use ed25519_dalek::Signature as DalekSignature;
+use ed25519_dalek::VerifyingKey as DalekPublicKey;
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::io::{Cursor, Read, Write};
+use uuid::Uuid;
+
+use crate::slate_versions::v3::{
+	InputV3, OutputV3, ParticipantDataV3, PaymentInfoV3, SlateV3, TransactionBodyV3, TransactionV3,
+	TxKernelV3, VersionCompatInfoV3,
+};
+
+/// Prefix of a binary encoded slate, followed by the slate version
+pub const BINARY_MAGIC: &[u8; 3] = b"ESL";
+
+const PEDERSEN_COMMITMENT_SIZE: usize = 33;
+const COMPRESSED_PUBLIC_KEY_SIZE: usize = 33;
+const SIGNATURE_SIZE: usize = 64;
+const SECRET_KEY_SIZE: usize = 32;
+const DALEK_PUBLIC_KEY_SIZE: usize = 32;
+const DALEK_SIGNATURE_SIZE: usize = 64;
+
+// Optional fields present in a binary slate
+const FLAG_NUM_PARTS: u8 = 1;
+const FLAG_LOCK: u8 = 2;
+const FLAG_TTL: u8 = 4;
+const FLAG_KERNEL: u8 = 8;
+const FLAG_PROOF: u8 = 16;
+// Optional fields present in a binary participant
+const FLAG_PART_SIG: u8 = 1;
+const FLAG_MSG: u8 = 2;
+const FLAG_MSG_SIG: u8 = 4;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SlateV4 {
+	/// Versioning info
+	pub ver: VersionCompatInfoV4,
+	/// Unique transaction ID, selected by sender
+	pub id: Uuid,
+	/// The number of participants intended to take part in this transaction
+	#[serde(
+		default = "default_num_participants",
+		skip_serializing_if = "is_default_num_participants"
+	)]
+	pub num_parts: usize,
+	/// base amount (excluding fee)
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amt: u64,
+	/// fee amount
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub fee: u64,
+	/// Block height for the transaction
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub height: u64,
+	/// Lock height
+	#[serde(
+		default,
+		skip_serializing_if = "is_zero",
+		with = "secp_ser::string_or_u64"
+	)]
+	pub lock: u64,
+	/// TTL, the block height at which wallets
+	/// should refuse to process the transaction and unlock all
+	/// associated outputs
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		with = "secp_ser::opt_string_or_u64"
+	)]
+	pub ttl: Option<u64>,
+	/// The kernel "offset" k2
+	#[serde(
+		serialize_with = "secp_ser::as_hex",
+		deserialize_with = "secp_ser::blind_from_hex"
+	)]
+	pub off: BlindingFactor,
+	/// Inputs added by the participants
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub ins: Vec<InputV4>,
+	/// Outputs added by the participants
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub outs: Vec<OutputV4>,
+	/// Kernel excess and signature, once the transaction is finalized
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub ker: Option<TxKernelV4>,
+	/// Participant data
+	pub parts: Vec<ParticipantDataV4>,
+	/// Payment Proof
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub proof: Option<PaymentInfoV4>,
+}
+
+fn default_num_participants() -> usize {
+	2
+}
+
+fn is_default_num_participants(n: &usize) -> bool {
+	*n == 2
+}
+
+fn is_zero(n: &u64) -> bool {
+	*n == 0
+}
+
+fn default_output_features() -> OutputFeatures {
+	OutputFeatures::Plain
+}
+
+fn is_plain(f: &OutputFeatures) -> bool {
+	*f == OutputFeatures::Plain
+}
+
+/// Versioning info, serialized as `<version>:<block header version>`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VersionCompatInfoV4 {
+	/// The current version of the slate format
+	pub version: u16,
+	/// Version of epic block header this slate is compatible with
+	pub block_header_version: u16,
+}
+
+impl VersionCompatInfoV4 {
+	/// Parse the `<version>:<block header version>` representation
+	pub fn parse(ver: &str) -> Option<VersionCompatInfoV4> {
+		let (version, block_header_version) = ver.split_once(':')?;
+		Some(VersionCompatInfoV4 {
+			version: version.parse().ok()?,
+			block_header_version: block_header_version.parse().ok()?,
+		})
+	}
+}
+
+impl Serialize for VersionCompatInfoV4 {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_str(&format!("{}:{}", self.version, self.block_header_version))
+	}
+}
+
+impl<'de> Deserialize<'de> for VersionCompatInfoV4 {
+	fn deserialize<D>(deserializer: D) -> Result<VersionCompatInfoV4, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let s = String::deserialize(deserializer)?;
+		VersionCompatInfoV4::parse(&s)
+			.ok_or_else(|| de::Error::custom(format!("invalid slate version '{}'", s)))
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ParticipantDataV4 {
+	/// Id of participant in the transaction. (For now, 0=sender, 1=rec)
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub id: u64,
+	/// Public key corresponding to private blinding factor
+	#[serde(with = "secp_ser::pubkey_serde")]
+	pub xs: PublicKey,
+	/// Public key corresponding to private nonce
+	#[serde(with = "secp_ser::pubkey_serde")]
+	pub nonce: PublicKey,
+	/// Public partial signature
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		with = "secp_ser::option_sig_serde"
+	)]
+	pub part: Option<Signature>,
+	/// A message for other participants
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub msg: Option<String>,
+	/// Signature, created with private key corresponding to 'xs'
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		with = "secp_ser::option_sig_serde"
+	)]
+	pub msg_sig: Option<Signature>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PaymentInfoV4 {
+	#[serde(with = "dalek_ser::dalek_pubkey_serde")]
+	pub saddr: DalekPublicKey,
+	#[serde(with = "dalek_ser::dalek_pubkey_serde")]
+	pub raddr: DalekPublicKey,
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		with = "dalek_ser::option_dalek_sig_serde"
+	)]
+	pub rsig: Option<DalekSignature>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InputV4 {
+	/// The features of the output being spent.
+	#[serde(default = "default_output_features", skip_serializing_if = "is_plain")]
+	pub feat: OutputFeatures,
+	/// The commit referencing the output being spent.
+	#[serde(
+		serialize_with = "secp_ser::as_hex",
+		deserialize_with = "secp_ser::commitment_from_hex"
+	)]
+	pub com: Commitment,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct OutputV4 {
+	/// Options for an output's structure or use
+	#[serde(default = "default_output_features", skip_serializing_if = "is_plain")]
+	pub feat: OutputFeatures,
+	/// The homomorphic commitment representing the output amount
+	#[serde(
+		serialize_with = "secp_ser::as_hex",
+		deserialize_with = "secp_ser::commitment_from_hex"
+	)]
+	pub com: Commitment,
+	/// A proof that the commitment is in the right range
+	#[serde(
+		serialize_with = "secp_ser::as_hex",
+		deserialize_with = "secp_ser::rangeproof_from_hex"
+	)]
+	pub prf: RangeProof,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TxKernelV4 {
+	/// Remainder of the sum of all transaction commitments
+	#[serde(
+		serialize_with = "secp_ser::as_hex",
+		deserialize_with = "secp_ser::commitment_from_hex"
+	)]
+	pub excess: Commitment,
+	/// The signature proving the excess is a valid public key
+	#[serde(with = "secp_ser::sig_serde")]
+	pub sig: secp::Signature,
+}
+
+impl SlateV4 {
+	/// Whether the slate is the initial slate of a standard send: only the
+	/// sender took part so far, and no payment proof has to be signed over
+	/// the full transaction by the recipient.
+	fn is_initial_send(slate: &SlateV3) -> bool {
+		slate.num_participants == 2
+			&& slate.payment_proof.is_none()
+			&& slate.participant_data.len() == 1
+			&& slate.participant_data[0].id == 0
+			&& slate.participant_data[0].part_sig.is_none()
+	}
+
+	/// Encode the slate in the compact binary format
+	pub fn to_binary(&self) -> Result<Vec<u8>, Error> {
+		let mut w = vec![];
+		w.write_all(BINARY_MAGIC)?;
+		w.write_u8(self.ver.version as u8)?;
+		w.write_u16::<BigEndian>(self.ver.block_header_version)?;
+		w.write_all(self.id.as_bytes())?;
+
+		let mut flags = 0;
+		if self.num_parts != 2 {
+			flags |= FLAG_NUM_PARTS;
+		}
+		if self.lock != 0 {
+			flags |= FLAG_LOCK;
+		}
+		if self.ttl.is_some() {
+			flags |= FLAG_TTL;
+		}
+		if self.ker.is_some() {
+			flags |= FLAG_KERNEL;
+		}
+		if self.proof.is_some() {
+			flags |= FLAG_PROOF;
+		}
+		w.write_u8(flags)?;
+		if self.num_parts != 2 {
+			w.write_u8(self.num_parts as u8)?;
+		}
+		w.write_u64::<BigEndian>(self.amt)?;
+		w.write_u64::<BigEndian>(self.fee)?;
+		w.write_u64::<BigEndian>(self.height)?;
+		if self.lock != 0 {
+			w.write_u64::<BigEndian>(self.lock)?;
+		}
+		if let Some(ttl) = self.ttl {
+			w.write_u64::<BigEndian>(ttl)?;
+		}
+		w.write_all(self.off.as_ref())?;
+
+		w.write_u16::<BigEndian>(self.ins.len() as u16)?;
+		for i in self.ins.iter() {
+			let input = Input {
+				features: i.feat,
+				commit: i.com,
+			};
+			write_blob(
+				&mut w,
+				&core_ser::ser_vec(&input, core_ser::ProtocolVersion(1))?,
+			)?;
+		}
+		w.write_u16::<BigEndian>(self.outs.len() as u16)?;
+		for o in self.outs.iter() {
+			let output = Output {
+				features: o.feat,
+				commit: o.com,
+				proof: o.prf,
+			};
+			write_blob(
+				&mut w,
+				&core_ser::ser_vec(&output, core_ser::ProtocolVersion(1))?,
+			)?;
+		}
+		if let Some(ref k) = self.ker {
+			w.write_all(&k.excess.0)?;
+			w.write_all(&k.sig.to_raw_data())?;
+		}
+
+		w.write_u8(self.parts.len() as u8)?;
+		for p in self.parts.iter() {
+			let mut flags = 0;
+			if p.part.is_some() {
+				flags |= FLAG_PART_SIG;
+			}
+			if p.msg.is_some() {
+				flags |= FLAG_MSG;
+			}
+			if p.msg_sig.is_some() {
+				flags |= FLAG_MSG_SIG;
+			}
+			w.write_u8(flags)?;
+			w.write_u64::<BigEndian>(p.id)?;
+			let secp = secp::Secp256k1::with_caps(secp::ContextFlag::VerifyOnly);
+			w.write_all(&p.xs.serialize_vec(&secp, true))?;
+			w.write_all(&p.nonce.serialize_vec(&secp, true))?;
+			if let Some(ref s) = p.part {
+				w.write_all(&s.to_raw_data())?;
+			}
+			if let Some(ref m) = p.msg {
+				write_blob(&mut w, m.as_bytes())?;
+			}
+			if let Some(ref s) = p.msg_sig {
+				w.write_all(&s.to_raw_data())?;
+			}
+		}
+
+		if let Some(ref p) = self.proof {
+			w.write_all(&p.saddr.to_bytes())?;
+			w.write_all(&p.raddr.to_bytes())?;
+			w.write_u8(p.rsig.is_some() as u8)?;
+			if let Some(ref s) = p.rsig {
+				w.write_all(&s.to_bytes())?;
+			}
+		}
+		Ok(w)
+	}
+
+	/// Decode a slate in the compact binary format
+	pub fn from_binary(bytes: &[u8]) -> Result<SlateV4, Error> {
+		let mut r = Cursor::new(bytes);
+		let slate = SlateV4::read_binary(&mut r).map_err(|_| Error::SlateDeser)?;
+		if r.position() as usize != bytes.len() {
+			return Err(Error::SlateDeser);
+		}
+		Ok(slate)
+	}
+
+	fn read_binary(r: &mut Cursor<&[u8]>) -> Result<SlateV4, Error> {
+		if read_bytes(r, BINARY_MAGIC.len())? != BINARY_MAGIC {
+			return Err(Error::SlateDeser);
+		}
+		let version = r.read_u8()? as u16;
+		if version != 4 {
+			return Err(Error::SlateVersion(version));
+		}
+		let ver = VersionCompatInfoV4 {
+			version,
+			block_header_version: r.read_u16::<BigEndian>()?,
+		};
+		let id = Uuid::from_slice(&read_bytes(r, 16)?).map_err(|_| Error::SlateDeser)?;
+
+		let flags = r.read_u8()?;
+		let num_parts = match flags & FLAG_NUM_PARTS {
+			0 => 2,
+			_ => r.read_u8()? as usize,
+		};
+		let amt = r.read_u64::<BigEndian>()?;
+		let fee = r.read_u64::<BigEndian>()?;
+		let height = r.read_u64::<BigEndian>()?;
+		let lock = match flags & FLAG_LOCK {
+			0 => 0,
+			_ => r.read_u64::<BigEndian>()?,
+		};
+		let ttl = match flags & FLAG_TTL {
+			0 => None,
+			_ => Some(r.read_u64::<BigEndian>()?),
+		};
+		let off = BlindingFactor::from_slice(&read_bytes(r, SECRET_KEY_SIZE)?);
+
+		let mut ins = vec![];
+		for _ in 0..r.read_u16::<BigEndian>()? {
+			let input: Input =
+				core_ser::deserialize(&mut &read_blob(r)?[..], core_ser::ProtocolVersion(1))?;
+			ins.push(InputV4 {
+				feat: input.features,
+				com: input.commit,
+			});
+		}
+		let mut outs = vec![];
+		for _ in 0..r.read_u16::<BigEndian>()? {
+			let output: Output =
+				core_ser::deserialize(&mut &read_blob(r)?[..], core_ser::ProtocolVersion(1))?;
+			outs.push(OutputV4 {
+				feat: output.features,
+				com: output.commit,
+				prf: output.proof,
+			});
+		}
+		let ker = match flags & FLAG_KERNEL {
+			0 => None,
+			_ => Some(TxKernelV4 {
+				excess: Commitment::from_vec(read_bytes(r, PEDERSEN_COMMITMENT_SIZE)?),
+				sig: read_signature(r)?,
+			}),
+		};
+
+		let secp = secp::Secp256k1::with_caps(secp::ContextFlag::VerifyOnly);
+		let mut parts = vec![];
+		for _ in 0..r.read_u8()? {
+			let flags = r.read_u8()?;
+			let id = r.read_u64::<BigEndian>()?;
+			let xs = PublicKey::from_slice(&secp, &read_bytes(r, COMPRESSED_PUBLIC_KEY_SIZE)?)?;
+			let nonce = PublicKey::from_slice(&secp, &read_bytes(r, COMPRESSED_PUBLIC_KEY_SIZE)?)?;
+			let part = match flags & FLAG_PART_SIG {
+				0 => None,
+				_ => Some(read_signature(r)?),
+			};
+			let msg = match flags & FLAG_MSG {
+				0 => None,
+				_ => Some(String::from_utf8(read_blob(r)?).map_err(|_| Error::SlateDeser)?),
+			};
+			let msg_sig = match flags & FLAG_MSG_SIG {
+				0 => None,
+				_ => Some(read_signature(r)?),
+			};
+			parts.push(ParticipantDataV4 {
+				id,
+				xs,
+				nonce,
+				part,
+				msg,
+				msg_sig,
+			});
+		}
+
+		let proof = match flags & FLAG_PROOF {
+			0 => None,
+			_ => {
+				let saddr = read_dalek_pubkey(r)?;
+				let raddr = read_dalek_pubkey(r)?;
+				let rsig = match r.read_u8()? {
+					0 => None,
+					_ => {
+						let mut b = [0u8; DALEK_SIGNATURE_SIZE];
+						r.read_exact(&mut b)?;
+						Some(DalekSignature::from_bytes(&b))
+					}
+				};
+				Some(PaymentInfoV4 { saddr, raddr, rsig })
+			}
+		};
+
+		Ok(SlateV4 {
+			ver,
+			id,
+			num_parts,
+			amt,
+			fee,
+			height,
+			lock,
+			ttl,
+			off,
+			ins,
+			outs,
+			ker,
+			parts,
+			proof,
+		})
+	}
+}
+
+fn write_blob(w: &mut Vec<u8>, data: &[u8]) -> Result<(), Error> {
+	w.write_u16::<BigEndian>(data.len() as u16)?;
+	w.write_all(data)?;
+	Ok(())
+}
+
+fn read_bytes(r: &mut Cursor<&[u8]>, len: usize) -> Result<Vec<u8>, Error> {
+	let mut buf = vec![0u8; len];
+	r.read_exact(&mut buf)?;
+	Ok(buf)
+}
+
+fn read_blob(r: &mut Cursor<&[u8]>) -> Result<Vec<u8>, Error> {
+	let len = r.read_u16::<BigEndian>()? as usize;
+	read_bytes(r, len)
+}
+
+fn read_signature(r: &mut Cursor<&[u8]>) -> Result<Signature, Error> {
+	let mut b = [0u8; SIGNATURE_SIZE];
+	r.read_exact(&mut b)?;
+	Ok(Signature::from_raw_data(&b)?)
+}
+
+fn read_dalek_pubkey(r: &mut Cursor<&[u8]>) -> Result<DalekPublicKey, Error> {
+	let mut b = [0u8; DALEK_PUBLIC_KEY_SIZE];
+	r.read_exact(&mut b)?;
+	DalekPublicKey::from_bytes(&b).map_err(|_| Error::SlateDeser)
+}
+
+// V3 to V4
+impl From<&SlateV3> for SlateV4 {
+	fn from(slate: &SlateV3) -> SlateV4 {
+		let SlateV3 {
+			num_participants,
+			id,
+			tx,
+			amount,
+			fee,
+			height,
+			lock_height,
+			ttl_cutoff_height,
+			participant_data,
+			version_info,
+			payment_proof,
+		} = slate;
+		let ver = VersionCompatInfoV4 {
+			version: 4,
+			block_header_version: version_info.block_header_version,
+		};
+		let (ins, outs) = match SlateV4::is_initial_send(slate) {
+			true => (vec![], vec![]),
+			false => (
+				map_vec!(tx.body.inputs, |i| InputV4::from(i)),
+				map_vec!(tx.body.outputs, |o| OutputV4::from(o)),
+			),
+		};
+		let placeholder = Commitment::from_vec(vec![0; PEDERSEN_COMMITMENT_SIZE]);
+		let ker = tx
+			.body
+			.kernels
+			.first()
+			.filter(|k| k.excess != placeholder)
+			.map(|k| TxKernelV4 {
+				excess: k.excess,
+				sig: k.excess_sig,
+			});
+		SlateV4 {
+			ver,
+			id: *id,
+			num_parts: *num_participants,
+			amt: *amount,
+			fee: *fee,
+			height: *height,
+			lock: *lock_height,
+			ttl: *ttl_cutoff_height,
+			off: tx.offset.clone(),
+			ins,
+			outs,
+			ker,
+			parts: map_vec!(participant_data, |p| ParticipantDataV4::from(p)),
+			proof: payment_proof.as_ref().map(|p| PaymentInfoV4::from(p)),
+		}
+	}
+}
+
+impl From<&ParticipantDataV3> for ParticipantDataV4 {
+	fn from(data: &ParticipantDataV3) -> ParticipantDataV4 {
+		ParticipantDataV4 {
+			id: data.id,
+			xs: data.public_blind_excess,
+			nonce: data.public_nonce,
+			part: data.part_sig,
+			msg: data.message.clone(),
+			msg_sig: data.message_sig,
+		}
+	}
+}
+
+impl From<&PaymentInfoV3> for PaymentInfoV4 {
+	fn from(data: &PaymentInfoV3) -> PaymentInfoV4 {
+		PaymentInfoV4 {
+			saddr: data.sender_address,
+			raddr: data.receiver_address,
+			rsig: data.receiver_signature,
+		}
+	}
+}
+
+impl From<&InputV3> for InputV4 {
+	fn from(input: &InputV3) -> InputV4 {
+		InputV4 {
+			feat: input.features,
+			com: input.commit,
+		}
+	}
+}
+
+impl From<&OutputV3> for OutputV4 {
+	fn from(output: &OutputV3) -> OutputV4 {
+		OutputV4 {
+			feat: output.features,
+			com: output.commit,
+			prf: output.proof,
+		}
+	}
+}
+
+// V4 to V3
+impl From<SlateV4> for SlateV3 {
+	fn from(slate: SlateV4) -> SlateV3 {
+		let SlateV4 {
+			ver,
+			id,
+			num_parts,
+			amt,
+			fee,
+			height,
+			lock,
+			ttl,
+			off,
+			ins,
+			outs,
+			ker,
+			parts,
+			proof,
+		} = slate;
+		let features = match lock {
+			0 => CompatKernelFeatures::Plain,
+			_ => CompatKernelFeatures::HeightLocked,
+		};
+		let (excess, excess_sig) = match ker {
+			Some(k) => (k.excess, k.sig),
+			None => (
+				Commitment::from_vec(vec![0; PEDERSEN_COMMITMENT_SIZE]),
+				Signature::from_raw_data(&[0; SIGNATURE_SIZE]).unwrap(),
+			),
+		};
+		let kernel = TxKernelV3 {
+			features,
+			fee,
+			lock_height: lock,
+			excess,
+			excess_sig,
+		};
+		let tx = TransactionV3 {
+			offset: off,
+			body: TransactionBodyV3 {
+				inputs: map_vec!(ins, |i| InputV3::from(i)),
+				outputs: map_vec!(outs, |o| OutputV3::from(o)),
+				kernels: vec![kernel],
+			},
+		};
+		SlateV3 {
+			version_info: VersionCompatInfoV3 {
+				version: ver.version,
+				orig_version: ver.version,
+				block_header_version: ver.block_header_version,
+			},
+			num_participants: num_parts,
+			id,
+			tx,
+			amount: amt,
+			fee,
+			height,
+			lock_height: lock,
+			ttl_cutoff_height: ttl,
+			participant_data: map_vec!(parts, |p| ParticipantDataV3::from(p)),
+			payment_proof: proof.as_ref().map(|p| PaymentInfoV3::from(p)),
+		}
+	}
+}
+
+impl From<&ParticipantDataV4> for ParticipantDataV3 {
+	fn from(data: &ParticipantDataV4) -> ParticipantDataV3 {
+		ParticipantDataV3 {
+			id: data.id,
+			public_blind_excess: data.xs,
+			public_nonce: data.nonce,
+			part_sig: data.part,
+			message: data.msg.clone(),
+			message_sig: data.msg_sig,
+		}
+	}
+}
+
+impl From<&PaymentInfoV4> for PaymentInfoV3 {
+	fn from(data: &PaymentInfoV4) -> PaymentInfoV3 {
+		PaymentInfoV3 {
+			sender_address: data.saddr,
+			receiver_address: data.raddr,
+			receiver_signature: data.rsig,
+		}
+	}
+}
+
+impl From<&InputV4> for InputV3 {
+	fn from(input: &InputV4) -> InputV3 {
+		InputV3 {
+			features: input.feat,
+			commit: input.com,
+		}
+	}
+}
+
+impl From<&OutputV4> for OutputV3 {
+	fn from(output: &OutputV4) -> OutputV3 {
+		OutputV3 {
+			features: output.feat,
+			commit: output.com,
+			proof: output.prf,
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::slate::{ParticipantData, Slate};
+
+	fn slate() -> SlateV3 {
+		let secp = secp::Secp256k1::new();
+		let sk = secp::key::SecretKey::from_slice(&secp, &[1; 32]).unwrap();
+		let pk = PublicKey::from_secret_key(&secp, &sk).unwrap();
+		let mut slate = Slate::blank(2);
+		slate.amount = 1_000;
+		slate.fee = 8_000_000;
+		slate.height = 1234;
+		slate.update_kernel();
+		slate.participant_data.push(ParticipantData {
+			id: 0,
+			public_blind_excess: pk,
+			public_nonce: pk,
+			part_sig: None,
+			message: Some("thanks".to_owned()),
+			message_sig: None,
+		});
+		SlateV3::from(slate)
+	}
+
+	#[test]
+	fn slate_v4_json_roundtrip() {
+		let v3 = slate();
+		let v4 = SlateV4::from(&v3);
+		let json = serde_json::to_string(&v4).unwrap();
+		assert!(json.contains("\"ver\":\"4:"));
+		assert!(!json.contains("num_parts"));
+		assert!(!json.contains("ker"));
+		assert!(json.len() < serde_json::to_string(&v3).unwrap().len());
+
+		let back = SlateV3::from(serde_json::from_str::<SlateV4>(&json).unwrap());
+		assert_eq!(back.id, v3.id);
+		assert_eq!(back.fee, v3.fee);
+		assert_eq!(back.version_info.orig_version, 4);
+		assert_eq!(back.tx.body.kernels.len(), 1);
+		assert_eq!(back.tx.body.kernels[0].fee, v3.fee);
+		assert_eq!(
+			back.participant_data[0].message,
+			v3.participant_data[0].message
+		);
+
+		// V3 json isn't mistaken for V4
+		assert!(serde_json::from_str::<SlateV4>(&serde_json::to_string(&v3).unwrap()).is_err());
+	}
+
+	#[test]
+	fn slate_v4_binary_roundtrip() {
+		let v4 = SlateV4::from(&slate());
+		let bin = v4.to_binary().unwrap();
+		assert!(bin.starts_with(BINARY_MAGIC));
+		assert!(bin.len() < serde_json::to_string(&v4).unwrap().len());
+
+		let back = SlateV4::from_binary(&bin).unwrap();
+		assert_eq!(back.id, v4.id);
+		assert_eq!(back.ver, v4.ver);
+		assert_eq!(back.amt, v4.amt);
+		assert_eq!(back.parts[0].xs, v4.parts[0].xs);
+		assert_eq!(back.parts[0].msg, v4.parts[0].msg);
+		assert!(back.ker.is_none());
+
+		assert!(SlateV4::from_binary(&bin[..bin.len() - 1]).is_err());
+		let mut extended = bin.clone();
+		extended.push(0);
+		assert!(SlateV4::from_binary(&extended).is_err());
+	}
+}
@@ -19,6 +19,7 @@ use crate::epic_util::secp::key::SecretKey;
 use crate::epic_util::secp::pedersen::Commitment;
 use crate::epic_util::secp::Signature;
 use crate::message::EncryptedMessage;
+use crate::slate::Slate;
 
 use crate::{Address, EpicboxAddress};
 use serde::{Deserialize, Serialize};
@@ -43,9 +44,13 @@ pub enum Error {
 	DecryptMessage,
 	#[error("Unable to parse slate")]
 	ParseSlate,
+	#[error("Proof doesn't match its slate")]
+	VerifySummary,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Signed epicbox message a slate was received in. The amount, fee, inputs
+/// and outputs summarize the slate it carries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TxProof {
 	pub address: EpicboxAddress,
 	pub message: String,
@@ -93,6 +98,23 @@ impl TxProof {
 		Ok((destination, slate))
 	}
 
+	/// Verify the proof, and that its summary matches the slate it carries.
+	/// Returns the address the slate was sent from, the one it was sent to
+	/// and the slate.
+	pub fn verify(&self) -> Result<(EpicboxAddress, EpicboxAddress, Slate), Error> {
+		let (destination, slate) = self.verify_extract(None)?;
+		let slate = Slate::from(slate);
+		let (amount, fee, inputs, outputs) = summary(&slate);
+		if self.amount != amount
+			|| self.fee != fee
+			|| self.inputs != inputs
+			|| self.outputs != outputs
+		{
+			return Err(Error::VerifySummary);
+		}
+		Ok((self.address.clone(), destination, slate))
+	}
+
 	pub fn from_response(
 		from: String,
 		message: String,
@@ -110,7 +132,7 @@ impl TxProof {
 			.key(&public_key, secret_key)
 			.map_err(|_| Error::DecryptionKey)?;
 
-		let proof = TxProof {
+		let mut proof = TxProof {
 			address,
 			message,
 			signature,
@@ -122,7 +144,85 @@ impl TxProof {
 		};
 
 		let (_, slate) = proof.verify_extract(expected_destination)?;
+		let (amount, fee, inputs, outputs) = summary(&Slate::from(&slate));
+		proof.amount = amount;
+		proof.fee = fee;
+		proof.inputs = inputs;
+		proof.outputs = outputs;
 
 		Ok((slate, proof))
 	}
 }
+
+fn summary(slate: &Slate) -> (u64, u64, Vec<Commitment>, Vec<Commitment>) {
+	(
+		slate.amount,
+		slate.fee,
+		slate.tx.inputs().iter().map(|i| i.commitment()).collect(),
+		slate.tx.outputs().iter().map(|o| o.commitment()).collect(),
+	)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::crypto::sign_challenge;
+	use crate::epic_util::secp::key::PublicKey;
+	use crate::epic_util::secp::Secp256k1;
+	use crate::slate_versions::SlateVersion;
+
+	fn key(b: u8) -> (SecretKey, PublicKey) {
+		let secp = Secp256k1::new();
+		let sk = SecretKey::from_slice(&secp, &[b; 32]).unwrap();
+		let pk = PublicKey::from_secret_key(&secp, &sk).unwrap();
+		(sk, pk)
+	}
+
+	#[test]
+	fn proof_roundtrip() {
+		let (sender_sk, sender_pk) = key(1);
+		let (recipient_sk, recipient_pk) = key(2);
+		let sender = EpicboxAddress::new(sender_pk, None, None);
+		let recipient = EpicboxAddress::new(recipient_pk.clone(), None, None);
+
+		let mut slate = Slate::blank(2);
+		slate.amount = 1_000_000;
+		slate.fee = 7_000;
+		let versioned = VersionedSlate::into_version(slate.clone(), SlateVersion::V3);
+		let encrypted = EncryptedMessage::new(
+			serde_json::to_string(&versioned).unwrap(),
+			&recipient,
+			&recipient_pk,
+			&sender_sk,
+		)
+		.unwrap();
+		let message = serde_json::to_string(&encrypted).unwrap();
+		let signature = sign_challenge(&message, &sender_sk).unwrap().to_hex();
+
+		let (_, proof) = TxProof::from_response(
+			sender.to_string(),
+			message,
+			signature,
+			&recipient_sk,
+			Some(&recipient),
+		)
+		.unwrap();
+		assert_eq!(proof.amount, slate.amount);
+		assert_eq!(proof.fee, slate.fee);
+
+		// survives being exported
+		let proof: TxProof = serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+		let (from, to, res) = proof.verify().unwrap();
+		assert_eq!(from, sender);
+		assert_eq!(to.public_key, recipient.public_key);
+		assert_eq!(res.id, slate.id);
+
+		let mut tampered = proof.clone();
+		tampered.amount += 1;
+		assert!(tampered.verify().is_err());
+
+		let mut tampered = proof.clone();
+		tampered.message.push(' ');
+		assert!(tampered.verify().is_err());
+	}
+}
@@ -15,7 +15,9 @@
 //! Types and traits that should be provided by a wallet
 //! implementation
 
-use crate::config::{EpicboxConfig, TorConfig, WalletConfig};
+use crate::config::{
+    EpicboxConfig, ScanConfig, SeedKdfConfig, SpendPolicy, TorConfig, WalletConfig, WebhookConfig,
+};
 use crate::epic_core::core::hash::Hash;
 use crate::epic_core::core::{Output, Transaction, TxKernel};
 use crate::epic_core::libtx::{aggsig, secp_ser};
@@ -28,6 +30,7 @@ use crate::epic_util::ZeroingString;
 use crate::error::Error;
 use crate::slate::ParticipantMessages;
 use crate::slate_versions::ser as dalek_ser;
+use crate::tx_proof::TxProof;
 use chrono::prelude::*;
 
 use ed25519_dalek::Signature as DalekSignature;
@@ -117,6 +120,28 @@ where
     /// default is assumed to be ~/.epic/main/wallet_data (or floonet equivalent)
     fn get_top_level_directory(&self) -> Result<String, Error>;
 
+    /// Sets the key derivation parameters used whenever the seed file is
+    /// (re)encrypted
+    fn set_seed_kdf_config(&mut self, config: SeedKdfConfig) -> Result<(), Error>;
+
+    /// Sets how UTXO scans spread their work over node requests and threads
+    fn set_scan_config(&mut self, config: ScanConfig) -> Result<(), Error>;
+
+    /// How UTXO scans spread their work over node requests and threads
+    fn scan_config(&self) -> ScanConfig;
+
+    /// Sets where the updater posts wallet events
+    fn set_webhook_config(&mut self, config: WebhookConfig) -> Result<(), Error>;
+
+    /// Where the updater posts wallet events
+    fn webhook_config(&self) -> WebhookConfig;
+
+    /// Sets the limits on what the wallet sends
+    fn set_spend_policy(&mut self, policy: SpendPolicy) -> Result<(), Error>;
+
+    /// Limits on what the wallet sends
+    fn spend_policy(&self) -> SpendPolicy;
+
     /// Output a epic-wallet.toml file into the current top-level system wallet directory
     fn create_config(
         &self,
@@ -128,7 +153,9 @@ where
         epicbox_config: Option<EpicboxConfig>,
     ) -> Result<(), Error>;
 
-    ///
+    /// Create a new wallet. A non-empty `passphrase` is mixed into the
+    /// keychain derivation and is required along with the mnemonic to
+    /// recover the wallet
     fn create_wallet(
         &mut self,
         name: Option<&str>,
@@ -136,6 +163,7 @@ where
         mnemonic_length: usize,
         password: ZeroingString,
         test_mode: bool,
+        passphrase: Option<ZeroingString>,
     ) -> Result<(), Error>;
 
     ///
@@ -169,6 +197,7 @@ where
         &self,
         mnemonic: ZeroingString,
         password: ZeroingString,
+        passphrase: Option<ZeroingString>,
     ) -> Result<(), Error>;
 
     /// changes password
@@ -264,12 +293,24 @@ where
     /// Gets an account path for a given label
     fn get_acct_path(&self, label: String) -> Result<Option<AcctPathMapping>, Error>;
 
+    /// Iterate over all stored address book contacts
+    fn contact_iter<'a>(&'a self) -> Box<dyn Iterator<Item = Contact> + 'a>;
+
+    /// Gets an address book contact by name
+    fn get_contact(&self, name: &str) -> Result<Option<Contact>, Error>;
+
     /// Stores a transaction
     fn store_tx(&self, uuid: &str, tx: &Transaction) -> Result<(), Error>;
 
     /// Retrieves a stored transaction from a TxLogEntry
     fn get_stored_tx(&self, entry: &TxLogEntry) -> Result<Option<Transaction>, Error>;
 
+    /// Stores an epicbox transaction proof
+    fn store_tx_proof(&self, uuid: &str, proof: &TxProof) -> Result<(), Error>;
+
+    /// Retrieves a stored epicbox transaction proof from a TxLogEntry
+    fn get_stored_tx_proof(&self, entry: &TxLogEntry) -> Result<Option<TxProof>, Error>;
+
     /// Create a new write batch to update or remove output data
     fn batch<'a>(
         &'a mut self,
@@ -291,6 +332,30 @@ where
     /// last block scanned during scan or restore
     fn last_scanned_block<'a>(&mut self) -> Result<ScannedBlockInfo, Error>;
 
+    /// Progress of an unfinished scan, if there is one
+    fn scan_checkpoint<'a>(&mut self) -> Result<Option<ScanCheckpoint>, Error>;
+
+    /// Outputs found so far by an unfinished scan
+    fn scanned_outputs<'a>(&'a self) -> Box<dyn Iterator<Item = ScannedOutput> + 'a>;
+
+    /// Chain tips recent updates scanned up to
+    fn scanned_block_history<'a>(&mut self) -> Result<ScannedBlockHistory, Error>;
+
+    /// Webhook events already queued for each transaction
+    fn webhook_tx_state_iter<'a>(&'a self) -> Box<dyn Iterator<Item = WebhookTxState> + 'a>;
+
+    /// Webhook deliveries waiting to be made
+    fn webhook_delivery_iter<'a>(&'a self) -> Box<dyn Iterator<Item = WebhookDelivery> + 'a>;
+
+    /// Chain height webhook events started being queued at, if they ever were
+    fn webhooks_init_height<'a>(&mut self) -> Result<Option<u64>, Error>;
+
+    /// Slates sent over epicbox and their delivery state
+    fn outbox_iter<'a>(&'a self) -> Box<dyn Iterator<Item = OutboxEntry> + 'a>;
+
+    /// Status of the epicbox listener, if it ever ran
+    fn epicbox_status<'a>(&mut self) -> Result<Option<EpicboxListenerStatus>, Error>;
+
     /// Flag whether the wallet needs a full UTXO scan on next update attempt
     fn init_status<'a>(&mut self) -> Result<WalletInitStatus, Error>;
 }
@@ -346,6 +411,39 @@ where
     /// Save flag indicating whether wallet needs a full UTXO scan
     fn save_init_status<'a>(&mut self, value: WalletInitStatus) -> Result<(), Error>;
 
+    /// Save the progress of a running scan
+    fn save_scan_checkpoint(&mut self, checkpoint: ScanCheckpoint) -> Result<(), Error>;
+
+    /// Save an output found by a running scan
+    fn save_scanned_output(&mut self, output: ScannedOutput) -> Result<(), Error>;
+
+    /// Remove the scan progress and the outputs it found once the scan completed
+    fn delete_scan_checkpoint(&mut self) -> Result<(), Error>;
+
+    /// Save the chain tips recent updates scanned up to
+    fn save_scanned_block_history(&mut self, history: ScannedBlockHistory) -> Result<(), Error>;
+
+    /// Save the webhook events queued for a transaction
+    fn save_webhook_tx_state(&mut self, state: WebhookTxState) -> Result<(), Error>;
+
+    /// Add or update a webhook delivery
+    fn save_webhook_delivery(&mut self, delivery: WebhookDelivery) -> Result<(), Error>;
+
+    /// Remove a webhook delivery once made or given up on
+    fn delete_webhook_delivery(&mut self, id: &Uuid) -> Result<(), Error>;
+
+    /// Record the chain height webhook events started being queued at
+    fn save_webhooks_init_height(&mut self, height: u64) -> Result<(), Error>;
+
+    /// Add or update a slate in the epicbox outbox
+    fn save_outbox_entry(&mut self, entry: OutboxEntry) -> Result<(), Error>;
+
+    /// Remove a slate from the epicbox outbox
+    fn delete_outbox_entry(&mut self, slate_id: &Uuid) -> Result<(), Error>;
+
+    /// Save the status of the epicbox listener
+    fn save_epicbox_status(&mut self, status: EpicboxListenerStatus) -> Result<(), Error>;
+
     /// get next output history table id
     fn next_output_history_id(&mut self) -> Result<u32, Error>;
 
@@ -364,6 +462,12 @@ where
     /// Iterate over account names stored in backend
     fn acct_path_iter(&self) -> Box<dyn Iterator<Item = AcctPathMapping>>;
 
+    /// Add or update an address book contact
+    fn save_contact(&mut self, contact: Contact) -> Result<(), Error>;
+
+    /// Delete an address book contact by name
+    fn delete_contact(&mut self, name: &str) -> Result<(), Error>;
+
     /// Save an output as locked in the backend
     fn lock_output(&mut self, out: &mut OutputData) -> Result<(), Error>;
 
@@ -416,6 +520,9 @@ pub trait NodeClient: Send + Sync + Clone {
     /// retrieves the current tip (height, hash) from the specified epic node
     fn get_chain_tip(&self) -> Result<(u64, String), Error>;
 
+    /// retrieves the hash of the block header at the given height
+    fn get_header_hash(&self, height: u64) -> Result<String, Error>;
+
     /// Get a kernel and the height of the block it's included in. Returns
     /// (tx_kernel, height, mmr_index)
     fn get_kernel(
@@ -465,6 +572,26 @@ pub trait NodeClient: Send + Sync + Clone {
 
     /// Optionally get onion addresses from the node (owner API)
     fn get_onion_addresses(&self) -> Result<Vec<String>, Error>;
+
+    /// Hit and miss counters of the client's lookup cache, if it has one
+    fn cache_stats(&self) -> Option<NodeCacheStats> {
+        None
+    }
+}
+
+/// Node cache hit and miss counters
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct NodeCacheStats {
+    /// Kernel lookups answered from the cache
+    pub kernel_hits: u64,
+    /// Kernel lookups sent to the node
+    pub kernel_misses: u64,
+    /// Output commitments answered from the cache
+    pub output_hits: u64,
+    /// Output commitments sent to the node
+    pub output_misses: u64,
+    /// Number of reorgs that cleared the cache
+    pub reorgs: u64,
 }
 
 /// Node version info
@@ -511,6 +638,13 @@ pub struct OutputData {
     pub is_coinbase: bool,
     /// Optional corresponding internal entry in tx entry log
     pub tx_log_entry: Option<u32>,
+    /// Whether the output has been frozen by the user. Frozen outputs are
+    /// never selected for spending until unfrozen.
+    #[serde(default)]
+    pub frozen: bool,
+    /// Private, user-supplied label for this output
+    #[serde(default)]
+    pub label: Option<String>,
 }
 
 impl ser::Writeable for OutputData {
@@ -555,6 +689,8 @@ impl OutputData {
     pub fn eligible_to_spend(&self, current_height: u64, minimum_confirmations: u64) -> bool {
         if [OutputStatus::Spent, OutputStatus::Locked].contains(&self.status) {
             return false;
+        } else if self.frozen {
+            return false;
         } else if self.status == OutputStatus::Unconfirmed && self.is_coinbase {
             return false;
         } else if self.lock_height > current_height {
@@ -570,6 +706,11 @@ impl OutputData {
         }
     }
 
+    /// Whether this output's label contains the given query (case insensitive)
+    pub fn label_matches(&self, query: &str) -> bool {
+        label_matches(&self.label, query)
+    }
+
     /// Marks this output as unspent if it was previously unconfirmed
     pub fn mark_unspent(&mut self) {
         match self.status {
@@ -587,6 +728,14 @@ impl OutputData {
         }
     }
 }
+
+fn label_matches(label: &Option<String>, query: &str) -> bool {
+    match label {
+        Some(l) => l.to_lowercase().contains(&query.to_lowercase()),
+        None => false,
+    }
+}
+
 /// Status of an output that's being tracked by the wallet. Can either be
 /// unconfirmed, spent, unspent, or locked (when it's been used to generate
 /// a transaction but we don't have confirmation that the transaction was
@@ -617,6 +766,92 @@ impl fmt::Display for OutputStatus {
     }
 }
 
+/// Picks the outputs to spend in a transaction from the set of outputs
+/// eligible for spending. Implementations are given eligible outputs sorted by
+/// increasing value and should return `None` if they can't cover the amount.
+pub trait CoinSelectionStrategy {
+    /// Whether this strategy may spend the given output at all. Outputs for
+    /// which this returns `false` are never offered to `select`.
+    fn can_spend(&self, _output: &OutputData) -> bool {
+        true
+    }
+
+    /// Select outputs from `eligible` whose total value is at least `amount`.
+    /// `max_outputs` is a soft limit on the number of outputs to select.
+    fn select(
+        &self,
+        amount: u64,
+        max_outputs: usize,
+        eligible: &[OutputData],
+    ) -> Option<Vec<OutputData>>;
+}
+
+/// Built-in coin selection strategies
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionStrategy {
+    /// Spend as many outputs as possible, up to `max_outputs`
+    All,
+    /// Spend the smallest outputs first, only as many as needed
+    Smallest,
+    /// Search for a set of outputs matching amount + fee exactly, so no change
+    /// output is needed. Falls back to `Smallest` if there is no exact match.
+    BranchAndBound,
+    /// Spend the largest outputs first, minimizing the number of inputs
+    Largest,
+    /// Spend the outputs with the lowest block height first
+    Oldest,
+    /// Prefer outputs created by the same transaction, so that spending them
+    /// together links as few of our previous transactions as possible
+    Privacy,
+}
+
+impl SelectionStrategy {
+    /// All built-in strategies
+    pub fn all() -> Vec<SelectionStrategy> {
+        vec![
+            SelectionStrategy::Smallest,
+            SelectionStrategy::All,
+            SelectionStrategy::BranchAndBound,
+            SelectionStrategy::Largest,
+            SelectionStrategy::Oldest,
+            SelectionStrategy::Privacy,
+        ]
+    }
+}
+
+impl fmt::Display for SelectionStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            SelectionStrategy::All => write!(f, "all"),
+            SelectionStrategy::Smallest => write!(f, "smallest"),
+            SelectionStrategy::BranchAndBound => write!(f, "branch_and_bound"),
+            SelectionStrategy::Largest => write!(f, "largest"),
+            SelectionStrategy::Oldest => write!(f, "oldest"),
+            SelectionStrategy::Privacy => write!(f, "privacy"),
+        }
+    }
+}
+
+impl std::str::FromStr for SelectionStrategy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(SelectionStrategy::All),
+            "smallest" => Ok(SelectionStrategy::Smallest),
+            "branch_and_bound" => Ok(SelectionStrategy::BranchAndBound),
+            "largest" => Ok(SelectionStrategy::Largest),
+            "oldest" => Ok(SelectionStrategy::Oldest),
+            "privacy" => Ok(SelectionStrategy::Privacy),
+            _ => Err(Error::ArgumentError(format!(
+                "Unknown selection strategy: {}",
+                s
+            ))),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 /// Holds the context for a single aggsig transaction
 pub struct Context {
@@ -801,6 +1036,9 @@ pub struct WalletInfo {
     /// amount locked via previous transactions
     #[serde(with = "secp_ser::string_or_u64")]
     pub amount_locked: u64,
+    /// amount in outputs frozen by the user, not included in any other bucket
+    #[serde(default, with = "secp_ser::string_or_u64")]
+    pub amount_frozen: u64,
 }
 
 /// Types of transactions that can be contained within a TXLog entry
@@ -882,10 +1120,18 @@ pub struct TxLogEntry {
     #[serde(with = "secp_ser::opt_string_or_u64")]
     #[serde(default)]
     pub ttl_cutoff_height: Option<u64>,
+    /// Height the kernel is locked until, if it is height locked
+    #[serde(with = "secp_ser::opt_string_or_u64")]
+    #[serde(default)]
+    pub lock_height: Option<u64>,
     /// Message data, stored as json
     pub messages: Option<ParticipantMessages>,
     /// Location of the store transaction, (reference or resending)
     pub stored_tx: Option<String>,
+    /// Location of the stored epicbox proof of the slate received for this
+    /// transaction
+    #[serde(default)]
+    pub tx_proof: Option<String>,
     /// Associated kernel excess, for later lookup if necessary
     #[serde(with = "secp_ser::option_commitment_serde")]
     #[serde(default)]
@@ -900,6 +1146,9 @@ pub struct TxLogEntry {
     /// From or To Address tx was send/received
     #[serde(default)]
     pub public_addr: Option<String>,
+    /// Private, user-supplied label for this transaction
+    #[serde(default)]
+    pub label: Option<String>,
 }
 
 impl ser::Writeable for TxLogEntry {
@@ -932,16 +1181,24 @@ impl TxLogEntry {
             num_outputs: 0,
             fee: None,
             ttl_cutoff_height: None,
+            lock_height: None,
             messages: None,
             stored_tx: None,
+            tx_proof: None,
             kernel_excess: None,
             kernel_lookup_min_height: None,
             payment_proof: None,
             public_addr: None,
             confirmation_height: None,
+            label: None,
         }
     }
 
+    /// Whether this entry's label contains the given query (case insensitive)
+    pub fn label_matches(&self, query: &str) -> bool {
+        label_matches(&self.label, query)
+    }
+
     /// Given a vec of TX log entries, return credited + debited sums
     pub fn sum_confirmed(txs: &Vec<TxLogEntry>) -> (u64, u64) {
         txs.iter().fold((0, 0), |acc, tx| match tx.confirmed {
@@ -1011,6 +1268,47 @@ impl ser::Readable for AcctPathMapping {
     }
 }
 
+/// Address book entry, mapping a name to a send method and destination
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Contact {
+    /// Name used to refer to the contact, e.g. `send -d @name`
+    pub name: String,
+    /// Method used to send to the contact (http, tor, epicbox or keybase)
+    pub method: String,
+    /// Destination address for the given method
+    pub destination: String,
+    /// Optional payment proof address of the contact
+    #[serde(default)]
+    pub proof_address: Option<String>,
+}
+
+impl Contact {
+    /// Whether the given counterparty address refers to this contact.
+    /// Only the scheme and host or domain are compared case insensitively,
+    /// epicbox keys are base58 and url paths may be case sensitive.
+    pub fn matches_address(&self, addr: &str) -> bool {
+        fn normalize(a: &str) -> String {
+            let a = a.trim();
+            let a = ["http://", "https://"]
+                .iter()
+                .find_map(|p| match a.get(..p.len()) {
+                    Some(s) if s.eq_ignore_ascii_case(p) => Some(&a[p.len()..]),
+                    _ => None,
+                })
+                .unwrap_or(a)
+                .trim_end_matches('/');
+            match a.rsplit_once('@') {
+                Some((key, domain)) => format!("{}@{}", key, domain.to_lowercase()),
+                None => match a.split_once('/') {
+                    Some((host, path)) => format!("{}/{}", host.to_lowercase(), path),
+                    None => a.to_lowercase(),
+                },
+            }
+        }
+        !addr.trim().is_empty() && normalize(&self.destination) == normalize(addr)
+    }
+}
+
 /// Dummy wrapper for the hex-encoded serialized transaction.
 #[derive(Serialize, Deserialize)]
 pub struct TxWrapper {
@@ -1044,6 +1342,367 @@ impl ser::Readable for ScannedBlockInfo {
     }
 }
 
+/// Number of scanned tips kept in the history
+pub const SCANNED_BLOCK_HISTORY_LEN: usize = 200;
+
+/// Chain tips the wallet scanned up to, oldest first. Checked against the
+/// node's chain to notice when it forks under the wallet.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScannedBlockHistory {
+    /// Height and hash of each scanned tip
+    pub blocks: Vec<(u64, String)>,
+}
+
+impl ScannedBlockHistory {
+    /// Record a scanned tip, replacing any recorded at or above its height
+    pub fn push(&mut self, height: u64, hash: String) {
+        self.truncate(height);
+        self.blocks.push((height, hash));
+        if self.blocks.len() > SCANNED_BLOCK_HISTORY_LEN {
+            let excess = self.blocks.len() - SCANNED_BLOCK_HISTORY_LEN;
+            self.blocks.drain(..excess);
+        }
+    }
+
+    /// Forget the tips at or above the given height
+    pub fn truncate(&mut self, height: u64) {
+        self.blocks.retain(|b| b.0 < height);
+    }
+}
+
+impl ser::Writeable for ScannedBlockHistory {
+    fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+        writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+    }
+}
+
+impl ser::Readable for ScannedBlockHistory {
+    fn read(reader: &mut dyn ser::Reader) -> Result<ScannedBlockHistory, ser::Error> {
+        let data = reader.read_bytes_len_prefix()?;
+        serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+    }
+}
+
+/// Wallet events posted to webhooks
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventType {
+    /// A transaction paying the wallet was received
+    Received,
+    /// A transaction reached the configured number of confirmations
+    Confirmed,
+    /// A transaction was cancelled
+    Cancelled,
+    /// A transaction reported confirmed left the chain in a reorg
+    Reorged,
+}
+
+impl fmt::Display for WebhookEventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            WebhookEventType::Received => "received",
+            WebhookEventType::Confirmed => "confirmed",
+            WebhookEventType::Cancelled => "cancelled",
+            WebhookEventType::Reorged => "reorged",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Payload posted to webhooks
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WebhookEvent {
+    /// Unique id of the event, the same for every URL it's posted to
+    pub event_id: Uuid,
+    /// What happened
+    pub event: WebhookEventType,
+    /// When the event was noticed
+    pub created: DateTime<Utc>,
+    /// Number of confirmations of the transaction at that point
+    pub confirmations: u64,
+    /// The transaction concerned
+    pub tx: TxLogEntry,
+}
+
+/// Webhook event waiting to be posted to one URL
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WebhookDelivery {
+    /// Unique id of the delivery
+    pub delivery_id: Uuid,
+    /// URL the event is posted to
+    pub url: String,
+    /// Kind of event
+    pub event: WebhookEventType,
+    /// Serialized [WebhookEvent](struct.WebhookEvent.html), posted as is
+    pub payload: String,
+    /// Number of failed attempts so far
+    pub attempts: u32,
+    /// Not to be attempted before
+    pub next_attempt: DateTime<Utc>,
+}
+
+/// Webhook events already queued for a transaction
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WebhookTxState {
+    /// Account the transaction belongs to
+    pub parent_key_id: Identifier,
+    /// Transaction log id
+    pub tx_id: u32,
+    /// Whether `received` was queued
+    pub received: bool,
+    /// Whether `confirmed` was queued and not reorged since
+    pub confirmed: bool,
+    /// Whether `cancelled` was queued
+    pub cancelled: bool,
+}
+
+impl WebhookTxState {
+    /// Nothing queued yet for the given transaction
+    pub fn new(tx: &TxLogEntry) -> WebhookTxState {
+        WebhookTxState {
+            parent_key_id: tx.parent_key_id.clone(),
+            tx_id: tx.id,
+            received: false,
+            confirmed: false,
+            cancelled: false,
+        }
+    }
+}
+
+/// Posts webhook payloads
+pub trait WebhookSender: Send + Sync {
+    /// Post the body to the URL with the given extra headers. Anything but a
+    /// successful response is an error.
+    fn post(&self, url: &str, body: &str, headers: &[(String, String)]) -> Result<(), Error>;
+}
+
+/// Delivery state of a slate sent over epicbox
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Not handed to the relay yet
+    Queued,
+    /// Sent to the relay, which didn't confirm it
+    Posted,
+    /// Accepted by the relay, waiting for the recipient
+    Acknowledged,
+    /// The recipient's response came back
+    ResponseReceived,
+    /// Given up on, the TTL was reached or the transaction cancelled
+    Expired,
+}
+
+impl DeliveryStatus {
+    /// Whether nothing more is sent for the slate
+    pub fn is_final(&self) -> bool {
+        match self {
+            DeliveryStatus::Acknowledged
+            | DeliveryStatus::ResponseReceived
+            | DeliveryStatus::Expired => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for DeliveryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            DeliveryStatus::Queued => "Queued",
+            DeliveryStatus::Posted => "Posted",
+            DeliveryStatus::Acknowledged => "Acknowledged",
+            DeliveryStatus::ResponseReceived => "Response Received",
+            DeliveryStatus::Expired => "Expired",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Slate sent over epicbox, kept along with its delivery state
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OutboxEntry {
+    /// Id of the slate
+    pub slate_id: Uuid,
+    /// Epicbox address of the recipient
+    pub dest: String,
+    /// Serialized versioned slate, sent as is
+    pub payload: String,
+    /// Delivery state
+    pub status: DeliveryStatus,
+    /// Number of times the slate was sent, or tried to be
+    pub attempts: u32,
+    /// When the slate was queued
+    pub created: DateTime<Utc>,
+    /// When the slate was last sent, or tried to be
+    pub last_attempt: Option<DateTime<Utc>>,
+    /// Not to be sent again before
+    pub next_attempt: DateTime<Utc>,
+    /// Height the slate expires at, if any
+    pub ttl_cutoff_height: Option<u64>,
+    /// Why the last attempt failed
+    pub last_error: Option<String>,
+}
+
+/// State of the epicbox listener's connection to its relays
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EpicboxConnectionState {
+    /// Connecting and subscribing to the relays
+    Connecting,
+    /// Subscribed, slates sent to the wallet are received
+    Listening,
+    /// The connection dropped or stalled, waiting to connect again
+    Reconnecting,
+}
+
+impl fmt::Display for EpicboxConnectionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            EpicboxConnectionState::Connecting => "Connecting",
+            EpicboxConnectionState::Listening => "Listening",
+            EpicboxConnectionState::Reconnecting => "Reconnecting",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Health of the epicbox listener, as last recorded by it. The listener
+/// runs in a process of its own, a `Listening` status that isn't refreshed
+/// by heartbeats anymore means it's gone.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EpicboxListenerStatus {
+    /// Connection state
+    pub state: EpicboxConnectionState,
+    /// Failed attempts to connect since the listener last received anything
+    pub reconnect_attempts: u32,
+    /// When the status was recorded, refreshed with every heartbeat while
+    /// listening
+    pub updated: DateTime<Utc>,
+    /// When the listener connects again, while reconnecting
+    pub next_attempt: Option<DateTime<Utc>>,
+    /// Why the connection was lost
+    pub last_error: Option<String>,
+}
+
+/// An output found on chain during a scan, not yet reconciled with the wallet
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScannedOutput {
+    /// Output commitment
+    #[serde(
+        serialize_with = "secp_ser::as_hex",
+        deserialize_with = "secp_ser::commitment_from_hex"
+    )]
+    pub commit: pedersen::Commitment,
+    /// Key the output was rewound with
+    pub key_id: Identifier,
+    /// Child index of the key
+    pub n_child: u32,
+    /// PMMR index of the output
+    pub mmr_index: u64,
+    /// Value of the output
+    pub value: u64,
+    /// Height of the block the output was found in
+    pub height: u64,
+    /// Height from which the output can be spent
+    pub lock_height: u64,
+    /// Whether the output is a coinbase output
+    pub is_coinbase: bool,
+}
+
+/// Progress of a scan, saved after every batch of PMMR indices so that an
+/// interrupted scan can pick up where it left off. Only the position is kept
+/// here, the outputs found are saved one by one as they turn up.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScanCheckpoint {
+    /// Block height the scan started from
+    pub start_height: u64,
+    /// Block height the scan runs up to
+    pub end_height: u64,
+    /// First PMMR index of the scanned range
+    pub start_pmmr_index: u64,
+    /// Last PMMR index of the scanned range
+    pub end_pmmr_index: u64,
+    /// Last PMMR index processed so far, if any
+    pub last_pmmr_index: Option<u64>,
+    /// Number of outputs belonging to the wallet found so far
+    #[serde(default)]
+    pub outputs_found: usize,
+}
+
+impl ScanCheckpoint {
+    /// Fresh checkpoint for a scan of the given height and PMMR index range
+    pub fn new(start_height: u64, end_height: u64, pmmr_range: (u64, u64)) -> ScanCheckpoint {
+        ScanCheckpoint {
+            start_height,
+            end_height,
+            start_pmmr_index: pmmr_range.0,
+            end_pmmr_index: pmmr_range.1,
+            last_pmmr_index: None,
+            outputs_found: 0,
+        }
+    }
+
+    /// PMMR index the scan continues from
+    pub fn next_pmmr_index(&self) -> u64 {
+        match self.last_pmmr_index {
+            Some(i) => i + 1,
+            None => self.start_pmmr_index,
+        }
+    }
+
+    /// Summary of the checkpoint, for reporting
+    pub fn progress(&self) -> ScanProgress {
+        let range = self.end_pmmr_index.saturating_sub(self.start_pmmr_index);
+        let done = self
+            .last_pmmr_index
+            .map_or(0, |i| i.saturating_sub(self.start_pmmr_index));
+        let percentage_complete = match range {
+            0 => 100,
+            r => std::cmp::min(done * 100 / r, 100) as u8,
+        };
+        ScanProgress {
+            start_height: self.start_height,
+            end_height: self.end_height,
+            start_pmmr_index: self.start_pmmr_index,
+            end_pmmr_index: self.end_pmmr_index,
+            last_pmmr_index: self.last_pmmr_index,
+            outputs_found: self.outputs_found,
+            percentage_complete,
+        }
+    }
+}
+
+impl ser::Writeable for ScanCheckpoint {
+    fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+        writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+    }
+}
+
+impl ser::Readable for ScanCheckpoint {
+    fn read(reader: &mut dyn ser::Reader) -> Result<ScanCheckpoint, ser::Error> {
+        let data = reader.read_bytes_len_prefix()?;
+        serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+    }
+}
+
+/// Progress of a running or interrupted scan
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScanProgress {
+    /// Block height the scan started from
+    pub start_height: u64,
+    /// Block height the scan runs up to
+    pub end_height: u64,
+    /// First PMMR index of the scanned range
+    pub start_pmmr_index: u64,
+    /// Last PMMR index of the scanned range
+    pub end_pmmr_index: u64,
+    /// Last PMMR index processed so far, if any
+    pub last_pmmr_index: Option<u64>,
+    /// Number of wallet outputs found so far
+    pub outputs_found: usize,
+    /// Percentage of the PMMR range processed
+    pub percentage_complete: u8,
+}
+
 /// Wrapper for reward output and kernel used when building a coinbase for a mining node.
 /// Note: Not serializable, must be converted to necesssary "versioned" representation
 /// before serializing to json to ensure compatibility with mining node.
@@ -0,0 +1,132 @@
+// Copyright 2019 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standalone epicbox relay, for testing and for running a relay of one's own
+
+#[macro_use]
+extern crate log;
+use clap::{Arg, Command};
+use epic_wallet_impls::epicbox::relay::{EpicboxRelay, RelayLimits, DEFAULT_EXPIRY_SECS};
+use epic_wallet_util::epic_util::init_logger;
+use epic_wallet_util::epic_util::logger::LoggingConfig;
+use std::time::Duration;
+
+fn main() {
+	let args = Command::new("epicbox-relay")
+		.version(env!("CARGO_PKG_VERSION"))
+		.about("Relays slates between epic wallets over epicbox")
+		.arg(
+			Arg::new("listen")
+				.short('l')
+				.long("listen")
+				.help("Address to listen on, serve it behind a TLS proxy for wss://")
+				.num_args(1)
+				.default_value("127.0.0.1:3420"),
+		)
+		.arg(
+			Arg::new("expiry")
+				.short('e')
+				.long("expiry")
+				.help("Seconds slates are kept for if their recipient doesn't pick them up")
+				.num_args(1)
+				.value_parser(clap::value_parser!(u64)),
+		)
+		.arg(
+			Arg::new("max_mailbox")
+				.long("max_mailbox")
+				.help("Most slates kept for an address")
+				.num_args(1)
+				.value_parser(clap::value_parser!(usize)),
+		)
+		.arg(
+			Arg::new("max_slate_size")
+				.long("max_slate_size")
+				.help("Largest slate accepted, in bytes")
+				.num_args(1)
+				.value_parser(clap::value_parser!(usize)),
+		)
+		.arg(
+			Arg::new("max_sender")
+				.long("max_sender")
+				.help("Most slates kept from an address, over all mailboxes")
+				.num_args(1)
+				.value_parser(clap::value_parser!(usize)),
+		)
+		.arg(
+			Arg::new("max_stored")
+				.long("max_stored")
+				.help("Most bytes of slates kept over all mailboxes")
+				.num_args(1)
+				.value_parser(clap::value_parser!(usize)),
+		)
+		.arg(
+			Arg::new("max_connections")
+				.long("max_connections")
+				.help("Most wallets connected at once")
+				.num_args(1)
+				.value_parser(clap::value_parser!(usize)),
+		)
+		.arg(
+			Arg::new("idle_timeout")
+				.long("idle_timeout")
+				.help("Seconds a connection may go without subscribing or posting a slate")
+				.num_args(1)
+				.value_parser(clap::value_parser!(u64)),
+		)
+		.get_matches();
+
+	init_logger(
+		Some(LoggingConfig {
+			log_to_file: false,
+			..LoggingConfig::default()
+		}),
+		None,
+	);
+
+	let listen = args.get_one::<String>("listen").unwrap();
+	let expiry = args.get_one::<u64>("expiry").copied();
+	let expiry = Duration::from_secs(expiry.unwrap_or(DEFAULT_EXPIRY_SECS));
+	let defaults = RelayLimits::default();
+	let limits = RelayLimits {
+		max_mailbox_len: args
+			.get_one::<usize>("max_mailbox")
+			.copied()
+			.unwrap_or(defaults.max_mailbox_len),
+		max_slate_len: args
+			.get_one::<usize>("max_slate_size")
+			.copied()
+			.unwrap_or(defaults.max_slate_len),
+		max_sender_len: args
+			.get_one::<usize>("max_sender")
+			.copied()
+			.unwrap_or(defaults.max_sender_len),
+		max_stored_len: args
+			.get_one::<usize>("max_stored")
+			.copied()
+			.unwrap_or(defaults.max_stored_len),
+		max_connections: args
+			.get_one::<usize>("max_connections")
+			.copied()
+			.unwrap_or(defaults.max_connections),
+		idle_timeout: args
+			.get_one::<u64>("idle_timeout")
+			.map(|s| Duration::from_secs(*s))
+			.unwrap_or(defaults.idle_timeout),
+	};
+	let res = EpicboxRelay::bind(listen, expiry).and_then(|relay| relay.limits(limits).run());
+	if let Err(e) = res {
+		error!("Epicbox relay stopped: {}", e);
+		std::process::exit(1);
+	}
+}
@@ -15,7 +15,7 @@
 use crate::cmd::wallet_args;
 use crate::config::GlobalWalletConfig;
 use clap::ArgMatches;
-use epic_wallet_impls::HTTPNodeClient;
+use epic_wallet_impls::{CachingNodeClient, MultiNodeClient};
 use epic_wallet_libwallet::{Error, NodeClient};
 use log::{error, info, warn};
 use semver::Version;
@@ -47,15 +47,16 @@ pub fn wallet_command(wallet_args: &ArgMatches, config: GlobalWalletConfig) -> i
 		_ => (wallet_args.get_flag("offline_mode"), false),
 	};
 
-	// Setup node client, check for provided node URL, else use default
-	let mut node_client = match wallet_args
-		.get_one::<String>("api_server_address")
-		.map(|s| s.as_str())
-	{
-		Some(node_url) => HTTPNodeClient::new(node_url, node_api_secret.clone()).unwrap(),
-		None => match HTTPNodeClient::new(
-			wallet_config.check_node_api_http_addr.as_str(),
+	// Setup node client, check for provided node URL, else use the configured
+	// node along with its fallbacks
+	let node_client = match wallet_args.get_one::<String>("api_server_address") {
+		Some(node_url) => {
+			MultiNodeClient::new(&[node_url.clone()], node_api_secret.clone(), false).unwrap()
+		}
+		None => match MultiNodeClient::new(
+			&wallet_config.node_api_http_addrs(),
 			node_api_secret.clone(),
+			wallet_config.node_cross_check.unwrap_or(false),
 		) {
 			Ok(client) => client,
 			Err(e) => match e {
@@ -68,14 +69,17 @@ pub fn wallet_command(wallet_args: &ArgMatches, config: GlobalWalletConfig) -> i
 					return 1;
 				}
 				_ => {
-					error!("Failed to create HTTPNodeClient: {}", e);
+					error!("Failed to create node client: {}", e);
 					return 1;
 				}
 			},
 		},
 	};
+	// repeated kernel and output lookups are answered from a cache
+	let mut node_client =
+		CachingNodeClient::new(node_client, wallet_config.node_cache.unwrap_or(true));
 
-	info!("Connecting to the node: {} ...", node_client.node_url);
+	info!("Connecting to the node: {} ...", node_client.node_url());
 
 	// Check the node sync status
 	match node_client.get_node_status() {
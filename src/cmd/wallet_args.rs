@@ -20,12 +20,14 @@ use crate::util::{to_hex, Mutex, ZeroingString};
 use clap::ArgMatches;
 use epic_wallet_config::{EpicboxConfig, TorConfig, WalletConfig};
 use epic_wallet_controller::command;
+use epic_wallet_controller::tokens::ApiScope;
 
 use epic_wallet_impls::tor::config::is_tor_address;
 use epic_wallet_impls::{DefaultLCProvider, DefaultWalletImpl};
 use epic_wallet_impls::{PathToSlate, SlateGetter as _};
 use epic_wallet_libwallet::{
-    address, Error, IssueInvoiceTxArgs, NodeClient, Slate, WalletInst, WalletLCProvider,
+    address, armor, Contact, Error, IssueInvoiceTxArgs, NodeClient, SelectionStrategy, Slate,
+    WalletInst, WalletLCProvider,
 };
 use epic_wallet_util::epic_core as core;
 use epic_wallet_util::epic_core::core::amount_to_hr_string;
@@ -144,20 +146,23 @@ pub fn build_cli() -> Command {
 				.help("Minimum number of confirmations required for an output to be spendable").default_value("10").num_args(1))
 				
 				.arg(Arg::new("selection_strategy").short('s').long("selection")
-				.help("Coin/Output selection strategy.").value_parser(["all", "smallest"]).default_value("smallest").num_args(1))
+				.help("Coin/Output selection strategy. 'branch_and_bound' avoids a change output when an exact match exists.").value_parser(["all", "smallest", "branch_and_bound", "largest", "oldest", "privacy"]).default_value("smallest").num_args(1))
 				
 				.arg(Arg::new("estimate_selection_strategies").short('e').long("estimate-selection")
 				.help("Estimates all possible Coin/Output selection strategies.")
 				.action(clap::ArgAction::SetTrue))
 				
+				.arg(Arg::new("inputs").short('i').long("inputs")
+				.help("Comma separated list of output commitments to spend, and no others. Overrides the selection strategy.").num_args(1))
+				
 				.arg(Arg::new("change_outputs").short('o').long("change_outputs")
 				.help("Number of change outputs to generate (mainly for testing)").default_value("1").num_args(1))
 				
 				.arg(Arg::new("method").short('m').long("method")
-				.help("Method for sending this transaction").value_parser(["http", "file", "self", "keybase", "emoji", "epicbox"]).default_value("http").num_args(1))
+				.help("Method for sending this transaction. 'armor' prints an ASCII armored slate, encrypted when a destination epicbox address is given.").value_parser(["http", "file", "self", "keybase", "emoji", "epicbox", "armor"]).default_value("http").num_args(1))
 				
 				.arg(Arg::new("dest").short('d').long("dest")
-				.help("Send the transaction to the provided server (start with http://) or save as file.").num_args(1))
+				.help("Send the transaction to the provided server (start with http://), to an address book contact (@name) or save as file.").num_args(1))
 				
 				.arg(Arg::new("request_payment_proof").short('y').long("request_payment_proof")
 				.help("Request a payment proof from the recipient. If sending to a tor address, the address will be filled automatically.")
@@ -178,8 +183,50 @@ pub fn build_cli() -> Command {
 				.arg(Arg::new("ttl_blocks").short('b').long("ttl_blocks")
 				.help("If present, the number of blocks from the current after which wallets should refuse to process transactions further").num_args(1))
 				
+				.arg(Arg::new("lock_height").long("lock_height")
+				.help("If present, the transaction can't be mined before this block height").num_args(1)
+				.conflicts_with("lock_after"))
+				
+				.arg(Arg::new("lock_after").long("lock_after")
+				.help("If present, the number of blocks from the current before the transaction can be mined").num_args(1))
+				
 				.arg(Arg::new("slate_version").short('v').long("slate_version")
-				.help("Target slate version to create/send").value_parser(clap::value_parser!(u16)).num_args(1))
+				.help("Target slate version to create/send, 4 for compact slates (epicbox, emoji and file)").value_parser(clap::value_parser!(u16)).num_args(1))
+		)
+		.subcommand(
+			Command::new("batch_send")
+				.about("Pays many recipients listed in a CSV or JSON file. Progress is journaled, run again to resume an interrupted batch.")
+				
+				.arg(Arg::new("input").short('i').long("input")
+				.help("Payout file with dest, amount and optional method per recipient").num_args(1).required(true))
+				
+				.arg(Arg::new("format")
+				.long("format")
+				.help("Payout file format: csv or json").value_parser(["csv", "json"]).default_value("csv").num_args(1))
+				
+				.arg(Arg::new("journal").short('j').long("journal")
+				.help("Progress journal file, defaults to the payout file name with a .journal suffix").num_args(1))
+				
+				.arg(Arg::new("method").short('m').long("method")
+				.help("Method for payouts that don't specify one").value_parser(["http", "tor", "keybase", "epicbox"]).default_value("http").num_args(1))
+				
+				.arg(Arg::new("minimum_confirmations").short('c').long("min_conf")
+				.help("Minimum number of confirmations required for an output to be spendable").default_value("10").num_args(1))
+				
+				.arg(Arg::new("selection_strategy").short('s').long("selection")
+				.help("Coin/Output selection strategy.").value_parser(["all", "smallest", "branch_and_bound", "largest", "oldest", "privacy"]).default_value("smallest").num_args(1))
+				
+				.arg(Arg::new("change_outputs").short('o').long("change_outputs")
+				.help("Number of change outputs to generate per payout").default_value("1").num_args(1))
+				
+				.arg(Arg::new("concurrency").short('n').long("concurrency")
+				.help("Number of recipients to dispatch slates to at the same time").default_value("4").num_args(1))
+				
+				.arg(Arg::new("fluff").short('f').long("fluff")
+				.help("Fluff the transactions (ignore Dandelion relay protocol)").action(clap::ArgAction::SetTrue))
+				
+				.arg(Arg::new("message").short('g').long("message")
+				.help("Optional participant message to include with every payout").num_args(1))
 		)
 		.subcommand(
 			Command::new("issue_invoice")
@@ -216,7 +263,7 @@ pub fn build_cli() -> Command {
 				.help("Method of receiving this transaction").value_parser(["file", "emoji"]).default_value("file").num_args(1))
 			   
 				.arg(Arg::new("input").short('i').long("input")
-				.help("Partial transaction to process, expects the sender's transaction file or emoji string.").num_args(1))
+				.help("Partial transaction to process, expects the sender's transaction file, emoji string or armored slate.").num_args(1))
 		)
 		.subcommand(
 			Command::new("finalize")
@@ -226,7 +273,7 @@ pub fn build_cli() -> Command {
 				.help("Method for finalize this transaction").value_parser(["file", "emoji"]).default_value("file").num_args(1))
 				
 				.arg(Arg::new("input").short('i').long("input")
-				.help("Partial transaction to process, expects the receiver's transaction file.").num_args(1))
+				.help("Partial transaction to process, expects the receiver's transaction file or armored slate.").num_args(1))
 				
 				.arg(Arg::new("fluff").short('f').long("fluff")
 				.help("Fluff the transaction (ignore Dandelion relay protocol)")
@@ -264,7 +311,7 @@ pub fn build_cli() -> Command {
 				.help("Minimum number of confirmations required for an output to be spendable").default_value("10").num_args(1))
 			  
 				.arg(Arg::new("selection_strategy").short('s').long("selection")
-				.help("Coin/Output selection strategy.").value_parser(["all", "smallest"]).default_value("all").num_args(1))
+				.help("Coin/Output selection strategy. 'branch_and_bound' avoids a change output when an exact match exists.").value_parser(["all", "smallest", "branch_and_bound", "largest", "oldest", "privacy"]).default_value("all").num_args(1))
 			  
 				.arg(Arg::new("estimate_selection_strategies").short('e').long("estimate-selection")
 				.help("Estimates all possible Coin/Output selection strategies.")
@@ -274,7 +321,7 @@ pub fn build_cli() -> Command {
 				.help("Method for sending the processed invoice back to the invoice creator").value_parser(["file", "http", "self"]).default_value("file").num_args(1))
 			  
 				.arg(Arg::new("dest").short('d').long("dest")
-				.help("Send the transaction to the provided server (start with http://) or save as file.").num_args(1))
+				.help("Send the transaction to the provided server (start with http://), to an address book contact (@name) or save as file.").num_args(1))
 			   
 				.arg(Arg::new("message").short('g').long("message")
 				.help("Optional participant message to include").num_args(1))
@@ -292,6 +339,9 @@ pub fn build_cli() -> Command {
 				.arg(Arg::new("show_full_history").short('f').long("show_full_history")
 				.help("If specified, display full outputs history").action(clap::ArgAction::SetTrue))
 				
+				.arg(Arg::new("frozen").short('z').long("frozen")
+				.help("If specified, only display outputs that have been frozen").action(clap::ArgAction::SetTrue))
+				
 				.arg(Arg::new("limit").short('l').long("limit")
 				.help("Limit the number of transactions to display").num_args(1))
 				
@@ -311,6 +361,9 @@ pub fn build_cli() -> Command {
 				.arg(Arg::new("txid").short('t').long("txid")
 				.help("If specified, display transaction with given TxID UUID and all associated Inputs/Outputs").num_args(1))
 				
+				.arg(Arg::new("label").long("label")
+				.help("If specified, only display transactions whose label contains the given text").num_args(1))
+				
 				.arg(Arg::new("limit").short('l').long("limit")
 				.help("Limit the number of transactions to display").num_args(1))
 				
@@ -355,6 +408,98 @@ pub fn build_cli() -> Command {
 				.arg(Arg::new("txid").short('t').long("txid")
 				.help("The TxID UUID of the transaction to cancel").num_args(1))
 		)
+		.subcommand(
+			Command::new("contacts")
+				.about("Manage the address book of send destinations, usable as '-d @name'")
+				.subcommand(
+					Command::new("list")
+						.about("List all contacts")
+				)
+				.subcommand(
+					Command::new("add")
+						.about("Add a contact, replacing any existing contact with the same name")
+						
+						.arg(Arg::new("name")
+						.help("Name of the contact").index(1))
+						
+						.arg(Arg::new("method").short('m').long("method")
+						.help("Method used to send to the contact").value_parser(["http", "tor", "epicbox", "keybase"]).default_value("epicbox").num_args(1))
+						
+						.arg(Arg::new("dest").short('d').long("dest")
+						.help("Address of the contact for the given method").num_args(1))
+						
+						.arg(Arg::new("proof_address").short('y').long("proof_address")
+						.help("Payment proof address of the contact, requested when sending to it").num_args(1))
+				)
+				.subcommand(
+					Command::new("remove")
+						.about("Remove a contact")
+						
+						.arg(Arg::new("name")
+						.help("Name of the contact").index(1))
+				)
+		)
+		.subcommand(
+			Command::new("api_tokens")
+				.about("Manage the scoped tokens the owner API accepts besides its secret")
+				.subcommand(
+					Command::new("list")
+						.about("List all tokens")
+				)
+				.subcommand(
+					Command::new("issue")
+						.about("Issue a new token, displayed only once")
+						
+						.arg(Arg::new("name")
+						.help("Name of the token").index(1))
+						
+						.arg(Arg::new("scope").short('s').long("scope")
+						.help("What the token allows: reading the wallet, also issuing invoices, or also spending").value_parser(["read", "receive", "spend"]).default_value("read").num_args(1))
+						
+						.arg(Arg::new("daily_limit").short('l').long("daily_limit")
+						.help("Most that can be spent with the token in a day (UTC)").num_args(1))
+				)
+				.subcommand(
+					Command::new("revoke")
+						.about("Revoke a token")
+						
+						.arg(Arg::new("name")
+						.help("Name of the token").index(1))
+				)
+		)
+		.subcommand(
+			Command::new("label")
+				.about("Sets or clears the private label of a transaction or output")
+				
+				.arg(Arg::new("id").short('i').long("id")
+				.help("The ID of the transaction to label").num_args(1))
+				
+				.arg(Arg::new("txid").short('t').long("txid")
+				.help("The TxID UUID of the transaction to label").num_args(1))
+				
+				.arg(Arg::new("commit").short('c').long("commit")
+				.help("Commitment of the output to label").num_args(1))
+				
+				.arg(Arg::new("clear").long("clear")
+				.help("Clear the existing label").action(clap::ArgAction::SetTrue))
+				
+				.arg(Arg::new("label")
+				.help("The label text").index(1))
+		)
+		.subcommand(
+			Command::new("freeze")
+				.about("Freezes an output, excluding it from coin selection until unfrozen")
+				
+				.arg(Arg::new("commit")
+				.help("Commitment of the output to freeze").index(1))
+		)
+		.subcommand(
+			Command::new("unfreeze")
+				.about("Unfreezes a previously frozen output, making it spendable again")
+				
+				.arg(Arg::new("commit")
+				.help("Commitment of the output to unfreeze").index(1))
+		)
 		.subcommand(
 			Command::new("info")
 				.about("Basic wallet contents summary")
@@ -374,6 +519,9 @@ pub fn build_cli() -> Command {
 				
 				.arg(Arg::new("recover").short('r').long("recover")
 				.help("Initialize new wallet using a recovery phrase").action(clap::ArgAction::SetTrue))
+
+				.arg(Arg::new("mnemonic_passphrase").long("mnemonic_passphrase")
+				.help("Prompt for a BIP39 passphrase protecting the recovery phrase. It is required along with the phrase to recover the wallet").action(clap::ArgAction::SetTrue))
 		)
 		.subcommand(
 			Command::new("recover")
@@ -439,6 +587,24 @@ pub fn build_cli() -> Command {
 			.about("Verify a payment proof")
 			.arg(Arg::new("input").help("Filename of a proof file").index(1))
 		)
+		.subcommand(
+			Command::new("export_tx_proof")
+				.about("Export the proof of a slate received over epicbox")
+				
+				.arg(Arg::new("output")
+				.help("Output proof file").index(1))
+				
+				.arg(Arg::new("id").short('i').long("id")
+				.help("If specified, retrieve the proof for the given transaction ID").num_args(1))
+				
+				.arg(Arg::new("txid").short('t').long("txid")
+				.help("If specified, retrieve the proof for the given Slate ID").num_args(1))
+		)
+		.subcommand(
+			Command::new("verify_tx_proof")
+			.about("Verify an epicbox tx proof, without opening the wallet")
+			.arg(Arg::new("input").help("Filename of a tx proof file").index(1))
+		)
 		.subcommand(
 		Command::new("change_password")
 			.about("Change the wallet password")
@@ -476,6 +642,17 @@ fn prompt_password_confirm() -> ZeroingString {
     first
 }
 
+fn prompt_mnemonic_passphrase() -> ZeroingString {
+    println!("The mnemonic passphrase is not stored anywhere. Losing it means losing the wallet.");
+    let mut first = ZeroingString::from("first");
+    let mut second = ZeroingString::from("second");
+    while first != second {
+        first = prompt_password_stdout("Mnemonic passphrase: ");
+        second = prompt_password_stdout("Confirm mnemonic passphrase: ");
+    }
+    first
+}
+
 fn prompt_recovery_phrase<L, C, K>(
     wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
 ) -> Result<ZeroingString, Error>
@@ -589,6 +766,10 @@ where
         as Box<dyn WalletInst<'static, L, C, K>>;
     let lc = wallet.lc_provider().unwrap();
     let _ = lc.set_top_level_directory(&config.data_file_dir);
+    let _ = lc.set_seed_kdf_config(config.seed_kdf());
+    let _ = lc.set_scan_config(config.scan());
+    let _ = lc.set_webhook_config(config.webhooks());
+    let _ = lc.set_spend_policy(config.spend_policy());
     Ok(Arc::new(Mutex::new(wallet)))
 }
 
@@ -710,6 +891,12 @@ where
         None
     };
 
+    let mnemonic_passphrase = if args.get_flag("mnemonic_passphrase") {
+        Some(prompt_mnemonic_passphrase())
+    } else {
+        None
+    };
+
     if recovery_phrase.is_some() {
         println!("Please provide a new password for the recovered wallet");
     } else {
@@ -726,6 +913,7 @@ where
         password,
         config: config.clone(),
         recovery_phrase,
+        mnemonic_passphrase,
         restore: false,
     })
 }
@@ -797,7 +985,8 @@ pub fn parse_send_args(args: &ArgMatches) -> Result<command::SendArgs, Error> {
     let min_c = parse_u64(min_c, "minimum_confirmations")?;
 
     // selection_strategy
-    let selection_strategy = parse_required(args, "selection_strategy")?;
+    let selection_strategy: SelectionStrategy =
+        parse_required(args, "selection_strategy")?.parse()?;
 
     // estimate_selection_strategies
     let estimate_selection_strategies = args.get_flag("estimate_selection_strategies");
@@ -814,6 +1003,12 @@ pub fn parse_send_args(args: &ArgMatches) -> Result<command::SendArgs, Error> {
             }
         } else if method == "emoji" {
             ""
+        } else if method == "armor" {
+            // armored slates are only encrypted when given a recipient
+            match args.get_one::<String>("dest").map(|s| s.as_str()) {
+                Some(d) => d,
+                None => "",
+            }
         } else {
             if !estimate_selection_strategies {
                 parse_required(args, "dest")?
@@ -823,7 +1018,11 @@ pub fn parse_send_args(args: &ArgMatches) -> Result<command::SendArgs, Error> {
         }
     };
 
+    // address book contacts are resolved when sending
+    let is_contact = dest.starts_with('@');
+
     if !estimate_selection_strategies
+        && !is_contact
         && method == "http"
         && !dest.starts_with("http://")
         && !dest.starts_with("https://")
@@ -846,9 +1045,27 @@ pub fn parse_send_args(args: &ArgMatches) -> Result<command::SendArgs, Error> {
     // ttl_blocks
     let ttl_blocks = parse_u64_or_none(args.get_one::<String>("ttl_blocks").map(|s| s.as_str()));
 
+    // lock_height / lock_after
+    let lock_height = match args.get_one::<String>("lock_height") {
+        Some(h) => Some(parse_u64(h, "lock_height")?),
+        None => None,
+    };
+    let lock_after_blocks = match args.get_one::<String>("lock_after") {
+        Some(b) => Some(parse_u64(b, "lock_after")?),
+        None => None,
+    };
+
     // max_outputs
     let max_outputs = 500;
 
+    // inputs
+    let inputs = args.get_one::<String>("inputs").map(|i| {
+        i.split(',')
+            .map(|c| c.trim().to_owned())
+            .filter(|c| !c.is_empty())
+            .collect::<Vec<String>>()
+    });
+
     // target slate version to create/send
     let target_slate_version = args.get_one::<u16>("slate_version").map(|v| *v);
 
@@ -859,6 +1076,8 @@ pub fn parse_send_args(args: &ArgMatches) -> Result<command::SendArgs, Error> {
                 // separately
                 match address::pubkey_from_onion_v3(&dest) {
                     Ok(k) => Some(to_hex(k.to_bytes().to_vec())),
+                    // fall back to the proof address stored with the contact
+                    Err(_) if is_contact => args.get_one::<String>("proof_address").cloned(),
                     Err(_) => Some(parse_required(args, "proof_address")?.to_owned()),
                 }
             }
@@ -870,19 +1089,80 @@ pub fn parse_send_args(args: &ArgMatches) -> Result<command::SendArgs, Error> {
         amount,
         message,
         minimum_confirmations: min_c,
-        selection_strategy: selection_strategy.to_owned(),
+        selection_strategy,
         estimate_selection_strategies,
         method: method.to_owned(),
         dest: dest.to_owned(),
         change_outputs,
         fluff,
         max_outputs,
+        inputs,
         payment_proof_address,
         ttl_blocks,
+        lock_height,
+        lock_after_blocks,
         target_slate_version,
     })
 }
 
+pub fn parse_batch_send_args(args: &ArgMatches) -> Result<command::BatchSendArgs, Error> {
+    // input
+    let input = parse_required(args, "input")?;
+    if !Path::new(&input).is_file() {
+        let msg = format!("File {} not found.", input);
+        return Err(Error::ArgumentError(msg));
+    }
+
+    // format
+    let format = parse_required(args, "format")?;
+
+    // journal
+    let journal = args.get_one::<String>("journal").cloned();
+
+    // method
+    let method = parse_required(args, "method")?;
+
+    // message
+    let message = args.get_one::<String>("message").map(|s| s.to_owned());
+
+    // minimum_confirmations
+    let min_c = parse_required(args, "minimum_confirmations")?;
+    let min_c = parse_u64(min_c, "minimum_confirmations")?;
+
+    // selection_strategy
+    let selection_strategy: SelectionStrategy =
+        parse_required(args, "selection_strategy")?.parse()?;
+
+    // change_outputs
+    let change_outputs = parse_required(args, "change_outputs")?;
+    let change_outputs = parse_u64(change_outputs, "change_outputs")? as usize;
+
+    // concurrency
+    let concurrency = parse_required(args, "concurrency")?;
+    let concurrency = parse_u64(concurrency, "concurrency")? as usize;
+    if concurrency == 0 {
+        let msg = "Concurrency must be at least 1".to_owned();
+        return Err(Error::ArgumentError(msg));
+    }
+
+    // fluff
+    let fluff = args.get_flag("fluff");
+
+    Ok(command::BatchSendArgs {
+        input: input.to_owned(),
+        format: format.to_owned(),
+        journal,
+        method: method.to_owned(),
+        message,
+        minimum_confirmations: min_c,
+        selection_strategy,
+        change_outputs,
+        max_outputs: 500,
+        fluff,
+        concurrency,
+    })
+}
+
 pub fn parse_receive_args(receive_args: &ArgMatches) -> Result<command::ReceiveArgs, Error> {
     // message
     let message = receive_args
@@ -896,7 +1176,7 @@ pub fn parse_receive_args(receive_args: &ArgMatches) -> Result<command::ReceiveA
     let tx_file = parse_required(receive_args, "input")?;
 
     // validate input
-    if method == "file" {
+    if method == "file" && !armor::is_armored(tx_file) {
         if !Path::new(&tx_file).is_file() {
             let msg = format!("File {} not found.", &tx_file);
             return Err(Error::ArgumentError(msg));
@@ -921,7 +1201,7 @@ pub fn parse_finalize_args(args: &ArgMatches) -> Result<command::FinalizeArgs, E
     let input = parse_required(args, "input")?;
 
     // validate input
-    if method == "file" {
+    if method == "file" && !armor::is_armored(input) {
         if !Path::new(&input).is_file() {
             let msg = format!("File {} not found.", input);
             return Err(Error::ArgumentError(msg));
@@ -984,7 +1264,8 @@ pub fn parse_process_invoice_args(
     let min_c = parse_u64(min_c, "minimum_confirmations")?;
 
     // selection_strategy
-    let selection_strategy = parse_required(args, "selection_strategy")?;
+    let selection_strategy: SelectionStrategy =
+        parse_required(args, "selection_strategy")?.parse()?;
 
     // estimate_selection_strategies
     let estimate_selection_strategies = args.get_flag("estimate_selection_strategies");
@@ -1009,6 +1290,7 @@ pub fn parse_process_invoice_args(
     };
     if !estimate_selection_strategies
         && method == "http"
+        && !dest.starts_with('@')
         && !dest.starts_with("http://")
         && !dest.starts_with("https://")
     {
@@ -1043,7 +1325,7 @@ pub fn parse_process_invoice_args(
     Ok(command::ProcessInvoiceArgs {
         message,
         minimum_confirmations: min_c,
-        selection_strategy: selection_strategy.to_owned(),
+        selection_strategy,
         estimate_selection_strategies,
         method: method.to_owned(),
         dest: dest.to_owned(),
@@ -1090,6 +1372,7 @@ pub fn parse_outputs_args(args: &ArgMatches) -> Result<command::OutputsArgs, Err
     };
     Ok(command::OutputsArgs {
         show_full_history,
+        frozen: args.get_flag("frozen"),
         limit,
         offset,
         sort_order,
@@ -1160,6 +1443,7 @@ pub fn parse_txs_args(args: &ArgMatches) -> Result<command::TxsArgs, Error> {
     Ok(command::TxsArgs {
         id: tx_id,
         tx_slate_id,
+        label: args.get_one::<String>("label").cloned(),
         limit,
         offset,
         sort_order,
@@ -1195,6 +1479,102 @@ pub fn parse_repost_args(args: &ArgMatches) -> Result<command::RepostArgs, Error
     })
 }
 
+pub fn parse_contacts_args(args: &ArgMatches) -> Result<command::ContactsArgs, Error> {
+    match args.subcommand() {
+        Some(("add", args)) => {
+            let name = parse_required(args, "name")?;
+            let method = parse_required(args, "method")?;
+            let dest = parse_required(args, "dest")?;
+            Ok(command::ContactsArgs::Add(Contact {
+                name: name.to_owned(),
+                method: method.to_owned(),
+                destination: dest.to_owned(),
+                proof_address: args.get_one::<String>("proof_address").cloned(),
+            }))
+        }
+        Some(("remove", args)) => {
+            let name = parse_required(args, "name")?;
+            Ok(command::ContactsArgs::Remove(name.to_owned()))
+        }
+        _ => Ok(command::ContactsArgs::List),
+    }
+}
+
+pub fn parse_api_tokens_args(args: &ArgMatches) -> Result<command::ApiTokensArgs, Error> {
+    match args.subcommand() {
+        Some(("issue", args)) => {
+            let name = parse_required(args, "name")?;
+            let scope = ApiScope::from_name(parse_required(args, "scope")?)?;
+            let daily_limit = match args.get_one::<String>("daily_limit") {
+                None => None,
+                Some(l) => match core::core::amount_from_hr_string(l) {
+                    Ok(a) => Some(a),
+                    Err(e) => {
+                        let msg = format!(
+                            "Could not parse daily_limit as a number with optional decimal point. e={:?}",
+                            e
+                        );
+                        return Err(Error::ArgumentError(msg));
+                    }
+                },
+            };
+            Ok(command::ApiTokensArgs::Issue {
+                name: name.to_owned(),
+                scope,
+                daily_limit,
+            })
+        }
+        Some(("revoke", args)) => {
+            let name = parse_required(args, "name")?;
+            Ok(command::ApiTokensArgs::Revoke(name.to_owned()))
+        }
+        _ => Ok(command::ApiTokensArgs::List),
+    }
+}
+
+pub fn parse_label_args(args: &ArgMatches) -> Result<command::LabelArgs, Error> {
+    let tx_id = match args.get_one::<String>("id") {
+        None => None,
+        Some(tx) => Some(parse_u64(tx, "id")? as u32),
+    };
+    let tx_slate_id = match args.get_one::<String>("txid") {
+        None => None,
+        Some(tx) => match tx.parse() {
+            Ok(t) => Some(t),
+            Err(e) => {
+                let msg = format!("Could not parse txid parameter. e={}", e);
+                return Err(Error::ArgumentError(msg));
+            }
+        },
+    };
+    let commit = args.get_one::<String>("commit").cloned();
+    let targets = [tx_id.is_some(), tx_slate_id.is_some(), commit.is_some()];
+    if targets.iter().filter(|t| **t).count() != 1 {
+        let msg = "Exactly one of 'id' (-i), 'txid' (-t) or 'commit' (-c) is required.".to_owned();
+        return Err(Error::ArgumentError(msg));
+    }
+    let label = args.get_one::<String>("label").cloned();
+    let clear = args.get_flag("clear");
+    if label.is_some() == clear {
+        let msg = "Either a label or the 'clear' flag is required.".to_owned();
+        return Err(Error::ArgumentError(msg));
+    }
+    Ok(command::LabelArgs {
+        tx_id,
+        tx_slate_id,
+        commit,
+        label,
+    })
+}
+
+pub fn parse_freeze_args(args: &ArgMatches, frozen: bool) -> Result<command::FreezeArgs, Error> {
+    let commit = parse_required(args, "commit")?;
+    Ok(command::FreezeArgs {
+        commit: commit.to_owned(),
+        frozen,
+    })
+}
+
 pub fn parse_cancel_args(args: &ArgMatches) -> Result<command::CancelArgs, Error> {
     let mut tx_id_string = "";
     let tx_id = match args.get_one::<String>("id") {
@@ -1343,6 +1723,10 @@ where
         let mut wallet_lock = wallet.lock();
         let lc = wallet_lock.lc_provider().unwrap();
         let _ = lc.set_top_level_directory(&wallet_config.data_file_dir);
+        let _ = lc.set_seed_kdf_config(wallet_config.seed_kdf());
+        let _ = lc.set_scan_config(wallet_config.scan());
+        let _ = lc.set_webhook_config(wallet_config.webhooks());
+        let _ = lc.set_spend_policy(wallet_config.spend_policy());
     }
 
     // provide wallet instance back to the caller (handy for testing with local wallet proxy, etc)
@@ -1353,6 +1737,8 @@ where
     match wallet_args.subcommand() {
         Some(("init", _)) => open_wallet = false,
         Some(("recover", _)) => open_wallet = false,
+        Some(("api_tokens", _)) => open_wallet = false,
+        Some(("verify_tx_proof", _)) => open_wallet = false,
         Some(("owner_api", _)) => {
             // If wallet exists, open it. Otherwise, that's fine too.
             let mut wallet_lock = wallet.lock();
@@ -1552,6 +1938,21 @@ where
                 Err(e) => Err(e),
             }
         }
+        Some(("batch_send", args)) => {
+            let a = arg_parse!(parse_batch_send_args(&args));
+            match command::batch_send(
+                wallet,
+                km,
+                Some(tor_config),
+                Some(epicbox_config),
+                a,
+                wallet_config.dark_background_color_scheme.unwrap_or(true),
+                is_node_synced.clone(),
+            ) {
+                Ok(_) => Ok("batch_send".to_string()),
+                Err(e) => Err(e),
+            }
+        }
         Some(("receive", args)) => {
             let a = arg_parse!(parse_receive_args(&args));
             match command::receive(wallet, km, &global_wallet_args, a) {
@@ -1649,6 +2050,41 @@ where
                 Err(e) => Err(e),
             }
         }
+        Some(("contacts", args)) => {
+            let a = arg_parse!(parse_contacts_args(&args));
+            match command::contacts(wallet, km, a, is_node_synced.clone()) {
+                Ok(_) => Ok("contacts".to_string()),
+                Err(e) => Err(e),
+            }
+        }
+        Some(("api_tokens", args)) => {
+            let a = arg_parse!(parse_api_tokens_args(&args));
+            match command::api_tokens(&wallet_config, a) {
+                Ok(_) => Ok("api_tokens".to_string()),
+                Err(e) => Err(e),
+            }
+        }
+        Some(("label", args)) => {
+            let a = arg_parse!(parse_label_args(&args));
+            match command::label(wallet, km, a, is_node_synced.clone()) {
+                Ok(_) => Ok("label".to_string()),
+                Err(e) => Err(e),
+            }
+        }
+        Some(("freeze", args)) => {
+            let a = arg_parse!(parse_freeze_args(&args, true));
+            match command::freeze(wallet, km, a, is_node_synced.clone()) {
+                Ok(_) => Ok("freeze".to_string()),
+                Err(e) => Err(e),
+            }
+        }
+        Some(("unfreeze", args)) => {
+            let a = arg_parse!(parse_freeze_args(&args, false));
+            match command::freeze(wallet, km, a, is_node_synced.clone()) {
+                Ok(_) => Ok("unfreeze".to_string()),
+                Err(e) => Err(e),
+            }
+        }
         Some(("export_proof", args)) => {
             let a = arg_parse!(parse_export_proof_args(&args));
             match command::proof_export(wallet, km, a, is_node_synced.clone()) {
@@ -1656,6 +2092,20 @@ where
                 Err(e) => Err(e),
             }
         }
+        Some(("export_tx_proof", args)) => {
+            let a = arg_parse!(parse_export_proof_args(&args));
+            match command::tx_proof_export(wallet, km, a, is_node_synced.clone()) {
+                Ok(_) => Ok("export_tx_proof".to_string()),
+                Err(e) => Err(e),
+            }
+        }
+        Some(("verify_tx_proof", args)) => {
+            let a = arg_parse!(parse_verify_proof_args(&args));
+            match command::tx_proof_verify(a) {
+                Ok(_) => Ok("verify_tx_proof".to_string()),
+                Err(e) => Err(e),
+            }
+        }
         Some(("verify_proof", args)) => {
             let a = arg_parse!(parse_verify_proof_args(&args));
             match command::proof_verify(wallet, km, a, is_node_synced.clone()) {